@@ -2,7 +2,7 @@
 
 fn main() {
     let version = Command::new("git")
-        .args(&["rev-parse", "--short", "HEAD"])
+        .args(["rev-parse", "--short", "HEAD"])
         .output()
         .expect("failed to get git version");
 
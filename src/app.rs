@@ -1,11 +1,27 @@
-use crate::modes::{Mode, ReadMode, Selected};
+use crate::config::MIN_PANE_WIDTH;
+use crate::date_range::DateRange;
+use crate::days::EntryRow;
+use crate::entry_filter::EntryFilter;
+use crate::feed_groups::{self, FeedRow, Group};
+use crate::images::GraphicsProtocol;
+use crate::keymap::{Action, Binding, Key, Pane};
+use crate::modes::{EntrySort, FeedSort, Mode, PaneLayout, ReadMode, Selected, Split};
+use crate::rss::VirtualFeed;
 use crate::util;
 use anyhow::Result;
-use copypasta::{ClipboardContext, ClipboardProvider};
-use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use tui::{backend::CrosstermBackend, Terminal};
 
+/// how many lines of an entry one notch of the scroll wheel scrolls
+const MOUSE_SCROLL_LINES: u16 = 3;
+/// how many changes can be undone
+const UNDO_LIMIT: usize = 50;
+/// what piped entries are wrapped to, as the command's width is not known
+const PIPED_TEXT_WIDTH: usize = 80;
+/// how much the pane width keys change a pane's width, in percent of the screen
+const PANE_WIDTH_STEP: i16 = 5;
+
 macro_rules! delegate_to_locked_inner {
     ($(($fn_name:ident, $t:ty)),* $(,)? ) => {
         $(
@@ -28,6 +44,35 @@ pub fn $fn_name(&self) -> $t {
     };
 }
 
+/// how far along a refresh of several feeds is
+#[derive(Clone, Debug)]
+pub struct RefreshProgress {
+    pub feeds_len: usize,
+    pub done_len: usize,
+    /// the feeds that have not finished yet
+    pub queued: HashSet<crate::rss::FeedId>,
+    /// the feeds being fetched right now, in the order they started
+    pub in_flight: Vec<crate::rss::FeedId>,
+    /// how many refreshes share this progress
+    pub refreshes_len: usize,
+    pub started_at: std::time::Instant,
+}
+
+/// a change to the database that `u` takes back
+#[derive(Clone, Debug)]
+pub struct Undo {
+    /// what was done, like "marking 12 entries read"
+    description: String,
+    change: UndoChange,
+}
+
+#[derive(Clone, Debug)]
+enum UndoChange {
+    EntryStates(Vec<crate::rss::SavedEntryState>),
+    DeletedEntries(crate::rss::DeletedEntries),
+    DeletedFeed(crate::rss::FeedId, crate::rss::DeletedFeed),
+}
+
 #[derive(Clone, Debug)]
 pub struct App {
     inner: Arc<Mutex<AppImpl>>,
@@ -35,110 +80,519 @@ pub struct App {
 
 impl App {
     delegate_to_locked_inner![
+        (current_article_link, Option<(crate::rss::EntryId, String)>),
+        (current_enclosure, Option<crate::rss::Enclosure>),
+        (current_play_link, Option<String>),
         (error_flash_is_empty, bool),
         (feed_ids, Result<Vec<crate::rss::FeedId>>),
         (feed_subscription_input, String),
         (force_redraw, Result<()>),
-        (http_client, ureq::Agent),
+        (http_client, crate::http::HttpClient),
+        (refresh_options, crate::config::RefreshOptions),
+        (save_session, Result<()>),
+        (sync_backend, Option<Arc<dyn crate::sync::SyncBackend>>),
         (mode, Mode),
-        (selected, Selected),
+        (play_command, String),
         (selected_feed_id, crate::rss::FeedId),
+        (selected_group_feed_ids, Option<Vec<crate::rss::FeedId>>),
     ];
 
     delegate_to_locked_mut_inner![
+        (clear_entry_filter, Result<()>),
+        (clear_date_range, Result<()>),
         (clear_error_flash, ()),
+        (clear_marked_entries, bool),
+        (delete_entries, Result<()>),
+        (apply_date_range, Result<()>),
+        (pop_date_range_input, ()),
         (clear_flash, ()),
+        (forget_drawn_images, ()),
+        (go_to_next_unread_entry, Result<()>),
+        (go_to_previous_unread_entry, Result<()>),
+        (open_random_unread_entry, Result<()>),
+        (cycle_entry_sort, Result<()>),
         (on_down, Result<()>),
         (on_enter, Result<()>),
         (on_left, Result<()>),
         (on_right, Result<()>),
         (on_up, Result<()>),
+        (focus_next_pane, Result<()>),
+        (toggle_split, ()),
+        (toggle_zen, Result<()>),
+        (widen_feeds_pane, ()),
+        (narrow_feeds_pane, ()),
+        (widen_entries_pane, ()),
+        (narrow_entries_pane, ()),
+        (reset_pane_widths, ()),
+        (move_selected_feed_down, Result<()>),
+        (move_selected_feed_up, Result<()>),
+        (open_current_link_in_browser, Result<()>),
         (page_up, ()),
         (page_down, ()),
+        (pop_entry_filter, Result<()>),
         (pop_feed_subscription_input, ()),
+        (pop_feed_tags_input, ()),
+        (pop_feed_url_input, ()),
+        (pop_pipe_command_input, ()),
+        (pop_entry_note_input, ()),
         (put_current_link_in_clipboard, Result<()>),
+        (subscribe_from_clipboard, Result<()>),
+        (on_command_palette_down, ()),
+        (on_command_palette_up, ()),
+        (on_feed_switcher_down, ()),
+        (on_feed_switcher_up, ()),
+        (on_feed_health_down, ()),
+        (on_feed_health_up, ()),
+        (on_search_result_down, ()),
+        (on_search_result_up, ()),
+        (open_selected_search_result, Result<()>),
+        (start_naming_search, Result<()>),
+        (pop_saved_search_name_input, ()),
+        (save_search, Result<()>),
+        (start_saved_searches, Result<()>),
+        (on_saved_search_down, ()),
+        (on_saved_search_up, ()),
+        (run_selected_saved_search, Result<()>),
+        (delete_selected_saved_search, Result<()>),
+        (pop_command_palette_input, ()),
+        (pop_feed_switcher_input, ()),
+        (pop_search_input, Result<()>),
         (reset_feed_subscription_input, ()),
+        (save_feed_tags, Result<()>),
+        (save_feed_url, Result<crate::rss::FeedId>),
+        (select_entries, Result<()>),
         (select_feeds, ()),
+        (select_feed_switcher_feed, Result<()>),
+        (select_feed_health_feed, Result<()>),
+        (choose_command, Result<()>),
+        (start_command_palette, ()),
+        (start_feed_switcher, ()),
+        (start_feed_health, ()),
+        (show_feed_stats, Result<()>),
+        (show_raw_feed, Result<()>),
+        (take_chosen_command, Option<Action>),
+        (start_editing_feed_tags, ()),
+        (start_editing_feed_url, ()),
+        (start_piping_entry, Result<()>),
+        (save_entry, Result<()>),
+        (start_editing_entry_note, Result<()>),
+        (save_entry_note, Result<()>),
+        (leave_entry, Result<()>),
+        (take_pipe_command, Result<(String, String)>),
+        (toggle_pipe_html, ()),
+        (toggle_selected_group, bool),
         (toggle_help, Result<()>),
+        (show_error_log, ()),
+        (toggle_mark, ()),
+        (toggle_paused, Result<()>),
+        (mark_all_read, Result<()>),
+        (undo, Result<()>),
+        (start_feed_deletion, Result<()>),
+        (cancel_feed_deletion, ()),
+        (confirm_feed_deletion, Result<()>),
+        (ask_about_feed_moves, ()),
+        (confirm_feed_move, Result<()>),
+        (decline_feed_move, Result<()>),
+        (toggle_visual_mode, ()),
         (toggle_read, Result<()>),
         (toggle_read_mode, Result<()>),
+        (toggle_starred, Result<()>),
+        (take_images_to_fetch, Vec<String>),
+        (take_count, Option<usize>),
+        (take_link_number, Option<usize>),
+        (toggle_unread_only, Result<()>),
         (update_current_feed_and_entries, Result<()>),
+        (update_feeds, Result<()>),
     ];
 
     pub fn new(
-        options: crate::Options,
+        config: crate::config::Config,
         event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
     ) -> Result<App> {
         Ok(App {
-            inner: Arc::new(Mutex::new(AppImpl::new(options, event_s)?)),
+            inner: Arc::new(Mutex::new(AppImpl::new(config, event_s)?)),
         })
     }
 
     pub fn draw(&self, terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
         let mut inner = self.inner.lock().unwrap();
 
-        terminal.draw(|mut f| {
-            let chunks = crate::ui::predraw(f);
+        terminal.draw(|f| {
+            let chunks = crate::ui::predraw(f, &inner);
 
             assert!(
-                chunks.len() >= 2,
-                "There must be at least two chunks in order to draw two columns"
+                !chunks.is_empty(),
+                "There must be at least one chunk in order to draw the entry"
             );
 
-            let new_width = chunks[1].width;
+            // the entry is read in the last pane
+            let new_width = chunks[chunks.len() - 1].width;
 
             if inner.entry_column_width != new_width {
                 inner.entry_column_width = new_width;
-                inner.on_enter().unwrap_or_else(|e| {
-                    inner.error_flash = vec![e];
-                })
+                // the previewed entry is rendered again by `update_preview`
+                inner.previewed_entry = None;
+                if matches!(inner.selected, Selected::Entry(_)) {
+                    inner.on_enter().unwrap_or_else(|e| {
+                        inner.clear_error_flash();
+                        inner.push_error(e);
+                    })
+                }
             }
 
-            inner.entry_column_width = chunks[1].width;
+            inner.update_preview().unwrap_or_else(|e| {
+                inner.clear_error_flash();
+                inner.push_error(e);
+            });
 
-            crate::ui::draw(&mut f, chunks, &mut inner);
+            crate::ui::draw(f, chunks, &mut inner);
         })?;
 
+        if inner.config.hyperlinks && matches!(inner.mode, Mode::Normal) {
+            crate::ui::draw_hyperlinks(terminal.backend_mut(), &inner)?;
+        }
+
+        if let Some(protocol) = inner.config.inline_images {
+            // sixel images stay on the screen until something is drawn over them,
+            // so when they move, the screen is cleared and drawn again without them
+            if protocol == GraphicsProtocol::Sixel
+                && !inner.drawn_images.is_empty()
+                && crate::ui::visible_images(&inner) != inner.drawn_images
+            {
+                inner.drawn_images.clear();
+                terminal.clear()?;
+                drop(inner);
+                return self.draw(terminal);
+            }
+
+            crate::ui::draw_images(terminal.backend_mut(), &mut inner, protocol)?;
+        }
+
         Ok(())
     }
 
-    pub fn on_key(&self, keycode: KeyCode, modifiers: KeyModifiers) -> Result<()> {
-        match (keycode, modifiers) {
+    /// the action bound to the keys pressed so far, the last of which is `key`.
+    /// `None` while they could still be the start of a longer binding
+    pub fn action(&self, key: Key) -> Option<Action> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending_keys.push(key);
+
+        match inner
+            .config
+            .keymap
+            .binding(&inner.pending_keys, &inner.selected)
+        {
+            Binding::Action(action) => {
+                inner.pending_keys.clear();
+                Some(action)
+            }
+            Binding::Pending => None,
+            Binding::Unbound => {
+                let was_pending = inner.pending_keys.len() > 1;
+                inner.pending_keys.clear();
+                drop(inner);
+
+                // a key that does not go on with the sequence is taken on its own
+                if was_pending {
+                    self.action(key)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn on_action(&self, action: Action) -> Result<()> {
+        // enter opens the link whose number is being typed, anything else gives up on it
+        if let Some(link_number) = self.take_link_number() {
+            if action == Action::Enter {
+                let mut inner = self.inner.lock().unwrap();
+                return inner.open_link_number(link_number);
+            }
+        }
+
+        let count = self.take_count();
+
+        match action {
             // movement
-            (KeyCode::Left, _) | (KeyCode::Char('h'), _) => self.on_left(),
-            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => self.on_down(),
-            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => self.on_up(),
-            (KeyCode::Right, _) | (KeyCode::Char('l'), _) => self.on_right(),
-            (KeyCode::PageUp, _) => {
+            Action::Left => self.on_left(),
+            Action::Down if count.is_none() => self.on_down(),
+            Action::Up if count.is_none() => self.on_up(),
+            Action::Right => self.on_right(),
+            Action::NextPane => self.focus_next_pane(),
+            Action::ToggleSplit => {
+                self.toggle_split();
+                Ok(())
+            }
+            Action::ToggleZen => self.toggle_zen(),
+            Action::WidenFeedsPane => {
+                self.widen_feeds_pane();
+                Ok(())
+            }
+            Action::NarrowFeedsPane => {
+                self.narrow_feeds_pane();
+                Ok(())
+            }
+            Action::WidenEntriesPane => {
+                self.widen_entries_pane();
+                Ok(())
+            }
+            Action::NarrowEntriesPane => {
+                self.narrow_entries_pane();
+                Ok(())
+            }
+            Action::ResetPaneWidths => {
+                self.reset_pane_widths();
+                Ok(())
+            }
+            Action::PageUp => {
                 self.page_up();
                 Ok(())
             }
-            (KeyCode::PageDown, _) => {
+            Action::PageDown => {
                 self.page_down();
                 Ok(())
             }
+            Action::Down
+            | Action::Up
+            | Action::HalfPageUp
+            | Action::HalfPageDown
+            | Action::Top
+            | Action::Bottom => self.on_motion(action, count),
             // modes, selections, editing, etc.
-            (KeyCode::Enter, _) => self.on_enter(),
-            (KeyCode::Char('?'), _) => self.toggle_help(),
-            (KeyCode::Char('a'), _) => self.toggle_read_mode(),
-            (KeyCode::Char('e'), _) | (KeyCode::Char('i'), _) => {
+            Action::Enter => {
+                if self.toggle_selected_group() {
+                    Ok(())
+                } else {
+                    self.on_enter()
+                }
+            }
+            Action::ToggleHelp => self.toggle_help(),
+            Action::ToggleReadMode => self.toggle_read_mode(),
+            Action::ToggleUnreadOnly => self.toggle_unread_only(),
+            Action::InsertMode => {
                 let mut inner = self.inner.lock().unwrap();
                 inner.mode = Mode::Editing;
                 Ok(())
             }
-            (KeyCode::Char('c'), _) => self.put_current_link_in_clipboard(),
-            _ => Ok(()),
+            Action::Search => {
+                let mut inner = self.inner.lock().unwrap();
+                inner.mode = Mode::Search;
+                Ok(())
+            }
+            Action::Filter => {
+                let mut inner = self.inner.lock().unwrap();
+                inner.mode = Mode::Filter;
+                Ok(())
+            }
+            Action::DateRange => {
+                let mut inner = self.inner.lock().unwrap();
+                inner.mode = Mode::DateRange;
+                Ok(())
+            }
+            Action::SwitchFeed => {
+                self.start_feed_switcher();
+                Ok(())
+            }
+            Action::FeedHealth => {
+                self.start_feed_health();
+                Ok(())
+            }
+            Action::SavedSearches => self.start_saved_searches(),
+            Action::FeedStats => self.show_feed_stats(),
+            Action::RawFeed => self.show_raw_feed(),
+            Action::ErrorLog => {
+                self.show_error_log();
+                Ok(())
+            }
+            Action::CommandPalette => {
+                self.start_command_palette();
+                Ok(())
+            }
+            Action::EditTags => {
+                self.start_editing_feed_tags();
+                Ok(())
+            }
+            Action::EditFeedUrl => {
+                self.start_editing_feed_url();
+                Ok(())
+            }
+            Action::TogglePaused => self.toggle_paused(),
+            Action::PipeEntry => self.start_piping_entry(),
+            Action::SaveEntry => self.save_entry(),
+            Action::EditNote => self.start_editing_entry_note(),
+            Action::CopyLink => self.put_current_link_in_clipboard(),
+            Action::SubscribeFromClipboard => self.subscribe_from_clipboard(),
+            Action::OpenLink => self.open_current_link_in_browser(),
+            Action::ToggleRead => self.toggle_read(),
+            Action::ToggleStarred => self.toggle_starred(),
+            Action::ToggleMark => {
+                self.toggle_mark();
+                Ok(())
+            }
+            Action::VisualMode => {
+                self.toggle_visual_mode();
+                Ok(())
+            }
+            Action::DeleteEntries => self.delete_entries(),
+            Action::MarkAllRead => self.mark_all_read(),
+            Action::Undo => self.undo(),
+            Action::DeleteFeed => self.start_feed_deletion(),
+            Action::CycleEntrySort => self.cycle_entry_sort(),
+            Action::NextUnread => self.go_to_next_unread_entry(),
+            Action::PreviousUnread => self.go_to_previous_unread_entry(),
+            Action::RandomUnread => self.open_random_unread_entry(),
+            Action::MoveFeedUp => self.move_selected_feed_up(),
+            Action::MoveFeedDown => self.move_selected_feed_down(),
+            // these talk to the IO thread, so main handles them
+            Action::Quit
+            | Action::RefreshFeed
+            | Action::RefreshAllFeeds
+            | Action::DownloadEnclosure
+            | Action::Play
+            | Action::FetchArticle => Ok(()),
         }
     }
 
+    pub fn on_digit(&self, digit: char) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_digit(digit)
+    }
+
+    pub fn on_motion(&self, action: Action, count: Option<usize>) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_motion(action, count)
+    }
+
+    pub fn scroll_popup_down(&self, lines: u16) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.scroll_popup_down(lines)
+    }
+
+    pub fn scroll_popup_up(&self, lines: u16) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.scroll_popup_up(lines)
+    }
+
+    pub fn on_mouse(&self, event: crossterm::event::MouseEvent) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_mouse(event)
+    }
+
+    pub fn on_article_fetched(
+        &self,
+        entry_id: crate::rss::EntryId,
+        full_content: String,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_article_fetched(entry_id, full_content)
+    }
+
+    /// `png` is `None` if the image could not be fetched
+    pub fn on_image_fetched(&self, url: String, png: Option<Vec<u8>>) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.on_image_fetched(url, png)
+    }
+
     pub fn set_flash(&self, flash: String) {
         let mut inner = self.inner.lock().unwrap();
         inner.flash = Some(flash)
     }
 
+    /// adds `feed_ids` to the refresh progress, returning the ones
+    /// that are not already being refreshed
+    pub fn start_refresh_progress(
+        &self,
+        feed_ids: &[crate::rss::FeedId],
+    ) -> Vec<crate::rss::FeedId> {
+        let mut inner = self.inner.lock().unwrap();
+        let refresh_progress = inner
+            .refresh_progress
+            .get_or_insert_with(|| RefreshProgress {
+                feeds_len: 0,
+                done_len: 0,
+                queued: HashSet::new(),
+                in_flight: vec![],
+                refreshes_len: 0,
+                started_at: std::time::Instant::now(),
+            });
+
+        let feed_ids = feed_ids
+            .iter()
+            .copied()
+            .filter(|feed_id| refresh_progress.queued.insert(*feed_id))
+            .collect::<Vec<_>>();
+
+        refresh_progress.feeds_len += feed_ids.len();
+        refresh_progress.refreshes_len += 1;
+
+        feed_ids
+    }
+
+    pub fn on_feed_refresh_started(&self, feed_id: crate::rss::FeedId) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(refresh_progress) = &mut inner.refresh_progress {
+            refresh_progress.in_flight.push(feed_id);
+        }
+    }
+
+    /// `refreshed` is whether the feed got refreshed, in which case
+    /// the feeds and its entries are reloaded
+    /// `has_changed` if the refresh changed the feed, which failing to refresh does too
+    pub fn on_feed_refresh_finished(&self, feed_id: crate::rss::FeedId, has_changed: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(refresh_progress) = &mut inner.refresh_progress {
+            refresh_progress.queued.remove(&feed_id);
+            refresh_progress.in_flight.retain(|id| *id != feed_id);
+            refresh_progress.done_len += 1;
+        }
+
+        if has_changed {
+            if let Err(e) = inner.on_feed_refreshed(feed_id) {
+                inner.push_error(e);
+            }
+        }
+    }
+
+    /// ends one refresh's part of the progress, and the progress with the last one
+    pub fn finish_refresh_progress(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(refresh_progress) = &mut inner.refresh_progress {
+            refresh_progress.refreshes_len -= 1;
+            if refresh_progress.refreshes_len == 0 {
+                inner.refresh_progress = None;
+            }
+        }
+    }
+
     pub fn push_error_flash(&self, e: anyhow::Error) {
         let mut inner = self.inner.lock().unwrap();
-        inner.error_flash.push(e);
+        inner.push_error(e);
+    }
+
+    /// like `push_error_flash`, for an error refreshing `feed_id`
+    pub fn push_feed_error_flash(&self, feed_id: crate::rss::FeedId, e: anyhow::Error) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.push_feed_error(feed_id, e);
+    }
+
+    pub fn go_to_feed_id(&self, feed_id: crate::rss::FeedId) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.go_to_feed_id(feed_id)
+    }
+
+    /// asks whether to subscribe to the fetched feed
+    pub fn set_feed_preview(&self, feed_preview: crate::rss::FeedPreview) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.feed_preview = Some(feed_preview);
+        inner.mode = Mode::ConfirmSubscription;
+    }
+
+    /// the feed to subscribe to, once it is confirmed
+    pub fn take_feed_preview(&self) -> Option<crate::rss::FeedPreview> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.mode = Mode::Editing;
+        inner.feed_preview.take()
     }
 
     pub fn set_mode(&self, mode: Mode) {
@@ -151,209 +605,1511 @@ pub fn push_feed_subscription_input(&self, input: char) {
         inner.feed_subscription_input.push(input);
     }
 
+    pub fn push_search_input(&self, input: char) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.search_input.push(input);
+        inner.update_search_results()
+    }
+
+    pub fn push_saved_search_name_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.saved_search_name_input.push(input);
+    }
+
+    pub fn push_entry_filter(&self, input: char) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entry_filter.push(input);
+        inner.reset_entries_for_filter()
+    }
+
+    pub fn push_date_range_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.date_range_input.push(input);
+    }
+
+    pub fn push_command_palette_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.command_palette_input.push(input);
+        inner.update_command_palette_results();
+    }
+
+    pub fn push_feed_switcher_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.feed_switcher_input.push(input);
+        inner.update_feed_switcher_results();
+    }
+
+    pub fn push_feed_tags_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.feed_tags_input.push(input);
+    }
+
+    pub fn push_feed_url_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.feed_url_input.push(input);
+    }
+
+    pub fn push_entry_note_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entry_note_input.push(input);
+    }
+
+    pub fn push_pipe_command_input(&self, input: char) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pipe_command_input.push(input);
+    }
+
     pub fn set_feeds(&self, feeds: Vec<crate::rss::Feed>) {
         let mut inner = self.inner.lock().unwrap();
         let feeds = feeds.into();
         inner.feeds = feeds;
+        inner.update_feed_rows();
+        if let Err(e) = inner.update_feed_entry_counts() {
+            inner.push_error(e);
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct AppImpl {
+    pub config: crate::config::Config,
     // database stuff
     pub conn: rusqlite::Connection,
     // network stuff
-    pub http_client: ureq::Agent,
+    pub http_client: crate::http::HttpClient,
+    /// feeds are synced with this instead of fetched, if it is set
+    pub sync_backend: Option<Arc<dyn crate::sync::SyncBackend>>,
     // feed stuff
     pub current_feed: Option<crate::rss::Feed>,
+    /// set instead of `current_feed` when a virtual feed is selected
+    pub current_virtual_feed: Option<VirtualFeed>,
+    /// virtual feeds share one entry sort, as they are not in the database
+    pub virtual_feed_entry_sort: EntrySort,
     pub feeds: util::StatefulList<crate::rss::Feed>,
+    pub feed_tags: HashMap<crate::rss::FeedId, Vec<String>>,
+    pub entry_notes: HashMap<crate::rss::EntryId, String>,
+    /// shown next to each feed, so kept up to date as entries are read and feeds refreshed
+    pub feed_entry_counts: HashMap<crate::rss::FeedId, crate::rss::EntryCounts>,
+    pub collapsed_groups: HashSet<Group>,
+    /// what the feed pane shows: feeds, grouped by tag if there are any tags
+    pub feed_rows: util::StatefulList<FeedRow>,
     // entry stuff
     pub current_entry_meta: Option<crate::rss::EntryMeta>,
+    /// the selected entry, shown in the reading pane of the three-pane layout while it is not being read
+    pub previewed_entry: Option<crate::rss::EntryMeta>,
     pub entries: util::StatefulList<crate::rss::EntryMeta>,
     pub entry_selection_position: usize,
-    pub current_entry_text: String,
+    /// entries marked one by one for a bulk action
+    pub marked_entries: HashSet<crate::rss::EntryId>,
+    /// the entry a visual range starts at. it ends at the selected entry
+    pub visual_anchor: Option<crate::rss::EntryId>,
+    /// the most recent change last
+    undo_stack: Vec<Undo>,
+    /// the feed waiting for its deletion to be confirmed
+    pub feed_to_delete: Option<crate::rss::FeedId>,
+    /// the feed `ConfirmFeedMove` asks about
+    pub feed_to_move: Option<crate::rss::FeedId>,
+    /// the feed `ConfirmSubscription` asks about
+    pub feed_preview: Option<crate::rss::FeedPreview>,
+    pub rendered_entry: crate::html::Rendered,
+    /// the digits typed so far of the number of a link to open
+    pub link_number_input: String,
+    /// the digits typed so far of how many times to do the next motion, like the `5` of `5j`
+    count_input: String,
+    /// the keys typed so far of a longer binding, like the first `g` of `g g`
+    pending_keys: Vec<Key>,
     pub entry_scroll_position: u16,
     pub entry_lines_len: usize,
     pub entry_lines_rendered_len: u16,
     pub entry_column_width: u16,
+    /// where the entry being read was last drawn, if it was
+    pub entry_text_area: Option<tui::layout::Rect>,
+    /// where the feed and entry lists were last drawn, for telling what is clicked
+    pub feeds_area: Option<tui::layout::Rect>,
+    pub entries_area: Option<tui::layout::Rect>,
+    pub image_cache: crate::images::ImageCache,
+    /// images the entry being read needs, that the IO thread has not been asked for yet
+    images_to_fetch: Vec<String>,
+    /// where images are on the screen, so they are only drawn again when that changes
+    pub drawn_images: Vec<(u16, u16, crate::html::ImagePlacement)>,
     // modes
-    pub should_quit: bool,
     pub selected: Selected,
     pub mode: Mode,
     pub read_mode: ReadMode,
-    pub show_help: bool,
+    pub split: Split,
+    /// whether entries are read in zen mode, with nothing else on the screen
+    pub zen: bool,
+    /// in percent of the screen, stacked or not
+    pub feeds_pane_width: u16,
+    pub entries_pane_width: u16,
+    /// how far the key help, the error log, or the raw feed is scrolled
+    pub popup_scroll: u16,
     // misc
     pub error_flash: Vec<anyhow::Error>,
+    pub error_log: crate::error_log::ErrorLog,
     pub feed_subscription_input: String,
+    /// narrows the current feed's entries to titles containing it
+    pub entry_filter: String,
+    pub date_range_input: String,
+    /// narrows every feed's entries, until it is cleared
+    pub date_range: DateRange,
+    pub search_input: String,
+    pub search_results: util::StatefulList<crate::rss::EntryMeta>,
+    pub saved_search_name_input: String,
+    /// read when they are shown
+    pub saved_searches: util::StatefulList<crate::rss::SavedSearch>,
+    pub feed_switcher_input: String,
+    /// indexes into `feeds`, best match first
+    pub feed_switcher_results: util::StatefulList<usize>,
+    /// the failing feeds, most failures first.
+    /// ids rather than indexes, as refreshes reload the feeds while they are shown
+    pub feed_health_results: util::StatefulList<crate::rss::FeedId>,
+    /// the shown statistics, computed when they are shown
+    pub feed_stats: Option<crate::rss::FeedStats>,
+    /// the shown raw feed, read when it is shown
+    pub raw_feed: Option<crate::rss::RawFeed>,
+    pub command_palette_input: String,
+    /// the actions that match, best match first
+    pub command_palette_results: util::StatefulList<Action>,
+    /// run by the main loop as if its key was pressed
+    chosen_command: Option<Action>,
+    pub feed_tags_input: String,
+    pub feed_url_input: String,
+    /// kept after piping, so the same command is offered next time
+    pub pipe_command_input: String,
+    /// whether the entry is piped as HTML instead of as text
+    pub pipe_html: bool,
+    pub entry_note_input: String,
     pub flash: Option<String>,
+    /// shown instead of the flash while feeds are refreshing
+    pub refresh_progress: Option<RefreshProgress>,
     event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
     is_wsl: Option<bool>,
 }
 
 impl AppImpl {
     pub fn new(
-        options: crate::Options,
+        config: crate::config::Config,
         event_s: std::sync::mpsc::Sender<crate::Event<crossterm::event::KeyEvent>>,
     ) -> Result<AppImpl> {
-        let conn = rusqlite::Connection::open(&options.database_path)?;
+        let conn = crate::rss::open_db(&config.database_path, config.database_passphrase.as_ref())?;
 
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(options.network_timeout)
-            .build();
+        let http_client = crate::http::HttpClient::from_config(&config)?;
+        let sync_backend = config
+            .sync
+            .as_ref()
+            .map(|sync_config| crate::sync::backend_from_config(sync_config, &http_client));
 
         crate::rss::initialize_db(&conn)?;
         let feeds: util::StatefulList<crate::rss::Feed> = vec![].into();
         let entries: util::StatefulList<crate::rss::EntryMeta> = vec![].into();
         let selected = Selected::Feeds;
         let initial_current_feed = None;
+        let split = config.split;
+        let feeds_pane_width = config.feeds_pane_width;
+        let entries_pane_width = config.entries_pane_width;
+        let error_log = crate::error_log::ErrorLog::new(config.error_log_path.clone());
 
         let mut app = AppImpl {
+            config,
             conn,
             http_client,
-            should_quit: false,
+            sync_backend,
             error_flash: vec![],
+            error_log,
             feeds,
+            feed_tags: HashMap::new(),
+            entry_notes: HashMap::new(),
+            feed_entry_counts: HashMap::new(),
+            collapsed_groups: HashSet::new(),
+            feed_rows: vec![].into(),
             entries,
             selected,
             entry_scroll_position: 0,
             entry_lines_len: 0,
             entry_lines_rendered_len: 0,
             entry_column_width: 0,
+            entry_text_area: None,
+            feeds_area: None,
+            entries_area: None,
+            image_cache: crate::images::ImageCache::default(),
+            images_to_fetch: vec![],
+            drawn_images: vec![],
             current_entry_meta: None,
-            current_entry_text: String::new(),
+            previewed_entry: None,
+            rendered_entry: crate::html::Rendered::default(),
+            link_number_input: String::new(),
+            count_input: String::new(),
+            pending_keys: vec![],
             current_feed: initial_current_feed,
+            current_virtual_feed: None,
+            virtual_feed_entry_sort: EntrySort::default(),
             feed_subscription_input: String::new(),
+            entry_filter: String::new(),
+            date_range_input: String::new(),
+            date_range: DateRange::default(),
+            search_input: String::new(),
+            search_results: vec![].into(),
+            saved_search_name_input: String::new(),
+            saved_searches: vec![].into(),
+            feed_switcher_input: String::new(),
+            feed_switcher_results: vec![].into(),
+            feed_health_results: vec![].into(),
+            feed_stats: None,
+            raw_feed: None,
+            command_palette_input: String::new(),
+            command_palette_results: vec![].into(),
+            chosen_command: None,
+            feed_tags_input: String::new(),
+            feed_url_input: String::new(),
+            pipe_command_input: String::new(),
+            pipe_html: false,
+            entry_note_input: String::new(),
             mode: Mode::Normal,
             read_mode: ReadMode::ShowUnread,
-            show_help: true,
+            split,
+            zen: false,
+            feeds_pane_width,
+            entries_pane_width,
+            popup_scroll: 0,
             entry_selection_position: 0,
+            marked_entries: HashSet::new(),
+            visual_anchor: None,
+            undo_stack: vec![],
+            feed_to_delete: None,
+            feed_to_move: None,
+            feed_preview: None,
             flash: None,
+            refresh_progress: None,
             event_s,
             is_wsl: None,
         };
 
         app.update_feeds()?;
         app.update_current_feed_and_entries()?;
+        app.restore_session()?;
 
         Ok(app)
     }
 
-    pub fn update_feeds(&mut self) -> Result<()> {
-        let feeds = crate::rss::get_feeds(&self.conn)?.into();
-        self.feeds = feeds;
-        Ok(())
+    /// remembers the current feed, the selected entry, the filters, and the pane,
+    /// for `restore_session` to pick up the next time russ starts
+    pub fn save_session(&self) -> Result<()> {
+        let entry_id = match &self.selected {
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            _ => self
+                .entries
+                .state
+                .selected()
+                .and_then(|idx| self.entries.items.get(idx))
+                .map(|entry_meta| entry_meta.id),
+        };
+
+        let date_range = if self.date_range.is_unbounded() {
+            String::new()
+        } else {
+            self.date_range_input.trim().to_owned()
+        };
+
+        crate::rss::set_session(
+            &self.conn,
+            &crate::rss::Session {
+                feed_id: self.current_feed.as_ref().map(|feed| feed.id),
+                virtual_feed: self.current_virtual_feed,
+                entry_id,
+                pane: Pane::from(&self.selected),
+                read_mode: self.read_mode.clone(),
+                entry_filter: self.entry_filter.clone(),
+                date_range,
+                // widths that were not changed follow the config file, if it changes
+                feeds_pane_width: (self.feeds_pane_width != self.config.feeds_pane_width)
+                    .then_some(self.feeds_pane_width),
+                entries_pane_width: (self.entries_pane_width != self.config.entries_pane_width)
+                    .then_some(self.entries_pane_width),
+            },
+        )
     }
 
-    pub fn update_current_feed_and_entries(&mut self) -> Result<()> {
-        self.update_current_feed()?;
+    /// goes back to where the last session left off.
+    /// a feed or entry that is gone since is skipped, leaving the selection where it starts
+    fn restore_session(&mut self) -> Result<()> {
+        let session = match crate::rss::get_session(&self.conn)? {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+
+        if let Some(feeds_pane_width) = session.feeds_pane_width {
+            self.feeds_pane_width = feeds_pane_width;
+        }
+        if let Some(entries_pane_width) = session.entries_pane_width {
+            self.entries_pane_width = entries_pane_width;
+        }
+
+        let row_idx = self.feed_rows.items.iter().position(|row| match row {
+            FeedRow::Virtual(virtual_feed) => session.virtual_feed == Some(*virtual_feed),
+            FeedRow::Feed { feed_idx } => {
+                session.virtual_feed.is_none()
+                    && session.feed_id == Some(self.feeds.items[*feed_idx].id)
+            }
+            FeedRow::Group { .. } => false,
+        });
+
+        if let Some(row_idx) = row_idx {
+            self.feed_rows.state.select(Some(row_idx));
+            self.on_feed_row_selected()?;
+        }
+
+        // a range that no longer parses is dropped, rather than keeping russ from starting
+        if !session.date_range.is_empty() {
+            if let Ok(date_range) = DateRange::parse(&session.date_range, chrono::Local::now()) {
+                self.date_range = date_range;
+                self.date_range_input = session.date_range;
+            }
+        }
+
+        self.read_mode = session.read_mode;
+        self.entry_filter = session.entry_filter;
         self.update_current_entries()?;
+
+        let entry_idx = session.entry_id.and_then(|entry_id| {
+            self.entries
+                .items
+                .iter()
+                .position(|entry_meta| entry_meta.id == entry_id)
+        });
+
+        if let Some(entry_idx) = entry_idx {
+            self.entries.state.select(Some(entry_idx));
+            self.entry_selection_position = entry_idx;
+        }
+
+        if matches!(session.pane, Pane::Entries | Pane::Entry) && !self.entries.items.is_empty() {
+            self.selected = Selected::Entries;
+            self.update_current_entry_meta()?;
+
+            if session.pane == Pane::Entry && entry_idx.is_some() {
+                self.on_enter()?;
+            }
+        }
+
         Ok(())
     }
 
-    fn update_current_feed(&mut self) -> Result<()> {
-        let current_feed = if self.feeds.items.is_empty() {
-            None
-        } else {
-            let selected_idx = match self.feeds.state.selected() {
-                Some(idx) => idx,
-                None => {
-                    self.feeds.reset();
-                    0
-                }
-            };
-            let feed_id = self.feeds.items[selected_idx].id;
-            Some(crate::rss::get_feed(&self.conn, feed_id)?)
-        };
+    /// reloads the feeds in `feed_sort` order, keeping the selected feed selected
+    pub fn update_feeds(&mut self) -> Result<()> {
+        let selected_feed_id = self
+            .feeds
+            .state
+            .selected()
+            .and_then(|idx| self.feeds.items.get(idx))
+            .map(|feed| feed.id);
 
-        self.current_feed = current_feed;
+        self.feeds.items = crate::rss::get_sorted_feeds(&self.conn, self.config.feed_sort)?;
+
+        let selected_feed_idx = selected_feed_id.and_then(|selected_feed_id| {
+            self.feeds
+                .items
+                .iter()
+                .position(|feed| feed.id == selected_feed_id)
+        });
+        self.feeds.state.select(selected_feed_idx);
+
+        self.feed_tags = crate::rss::get_feed_tags(&self.conn)?;
+        self.entry_notes = crate::rss::get_entry_notes(&self.conn)?;
+        self.update_feed_rows();
+        self.update_feed_entry_counts()
+    }
 
+    fn update_feed_entry_counts(&mut self) -> Result<()> {
+        self.feed_entry_counts = crate::rss::get_entry_counts(&self.conn)?;
         Ok(())
     }
 
-    fn update_current_entries(&mut self) -> Result<()> {
-        let entries = if let Some(feed) = &self.current_feed {
-            crate::rss::get_entries_metas(&self.conn, &self.read_mode, feed.id)?
-                .into_iter()
-                .collect::<Vec<_>>()
-                .into()
-        } else {
-            vec![].into()
-        };
+    /// reloads the feeds, and the entries if they may include `feed_id`'s,
+    /// keeping the selected feed and entry selected
+    fn on_feed_refreshed(&mut self, feed_id: crate::rss::FeedId) -> Result<()> {
+        self.update_feeds()?;
 
-        self.entries = entries;
+        let shows_feed_entries = self.current_virtual_feed.is_some()
+            || self.current_feed.as_ref().map(|feed| feed.id) == Some(feed_id);
 
-        if self.entry_selection_position < self.entries.items.len() {
-            self.entries
+        if shows_feed_entries {
+            let selected_entry_id = self
+                .entries
                 .state
-                .select(Some(self.entry_selection_position))
-        } else {
-            match self.entries.items.len().checked_sub(1) {
-                Some(n) => self.entries.state.select(Some(n)),
-                None => self.entries.reset(),
+                .selected()
+                .and_then(|idx| self.entries.items.get(idx))
+                .map(|entry| entry.id);
+
+            self.update_current_feed_and_entries()?;
+
+            let selected_entry_idx = selected_entry_id.and_then(|selected_entry_id| {
+                self.entries
+                    .items
+                    .iter()
+                    .position(|entry| entry.id == selected_entry_id)
+            });
+
+            if let Some(selected_entry_idx) = selected_entry_idx {
+                self.entry_selection_position = selected_entry_idx;
+                self.entries.state.select(Some(selected_entry_idx));
             }
         }
+
         Ok(())
     }
 
-    fn update_entry_selection_position(&mut self) {
-        if self.entries.items.is_empty() {
-            self.entry_selection_position = 0
-        } else if self.entry_selection_position > self.entries.items.len() - 1 {
-            self.entry_selection_position = self.entries.items.len() - 1
+    /// rebuilds the feed pane, keeping the selected group or feed selected
+    fn update_feed_rows(&mut self) {
+        let selected_row = match self.selected_feed_row() {
+            Some(FeedRow::Feed { .. }) | None => None,
+            Some(row) => Some(row.clone()),
         };
-    }
 
-    fn get_selected_entry(&self) -> Option<Result<crate::rss::EntryContent>> {
-        self.entries.state.selected().and_then(|selected_idx| {
-            self.entries
+        self.feed_rows = feed_groups::feed_rows(
+            &self.feeds.items,
+            &self.feed_tags,
+            &self.collapsed_groups,
+            self.config.smart_feeds.len(),
+        )
+        .into();
+
+        let group_row_idx = selected_row.and_then(|selected_row| {
+            self.feed_rows
                 .items
-                .get(selected_idx)
-                .map(|item| item.id)
-                .map(|entry_id| crate::rss::get_entry_content(&self.conn, entry_id))
-        })
+                .iter()
+                .position(|row| match (row, &selected_row) {
+                    (
+                        FeedRow::Group { group, .. },
+                        FeedRow::Group {
+                            group: selected_group,
+                            ..
+                        },
+                    ) => group == selected_group,
+                    _ => *row == selected_row,
+                })
+        });
+
+        let feed_row_idx = || match self.feeds.state.selected() {
+            Some(selected_feed_idx) => self.feed_rows.items.iter().position(
+                |row| matches!(row, FeedRow::Feed { feed_idx } if *feed_idx == selected_feed_idx),
+            ),
+            None => self
+                .feed_rows
+                .items
+                .iter()
+                .position(|row| matches!(row, FeedRow::Feed { .. })),
+        };
+
+        let row_idx = group_row_idx
+            .or_else(feed_row_idx)
+            .or(if self.feed_rows.items.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+
+        self.feed_rows.state.select(row_idx);
+
+        if self.feeds.state.selected().is_none() {
+            if let Some(FeedRow::Feed { feed_idx }) = self.selected_feed_row() {
+                let feed_idx = *feed_idx;
+                self.feeds.state.select(Some(feed_idx));
+            }
+        }
     }
 
-    fn get_selected_entry_meta(&self) -> Option<Result<crate::rss::EntryMeta>> {
-        self.entries.state.selected().and_then(|selected_idx| {
-            self.entries
-                .items
-                .get(selected_idx)
-                .map(|item| item.id)
-                .map(|entry_id| crate::rss::get_entry_meta(&self.conn, entry_id))
-        })
+    fn selected_feed_row(&self) -> Option<&FeedRow> {
+        self.feed_rows
+            .state
+            .selected()
+            .and_then(|idx| self.feed_rows.items.get(idx))
     }
 
-    fn update_current_entry_meta(&mut self) -> Result<()> {
-        if let Some(entry_meta) = self.get_selected_entry_meta() {
-            let entry_meta = entry_meta?;
-            self.current_entry_meta = Some(entry_meta);
+    /// makes the feed at `feed_idx` the current feed,
+    /// expanding its group if it is collapsed
+    fn select_feed(&mut self, feed_idx: usize) -> Result<()> {
+        self.feeds.state.select(Some(feed_idx));
+        self.current_virtual_feed = None;
+
+        let is_visible = self
+            .feed_rows
+            .items
+            .iter()
+            .any(|row| matches!(row, FeedRow::Feed { feed_idx: idx } if *idx == feed_idx));
+
+        if !is_visible {
+            let feed_tags = self
+                .feeds
+                .items
+                .get(feed_idx)
+                .and_then(|feed| self.feed_tags.get(&feed.id))
+                .cloned()
+                .unwrap_or_default();
+
+            self.collapsed_groups.remove(&feed_tags.first().cloned());
         }
-        Ok(())
+
+        // forget the selected row, so the feed's row gets selected
+        self.feed_rows.unselect();
+        self.update_feed_rows();
+
+        self.entry_filter.clear();
+        self.update_current_feed_and_entries()
+    }
+
+    /// moves the current feed along with the feed pane's selection
+    fn on_feed_row_selected(&mut self) -> Result<()> {
+        match self.selected_feed_row() {
+            Some(FeedRow::Feed { feed_idx }) => {
+                let feed_idx = *feed_idx;
+                self.feeds.state.select(Some(feed_idx));
+                self.current_virtual_feed = None;
+            }
+            Some(FeedRow::Virtual(virtual_feed)) => {
+                self.current_virtual_feed = Some(*virtual_feed);
+            }
+            Some(FeedRow::Group { .. }) | None => return Ok(()),
+        }
+
+        self.entry_filter.clear();
+        self.update_current_feed_and_entries()
+    }
+
+    /// collapses or expands the selected group.
+    /// returns false if the selection is not a group.
+    pub fn toggle_selected_group(&mut self) -> bool {
+        if !matches!(self.selected, Selected::Feeds) {
+            return false;
+        }
+
+        let group = match self.selected_feed_row() {
+            Some(FeedRow::Group { group, .. }) => group.clone(),
+            _ => return false,
+        };
+
+        if !self.collapsed_groups.remove(&group) {
+            self.collapsed_groups.insert(group);
+        }
+
+        self.update_feed_rows();
+
+        true
+    }
+
+    /// the feeds in the selected group or virtual feed, if one is selected
+    pub fn selected_group_feed_ids(&self) -> Option<Vec<crate::rss::FeedId>> {
+        match self.selected_feed_row() {
+            Some(FeedRow::Virtual(_)) => Some(
+                self.feeds
+                    .items
+                    .iter()
+                    .filter(|feed| !feed.paused)
+                    .map(|feed| feed.id)
+                    .collect(),
+            ),
+            Some(FeedRow::Group { group, .. }) => Some(
+                feed_groups::group_feed_indexes(&self.feeds.items, &self.feed_tags, group)
+                    .into_iter()
+                    .map(|feed_idx| &self.feeds.items[feed_idx])
+                    .filter(|feed| !feed.paused)
+                    .map(|feed| feed.id)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// asks whether to delete the selected feed, which only `confirm_feed_deletion` does
+    pub fn start_feed_deletion(&mut self) -> Result<()> {
+        match self.selected_feed_row() {
+            Some(FeedRow::Feed { feed_idx }) => {
+                self.feed_to_delete = Some(self.feeds.items[*feed_idx].id);
+                self.mode = Mode::ConfirmFeedDeletion;
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Select a feed to delete it")),
+        }
+    }
+
+    pub fn cancel_feed_deletion(&mut self) {
+        self.feed_to_delete = None;
+        self.mode = Mode::Normal;
+    }
+
+    /// deletes the feed `start_feed_deletion` asked about, and selects the feed next to it
+    pub fn confirm_feed_deletion(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        let feed_id = match self.feed_to_delete.take() {
+            Some(feed_id) => feed_id,
+            None => return Ok(()),
+        };
+
+        let feed_title = self
+            .feeds
+            .items
+            .iter()
+            .find(|feed| feed.id == feed_id)
+            .and_then(|feed| feed.title.clone())
+            .unwrap_or_else(|| "No feed title".to_string());
+
+        let feed_ids_in_rows = self
+            .feed_rows
+            .items
+            .iter()
+            .filter_map(|row| match row {
+                FeedRow::Feed { feed_idx } => Some(self.feeds.items[*feed_idx].id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let next_feed_id = feed_ids_in_rows
+            .iter()
+            .position(|id| *id == feed_id)
+            .and_then(|position| {
+                feed_ids_in_rows
+                    .get(position + 1)
+                    .or_else(|| position.checked_sub(1).map(|idx| &feed_ids_in_rows[idx]))
+            })
+            .copied();
+
+        let deleted_feed = crate::rss::delete_feed(&self.conn, feed_id)?;
+
+        self.selected = Selected::Feeds;
+        self.update_feeds()?;
+
+        match next_feed_id.and_then(|next_feed_id| {
+            self.feeds
+                .items
+                .iter()
+                .position(|feed| feed.id == next_feed_id)
+        }) {
+            Some(feed_idx) => self.select_feed(feed_idx)?,
+            None => {
+                self.update_feed_rows();
+                self.update_current_feed_and_entries()?;
+            }
+        }
+        self.update_current_entry_meta()?;
+
+        self.flash = Some(format!("Deleted {}, u to undo", feed_title));
+        self.push_undo(Undo {
+            description: format!("deleting {}", feed_title),
+            change: UndoChange::DeletedFeed(feed_id, deleted_feed),
+        });
+
+        Ok(())
+    }
+
+    /// with `confirm_feed_moves`, asks about the first feed that has permanently moved,
+    /// unless something else is being done
+    pub fn ask_about_feed_moves(&mut self) {
+        if !self.config.confirm_feed_moves || !matches!(self.mode, Mode::Normal) {
+            return;
+        }
+
+        if let Some(feed) = self.feeds.items.iter().find(|feed| feed.moved_to.is_some()) {
+            self.feed_to_move = Some(feed.id);
+            self.mode = Mode::ConfirmFeedMove;
+        }
+    }
+
+    /// moves the feed `ask_about_feed_moves` asked about, then asks about the next one
+    pub fn confirm_feed_move(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        let moved_to = self.feed_to_move.take().and_then(|feed_id| {
+            self.feeds
+                .items
+                .iter()
+                .find(|feed| feed.id == feed_id)
+                .and_then(|feed| Some((feed_id, feed.moved_to.clone()?)))
+        });
+
+        if let Some((feed_id, moved_to)) = moved_to {
+            crate::rss::update_feed_url(&self.conn, feed_id, &moved_to)?;
+            self.update_feeds()?;
+            self.update_current_feed_and_entries()?;
+            self.flash = Some(format!("Moved feed to {}", moved_to));
+        }
+
+        self.ask_about_feed_moves();
+
+        Ok(())
+    }
+
+    /// keeps the feed `ask_about_feed_moves` asked about where it is, then asks about the next one
+    pub fn decline_feed_move(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        if let Some(feed_id) = self.feed_to_move.take() {
+            crate::rss::decline_feed_move(&self.conn, feed_id)?;
+            self.update_feeds()?;
+        }
+
+        self.ask_about_feed_moves();
+
+        Ok(())
+    }
+
+    pub fn start_editing_feed_tags(&mut self) {
+        if let Some(feed) = &self.current_feed {
+            self.feed_tags_input = self
+                .feed_tags
+                .get(&feed.id)
+                .map(|tags| tags.join(", "))
+                .unwrap_or_default();
+            self.mode = Mode::EditingTags;
+        }
+    }
+
+    pub fn pop_feed_tags_input(&mut self) {
+        self.feed_tags_input.pop();
+    }
+
+    pub fn save_feed_tags(&mut self) -> Result<()> {
+        let feed_id = match &self.current_feed {
+            Some(feed) => feed.id,
+            None => return Ok(()),
+        };
+
+        let tags = feed_groups::parse_tags(&self.feed_tags_input);
+        crate::rss::set_feed_tags(&self.conn, feed_id, &tags)?;
+        self.feed_tags = crate::rss::get_feed_tags(&self.conn)?;
+        self.mode = Mode::Normal;
+
+        match self.feeds.state.selected() {
+            Some(feed_idx) => self.select_feed(feed_idx),
+            None => {
+                self.update_feed_rows();
+                Ok(())
+            }
+        }
+    }
+
+    /// a paused feed is only refreshed when it is refreshed by itself
+    pub fn toggle_paused(&mut self) -> Result<()> {
+        let feed = self
+            .current_feed
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Select a feed to pause it"))?;
+        let paused = !feed.paused;
+        let feed_title = feed.title.clone().unwrap_or_else(|| "the feed".to_owned());
+
+        crate::rss::set_feed_paused(&self.conn, feed.id, paused)?;
+        self.update_feeds()?;
+        self.update_current_feed()?;
+
+        self.flash = Some(if paused {
+            format!("Paused {}", feed_title)
+        } else {
+            format!("Unpaused {}", feed_title)
+        });
+
+        Ok(())
+    }
+
+    pub fn start_editing_feed_url(&mut self) {
+        if let Some(feed) = &self.current_feed {
+            self.feed_url_input = feed.feed_link.clone().unwrap_or_default();
+            self.mode = Mode::EditingFeedUrl;
+        }
+    }
+
+    pub fn pop_feed_url_input(&mut self) {
+        self.feed_url_input.pop();
+    }
+
+    pub fn start_piping_entry(&mut self) -> Result<()> {
+        if self.focused_entry_meta().is_none() {
+            return Err(anyhow::anyhow!("There is no entry to pipe"));
+        }
+
+        if self.pipe_command_input.is_empty() {
+            self.pipe_command_input = self.config.pipe_command.clone();
+        }
+        self.mode = Mode::PipeCommand;
+
+        Ok(())
+    }
+
+    /// the selected entry, or the one being read
+    fn focused_entry_meta(&self) -> Option<&crate::rss::EntryMeta> {
+        match &self.selected {
+            Selected::Feeds => None,
+            Selected::Entries => self.current_entry_meta.as_ref(),
+            Selected::Entry(entry_meta) => Some(entry_meta),
+        }
+    }
+
+    /// saves the entry into `save_directory` as `save_format`
+    fn save_entry(&mut self) -> Result<()> {
+        let entry_meta = self
+            .focused_entry_meta()
+            .ok_or_else(|| anyhow::anyhow!("There is no entry to save"))?;
+        let entry = crate::rss::get_entry_content(&self.conn, entry_meta.id)?;
+
+        let feed_title = self
+            .feeds
+            .items
+            .iter()
+            .find(|feed| feed.id == entry_meta.feed_id)
+            .and_then(|feed| feed.title.as_deref());
+
+        let exported_entry = crate::export::ExportedEntry {
+            meta: entry_meta,
+            feed_title,
+            html: entry.html(),
+            note: self.entry_notes.get(&entry_meta.id).map(String::as_str),
+        };
+
+        let path = exported_entry.save(&self.config.save_directory, self.config.save_format)?;
+        self.flash = Some(format!("Saved to {}", path.display()));
+
+        Ok(())
+    }
+
+    pub fn start_editing_entry_note(&mut self) -> Result<()> {
+        let entry_id = self
+            .focused_entry_meta()
+            .ok_or_else(|| anyhow::anyhow!("There is no entry to write a note about"))?
+            .id;
+
+        self.entry_note_input = self.entry_notes.get(&entry_id).cloned().unwrap_or_default();
+        self.mode = Mode::EditingNote;
+
+        Ok(())
+    }
+
+    pub fn pop_entry_note_input(&mut self) {
+        self.entry_note_input.pop();
+    }
+
+    /// a blank note removes the entry's note
+    pub fn save_entry_note(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        let entry_id = self
+            .focused_entry_meta()
+            .ok_or_else(|| anyhow::anyhow!("There is no entry to write a note about"))?
+            .id;
+
+        crate::rss::set_entry_note(&self.conn, entry_id, &self.entry_note_input)?;
+        self.entry_notes = crate::rss::get_entry_notes(&self.conn)?;
+
+        Ok(())
+    }
+
+    pub fn pop_pipe_command_input(&mut self) {
+        self.pipe_command_input.pop();
+    }
+
+    pub fn toggle_pipe_html(&mut self) {
+        self.pipe_html = !self.pipe_html;
+    }
+
+    /// the command to pipe to, and what to pipe to it:
+    /// the entry's title, link, and text, or its HTML as it is
+    pub fn take_pipe_command(&mut self) -> Result<(String, String)> {
+        self.mode = Mode::Normal;
+
+        let entry_meta = self
+            .focused_entry_meta()
+            .ok_or_else(|| anyhow::anyhow!("There is no entry to pipe"))?;
+        let entry = crate::rss::get_entry_content(&self.conn, entry_meta.id)?;
+        let html = entry.html();
+
+        let input = if self.pipe_html {
+            html.to_owned()
+        } else {
+            let base_url = entry.base_url.as_deref().or(entry_meta.link.as_deref());
+            let rendered =
+                crate::html::render(html, base_url, PIPED_TEXT_WIDTH, &self.config.theme, None);
+
+            let mut text = String::new();
+            if let Some(title) = &entry_meta.title {
+                text.push_str(title);
+                text.push('\n');
+            }
+            if let Some(link) = &entry_meta.link {
+                text.push_str(link);
+                text.push('\n');
+            }
+            text.push('\n');
+            text.push_str(&rendered.plain_text());
+            text.push('\n');
+            text
+        };
+
+        Ok((self.pipe_command_input.trim().to_owned(), input))
+    }
+
+    /// the feed keeps its entries and read state, and the caller refreshes it from the new URL
+    pub fn save_feed_url(&mut self) -> Result<crate::rss::FeedId> {
+        let feed_id = match &self.current_feed {
+            Some(feed) => feed.id,
+            None => return Err(anyhow::anyhow!("No feed selected")),
+        };
+
+        crate::rss::change_feed_url(&self.conn, feed_id, &self.feed_url_input)?;
+        self.mode = Mode::Normal;
+        self.update_current_feed()?;
+        self.flash = Some(format!(
+            "Changed the URL, refreshing from {}",
+            self.feed_url_input.trim()
+        ));
+
+        Ok(feed_id)
+    }
+
+    pub fn update_current_feed_and_entries(&mut self) -> Result<()> {
+        self.clear_marked_entries();
+        self.update_current_feed()?;
+        self.update_current_entries()?;
+        Ok(())
+    }
+
+    fn update_current_feed(&mut self) -> Result<()> {
+        let current_feed = if self.feeds.items.is_empty() || self.current_virtual_feed.is_some() {
+            None
+        } else {
+            let selected_idx = match self.feeds.state.selected() {
+                Some(idx) => idx,
+                None => {
+                    self.feeds.reset();
+                    0
+                }
+            };
+            let feed_id = self.feeds.items[selected_idx].id;
+            Some(crate::rss::get_feed(&self.conn, feed_id)?)
+        };
+
+        self.current_feed = current_feed;
+
+        Ok(())
+    }
+
+    fn update_current_entries(&mut self) -> Result<()> {
+        let entry_filter = EntryFilter::parse(&self.entry_filter);
+
+        let entries = if let Some(VirtualFeed::Smart(idx)) = self.current_virtual_feed {
+            match self.config.smart_feeds.get(idx) {
+                Some(smart_feed) => crate::rss::get_smart_feed_entries_metas(
+                    &self.conn,
+                    &self.read_mode,
+                    self.virtual_feed_entry_sort,
+                    &self.date_range,
+                    &smart_feed.query,
+                )?,
+                None => vec![],
+            }
+        } else if let Some(virtual_feed) = self.current_virtual_feed {
+            crate::rss::get_virtual_feed_entries_metas(
+                &self.conn,
+                &self.read_mode,
+                self.virtual_feed_entry_sort,
+                &self.date_range,
+                virtual_feed,
+                self.config.collapse_duplicate_entries
+                    && virtual_feed == crate::rss::VirtualFeed::AllEntries,
+            )?
+        } else if let Some(feed) = &self.current_feed {
+            crate::rss::get_entries_metas(
+                &self.conn,
+                &self.read_mode,
+                feed.entry_sort,
+                &self.date_range,
+                feed.id,
+            )?
+        } else {
+            vec![]
+        };
+
+        let entries = entries
+            .into_iter()
+            .filter(|entry| entry_filter.matches(entry))
+            .collect::<Vec<_>>()
+            .into();
+
+        self.entries = entries;
+
+        if self.entry_selection_position < self.entries.items.len() {
+            self.entries
+                .state
+                .select(Some(self.entry_selection_position))
+        } else {
+            match self.entries.items.len().checked_sub(1) {
+                Some(n) => self.entries.state.select(Some(n)),
+                None => self.entries.reset(),
+            }
+        }
+        Ok(())
+    }
+
+    /// the rows of the entry list, with day headers between the entries if they are on
+    pub fn entry_rows(&self) -> Vec<EntryRow> {
+        if self.config.day_headers {
+            crate::days::entry_rows(
+                &self.entries.items,
+                self.entry_sort(),
+                chrono::Local::now(),
+                &self.config.day_format,
+            )
+        } else {
+            (0..self.entries.items.len()).map(EntryRow::Entry).collect()
+        }
+    }
+
+    /// `date` in the local timezone, in `date_format`
+    pub fn format_date(&self, date: chrono::DateTime<chrono::Utc>) -> String {
+        util::format_date(date, &chrono::Local, &self.config.date_format)
+    }
+
+    /// the virtual feed's name, which for a smart feed is the one in the config
+    pub fn virtual_feed_title(&self, virtual_feed: VirtualFeed) -> String {
+        match virtual_feed {
+            VirtualFeed::Smart(idx) => match self.config.smart_feeds.get(idx) {
+                Some(smart_feed) => smart_feed.name.clone(),
+                None => virtual_feed.to_string(),
+            },
+            _ => virtual_feed.to_string(),
+        }
+    }
+
+    /// `date`'s day in the local timezone, in `day_format`
+    pub fn format_day(&self, date: chrono::DateTime<chrono::Utc>) -> String {
+        util::format_date(date, &chrono::Local, &self.config.day_format)
+    }
+
+    pub fn entry_sort(&self) -> EntrySort {
+        match (self.current_virtual_feed, &self.current_feed) {
+            (Some(VirtualFeed::RecentlyRead), _) => EntrySort::default(),
+            (Some(_), _) => self.virtual_feed_entry_sort,
+            (None, Some(feed)) => feed.entry_sort,
+            (None, None) => EntrySort::default(),
+        }
+    }
+
+    /// switches the current feed to the next entry sort,
+    /// which is saved for real feeds and kept until quitting for virtual ones
+    pub fn cycle_entry_sort(&mut self) -> Result<()> {
+        let entry_sort = self.entry_sort().next();
+
+        if self.current_virtual_feed == Some(VirtualFeed::RecentlyRead) {
+            return Err(anyhow::anyhow!(
+                "Recently read entries are always sorted by when they were opened"
+            ));
+        } else if self.current_virtual_feed.is_some() {
+            self.virtual_feed_entry_sort = entry_sort;
+        } else if let Some(feed) = &self.current_feed {
+            crate::rss::set_feed_entry_sort(&self.conn, feed.id, entry_sort)?;
+        } else {
+            return Ok(());
+        }
+
+        self.entry_selection_position = 0;
+        self.update_current_feed_and_entries()?;
+        self.update_current_entry_meta()
+    }
+
+    /// the entries marked one by one or in the visual range, in the entry list's order
+    pub fn marked_entry_ids(&self) -> Vec<crate::rss::EntryId> {
+        let visual_range = self.visual_anchor.and_then(|anchor| {
+            let anchor_idx = self
+                .entries
+                .items
+                .iter()
+                .position(|entry| entry.id == anchor)?;
+            let selected_idx = self.entries.state.selected()?;
+            Some(anchor_idx.min(selected_idx)..=anchor_idx.max(selected_idx))
+        });
+
+        self.entries
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| {
+                self.marked_entries.contains(&entry.id)
+                    || visual_range
+                        .as_ref()
+                        .is_some_and(|visual_range| visual_range.contains(idx))
+            })
+            .map(|(_, entry)| entry.id)
+            .collect()
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(entry_meta) = &self.current_entry_meta {
+            if !self.marked_entries.remove(&entry_meta.id) {
+                self.marked_entries.insert(entry_meta.id);
+            }
+        }
+    }
+
+    /// ending a visual range keeps its entries marked, so that ranges can be combined
+    pub fn toggle_visual_mode(&mut self) {
+        if self.visual_anchor.is_some() {
+            self.marked_entries.extend(self.marked_entry_ids());
+            self.visual_anchor = None;
+        } else {
+            self.visual_anchor = self.current_entry_meta.as_ref().map(|entry| entry.id);
+        }
+    }
+
+    /// returns whether any entries were marked
+    pub fn clear_marked_entries(&mut self) -> bool {
+        let had_marked_entries = !self.marked_entries.is_empty() || self.visual_anchor.is_some();
+        self.marked_entries.clear();
+        self.visual_anchor = None;
+        had_marked_entries
+    }
+
+    /// marks the marked entries read, or unread if they all are read already
+    fn toggle_marked_entries_read(&mut self, entry_ids: &[crate::rss::EntryId]) -> Result<()> {
+        let is_read = self
+            .entries
+            .items
+            .iter()
+            .any(|entry| entry_ids.contains(&entry.id) && entry.read_at.is_none());
+
+        let entry_ids_with_duplicates = if is_read {
+            [entry_ids, &self.unread_duplicate_entry_ids(entry_ids)?].concat()
+        } else {
+            entry_ids.to_vec()
+        };
+
+        let read_or_unread = if is_read { "read" } else { "unread" };
+        self.save_entry_states(
+            format!("marking {} entries {}", entry_ids.len(), read_or_unread),
+            &entry_ids_with_duplicates,
+        )?;
+        crate::rss::set_entries_read(&self.conn, &entry_ids_with_duplicates, is_read)?;
+        self.after_bulk_action()?;
+        self.flash = Some(format!(
+            "Marked {} entries {}",
+            entry_ids.len(),
+            read_or_unread
+        ));
+
+        Ok(())
+    }
+
+    /// stars the marked entries, or unstars them if they all are starred already
+    fn toggle_marked_entries_starred(&mut self, entry_ids: &[crate::rss::EntryId]) -> Result<()> {
+        let is_starred = self
+            .entries
+            .items
+            .iter()
+            .any(|entry| entry_ids.contains(&entry.id) && !entry.starred);
+
+        let starred_or_unstarred = if is_starred { "starred" } else { "unstarred" };
+        self.save_entry_states(
+            format!(
+                "marking {} entries {}",
+                entry_ids.len(),
+                starred_or_unstarred
+            ),
+            entry_ids,
+        )?;
+        crate::rss::set_entries_starred(&self.conn, entry_ids, is_starred)?;
+        self.after_bulk_action()?;
+        self.flash = Some(format!(
+            "Marked {} entries {}",
+            entry_ids.len(),
+            starred_or_unstarred
+        ));
+
+        Ok(())
+    }
+
+    /// deletes the marked entries, or the selected or open one if none are marked
+    pub fn delete_entries(&mut self) -> Result<()> {
+        let entry_ids = match &self.selected {
+            Selected::Entry(entry_meta) => vec![entry_meta.id],
+            Selected::Entries => {
+                let marked_entry_ids = self.marked_entry_ids();
+                if marked_entry_ids.is_empty() {
+                    self.current_entry_meta
+                        .iter()
+                        .map(|entry_meta| entry_meta.id)
+                        .collect()
+                } else {
+                    marked_entry_ids
+                }
+            }
+            Selected::Feeds => vec![],
+        };
+
+        if entry_ids.is_empty() {
+            return Ok(());
+        }
+
+        let deleted_entries = crate::rss::delete_entries(&self.conn, &entry_ids)?;
+        let description = format!("deleting {} entries", deleted_entries.len());
+
+        self.leave_entry()?;
+        self.selected = Selected::Entries;
+        self.after_bulk_action()?;
+        self.update_feeds()?;
+        self.flash = Some(format!(
+            "Deleted {} entries, u to undo",
+            deleted_entries.len()
+        ));
+        self.push_undo(Undo {
+            description,
+            change: UndoChange::DeletedEntries(deleted_entries),
+        });
+
+        Ok(())
+    }
+
+    /// marks every entry in the entry list read, as it is filtered
+    pub fn mark_all_read(&mut self) -> Result<()> {
+        let entry_ids = self
+            .entries
+            .items
+            .iter()
+            .filter(|entry| entry.read_at.is_none())
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+
+        if entry_ids.is_empty() {
+            return Ok(());
+        }
+
+        let entry_ids_with_duplicates = [
+            &entry_ids[..],
+            &self.unread_duplicate_entry_ids(&entry_ids)?,
+        ]
+        .concat();
+
+        self.save_entry_states(
+            format!("marking {} entries read", entry_ids.len()),
+            &entry_ids_with_duplicates,
+        )?;
+        crate::rss::set_entries_read(&self.conn, &entry_ids_with_duplicates, true)?;
+
+        if let Selected::Entry(_) = self.selected {
+            self.leave_entry()?;
+            self.selected = Selected::Entries;
+        }
+        self.after_bulk_action()?;
+        self.update_feeds()?;
+        self.flash = Some(format!(
+            "Marked {} entries read, u to undo",
+            entry_ids.len()
+        ));
+
+        Ok(())
+    }
+
+    /// the unread duplicates of `entry_ids` in other feeds, to be marked read along with them,
+    /// if `mark_duplicate_entries_read` is on
+    fn unread_duplicate_entry_ids(
+        &self,
+        entry_ids: &[crate::rss::EntryId],
+    ) -> Result<Vec<crate::rss::EntryId>> {
+        if self.config.mark_duplicate_entries_read {
+            crate::rss::get_unread_duplicate_entry_ids(&self.conn, entry_ids)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// marks the entry read, along with its duplicates, or unread
+    fn toggle_entry_read(&mut self, entry_meta: &crate::rss::EntryMeta) -> Result<()> {
+        let duplicate_entry_ids = if entry_meta.read_at.is_none() {
+            self.unread_duplicate_entry_ids(&[entry_meta.id])?
+        } else {
+            vec![]
+        };
+
+        self.save_entry_states(
+            marking_description(entry_meta, read_or_unread(entry_meta)),
+            &[&[entry_meta.id][..], &duplicate_entry_ids].concat(),
+        )?;
+        entry_meta.toggle_read(&self.conn)?;
+        crate::rss::set_entries_read(&self.conn, &duplicate_entry_ids, true)
+    }
+
+    /// remembers the read and starred state of `entry_ids` before `description` changes them
+    fn save_entry_states(
+        &mut self,
+        description: String,
+        entry_ids: &[crate::rss::EntryId],
+    ) -> Result<()> {
+        let states = crate::rss::get_saved_entry_states(&self.conn, entry_ids)?;
+        self.push_undo(Undo {
+            description,
+            change: UndoChange::EntryStates(states),
+        });
+        Ok(())
+    }
+
+    fn push_undo(&mut self, undo: Undo) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(undo);
+    }
+
+    /// takes back the most recent change
+    pub fn undo(&mut self) -> Result<()> {
+        let undo = self
+            .undo_stack
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("There is nothing to undo"))?;
+
+        match &undo.change {
+            UndoChange::EntryStates(states) => {
+                crate::rss::restore_entry_states(&self.conn, states)?
+            }
+            UndoChange::DeletedEntries(deleted_entries) => {
+                crate::rss::restore_entries(&self.conn, deleted_entries)?
+            }
+            UndoChange::DeletedFeed(feed_id, deleted_feed) => {
+                crate::rss::restore_feed(&self.conn, deleted_feed)?;
+                self.update_feeds()?;
+
+                if let Some(feed_idx) = self.feeds.items.iter().position(|feed| feed.id == *feed_id)
+                {
+                    self.selected = Selected::Feeds;
+                    self.select_feed(feed_idx)?;
+                }
+            }
+        }
+
+        self.after_bulk_action()?;
+        if let Selected::Entry(entry) = &self.selected {
+            let entry = crate::rss::get_entry_meta(&self.conn, entry.id)?;
+            self.selected = Selected::Entry(Box::new(entry));
+        }
+        self.update_feeds()?;
+        self.flash = Some(format!("Undid {}", undo.description));
+
+        Ok(())
+    }
+
+    fn after_bulk_action(&mut self) -> Result<()> {
+        self.clear_marked_entries();
+        self.update_current_entries()?;
+        self.update_current_entry_meta()?;
+        self.update_entry_selection_position();
+        Ok(())
+    }
+
+    fn update_entry_selection_position(&mut self) {
+        if self.entries.items.is_empty() {
+            self.entry_selection_position = 0
+        } else if self.entry_selection_position > self.entries.items.len() - 1 {
+            self.entry_selection_position = self.entries.items.len() - 1
+        };
+    }
+
+    fn get_selected_entry_meta(&self) -> Option<Result<crate::rss::EntryMeta>> {
+        self.entries.state.selected().and_then(|selected_idx| {
+            self.entries
+                .items
+                .get(selected_idx)
+                .map(|item| item.id)
+                .map(|entry_id| crate::rss::get_entry_meta(&self.conn, entry_id))
+        })
+    }
+
+    fn update_current_entry_meta(&mut self) -> Result<()> {
+        if let Some(entry_meta) = self.get_selected_entry_meta() {
+            let entry_meta = entry_meta?;
+            self.current_entry_meta = Some(entry_meta);
+        }
+        Ok(())
     }
 
     fn page_up(&mut self) {
         if matches!(self.selected, Selected::Entry(_)) {
-            self.entry_scroll_position = if let Some(position) = self
+            self.entry_scroll_position = self
                 .entry_scroll_position
-                .checked_sub(self.entry_lines_rendered_len)
+                .saturating_sub(self.entry_lines_rendered_len);
+        };
+    }
+
+    fn page_down(&mut self) {
+        if matches!(self.selected, Selected::Entry(_)) {
+            self.entry_scroll_position = if self.entry_scroll_position
+                + self.entry_lines_rendered_len
+                >= self.entry_lines_len as u16
             {
-                position
+                self.entry_lines_len as u16
             } else {
+                self.entry_scroll_position + self.entry_lines_rendered_len
+            };
+        }
+    }
+
+    /// remembers how far the entry being read was scrolled, to pick up there next time,
+    /// and scrolls back to the top for the next entry. an entry read to the end starts over
+    pub fn leave_entry(&mut self) -> Result<()> {
+        if let Selected::Entry(entry_meta) = &self.selected {
+            let read_to_the_end = self.entry_scroll_position as usize
+                + self.entry_lines_rendered_len as usize
+                >= self.entry_lines_len;
+            let scroll_position = if read_to_the_end {
                 0
+            } else {
+                self.entry_scroll_position
             };
-        };
+
+            crate::rss::set_entry_scroll_position(&self.conn, entry_meta.id, scroll_position)?;
+        }
+
+        self.entry_scroll_position = 0;
+        self.previewed_entry = None;
+
+        Ok(())
+    }
+
+    /// the entry in the reading pane: the one being read,
+    /// or in the three-pane layout, the selected one
+    pub fn reading_entry(&self) -> Option<&crate::rss::EntryMeta> {
+        match &self.selected {
+            Selected::Entry(entry_meta) => Some(entry_meta),
+            _ => self.previewed_entry.as_ref(),
+        }
     }
 
-    fn page_down(&mut self) {
-        if matches!(self.selected, Selected::Entry(_)) {
-            self.entry_scroll_position = if self.entry_scroll_position
-                + self.entry_lines_rendered_len
-                >= self.entry_lines_len as u16
-            {
-                self.entry_lines_len as u16
-            } else {
-                self.entry_scroll_position + self.entry_lines_rendered_len
-            };
+    /// in the three-pane layout, renders the selected entry into the reading pane
+    /// when the selection moves to it, without opening it
+    fn update_preview(&mut self) -> Result<()> {
+        if self.config.layout != PaneLayout::ThreePane
+            || matches!(self.selected, Selected::Entry(_))
+        {
+            return Ok(());
+        }
+
+        let selected_entry = self
+            .entries
+            .state
+            .selected()
+            .and_then(|idx| self.entries.items.get(idx))
+            .cloned();
+
+        if selected_entry.as_ref().map(|entry_meta| entry_meta.id)
+            == self
+                .previewed_entry
+                .as_ref()
+                .map(|entry_meta| entry_meta.id)
+        {
+            return Ok(());
+        }
+
+        self.previewed_entry = selected_entry.clone();
+        self.entry_scroll_position = 0;
+
+        match selected_entry {
+            Some(entry_meta) => self.render_entry(&entry_meta),
+            None => {
+                self.rendered_entry = crate::html::Rendered::default();
+                Ok(())
+            }
         }
     }
 
@@ -361,42 +2117,24 @@ pub fn on_enter(&mut self) -> Result<()> {
         match self.selected {
             Selected::Entries | Selected::Entry(_) => {
                 if !self.entries.items.is_empty() {
-                    if let Some(entry_meta) = &self.current_entry_meta {
-                        if let Some(entry) = self.get_selected_entry() {
-                            let entry = entry?;
-                            let empty_string =
-                                String::from("No content or description tag provided.");
-
-                            // try content tag first,
-                            // if there is not content tag,
-                            // go to description tag,
-                            // if no description tag,
-                            // use empty string.
-                            // TODO figure out what to actually do if there are neither
-                            let entry_html = entry
-                                .content
-                                .as_ref()
-                                .or_else(|| entry.description.as_ref())
-                                .or(Some(&empty_string));
-
-                            // minimum is 1
-                            let line_length = if self.entry_column_width >= 5 {
-                                self.entry_column_width - 4
-                            } else {
-                                1
-                            };
-
-                            if let Some(html) = entry_html {
-                                let text =
-                                    html2text::from_read(html.as_bytes(), line_length.into());
-                                self.entry_lines_len = text.matches('\n').count();
-                                self.current_entry_text = text;
-                            } else {
-                                self.current_entry_text = String::new();
-                            }
+                    if let Some(entry_meta) = self.current_entry_meta.clone() {
+                        self.render_entry(&entry_meta)?;
+
+                        // an entry that is opened, rather than drawn again, picks up where it was left
+                        if !matches!(&self.selected, Selected::Entry(reading) if reading.id == entry_meta.id)
+                        {
+                            let scroll_position =
+                                crate::rss::get_entry_scroll_position(&self.conn, entry_meta.id)?;
+                            self.entry_scroll_position = scroll_position
+                                .min((self.entry_lines_len as u16).saturating_sub(1));
+                            crate::rss::set_entry_opened_at(
+                                &self.conn,
+                                entry_meta.id,
+                                chrono::Utc::now(),
+                            )?;
                         }
 
-                        self.selected = Selected::Entry(entry_meta.clone());
+                        self.selected = Selected::Entry(Box::new(entry_meta));
                     }
                 }
 
@@ -406,15 +2144,656 @@ pub fn on_enter(&mut self) -> Result<()> {
         }
     }
 
+    /// renders the entry, or its full article if it was fetched, to fit the reading pane
+    fn render_entry(&mut self, entry_meta: &crate::rss::EntryMeta) -> Result<()> {
+        let entry = crate::rss::get_entry_content(&self.conn, entry_meta.id)?;
+        let empty_string = String::from("No content or description tag provided.");
+
+        // try content tag first,
+        // if there is not content tag,
+        // go to description tag,
+        // if no description tag,
+        // use empty string.
+        // TODO figure out what to actually do if there are neither
+        let entry_html = entry
+            .full_content
+            .as_ref()
+            .or(entry.content.as_ref())
+            .or(entry.description.as_ref())
+            .or(Some(&empty_string));
+
+        // minimum is 1
+        let line_length = if self.entry_column_width >= 5 {
+            self.entry_column_width - 4
+        } else {
+            1
+        };
+
+        if let Some(html) = entry_html {
+            let inline_images = self
+                .config
+                .inline_images
+                .map(|_| crate::html::InlineImages {
+                    cache: &self.image_cache,
+                });
+            // entries from before base URLs were kept are relative to their link
+            let base_url = entry.base_url.as_deref().or(entry_meta.link.as_deref());
+            let mut rendered = crate::html::render(
+                html,
+                base_url,
+                line_length.into(),
+                &self.config.theme,
+                inline_images.as_ref(),
+            );
+            let feed_links = self
+                .feeds
+                .items
+                .iter()
+                .find(|feed| feed.id == entry_meta.feed_id)
+                .map(|feed| feed.feed_links())
+                .unwrap_or_default();
+            rendered.text = self.config.highlight_keywords.highlight(
+                &feed_links,
+                rendered.text,
+                self.config.theme.highlight_keyword_style(),
+            );
+            let images_to_fetch = self.image_cache.start_fetching(&rendered.image_urls);
+            self.images_to_fetch.extend(images_to_fetch);
+            self.entry_lines_len = rendered.text.lines.len();
+            self.rendered_entry = rendered;
+        } else {
+            self.rendered_entry = crate::html::Rendered::default();
+        }
+
+        Ok(())
+    }
+
+    /// the selected entry, and the link of the web page its full article is on
+    pub fn current_article_link(&self) -> Option<(crate::rss::EntryId, String)> {
+        let entry_meta = match &self.selected {
+            Selected::Feeds => return None,
+            Selected::Entries => self.current_entry_meta.as_ref()?,
+            Selected::Entry(entry_meta) => entry_meta,
+        };
+
+        Some((entry_meta.id, entry_meta.link.clone()?))
+    }
+
+    fn on_article_fetched(
+        &mut self,
+        entry_id: crate::rss::EntryId,
+        full_content: String,
+    ) -> Result<()> {
+        let word_count = crate::rss::set_entry_full_content(&self.conn, entry_id, &full_content)?;
+
+        for entry_meta in self
+            .entries
+            .items
+            .iter_mut()
+            .chain(self.current_entry_meta.as_mut())
+            .filter(|entry_meta| entry_meta.id == entry_id)
+        {
+            entry_meta.word_count = word_count;
+        }
+
+        if matches!(&self.selected, Selected::Entry(entry_meta) if entry_meta.id == entry_id) {
+            self.entry_scroll_position = 0;
+            self.on_enter()?;
+        } else if matches!(&self.previewed_entry, Some(entry_meta) if entry_meta.id == entry_id) {
+            self.previewed_entry = None;
+        }
+
+        Ok(())
+    }
+
+    pub fn take_images_to_fetch(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.images_to_fetch)
+    }
+
+    fn on_image_fetched(&mut self, url: String, png: Option<Vec<u8>>) -> Result<()> {
+        self.image_cache.insert(url.clone(), png);
+
+        // the entry is rendered again, with room for the image
+        if self.rendered_entry.image_urls.contains(&url) {
+            if matches!(self.selected, Selected::Entry(_)) {
+                self.on_enter()?;
+            } else {
+                self.previewed_entry = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// after something else has had the screen, images are drawn again
+    pub fn forget_drawn_images(&mut self) {
+        self.drawn_images.clear();
+    }
+
+    /// shows every key, as the keymap has them
     pub fn toggle_help(&mut self) -> Result<()> {
-        self.show_help = !self.show_help;
+        self.popup_scroll = 0;
+        self.mode = Mode::Help;
         Ok(())
     }
 
+    /// newest first
+    pub fn show_error_log(&mut self) {
+        self.popup_scroll = 0;
+        self.mode = Mode::ErrorLog;
+    }
+
+    /// popups are drawn no further down than their last line, however far they are scrolled
+    pub fn scroll_popup_down(&mut self, lines: u16) {
+        self.popup_scroll = self.popup_scroll.saturating_add(lines);
+    }
+
+    pub fn scroll_popup_up(&mut self, lines: u16) {
+        self.popup_scroll = self.popup_scroll.saturating_sub(lines);
+    }
+
     pub fn clear_error_flash(&mut self) {
         self.error_flash = vec![];
     }
 
+    /// flashes the error until the next key, and keeps it in the error log
+    pub fn push_error(&mut self, e: anyhow::Error) {
+        self.error_log.push(&e, None, chrono::Utc::now());
+        self.error_flash.push(e);
+    }
+
+    /// like `push_error`, with the feed the error happened to in the error log
+    pub fn push_feed_error(&mut self, feed_id: crate::rss::FeedId, e: anyhow::Error) {
+        let feed = self
+            .feeds
+            .items
+            .iter()
+            .find(|feed| feed.id == feed_id)
+            .and_then(|feed| feed.title.clone().or_else(|| feed.feed_link.clone()));
+        self.error_log.push(&e, feed, chrono::Utc::now());
+        self.error_flash.push(e);
+    }
+
+    fn update_search_results(&mut self) -> Result<()> {
+        self.search_results = crate::rss::search_entries(&self.conn, &self.search_input)?.into();
+
+        if !self.search_results.items.is_empty() {
+            self.search_results.reset();
+        }
+
+        Ok(())
+    }
+
+    pub fn pop_search_input(&mut self) -> Result<()> {
+        self.search_input.pop();
+        self.update_search_results()
+    }
+
+    pub fn on_search_result_down(&mut self) {
+        if !self.search_results.items.is_empty() {
+            self.search_results.next();
+        }
+    }
+
+    pub fn on_search_result_up(&mut self) {
+        if !self.search_results.items.is_empty() {
+            self.search_results.previous();
+        }
+    }
+
+    /// selects the search result's feed and entry, and opens the entry
+    pub fn open_selected_search_result(&mut self) -> Result<()> {
+        let entry_meta = match self
+            .search_results
+            .state
+            .selected()
+            .and_then(|idx| self.search_results.items.get(idx))
+        {
+            Some(entry_meta) => entry_meta.clone(),
+            None => return Ok(()),
+        };
+
+        self.open_entry(entry_meta)
+    }
+
+    /// asks for a name to save the search under, the search itself to start with
+    pub fn start_naming_search(&mut self) -> Result<()> {
+        if self.search_input.trim().is_empty() {
+            return Err(anyhow::anyhow!("There is no search to save"));
+        }
+
+        self.saved_search_name_input = self.search_input.trim().to_owned();
+        self.mode = Mode::NamingSearch;
+
+        Ok(())
+    }
+
+    pub fn pop_saved_search_name_input(&mut self) {
+        self.saved_search_name_input.pop();
+    }
+
+    /// saves the search under the name typed, and goes back to it
+    pub fn save_search(&mut self) -> Result<()> {
+        self.mode = Mode::Search;
+
+        crate::rss::save_search(
+            &self.conn,
+            &self.saved_search_name_input,
+            &self.search_input,
+        )?;
+        self.flash = Some(format!(
+            "Saved the search as {}",
+            self.saved_search_name_input.trim()
+        ));
+
+        Ok(())
+    }
+
+    pub fn start_saved_searches(&mut self) -> Result<()> {
+        self.saved_searches = crate::rss::get_saved_searches(&self.conn)?.into();
+
+        if !self.saved_searches.items.is_empty() {
+            self.saved_searches.reset();
+        }
+
+        self.mode = Mode::SavedSearches;
+
+        Ok(())
+    }
+
+    pub fn on_saved_search_down(&mut self) {
+        if !self.saved_searches.items.is_empty() {
+            self.saved_searches.next();
+        }
+    }
+
+    pub fn on_saved_search_up(&mut self) {
+        if !self.saved_searches.items.is_empty() {
+            self.saved_searches.previous();
+        }
+    }
+
+    /// searches for the selected saved search's query, as if it was typed
+    pub fn run_selected_saved_search(&mut self) -> Result<()> {
+        let query = match self
+            .saved_searches
+            .state
+            .selected()
+            .and_then(|idx| self.saved_searches.items.get(idx))
+        {
+            Some(saved_search) => saved_search.query.clone(),
+            None => return Ok(()),
+        };
+
+        self.search_input = query;
+        self.mode = Mode::Search;
+        self.update_search_results()
+    }
+
+    /// deletes the selected saved search, keeping the one after it selected
+    pub fn delete_selected_saved_search(&mut self) -> Result<()> {
+        let idx = match self.saved_searches.state.selected() {
+            Some(idx) if idx < self.saved_searches.items.len() => idx,
+            _ => return Ok(()),
+        };
+
+        crate::rss::delete_saved_search(&self.conn, &self.saved_searches.items[idx].name)?;
+
+        self.saved_searches.items.remove(idx);
+        let selected = if self.saved_searches.items.is_empty() {
+            None
+        } else {
+            Some(idx.min(self.saved_searches.items.len() - 1))
+        };
+        self.saved_searches.state.select(selected);
+
+        Ok(())
+    }
+
+    /// opens an unread entry from any feed in the date range, other than the one being read
+    pub fn open_random_unread_entry(&mut self) -> Result<()> {
+        let reading = match &self.selected {
+            Selected::Entry(entry_meta) => Some(entry_meta.id),
+            _ => None,
+        };
+
+        let entry_id =
+            crate::rss::get_random_unread_entry_id(&self.conn, &self.date_range, reading)?
+                .ok_or_else(|| anyhow::anyhow!("There are no unread entries"))?;
+        let entry_meta = crate::rss::get_entry_meta(&self.conn, entry_id)?;
+
+        self.open_entry(entry_meta)
+    }
+
+    /// selects the entry's feed and the entry, and opens the entry
+    fn open_entry(&mut self, entry_meta: crate::rss::EntryMeta) -> Result<()> {
+        let feed_idx = self
+            .feeds
+            .items
+            .iter()
+            .position(|feed| feed.id == entry_meta.feed_id)
+            .ok_or_else(|| anyhow::anyhow!("Unable to find the feed for this entry"))?;
+
+        self.select_feed(feed_idx)?;
+
+        let entry_position = |entries: &util::StatefulList<crate::rss::EntryMeta>| {
+            entries
+                .items
+                .iter()
+                .position(|entry| entry.id == entry_meta.id)
+        };
+
+        // the entry might be hidden by the current read mode
+        let entry_idx = match entry_position(&self.entries) {
+            Some(entry_idx) => entry_idx,
+            None => {
+                self.read_mode = ReadMode::All;
+                self.update_current_entries()?;
+                entry_position(&self.entries)
+                    .ok_or_else(|| anyhow::anyhow!("Unable to find entry {}", entry_meta.id))?
+            }
+        };
+
+        self.leave_entry()?;
+        self.entries.state.select(Some(entry_idx));
+        self.entry_selection_position = entry_idx;
+        self.current_entry_meta = Some(entry_meta);
+        self.selected = Selected::Entries;
+        self.mode = Mode::Normal;
+
+        self.on_enter()
+    }
+
+    pub fn pop_entry_filter(&mut self) -> Result<()> {
+        self.entry_filter.pop();
+        self.reset_entries_for_filter()
+    }
+
+    pub fn clear_entry_filter(&mut self) -> Result<()> {
+        self.entry_filter.clear();
+        self.mode = Mode::Normal;
+        self.reset_entries_for_filter()
+    }
+
+    fn reset_entries_for_filter(&mut self) -> Result<()> {
+        self.entry_selection_position = 0;
+        self.reset_entries_for_read_mode()
+    }
+
+    pub fn pop_date_range_input(&mut self) {
+        self.date_range_input.pop();
+    }
+
+    /// narrows the entries to the typed date range, or to no range if nothing is typed
+    pub fn apply_date_range(&mut self) -> Result<()> {
+        self.date_range = if self.date_range_input.trim().is_empty() {
+            DateRange::default()
+        } else {
+            DateRange::parse(&self.date_range_input, chrono::Local::now())?
+        };
+
+        self.mode = Mode::Normal;
+        self.reset_entries_for_filter()
+    }
+
+    pub fn clear_date_range(&mut self) -> Result<()> {
+        self.date_range_input.clear();
+        self.apply_date_range()
+    }
+
+    /// keeps the filter and moves to the filtered entries
+    pub fn select_entries(&mut self) -> Result<()> {
+        self.mode = Mode::Normal;
+
+        if !self.entries.items.is_empty() {
+            self.selected = Selected::Entries;
+            self.update_current_entry_meta()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn start_feed_switcher(&mut self) {
+        self.feed_switcher_input.clear();
+        self.update_feed_switcher_results();
+        self.mode = Mode::FeedSwitcher;
+    }
+
+    fn update_feed_switcher_results(&mut self) {
+        let mut scored = self
+            .feeds
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, feed)| {
+                let title = feed.title.as_deref().unwrap_or_default();
+                util::fuzzy_score(&self.feed_switcher_input, title).map(|score| (score, idx))
+            })
+            .collect::<Vec<_>>();
+
+        // stable, so equally good matches keep the feed list's order
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        self.feed_switcher_results = scored
+            .into_iter()
+            .map(|(_, idx)| idx)
+            .collect::<Vec<_>>()
+            .into();
+
+        if !self.feed_switcher_results.items.is_empty() {
+            self.feed_switcher_results.reset();
+        }
+    }
+
+    pub fn pop_feed_switcher_input(&mut self) {
+        self.feed_switcher_input.pop();
+        self.update_feed_switcher_results();
+    }
+
+    pub fn on_feed_switcher_down(&mut self) {
+        if !self.feed_switcher_results.items.is_empty() {
+            self.feed_switcher_results.next();
+        }
+    }
+
+    pub fn on_feed_switcher_up(&mut self) {
+        if !self.feed_switcher_results.items.is_empty() {
+            self.feed_switcher_results.previous();
+        }
+    }
+
+    pub fn start_feed_health(&mut self) {
+        let mut failing_feeds = self
+            .feeds
+            .items
+            .iter()
+            .filter(|feed| feed.is_failing())
+            .collect::<Vec<_>>();
+
+        // stable, so feeds that failed as often keep the feed list's order
+        failing_feeds.sort_by(|a, b| {
+            b.health
+                .consecutive_failures
+                .cmp(&a.health.consecutive_failures)
+        });
+
+        self.feed_health_results = failing_feeds
+            .into_iter()
+            .map(|feed| feed.id)
+            .collect::<Vec<_>>()
+            .into();
+
+        if !self.feed_health_results.items.is_empty() {
+            self.feed_health_results.reset();
+        }
+
+        self.mode = Mode::FeedHealth;
+    }
+
+    pub fn show_feed_stats(&mut self) -> Result<()> {
+        let feed_id = self
+            .current_feed
+            .as_ref()
+            .map(|feed| feed.id)
+            .ok_or_else(|| anyhow::anyhow!("Select a feed to see its statistics"))?;
+
+        self.feed_stats = Some(crate::rss::get_feed_stats(
+            &self.conn,
+            feed_id,
+            chrono::Utc::now(),
+        )?);
+        self.mode = Mode::FeedStats;
+
+        Ok(())
+    }
+
+    pub fn show_raw_feed(&mut self) -> Result<()> {
+        let feed_id = self
+            .current_feed
+            .as_ref()
+            .map(|feed| feed.id)
+            .ok_or_else(|| anyhow::anyhow!("Select a feed to see it as it was last fetched"))?;
+
+        self.raw_feed = Some(
+            crate::rss::get_raw_feed(&self.conn, feed_id)?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Russ has not kept this feed since it last fetched it. Refresh it first"
+                )
+            })?,
+        );
+        self.popup_scroll = 0;
+        self.mode = Mode::RawFeed;
+
+        Ok(())
+    }
+
+    pub fn on_feed_health_down(&mut self) {
+        if !self.feed_health_results.items.is_empty() {
+            self.feed_health_results.next();
+        }
+    }
+
+    pub fn on_feed_health_up(&mut self) {
+        if !self.feed_health_results.items.is_empty() {
+            self.feed_health_results.previous();
+        }
+    }
+
+    pub fn start_command_palette(&mut self) {
+        self.command_palette_input.clear();
+        self.update_command_palette_results();
+        self.mode = Mode::CommandPalette;
+    }
+
+    /// the actions that can be run on what is selected, matched by what they do
+    fn update_command_palette_results(&mut self) {
+        let pane = Pane::from(&self.selected);
+
+        let mut scored = Action::ALL
+            .iter()
+            .filter(|action| **action != Action::CommandPalette && action.applies_to(pane))
+            .filter_map(|action| {
+                util::fuzzy_score(&self.command_palette_input, action.description())
+                    .map(|score| (score, *action))
+            })
+            .collect::<Vec<_>>();
+
+        // stable, so equally good matches keep the help's order
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        self.command_palette_results = scored
+            .into_iter()
+            .map(|(_, action)| action)
+            .collect::<Vec<_>>()
+            .into();
+
+        if !self.command_palette_results.items.is_empty() {
+            self.command_palette_results.reset();
+        }
+    }
+
+    pub fn pop_command_palette_input(&mut self) {
+        self.command_palette_input.pop();
+        self.update_command_palette_results();
+    }
+
+    pub fn on_command_palette_down(&mut self) {
+        if !self.command_palette_results.items.is_empty() {
+            self.command_palette_results.next();
+        }
+    }
+
+    pub fn on_command_palette_up(&mut self) {
+        if !self.command_palette_results.items.is_empty() {
+            self.command_palette_results.previous();
+        }
+    }
+
+    /// closes the palette, leaving the chosen action for the main loop to run
+    pub fn choose_command(&mut self) -> Result<()> {
+        let action = self
+            .command_palette_results
+            .state
+            .selected()
+            .and_then(|idx| self.command_palette_results.items.get(idx))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("No command matches {}", self.command_palette_input))?;
+
+        self.mode = Mode::Normal;
+        self.chosen_command = Some(action);
+
+        Ok(())
+    }
+
+    pub fn take_chosen_command(&mut self) -> Option<Action> {
+        self.chosen_command.take()
+    }
+
+    /// jumps the feed selection to the chosen feed
+    pub fn select_feed_switcher_feed(&mut self) -> Result<()> {
+        let feed_idx = match self
+            .feed_switcher_results
+            .state
+            .selected()
+            .and_then(|idx| self.feed_switcher_results.items.get(idx))
+        {
+            Some(feed_idx) => *feed_idx,
+            None => return Ok(()),
+        };
+
+        self.go_to_feed(feed_idx)
+    }
+
+    pub fn select_feed_health_feed(&mut self) -> Result<()> {
+        let feed_id = match self
+            .feed_health_results
+            .state
+            .selected()
+            .and_then(|idx| self.feed_health_results.items.get(idx))
+        {
+            Some(feed_id) => *feed_id,
+            None => return Ok(()),
+        };
+
+        self.go_to_feed_id(feed_id)
+    }
+
+    pub fn go_to_feed_id(&mut self, feed_id: crate::rss::FeedId) -> Result<()> {
+        match self.feeds.items.iter().position(|feed| feed.id == feed_id) {
+            Some(feed_idx) => self.go_to_feed(feed_idx),
+            None => Ok(()),
+        }
+    }
+
+    fn go_to_feed(&mut self, feed_idx: usize) -> Result<()> {
+        self.mode = Mode::Normal;
+        self.entry_selection_position = 0;
+        self.leave_entry()?;
+        self.rendered_entry = crate::html::Rendered::default();
+        self.selected = Selected::Feeds;
+        self.select_feed(feed_idx)
+    }
+
     pub fn reset_feed_subscription_input(&mut self) {
         self.feed_subscription_input.clear();
     }
@@ -431,41 +2810,133 @@ pub fn error_flash_is_empty(&self) -> bool {
         self.error_flash.is_empty()
     }
 
-    pub fn clear_flash(&mut self) {
-        self.flash = None
+    pub fn clear_flash(&mut self) {
+        self.flash = None
+    }
+
+    pub fn select_feeds(&mut self) {
+        self.selected = Selected::Feeds;
+    }
+
+    pub fn selected_feed_id(&self) -> crate::rss::FeedId {
+        let selected_idx = self.feeds.state.selected().unwrap();
+        self.feeds.items[selected_idx].id
+    }
+
+    pub fn feed_ids(&self) -> Result<Vec<crate::rss::FeedId>> {
+        let ids = crate::rss::get_feed_ids(&self.conn)?;
+        Ok(ids)
+    }
+
+    pub fn toggle_read(&mut self) -> Result<()> {
+        let selected = self.selected.clone();
+        match selected {
+            Selected::Entry(entry) => {
+                self.toggle_entry_read(&entry)?;
+                self.leave_entry()?;
+                self.selected = Selected::Entries;
+                self.update_current_entries()?;
+                self.update_current_entry_meta()?;
+            }
+            Selected::Entries => {
+                let marked_entry_ids = self.marked_entry_ids();
+
+                if !marked_entry_ids.is_empty() {
+                    self.toggle_marked_entries_read(&marked_entry_ids)?;
+                } else if let Some(entry_meta) = self.current_entry_meta.clone() {
+                    self.toggle_entry_read(&entry_meta)?;
+                    self.update_current_entries()?;
+                    self.update_current_entry_meta()?;
+                    self.update_entry_selection_position();
+                }
+            }
+            Selected::Feeds => (),
+        }
+
+        // the counts changed, and with them maybe the order
+        self.update_feeds()
     }
 
-    pub fn select_feeds(&mut self) {
-        self.selected = Selected::Feeds;
+    pub fn move_selected_feed_up(&mut self) -> Result<()> {
+        self.move_selected_feed(true)
     }
 
-    pub fn selected(&self) -> Selected {
-        self.selected.clone()
+    pub fn move_selected_feed_down(&mut self) -> Result<()> {
+        self.move_selected_feed(false)
     }
 
-    pub fn selected_feed_id(&self) -> crate::rss::FeedId {
-        let selected_idx = self.feeds.state.selected().unwrap();
-        self.feeds.items[selected_idx].id
-    }
+    /// swaps the selected feed with the feed above or below it in the feed pane,
+    /// which keeps it within its group
+    fn move_selected_feed(&mut self, up: bool) -> Result<()> {
+        if self.config.feed_sort != FeedSort::Manual {
+            return Err(anyhow::anyhow!(
+                "Feeds can only be moved when feed_sort is \"manual\""
+            ));
+        }
 
-    pub fn feed_ids(&self) -> Result<Vec<crate::rss::FeedId>> {
-        let ids = crate::rss::get_feed_ids(&self.conn)?;
-        Ok(ids)
+        let row_idx = match self.feed_rows.state.selected() {
+            Some(row_idx) => row_idx,
+            None => return Ok(()),
+        };
+
+        let neighbor_row_idx = if up {
+            row_idx.checked_sub(1)
+        } else {
+            Some(row_idx + 1)
+        };
+
+        let rows = &self.feed_rows.items;
+        let (feed_idx, neighbor_feed_idx) = match (
+            rows.get(row_idx),
+            neighbor_row_idx.and_then(|idx| rows.get(idx)),
+        ) {
+            (
+                Some(FeedRow::Feed { feed_idx }),
+                Some(FeedRow::Feed {
+                    feed_idx: neighbor_feed_idx,
+                }),
+            ) => (*feed_idx, *neighbor_feed_idx),
+            _ => return Ok(()),
+        };
+
+        let mut feed_ids = self
+            .feeds
+            .items
+            .iter()
+            .map(|feed| feed.id)
+            .collect::<Vec<_>>();
+        feed_ids.swap(feed_idx, neighbor_feed_idx);
+
+        crate::rss::set_feed_positions(&self.conn, &feed_ids)?;
+
+        self.update_feeds()
     }
 
-    pub fn toggle_read(&mut self) -> Result<()> {
+    pub fn toggle_starred(&mut self) -> Result<()> {
         let selected = self.selected.clone();
         match selected {
             Selected::Entry(entry) => {
-                entry.toggle_read(&self.conn)?;
-                self.selected = Selected::Entries;
+                self.save_entry_states(
+                    marking_description(&entry, starred_or_unstarred(&entry)),
+                    &[entry.id],
+                )?;
+                entry.toggle_starred(&self.conn)?;
                 self.update_current_entries()?;
                 self.update_current_entry_meta()?;
-                self.entry_scroll_position = 0;
+                let entry = crate::rss::get_entry_meta(&self.conn, entry.id)?;
+                self.selected = Selected::Entry(Box::new(entry));
             }
             Selected::Entries => {
-                if let Some(entry_meta) = &self.current_entry_meta {
-                    entry_meta.toggle_read(&self.conn)?;
+                let marked_entry_ids = self.marked_entry_ids();
+
+                if !marked_entry_ids.is_empty() {
+                    self.toggle_marked_entries_starred(&marked_entry_ids)?;
+                } else if let Some(entry_meta) = self.current_entry_meta.clone() {
+                    self.save_entry_states(
+                        marking_description(&entry_meta, starred_or_unstarred(&entry_meta)),
+                        &[entry_meta.id],
+                    )?;
+                    entry_meta.toggle_starred(&self.conn)?;
                     self.update_current_entries()?;
                     self.update_current_entry_meta()?;
                     self.update_entry_selection_position();
@@ -477,23 +2948,58 @@ pub fn toggle_read(&mut self) -> Result<()> {
         Ok(())
     }
 
-    pub fn http_client(&self) -> ureq::Agent {
+    pub fn refresh_options(&self) -> crate::config::RefreshOptions {
+        self.config.refresh_options()
+    }
+
+    pub fn play_command(&self) -> String {
+        self.config.play_command.clone()
+    }
+
+    pub fn http_client(&self) -> crate::http::HttpClient {
         // this is cheap because it only clones a struct containing two Arcs
         self.http_client.clone()
     }
 
+    pub fn sync_backend(&self) -> Option<Arc<dyn crate::sync::SyncBackend>> {
+        self.sync_backend.clone()
+    }
+
     pub fn toggle_read_mode(&mut self) -> Result<()> {
         match (&self.read_mode, &self.selected) {
             (ReadMode::ShowRead, Selected::Feeds) | (ReadMode::ShowRead, Selected::Entries) => {
                 self.entry_selection_position = 0;
                 self.read_mode = ReadMode::ShowUnread
             }
-            (ReadMode::ShowUnread, Selected::Feeds) | (ReadMode::ShowUnread, Selected::Entries) => {
+            (ReadMode::ShowUnread, Selected::Feeds)
+            | (ReadMode::ShowUnread, Selected::Entries)
+            | (ReadMode::All, Selected::Feeds)
+            | (ReadMode::All, Selected::Entries) => {
                 self.entry_selection_position = 0;
                 self.read_mode = ReadMode::ShowRead
             }
             _ => (),
         }
+
+        self.reset_entries_for_read_mode()
+    }
+
+    /// switches between showing only unread entries and showing every entry
+    pub fn toggle_unread_only(&mut self) -> Result<()> {
+        if matches!(self.selected, Selected::Entry(_)) {
+            return Ok(());
+        }
+
+        self.entry_selection_position = 0;
+        self.read_mode = match self.read_mode {
+            ReadMode::ShowUnread => ReadMode::All,
+            ReadMode::ShowRead | ReadMode::All => ReadMode::ShowUnread,
+        };
+
+        self.reset_entries_for_read_mode()
+    }
+
+    fn reset_entries_for_read_mode(&mut self) -> Result<()> {
         self.update_current_entries()?;
 
         if !self.entries.items.is_empty() {
@@ -507,39 +3013,231 @@ pub fn toggle_read_mode(&mut self) -> Result<()> {
         Ok(())
     }
 
-    fn put_current_link_in_clipboard(&mut self) -> Result<()> {
-        let current_link = match &self.selected {
+    /// the link of the selected entry,
+    /// or of the current feed's website if feeds are selected
+    fn current_link(&self) -> Option<String> {
+        match &self.selected {
+            Selected::Feeds => self
+                .current_feed
+                .as_ref()
+                .and_then(|feed| feed.link.clone().or_else(|| feed.feed_link.clone())),
+            Selected::Entries => self
+                .entries
+                .items
+                .get(self.entry_selection_position)
+                .and_then(|entry| entry.link.clone()),
+            Selected::Entry(e) => e.link.clone(),
+        }
+    }
+
+    fn open_current_link_in_browser(&mut self) -> Result<()> {
+        let current_link = self
+            .current_link()
+            .ok_or_else(|| anyhow::anyhow!("There is no link to open"))?;
+
+        // an entry's feed can have its own way of opening it
+        let entry_feed_id = match &self.selected {
+            Selected::Feeds => None,
+            Selected::Entries => self
+                .entries
+                .items
+                .get(self.entry_selection_position)
+                .map(|entry| entry.feed_id),
+            Selected::Entry(e) => Some(e.feed_id),
+        };
+
+        let feed_links = entry_feed_id
+            .and_then(|feed_id| self.feeds.items.iter().find(|feed| feed.id == feed_id))
+            .map(|feed| feed.feed_links())
+            .unwrap_or_default();
+
+        let link = match self.config.feed_link_templates.get(&feed_links) {
+            Some(template) => util::expand_link_template(template, &current_link),
+            None => current_link,
+        };
+
+        let open_command = self
+            .config
+            .feed_open_commands
+            .get(&feed_links)
+            .or(self.config.open_command.as_ref());
+
+        util::open_link(&link, open_command.map(String::as_str))
+    }
+
+    /// the enclosure of the selected entry, like a podcast episode
+    pub fn current_enclosure(&self) -> Option<crate::rss::Enclosure> {
+        match &self.selected {
+            Selected::Feeds => None,
+            Selected::Entries => self
+                .entries
+                .items
+                .get(self.entry_selection_position)
+                .and_then(|entry| entry.enclosure.clone()),
+            Selected::Entry(e) => e.enclosure.clone(),
+        }
+    }
+
+    /// what the play command plays: the selected entry's enclosure, or its link
+    pub fn current_play_link(&self) -> Option<String> {
+        match &self.selected {
+            Selected::Feeds => None,
+            Selected::Entries | Selected::Entry(_) => self
+                .current_enclosure()
+                .map(|enclosure| enclosure.url)
+                .or_else(|| self.current_link()),
+        }
+    }
+
+    /// digits are link numbers in the entry being read, and counts in the feed and entry lists
+    fn on_digit(&mut self, digit: char) -> Result<()> {
+        if matches!(self.selected, Selected::Entry(_)) {
+            return self.on_link_number_digit(digit);
+        }
+
+        // like in vim, a count does not start with 0
+        if digit != '0' || !self.count_input.is_empty() {
+            self.count_input.push(digit);
+        }
+
+        Ok(())
+    }
+
+    /// the count typed for the next motion, if there is one, which is then used up
+    fn take_count(&mut self) -> Option<usize> {
+        std::mem::take(&mut self.count_input).parse().ok()
+    }
+
+    /// moves the selection in the feed or entry list `count` rows, or half pages,
+    /// or to the top or bottom, stopping at either end. `count` makes `Top` and `Bottom`
+    /// go to that row instead. in the entry being read, scrolls it instead
+    fn on_motion(&mut self, action: Action, count: Option<usize>) -> Result<()> {
+        let inner_height = |area: Option<tui::layout::Rect>| {
+            area.map_or(0, |area| area.height.saturating_sub(2) as usize)
+        };
+
+        let (position, last_position, height) = match self.selected {
+            Selected::Feeds => (
+                self.feed_rows.state.selected().unwrap_or(0),
+                self.feed_rows.items.len().checked_sub(1),
+                inner_height(self.feeds_area),
+            ),
+            Selected::Entries => (
+                self.entries.state.selected().unwrap_or(0),
+                self.entries.items.len().checked_sub(1),
+                inner_height(self.entries_area),
+            ),
+            Selected::Entry(_) => {
+                let height = self.entry_lines_rendered_len as usize;
+                (
+                    self.entry_scroll_position as usize,
+                    Some(self.entry_lines_len.saturating_sub(height)),
+                    height,
+                )
+            }
+        };
+
+        let last_position = match last_position {
+            Some(last_position) => last_position,
+            None => return Ok(()),
+        };
+
+        let n = count.unwrap_or(1);
+        let half_page = (height / 2).max(1);
+
+        let position = match action {
+            Action::Down => position.saturating_add(n),
+            Action::Up => position.saturating_sub(n),
+            Action::HalfPageDown => position.saturating_add(half_page.saturating_mul(n)),
+            Action::HalfPageUp => position.saturating_sub(half_page.saturating_mul(n)),
+            Action::Top => count.map_or(0, |n| n - 1),
+            Action::Bottom => count.map_or(last_position, |n| n - 1),
+            _ => return Ok(()),
+        }
+        .min(last_position);
+
+        match self.selected {
             Selected::Feeds => {
-                let feed = self.current_feed.clone().unwrap();
-                feed.link.clone().unwrap_or_else(|| feed.feed_link.unwrap())
+                self.feed_rows.state.select(Some(position));
+                self.on_feed_row_selected()
             }
             Selected::Entries => {
-                if let Some(entry) = self.entries.items.get(self.entry_selection_position) {
-                    entry.link.clone().unwrap_or_else(|| "".to_string())
-                } else {
-                    "".to_string()
-                }
+                self.entries.state.select(Some(position));
+                self.entry_selection_position = position;
+                self.update_current_entry_meta()
             }
-            Selected::Entry(e) => e.link.clone().unwrap_or_else(|| "".to_string()),
-        };
-
-        if self.is_wsl() {
-            #[cfg(target_os = "linux")]
-            {
-                util::set_wsl_clipboard_contents(&current_link)
+            Selected::Entry(_) => {
+                self.entry_scroll_position = position as u16;
+                Ok(())
             }
+        }
+    }
 
-            #[cfg(not(target_os = "linux"))]
-            {
-                unreachable!("This should never happen. This code should only be reachable if the target OS is WSL.")
-            }
+    /// adds a digit to the number of the link to open,
+    /// and opens it once another digit could not make it a different link
+    fn on_link_number_digit(&mut self, digit: char) -> Result<()> {
+        if !matches!(self.selected, Selected::Entry(_)) {
+            return Ok(());
+        }
+
+        self.link_number_input.push(digit);
+
+        let links_len = self.rendered_entry.links.len();
+        let link_number = self.link_number_input.parse::<usize>().unwrap_or(0);
+
+        if link_number == 0 || link_number > links_len {
+            let link_number_input = self.link_number_input.clone();
+            self.take_link_number();
+            return Err(anyhow::anyhow!("There is no link {}", link_number_input));
+        }
+
+        if link_number * 10 > links_len {
+            self.take_link_number();
+            self.open_link_number(link_number)
         } else {
-            let mut ctx = ClipboardContext::new().map_err(|e| anyhow::anyhow!(e))?;
-            ctx.set_contents(current_link)
-                .map_err(|e| anyhow::anyhow!(e))
+            self.flash = Some(format!("Open link {}...", link_number));
+            Ok(())
         }
     }
 
+    /// the number of the link being typed, if there is one, which is then given up on
+    fn take_link_number(&mut self) -> Option<usize> {
+        if self.link_number_input.is_empty() {
+            return None;
+        }
+
+        self.flash = None;
+        std::mem::take(&mut self.link_number_input).parse().ok()
+    }
+
+    fn open_link_number(&mut self, link_number: usize) -> Result<()> {
+        let link = link_number
+            .checked_sub(1)
+            .and_then(|i| self.rendered_entry.links.get(i))
+            .ok_or_else(|| anyhow::anyhow!("There is no link {}", link_number))?;
+
+        util::open_link(link, self.config.open_command.as_deref())
+    }
+
+    fn put_current_link_in_clipboard(&mut self) -> Result<()> {
+        let current_link = self.current_link().unwrap_or_default();
+        let is_wsl = self.is_wsl();
+        util::set_clipboard_contents(&current_link, is_wsl)
+    }
+
+    /// starts subscribing to the URL in the clipboard, which can still be changed before fetching it
+    fn subscribe_from_clipboard(&mut self) -> Result<()> {
+        let is_wsl = self.is_wsl();
+        let clipboard_contents = util::get_clipboard_contents(is_wsl)?;
+        let url = util::find_feed_url(&clipboard_contents)
+            .ok_or_else(|| anyhow::anyhow!("There is no URL in the clipboard"))?;
+
+        self.feed_subscription_input = url;
+        self.mode = Mode::Editing;
+
+        Ok(())
+    }
+
     fn is_wsl(&mut self) -> bool {
         if let Some(is_wsl) = self.is_wsl {
             is_wsl
@@ -557,9 +3255,9 @@ pub fn on_left(&mut self) -> Result<()> {
                 self.selected = Selected::Feeds
             }
             Selected::Entry(_) => {
-                self.entry_scroll_position = 0;
+                self.leave_entry()?;
                 self.selected = {
-                    self.current_entry_text = String::new();
+                    self.rendered_entry = crate::html::Rendered::default();
                     Selected::Entries
                 }
             }
@@ -571,8 +3269,10 @@ pub fn on_left(&mut self) -> Result<()> {
     pub fn on_up(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
-                self.feeds.previous();
-                self.update_current_feed_and_entries()?;
+                if !self.feed_rows.items.is_empty() {
+                    self.feed_rows.previous();
+                    self.on_feed_row_selected()?;
+                }
             }
             Selected::Entries => {
                 if !self.entries.items.is_empty() {
@@ -594,6 +3294,10 @@ pub fn on_up(&mut self) -> Result<()> {
     pub fn on_right(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
+                if self.toggle_selected_group() {
+                    return Ok(());
+                }
+
                 if !self.entries.items.is_empty() {
                     self.selected = Selected::Entries;
                     self.entries.reset();
@@ -606,11 +3310,120 @@ pub fn on_right(&mut self) -> Result<()> {
         }
     }
 
+    /// puts the panes one above the other, or back side by side.
+    /// the entry is rendered again for its new width when it is drawn
+    pub fn toggle_split(&mut self) {
+        self.split = self.split.toggled();
+    }
+
+    pub fn widen_feeds_pane(&mut self) {
+        self.feeds_pane_width =
+            self.resized_pane_width(self.feeds_pane_width, true, PANE_WIDTH_STEP);
+    }
+
+    pub fn narrow_feeds_pane(&mut self) {
+        self.feeds_pane_width =
+            self.resized_pane_width(self.feeds_pane_width, true, -PANE_WIDTH_STEP);
+    }
+
+    pub fn widen_entries_pane(&mut self) {
+        if self.config.layout == PaneLayout::ThreePane {
+            self.entries_pane_width =
+                self.resized_pane_width(self.entries_pane_width, false, PANE_WIDTH_STEP);
+        }
+    }
+
+    pub fn narrow_entries_pane(&mut self) {
+        if self.config.layout == PaneLayout::ThreePane {
+            self.entries_pane_width =
+                self.resized_pane_width(self.entries_pane_width, false, -PANE_WIDTH_STEP);
+        }
+    }
+
+    pub fn reset_pane_widths(&mut self) {
+        self.feeds_pane_width = self.config.feeds_pane_width;
+        self.entries_pane_width = self.config.entries_pane_width;
+    }
+
+    /// `width` changed by `step`, as far as it can be while every pane keeps `MIN_PANE_WIDTH`.
+    /// the entries pane only takes room from the reading pane in the three-pane layout
+    fn resized_pane_width(&self, width: u16, is_feeds_pane: bool, step: i16) -> u16 {
+        let other_panes_width = match (self.config.layout, is_feeds_pane) {
+            (PaneLayout::TwoPane, _) => 0,
+            (PaneLayout::ThreePane, true) => self.entries_pane_width,
+            (PaneLayout::ThreePane, false) => self.feeds_pane_width,
+        };
+        let max_width = 100u16
+            .saturating_sub(other_panes_width)
+            .saturating_sub(MIN_PANE_WIDTH)
+            .max(MIN_PANE_WIDTH);
+
+        (width as i16 + step).clamp(MIN_PANE_WIDTH as i16, max_width as i16) as u16
+    }
+
+    /// starts reading the selected entry in zen mode, or goes back to the panes.
+    /// zen mode lasts across entries until it is toggled off
+    pub fn toggle_zen(&mut self) -> Result<()> {
+        if self.zen {
+            self.zen = false;
+            return Ok(());
+        }
+
+        if matches!(self.selected, Selected::Entries) {
+            self.on_enter()?;
+        }
+
+        self.zen = matches!(self.selected, Selected::Entry(_));
+
+        Ok(())
+    }
+
+    /// whether only the entry being read is drawn.
+    /// typing into the input box below the feeds brings the panes back
+    pub fn is_zen_reading(&self) -> bool {
+        self.zen
+            && matches!(self.selected, Selected::Entry(_))
+            && !matches!(
+                self.mode,
+                Mode::Editing
+                    | Mode::EditingTags
+                    | Mode::EditingFeedUrl
+                    | Mode::PipeCommand
+                    | Mode::Search
+                    | Mode::NamingSearch
+                    | Mode::Filter
+                    | Mode::DateRange
+            )
+    }
+
+    /// focuses the feeds, the entries, and the entry in turn, back around to the feeds
+    pub fn focus_next_pane(&mut self) -> Result<()> {
+        match self.selected {
+            Selected::Feeds => {
+                if !self.entries.items.is_empty() {
+                    if self.entries.state.selected().is_none() {
+                        self.entries.reset();
+                    }
+                    self.selected = Selected::Entries;
+                    self.update_current_entry_meta()?;
+                }
+                Ok(())
+            }
+            Selected::Entries => self.on_enter(),
+            Selected::Entry(_) => {
+                self.focus_feeds();
+                Ok(())
+            }
+        }
+    }
+
     pub fn on_down(&mut self) -> Result<()> {
         match self.selected {
             Selected::Feeds => {
-                self.feeds.next();
-                self.update_current_feed_and_entries()?;
+                if !self.feed_rows.items.is_empty() {
+                    self.feed_rows.next();
+                    self.on_feed_row_selected()?;
+                }
             }
             Selected::Entries => {
                 if !self.entries.items.is_empty() {
@@ -629,6 +3442,281 @@ pub fn on_down(&mut self) -> Result<()> {
         Ok(())
     }
 
+    /// clicks select feeds and entries, open the selected entry, and open links in it.
+    /// the scroll wheel moves through the list or the entry it is over
+    fn on_mouse(&mut self, event: crossterm::event::MouseEvent) -> Result<()> {
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        if matches!(self.mode, Mode::Help | Mode::ErrorLog | Mode::RawFeed) {
+            match event.kind {
+                MouseEventKind::ScrollUp => self.scroll_popup_up(MOUSE_SCROLL_LINES),
+                MouseEventKind::ScrollDown => self.scroll_popup_down(MOUSE_SCROLL_LINES),
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        let (column, row) = (event.column, event.row);
+        let inner_row = |area: Option<tui::layout::Rect>| {
+            area.filter(|area| {
+                column > area.x
+                    && column < area.right().saturating_sub(1)
+                    && row > area.y
+                    && row < area.bottom().saturating_sub(1)
+            })
+            .map(|area| (row - area.y - 1) as usize)
+        };
+
+        if let Some(list_row) = inner_row(self.feeds_area) {
+            return match event.kind {
+                MouseEventKind::ScrollUp => {
+                    self.focus_feeds();
+                    self.on_up()
+                }
+                MouseEventKind::ScrollDown => {
+                    self.focus_feeds();
+                    self.on_down()
+                }
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let idx = match self.feed_rows.item_at_row(list_row) {
+                        Some(idx) => idx,
+                        None => return Ok(()),
+                    };
+
+                    self.focus_feeds();
+
+                    if self.feed_rows.state.selected() == Some(idx) {
+                        self.toggle_selected_group();
+                        Ok(())
+                    } else {
+                        self.feed_rows.state.select(Some(idx));
+                        self.on_feed_row_selected()
+                    }
+                }
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(list_row) = inner_row(self.entries_area) {
+            if self.entries.items.is_empty() {
+                return Ok(());
+            }
+
+            return match event.kind {
+                MouseEventKind::ScrollUp => {
+                    self.focus_entries();
+                    self.on_up()
+                }
+                MouseEventKind::ScrollDown => {
+                    self.focus_entries();
+                    self.on_down()
+                }
+                MouseEventKind::Down(MouseButton::Left) => {
+                    // a day header is not an entry to click on
+                    let idx = match self.entry_rows().get(self.entries.offset() + list_row) {
+                        Some(EntryRow::Entry(idx)) => *idx,
+                        Some(EntryRow::Day(_)) | None => return Ok(()),
+                    };
+
+                    if matches!(self.selected, Selected::Entries)
+                        && self.entries.state.selected() == Some(idx)
+                    {
+                        return self.on_enter();
+                    }
+
+                    self.focus_entries();
+                    self.entries.state.select(Some(idx));
+                    self.entry_selection_position = idx;
+                    self.update_current_entry_meta()
+                }
+                _ => Ok(()),
+            };
+        }
+
+        let area = match (self.entry_text_area, &self.selected) {
+            (Some(area), Selected::Entry(_)) => area,
+            _ => return Ok(()),
+        };
+
+        match event.kind {
+            MouseEventKind::ScrollUp if inner_row(Some(area)).is_some() => {
+                self.entry_scroll_position = self
+                    .entry_scroll_position
+                    .saturating_sub(MOUSE_SCROLL_LINES);
+                Ok(())
+            }
+            MouseEventKind::ScrollDown if inner_row(Some(area)).is_some() => {
+                self.entry_scroll_position = (self.entry_scroll_position + MOUSE_SCROLL_LINES)
+                    .min(self.entry_lines_len as u16);
+                Ok(())
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                // the title is drawn on the top border
+                if row == area.y && column > area.x {
+                    let title_width = match &self.selected {
+                        Selected::Entry(entry_meta) => entry_meta
+                            .title
+                            .as_deref()
+                            .map_or(0, |title| tui::text::Span::raw(title).width()),
+                        _ => 0,
+                    };
+
+                    if ((column - area.x - 1) as usize) < title_width {
+                        return self.open_current_link_in_browser();
+                    }
+
+                    return Ok(());
+                }
+
+                let line = match inner_row(Some(area)) {
+                    Some(text_row) => self.entry_scroll_position as usize + text_row,
+                    None => return Ok(()),
+                };
+                let text_column = (column - area.x - 1) as usize;
+
+                let link_number = self
+                    .rendered_entry
+                    .link_spans
+                    .iter()
+                    .find(|link_span| {
+                        link_span.line == line
+                            && text_column >= link_span.column
+                            && text_column
+                                < link_span.column
+                                    + tui::text::Span::raw(link_span.text.as_str()).width()
+                    })
+                    .map(|link_span| link_span.link_number);
+
+                match link_number {
+                    Some(link_number) => self.open_link_number(link_number),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn go_to_next_unread_entry(&mut self) -> Result<()> {
+        self.go_to_unread_entry(true)
+    }
+
+    pub fn go_to_previous_unread_entry(&mut self) -> Result<()> {
+        self.go_to_unread_entry(false)
+    }
+
+    /// selects the next unread entry after the selected one, or the previous one before it,
+    /// going on to the next or previous feed with unread entries once the current feed has none left.
+    /// if an entry is being read, the unread one is opened
+    fn go_to_unread_entry(&mut self, forward: bool) -> Result<()> {
+        let is_reading = matches!(self.selected, Selected::Entry(_));
+        let position = match self.selected {
+            Selected::Feeds => None,
+            Selected::Entries | Selected::Entry(_) => self.entries.state.selected(),
+        };
+
+        let mut entry_idx = self.unread_entry_idx(position, forward);
+
+        // the virtual feeds already have every feed's entries in them
+        if entry_idx.is_none()
+            && self.current_virtual_feed.is_none()
+            && !matches!(self.read_mode, ReadMode::ShowRead)
+        {
+            for feed_idx in self.feeds_with_unread_entries(forward) {
+                self.select_feed(feed_idx)?;
+                entry_idx = self.unread_entry_idx(None, forward);
+
+                if entry_idx.is_some() {
+                    break;
+                }
+            }
+        }
+
+        let entry_idx =
+            entry_idx.ok_or_else(|| anyhow::anyhow!("There are no more unread entries"))?;
+
+        self.entries.state.select(Some(entry_idx));
+        self.entry_selection_position = entry_idx;
+        self.update_current_entry_meta()?;
+
+        if is_reading {
+            self.leave_entry()?;
+            self.on_enter()
+        } else {
+            self.selected = Selected::Entries;
+            Ok(())
+        }
+    }
+
+    /// the first unread entry after `position` in the entry list, or the last one before it.
+    /// without a position, the whole list is searched
+    fn unread_entry_idx(&self, position: Option<usize>, forward: bool) -> Option<usize> {
+        let is_unread = |idx: &usize| self.entries.items[*idx].read_at.is_none();
+
+        if forward {
+            let start = position.map_or(0, |position| position + 1);
+            (start..self.entries.items.len()).find(is_unread)
+        } else {
+            let end = position.unwrap_or(self.entries.items.len());
+            (0..end).rev().find(is_unread)
+        }
+    }
+
+    /// the feeds after the current one in the feed pane, or before it going up,
+    /// that have unread entries. feeds in collapsed groups count as well
+    fn feeds_with_unread_entries(&self, forward: bool) -> Vec<usize> {
+        let mut feed_idxs: Vec<usize> = vec![];
+
+        for row in feed_groups::feed_rows(&self.feeds.items, &self.feed_tags, &HashSet::new(), 0) {
+            // a feed with several tags is under each of them
+            if let FeedRow::Feed { feed_idx } = row {
+                if !feed_idxs.contains(&feed_idx) {
+                    feed_idxs.push(feed_idx);
+                }
+            }
+        }
+
+        if !forward {
+            feed_idxs.reverse();
+        }
+
+        let current_position = self
+            .feeds
+            .state
+            .selected()
+            .and_then(|current| feed_idxs.iter().position(|feed_idx| *feed_idx == current));
+
+        if let Some(current_position) = current_position {
+            feed_idxs.drain(..=current_position);
+        }
+
+        feed_idxs
+            .into_iter()
+            .filter(|feed_idx| {
+                let feed_id = self.feeds.items[*feed_idx].id;
+                self.feed_entry_counts
+                    .get(&feed_id)
+                    .is_some_and(|entry_counts| entry_counts.unread > 0)
+            })
+            .collect()
+    }
+
+    /// leaves the entry or entry list for the feeds, as `h` does
+    fn focus_feeds(&mut self) {
+        while !matches!(self.selected, Selected::Feeds) {
+            // `on_left` cannot fail
+            let _ = self.on_left();
+        }
+    }
+
+    /// leaves the entry for the entry list, which the three-pane layout shows beside it
+    fn focus_entries(&mut self) {
+        if matches!(self.selected, Selected::Entry(_)) {
+            // `on_left` cannot fail
+            let _ = self.on_left();
+        }
+        self.selected = Selected::Entries;
+    }
+
     pub fn mode(&self) -> Mode {
         self.mode
     }
@@ -637,3 +3725,29 @@ pub fn force_redraw(&self) -> Result<()> {
         self.event_s.send(crate::Event::Tick).map_err(|e| e.into())
     }
 }
+
+/// what marking `entry` as `state`, like "read" or "unstarred", does, for undoing it
+fn marking_description(entry: &crate::rss::EntryMeta, state: &str) -> String {
+    format!(
+        "marking \"{}\" {}",
+        entry.title.as_deref().unwrap_or("an entry"),
+        state
+    )
+}
+
+/// what toggling `entry`'s read state marks it as
+fn read_or_unread(entry: &crate::rss::EntryMeta) -> &'static str {
+    if entry.read_at.is_some() {
+        "unread"
+    } else {
+        "read"
+    }
+}
+
+fn starred_or_unstarred(entry: &crate::rss::EntryMeta) -> &'static str {
+    if entry.starred {
+        "unstarred"
+    } else {
+        "starred"
+    }
+}
@@ -0,0 +1,1099 @@
+use crate::entry_format::EntryFormat;
+use crate::export::SaveFormat;
+use crate::http::{Credentials, RetryPolicy};
+use crate::keymap::{Action, KeySequence, Keymap};
+use crate::keywords::HighlightKeywords;
+use crate::modes::{EntryDates, FeedSort, PaneLayout, Split};
+use crate::query::SmartFeed;
+use crate::rss::{Passphrase, RetentionPolicy};
+use crate::rules::{Rule, RuleAction, Rules};
+use crate::sync::SyncConfig;
+use crate::theme::{parse_color, ColorSupport, Theme};
+use anyhow::{Context, Result};
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time;
+use tui::style::Color;
+
+const DEFAULT_TICK_RATE: u64 = 250;
+const DEFAULT_FLASH_DISPLAY_DURATION_SECONDS: u64 = 4;
+const DEFAULT_NETWORK_TIMEOUT: u64 = 5;
+const DEFAULT_REFRESH_INTERVAL_MINUTES: u64 = 0;
+const DEFAULT_REFRESH_RETRIES: u32 = 2;
+const DEFAULT_REFRESH_RETRY_BACKOFF_MS: u64 = 500;
+const DEFAULT_MAX_REQUESTS_PER_HOST: usize = 2;
+const DEFAULT_PLAY_COMMAND: &str = "mpv";
+const DEFAULT_PIPE_COMMAND: &str = "less";
+const DEFAULT_MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+const DEFAULT_WORDS_PER_MINUTE: usize = 230;
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+const DEFAULT_DAY_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_ZEN_LINE_WIDTH: u16 = 80;
+const DEFAULT_ENTRIES_PANE_WIDTH: u16 = 30;
+/// the narrowest a pane can be, in percent of the screen
+pub const MIN_PANE_WIDTH: u16 = 10;
+/// where the daemon and scripts, which have no terminal to ask on, get the passphrase from
+const DATABASE_PASSPHRASE_VAR: &str = "RUSS_DATABASE_PASSPHRASE";
+
+/// the fully resolved configuration:
+/// CLI flags override the config file, which overrides the defaults
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub database_path: PathBuf,
+    /// set when the database is encrypted
+    pub database_passphrase: Option<Passphrase>,
+    pub tick_rate: time::Duration,
+    pub flash_display_duration_seconds: time::Duration,
+    pub network_timeout: time::Duration,
+    /// how often every feed is refreshed automatically. zero means never.
+    pub refresh_interval: time::Duration,
+    /// overrides `refresh_interval` for the feeds with these feed links
+    pub feed_refresh_intervals: FeedSettings<time::Duration>,
+    /// basic auth for the feeds with these feed links
+    pub feed_credentials: FeedSettings<Credentials>,
+    pub user_agent: String,
+    /// extra request headers for the feeds with these feed links
+    pub feed_headers: FeedSettings<HashMap<String, String>>,
+    /// the proxy every feed is fetched through
+    pub proxy: Option<String>,
+    /// overrides `proxy` for the feeds with these feed links
+    pub feed_proxies: FeedSettings<String>,
+    /// a PEM file of certificates to trust on top of the usual ones
+    pub ca_certificate: Option<PathBuf>,
+    /// the feeds whose certificates are not verified
+    pub insecure_feeds: FeedSettings<()>,
+    pub retry_policy: RetryPolicy,
+    /// how many feeds from the same host are fetched at once
+    pub max_requests_per_host: usize,
+    pub retention_policy: RetentionPolicy,
+    /// what happens to the new entries they match as feeds are refreshed
+    pub rules: Rules,
+    /// run for each entry a refresh adds, with the entry as JSON on its stdin
+    pub new_entry_command: Option<String>,
+    /// whether an article in more than one feed is shown once in "All entries"
+    pub collapse_duplicate_entries: bool,
+    /// whether reading an article marks its duplicates in other feeds read too
+    pub mark_duplicate_entries_read: bool,
+    /// whether an entry the feed edits after it was read becomes unread again
+    pub mark_revised_entries_unread: bool,
+    /// whether a feed that has permanently moved keeps its URL until the move is confirmed
+    pub confirm_feed_moves: bool,
+    /// shown in the feed pane after the other virtual feeds, in the order they are written
+    pub smart_feeds: Vec<SmartFeed>,
+    /// the command used to open links, with `{}` standing in for the link.
+    /// if there is no `{}`, the link is appended.
+    pub open_command: Option<String>,
+    /// overrides `open_command` for the entries of the feeds with these feed links
+    pub feed_open_commands: FeedSettings<String>,
+    /// what the links of the entries of the feeds with these feed links are rewritten to when opened
+    pub feed_link_templates: FeedSettings<String>,
+    /// whether links in entries are made clickable with OSC 8 escape sequences
+    pub hyperlinks: bool,
+    /// how entries' images are drawn, if they are fetched and drawn at all.
+    /// `None` when they are not asked for, or the terminal cannot draw them, which shows their alt text
+    pub inline_images: Option<crate::images::GraphicsProtocol>,
+    /// whether clicks and the scroll wheel are taken from the terminal
+    pub mouse: bool,
+    /// images larger than this are not fetched
+    pub max_image_bytes: u64,
+    /// the command `p` plays enclosures and links with, like `open_command`,
+    /// except that it has the terminal to itself until it exits
+    pub play_command: String,
+    /// what `|` first offers to pipe entries to
+    pub pipe_command: String,
+    /// where enclosures are downloaded to
+    pub download_directory: PathBuf,
+    /// where `w` saves entries to
+    pub save_directory: PathBuf,
+    /// where every error is also appended to, a line each
+    pub error_log_path: Option<PathBuf>,
+    pub save_format: SaveFormat,
+    /// how fast entries' reading times assume they are read
+    pub words_per_minute: usize,
+    /// whether the entry list has a header over each day's entries
+    pub day_headers: bool,
+    pub entry_dates: EntryDates,
+    /// the entry list's columns, instead of the usual ones
+    pub entry_format: Option<EntryFormat>,
+    pub layout: PaneLayout,
+    /// how the panes are split at startup. `Action::ToggleSplit` switches it
+    pub split: Split,
+    /// how wide the feed pane is, in percent of the screen, until it is resized
+    pub feeds_pane_width: u16,
+    /// how wide the entry list is in the three-pane layout, in percent of the screen, until it is resized
+    pub entries_pane_width: u16,
+    /// how long the lines of an entry read in zen mode are at most
+    pub zen_line_width: u16,
+    /// how dates with times are shown, in the local timezone
+    pub date_format: String,
+    /// how days are shown, like in the day headers
+    pub day_format: String,
+    pub feed_sort: FeedSort,
+    pub highlight_keywords: HighlightKeywords,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// the server feeds are synced with instead of fetched from
+    pub sync: Option<SyncConfig>,
+}
+
+/// settings for individual feeds, by feed link. links that only differ in what
+/// `util::normalize_url` leaves out, like `http://` and `https://`, are the same feed's
+#[derive(Clone, Debug)]
+pub struct FeedSettings<T>(HashMap<String, T>);
+
+impl<T> FeedSettings<T> {
+    /// the setting of the first of `feed_links` that has one.
+    /// `Feed::feed_links` has a feed's, so its settings follow it when it moves
+    pub fn get(&self, feed_links: &[&str]) -> Option<&T> {
+        feed_links
+            .iter()
+            .find_map(|feed_link| self.0.get(&crate::util::normalize_url(feed_link)))
+    }
+
+    pub fn contains(&self, feed_links: &[&str]) -> bool {
+        self.get(feed_links).is_some()
+    }
+
+    /// normalized
+    pub fn feed_links(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.0.values()
+    }
+}
+
+impl<T> Default for FeedSettings<T> {
+    fn default() -> Self {
+        FeedSettings(HashMap::new())
+    }
+}
+
+impl<T> FromIterator<(String, T)> for FeedSettings<T> {
+    fn from_iter<I: IntoIterator<Item = (String, T)>>(iter: I) -> Self {
+        FeedSettings(
+            iter.into_iter()
+                .map(|(feed_link, setting)| (crate::util::normalize_url(&feed_link), setting))
+                .collect(),
+        )
+    }
+}
+
+/// what happens to the entries of the feeds that are refreshed
+#[derive(Clone, Debug)]
+pub struct RefreshOptions {
+    pub retention_policy: RetentionPolicy,
+    pub rules: Rules,
+    pub new_entry_command: Option<String>,
+    pub mark_duplicate_entries_read: bool,
+    pub mark_revised_entries_unread: bool,
+    pub confirm_feed_moves: bool,
+}
+
+/// what is allowed to appear in `config.toml`.
+/// everything is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    database_path: Option<PathBuf>,
+    encrypt_database: Option<bool>,
+    tick_rate: Option<u64>,
+    flash_display_duration_seconds: Option<u64>,
+    network_timeout: Option<u64>,
+    refresh_interval_minutes: Option<u64>,
+    feed_refresh_interval_minutes: HashMap<String, u64>,
+    feed_credentials: HashMap<String, Credentials>,
+    user_agent: Option<String>,
+    feed_headers: HashMap<String, HashMap<String, String>>,
+    proxy: Option<String>,
+    feed_proxies: HashMap<String, String>,
+    ca_certificate: Option<PathBuf>,
+    insecure_feeds: HashSet<String>,
+    refresh_retries: Option<u32>,
+    refresh_retry_backoff_ms: Option<u64>,
+    max_requests_per_host: Option<usize>,
+    max_entries_per_feed: Option<usize>,
+    max_entry_age_days: Option<u64>,
+    rules: Vec<RuleFile>,
+    new_entry_command: Option<String>,
+    collapse_duplicate_entries: Option<bool>,
+    mark_duplicate_entries_read: Option<bool>,
+    mark_revised_entries_unread: Option<bool>,
+    confirm_feed_moves: Option<bool>,
+    smart_feeds: Vec<SmartFeed>,
+    open_command: Option<String>,
+    feed_open_commands: HashMap<String, String>,
+    feed_link_templates: HashMap<String, String>,
+    hyperlinks: Option<bool>,
+    inline_images: Option<bool>,
+    mouse: Option<bool>,
+    max_image_bytes: Option<u64>,
+    play_command: Option<String>,
+    pipe_command: Option<String>,
+    download_directory: Option<PathBuf>,
+    save_directory: Option<PathBuf>,
+    error_log_path: Option<PathBuf>,
+    save_format: Option<SaveFormat>,
+    words_per_minute: Option<usize>,
+    day_headers: Option<bool>,
+    entry_dates: Option<EntryDates>,
+    entry_format: Option<EntryFormat>,
+    layout: Option<PaneLayout>,
+    split: Option<Split>,
+    feeds_pane_width: Option<u16>,
+    entries_pane_width: Option<u16>,
+    zen_line_width: Option<u16>,
+    date_format: Option<ConfigDateFormat>,
+    day_format: Option<ConfigDateFormat>,
+    feed_sort: Option<FeedSort>,
+    highlight_keywords: Vec<ConfigRegex>,
+    feed_highlight_keywords: HashMap<String, Vec<ConfigRegex>>,
+    theme: Option<String>,
+    color_support: Option<ColorSupport>,
+    colors: ColorsFile,
+    keybindings: HashMap<String, OneOrMany<KeySequence>>,
+    sync: Option<SyncConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ColorsFile {
+    title: Option<ConfigColor>,
+    highlight: Option<ConfigColor>,
+    input: Option<ConfigColor>,
+    read: Option<ConfigColor>,
+    unread: Option<ConfigColor>,
+    flash: Option<ConfigColor>,
+    error: Option<ConfigColor>,
+    border: Option<ConfigColor>,
+    code: Option<ConfigColor>,
+    code_keyword: Option<ConfigColor>,
+    code_literal: Option<ConfigColor>,
+    code_comment: Option<ConfigColor>,
+    highlight_keyword: Option<ConfigColor>,
+}
+
+impl ColorsFile {
+    /// overrides the theme's colors with any colors given in the config file
+    fn apply(&self, theme: &mut Theme) {
+        let overrides = [
+            (&self.title, &mut theme.title),
+            (&self.highlight, &mut theme.highlight),
+            (&self.input, &mut theme.input),
+            (&self.read, &mut theme.read),
+            (&self.unread, &mut theme.unread),
+            (&self.flash, &mut theme.flash),
+            (&self.error, &mut theme.error),
+            (&self.border, &mut theme.border),
+            (&self.code, &mut theme.code),
+            (&self.code_keyword, &mut theme.code_keyword),
+            (&self.code_literal, &mut theme.code_literal),
+            (&self.code_comment, &mut theme.code_comment),
+            (&self.highlight_keyword, &mut theme.highlight_keyword),
+        ];
+
+        for (config_color, theme_color) in overrides {
+            if let Some(ConfigColor(color)) = config_color {
+                *theme_color = *color;
+            }
+        }
+    }
+}
+
+/// one of the `[[rules]]`, with each pattern a regex
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    title: Option<ConfigRegex>,
+    author: Option<ConfigRegex>,
+    feed: Option<ConfigRegex>,
+    action: RuleAction,
+}
+
+impl RuleFile {
+    fn into_rule(self) -> Result<Rule> {
+        if self.title.is_none() && self.author.is_none() && self.feed.is_none() {
+            return Err(anyhow::anyhow!(
+                "Every rule needs a title, author, or feed pattern, otherwise it matches every entry"
+            ));
+        }
+
+        Ok(Rule {
+            title: self.title.map(|ConfigRegex(regex)| regex),
+            author: self.author.map(|ConfigRegex(regex)| regex),
+            feed: self.feed.map(|ConfigRegex(regex)| regex),
+            action: self.action,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<OneOrMany<T>> for Vec<T> {
+    fn from(other: OneOrMany<T>) -> Self {
+        match other {
+            OneOrMany::One(t) => vec![t],
+            OneOrMany::Many(ts) => ts,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ConfigColor(Color);
+
+impl<'de> Deserialize<'de> for ConfigColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        parse_color(&s)
+            .map(ConfigColor)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug)]
+struct ConfigRegex(regex::Regex);
+
+impl<'de> Deserialize<'de> for ConfigRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        regex::Regex::new(&s)
+            .map(ConfigRegex)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// a strftime-style format, checked up front, as chrono panics on bad ones when it formats with them
+#[derive(Debug)]
+struct ConfigDateFormat(String);
+
+impl<'de> Deserialize<'de> for ConfigDateFormat {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        if chrono::format::StrftimeItems::new(&s)
+            .any(|item| matches!(item, chrono::format::Item::Error))
+        {
+            return Err(serde::de::Error::custom(format!(
+                "{} is not a valid date format",
+                s
+            )));
+        }
+
+        Ok(ConfigDateFormat(s))
+    }
+}
+
+fn regexes(config_regexes: Vec<ConfigRegex>) -> Vec<regex::Regex> {
+    config_regexes
+        .into_iter()
+        .map(|ConfigRegex(regex)| regex)
+        .collect()
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "russ")
+        .map(|project_dirs| project_dirs.config_dir().join("config.toml"))
+}
+
+/// `$XDG_DATA_HOME/russ/feeds.db` on Linux,
+/// `~/Library/Application Support/russ/feeds.db` on macOS,
+/// and `{FOLDERID_RoamingAppData}\russ\data\feeds.db` on Windows
+pub fn default_database_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "russ")
+        .map(|project_dirs| project_dirs.data_dir().join("feeds.db"))
+}
+
+/// the user's downloads directory, or their home directory if they have none
+fn default_download_directory() -> PathBuf {
+    directories::UserDirs::new()
+        .map(|user_dirs| {
+            user_dirs
+                .download_dir()
+                .unwrap_or_else(|| user_dirs.home_dir())
+                .to_owned()
+        })
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// expands a leading `~` to the user's home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (
+        path.strip_prefix("~"),
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_owned()),
+    ) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_owned(),
+    }
+}
+
+impl Config {
+    pub fn load(options: &crate::Options) -> Result<Config> {
+        let config_file = match &options.config_path {
+            Some(config_path) => read_config_file(config_path)?,
+            None => match default_config_path() {
+                Some(config_path) if config_path.exists() => read_config_file(&config_path)?,
+                _ => ConfigFile::default(),
+            },
+        };
+
+        let encrypt_database =
+            options.encrypt_database || config_file.encrypt_database.unwrap_or(false);
+
+        let mut config = Config::resolve(options, config_file)?;
+
+        if let Some(database_dir) = config.database_path.parent() {
+            if !database_dir.as_os_str().is_empty() {
+                std::fs::create_dir_all(database_dir).with_context(|| {
+                    format!(
+                        "Unable to create database directory {}",
+                        database_dir.display()
+                    )
+                })?;
+            }
+        }
+
+        if encrypt_database {
+            config.database_passphrase = Some(database_passphrase(&config.database_path)?);
+        }
+
+        Ok(config)
+    }
+
+    pub fn refresh_options(&self) -> RefreshOptions {
+        RefreshOptions {
+            retention_policy: self.retention_policy,
+            rules: self.rules.clone(),
+            new_entry_command: self.new_entry_command.clone(),
+            mark_duplicate_entries_read: self.mark_duplicate_entries_read,
+            mark_revised_entries_unread: self.mark_revised_entries_unread,
+            confirm_feed_moves: self.confirm_feed_moves,
+        }
+    }
+
+    fn resolve(options: &crate::Options, config_file: ConfigFile) -> Result<Config> {
+        let database_path = options
+            .database_path
+            .clone()
+            .or(config_file.database_path)
+            .map(|path| expand_home(&path))
+            .or_else(default_database_path)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unable to find a data directory, so a database path must be given with --database-path or in the config file"
+                )
+            })?;
+
+        let tick_rate = options
+            .tick_rate
+            .or(config_file.tick_rate)
+            .unwrap_or(DEFAULT_TICK_RATE);
+
+        let flash_display_duration_seconds = options
+            .flash_display_duration_seconds
+            .or(config_file.flash_display_duration_seconds)
+            .unwrap_or(DEFAULT_FLASH_DISPLAY_DURATION_SECONDS);
+
+        let network_timeout = options
+            .network_timeout
+            .or(config_file.network_timeout)
+            .unwrap_or(DEFAULT_NETWORK_TIMEOUT);
+
+        let refresh_interval_minutes = options
+            .refresh_interval_minutes
+            .or(config_file.refresh_interval_minutes)
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL_MINUTES);
+
+        let feed_refresh_intervals = config_file
+            .feed_refresh_interval_minutes
+            .into_iter()
+            .map(|(feed_link, minutes)| (feed_link, time::Duration::from_secs(minutes * 60)))
+            .collect();
+
+        if let Some((feed_link, _)) =
+            config_file
+                .feed_credentials
+                .iter()
+                .find(|(_, credentials)| {
+                    credentials.password.is_some() && credentials.password_command.is_some()
+                })
+        {
+            return Err(anyhow::anyhow!(
+                "The credentials for {} have both a password and a password_command, only one is allowed",
+                feed_link
+            ));
+        }
+
+        if let Some(sync) = &config_file.sync {
+            if sync.password.is_some() && sync.password_command.is_some() {
+                return Err(anyhow::anyhow!(
+                    "The sync config has both a password and a password_command, only one is allowed"
+                ));
+            }
+
+            if sync.username.is_none() && sync.backend.needs_username() {
+                return Err(anyhow::anyhow!(
+                    "The sync config needs a username for this backend"
+                ));
+            }
+        }
+
+        let rules = config_file
+            .rules
+            .into_iter()
+            .map(RuleFile::into_rule)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut theme = Theme::preset(config_file.theme.as_deref().unwrap_or("auto"))?;
+        config_file.colors.apply(&mut theme);
+        let theme = theme.with_color_support(
+            config_file
+                .color_support
+                .unwrap_or_else(ColorSupport::detect),
+        );
+
+        let keybinding_overrides = config_file
+            .keybindings
+            .into_iter()
+            .map(|(action_name, keys)| {
+                let action: Result<Action, serde::de::value::Error> =
+                    Action::deserialize(action_name.as_str().into_deserializer());
+                action
+                    .map(|action| (action, keys.into()))
+                    .with_context(|| format!("{} is not a valid keybinding action", action_name))
+            })
+            .collect::<Result<HashMap<Action, Vec<KeySequence>>>>()?;
+
+        let download_directory = config_file
+            .download_directory
+            .map(|path| expand_home(&path))
+            .unwrap_or_else(default_download_directory);
+
+        let layout = config_file.layout.unwrap_or_default();
+
+        Ok(Config {
+            database_path,
+            database_passphrase: None,
+            tick_rate: time::Duration::from_millis(tick_rate),
+            flash_display_duration_seconds: time::Duration::from_secs(
+                flash_display_duration_seconds,
+            ),
+            network_timeout: time::Duration::from_secs(network_timeout),
+            refresh_interval: time::Duration::from_secs(refresh_interval_minutes * 60),
+            feed_refresh_intervals,
+            feed_credentials: config_file.feed_credentials.into_iter().collect(),
+            user_agent: options
+                .user_agent
+                .clone()
+                .or(config_file.user_agent)
+                .unwrap_or_else(|| crate::http::DEFAULT_USER_AGENT.to_owned()),
+            feed_headers: config_file.feed_headers.into_iter().collect(),
+            proxy: options.proxy.clone().or(config_file.proxy),
+            feed_proxies: config_file.feed_proxies.into_iter().collect(),
+            ca_certificate: config_file.ca_certificate.map(|path| expand_home(&path)),
+            insecure_feeds: config_file
+                .insecure_feeds
+                .into_iter()
+                .map(|feed_link| (feed_link, ()))
+                .collect(),
+            retry_policy: RetryPolicy {
+                retries: config_file
+                    .refresh_retries
+                    .unwrap_or(DEFAULT_REFRESH_RETRIES),
+                backoff: time::Duration::from_millis(
+                    config_file
+                        .refresh_retry_backoff_ms
+                        .unwrap_or(DEFAULT_REFRESH_RETRY_BACKOFF_MS),
+                ),
+            },
+            max_requests_per_host: config_file
+                .max_requests_per_host
+                .unwrap_or(DEFAULT_MAX_REQUESTS_PER_HOST),
+            retention_policy: RetentionPolicy {
+                max_entries_per_feed: config_file.max_entries_per_feed,
+                max_age: config_file
+                    .max_entry_age_days
+                    .map(|days| chrono::Duration::days(days as i64)),
+            },
+            rules: Rules::new(rules),
+            new_entry_command: config_file.new_entry_command,
+            collapse_duplicate_entries: config_file.collapse_duplicate_entries.unwrap_or(false),
+            mark_duplicate_entries_read: config_file.mark_duplicate_entries_read.unwrap_or(false),
+            mark_revised_entries_unread: config_file.mark_revised_entries_unread.unwrap_or(false),
+            confirm_feed_moves: config_file.confirm_feed_moves.unwrap_or(false),
+            smart_feeds: config_file.smart_feeds,
+            open_command: config_file.open_command,
+            feed_open_commands: config_file.feed_open_commands.into_iter().collect(),
+            feed_link_templates: config_file.feed_link_templates.into_iter().collect(),
+            hyperlinks: config_file
+                .hyperlinks
+                .unwrap_or_else(crate::util::terminal_supports_hyperlinks),
+            inline_images: if config_file.inline_images.unwrap_or(false) {
+                crate::util::terminal_graphics_protocol()
+            } else {
+                None
+            },
+            mouse: config_file.mouse.unwrap_or(true),
+            max_image_bytes: config_file
+                .max_image_bytes
+                .unwrap_or(DEFAULT_MAX_IMAGE_BYTES),
+            play_command: config_file
+                .play_command
+                .unwrap_or_else(|| DEFAULT_PLAY_COMMAND.to_owned()),
+            pipe_command: config_file
+                .pipe_command
+                .unwrap_or_else(|| DEFAULT_PIPE_COMMAND.to_owned()),
+            save_directory: config_file
+                .save_directory
+                .map(|path| expand_home(&path))
+                .unwrap_or_else(|| download_directory.clone()),
+            error_log_path: config_file.error_log_path.map(|path| expand_home(&path)),
+            save_format: config_file.save_format.unwrap_or_default(),
+            download_directory,
+            words_per_minute: config_file
+                .words_per_minute
+                .unwrap_or(DEFAULT_WORDS_PER_MINUTE)
+                .max(1),
+            day_headers: config_file.day_headers.unwrap_or(true),
+            entry_dates: config_file.entry_dates.unwrap_or_default(),
+            entry_format: config_file.entry_format,
+            layout,
+            split: config_file.split.unwrap_or_default(),
+            // the feeds get less room when the entries get a pane of their own
+            feeds_pane_width: config_file
+                .feeds_pane_width
+                .unwrap_or(match layout {
+                    PaneLayout::TwoPane => 30,
+                    PaneLayout::ThreePane => 20,
+                })
+                .clamp(MIN_PANE_WIDTH, 100 - MIN_PANE_WIDTH),
+            entries_pane_width: config_file
+                .entries_pane_width
+                .unwrap_or(DEFAULT_ENTRIES_PANE_WIDTH)
+                .clamp(MIN_PANE_WIDTH, 100 - MIN_PANE_WIDTH),
+            zen_line_width: config_file
+                .zen_line_width
+                .unwrap_or(DEFAULT_ZEN_LINE_WIDTH)
+                .max(1),
+            date_format: config_file
+                .date_format
+                .map(|ConfigDateFormat(format)| format)
+                .unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_owned()),
+            day_format: config_file
+                .day_format
+                .map(|ConfigDateFormat(format)| format)
+                .unwrap_or_else(|| DEFAULT_DAY_FORMAT.to_owned()),
+            feed_sort: config_file.feed_sort.unwrap_or_default(),
+            highlight_keywords: HighlightKeywords {
+                global: regexes(config_file.highlight_keywords),
+                feeds: config_file
+                    .feed_highlight_keywords
+                    .into_iter()
+                    .map(|(feed_link, keywords)| (feed_link, regexes(keywords)))
+                    .collect(),
+            },
+            theme,
+            keymap: Keymap::with_overrides(&keybinding_overrides),
+            sync: config_file.sync,
+        })
+    }
+}
+
+/// a new database asks for its passphrase twice, so a typo does not lock it away
+fn database_passphrase(database_path: &Path) -> Result<Passphrase> {
+    if !cfg!(feature = "sqlcipher") {
+        return Err(anyhow::anyhow!(
+            "This russ was built without SQLCipher, so it cannot encrypt its database. Build it with `--features sqlcipher`"
+        ));
+    }
+
+    if let Ok(passphrase) = std::env::var(DATABASE_PASSPHRASE_VAR) {
+        return Ok(Passphrase(passphrase));
+    }
+
+    let passphrase = read_passphrase(&format!("passphrase for {}: ", database_path.display()))
+        .with_context(|| {
+            format!(
+                "Unable to ask for the database passphrase, set {} instead",
+                DATABASE_PASSPHRASE_VAR
+            )
+        })?;
+
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("The database passphrase cannot be empty"));
+    }
+
+    if !database_path.exists() && read_passphrase("passphrase again: ")? != passphrase {
+        return Err(anyhow::anyhow!("The passphrases do not match"));
+    }
+
+    Ok(Passphrase(passphrase))
+}
+
+/// reads a line from the terminal without echoing it
+fn read_passphrase(prompt: &str) -> Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+    use std::io::Write;
+
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    crossterm::terminal::enable_raw_mode()?;
+
+    let mut passphrase = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) => match key.code {
+                KeyCode::Enter => break Ok(passphrase),
+                KeyCode::Esc => break Err(anyhow::anyhow!("Cancelled")),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("Cancelled"))
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                _ => (),
+            },
+            Ok(_) => (),
+            Err(e) => break Err(e.into()),
+        }
+    };
+
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+
+    result
+}
+
+fn read_config_file(config_path: &Path) -> Result<ConfigFile> {
+    let s = std::fs::read_to_string(config_path)
+        .with_context(|| format!("Unable to read config file {}", config_path.display()))?;
+
+    toml::from_str(&s)
+        .with_context(|| format!("Unable to parse config file {}", config_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    /// resolves the config file, with only the database path given on the command line
+    fn resolve(config_file: &str) -> Result<Config> {
+        let options = crate::Options::from_iter(["russ", "-d", "cli.db"]);
+
+        Config::resolve(&options, toml::from_str(config_file)?)
+    }
+
+    #[test]
+    fn cli_flags_override_the_config_file() {
+        let options = crate::Options::from_iter([
+            "russ",
+            "-d",
+            "cli.db",
+            "-t",
+            "100",
+            "--proxy",
+            "socks5://localhost:1080",
+        ]);
+        let config_file: ConfigFile = toml::from_str(
+            r##"
+            database_path = "file.db"
+            tick_rate = 500
+            proxy = "http://proxy.example:3128"
+            "##,
+        )
+        .unwrap();
+
+        let config = Config::resolve(&options, config_file).unwrap();
+
+        assert_eq!(config.database_path, PathBuf::from("cli.db"));
+        assert_eq!(config.tick_rate, time::Duration::from_millis(100));
+        assert_eq!(config.proxy.as_deref(), Some("socks5://localhost:1080"));
+    }
+
+    #[test]
+    fn options_left_out_of_the_config_file_have_defaults() {
+        let config = resolve("").unwrap();
+
+        assert_eq!(
+            config.flash_display_duration_seconds,
+            time::Duration::from_secs(DEFAULT_FLASH_DISPLAY_DURATION_SECONDS)
+        );
+        assert_eq!(config.user_agent, crate::http::DEFAULT_USER_AGENT);
+        assert_eq!(
+            config.retry_policy.backoff,
+            time::Duration::from_millis(DEFAULT_REFRESH_RETRY_BACKOFF_MS)
+        );
+    }
+
+    #[test]
+    fn network_options_are_read() {
+        let config = resolve(
+            r##"
+            network_timeout = 10
+            refresh_retries = 4
+            insecure_feeds = ["https://self-signed.example/feed.xml"]
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(config.network_timeout, time::Duration::from_secs(10));
+        assert_eq!(
+            config.retry_policy,
+            RetryPolicy {
+                retries: 4,
+                backoff: time::Duration::from_millis(DEFAULT_REFRESH_RETRY_BACKOFF_MS)
+            }
+        );
+        assert!(config
+            .insecure_feeds
+            .contains(&["https://self-signed.example/feed.xml"]));
+    }
+
+    #[test]
+    fn refresh_intervals_are_in_minutes_and_can_be_set_per_feed() {
+        let config = resolve(
+            r##"
+            refresh_interval_minutes = 30
+
+            [feed_refresh_interval_minutes]
+            "https://example.com/feed.xml" = 5
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(config.refresh_interval, time::Duration::from_secs(30 * 60));
+        assert_eq!(
+            config
+                .feed_refresh_intervals
+                .get(&["https://example.com/feed.xml"]),
+            Some(&time::Duration::from_secs(5 * 60))
+        );
+    }
+
+    #[test]
+    fn feed_credentials_and_headers_are_read_by_feed_link() {
+        let config = resolve(
+            r##"
+            [feed_headers."https://example.com/private.xml"]
+            X-Api-Token = "token"
+
+            [feed_credentials."https://example.com/private.xml"]
+            username = "me"
+            password_command = "pass show feeds/example"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config
+                .feed_credentials
+                .get(&["https://example.com/private.xml"])
+                .unwrap()
+                .password_command,
+            Some("pass show feeds/example".to_string())
+        );
+        assert_eq!(
+            config
+                .feed_headers
+                .get(&["https://example.com/private.xml"])
+                .unwrap()["X-Api-Token"],
+            "token"
+        );
+
+        assert!(resolve(
+            r##"
+            [feed_credentials."https://example.com/private.xml"]
+            username = "me"
+            password = "secret"
+            password_command = "pass show feeds/example"
+            "##,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn feed_open_commands_are_read_by_feed_link() {
+        let config = resolve(
+            r##"
+            [feed_open_commands]
+            "https://example.com/videos.xml" = "mpv {}"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config
+                .feed_open_commands
+                .get(&["https://example.com/videos.xml"])
+                .map(String::as_str),
+            Some("mpv {}")
+        );
+    }
+
+    #[test]
+    fn feed_sort_is_read() {
+        assert_eq!(resolve("").unwrap().feed_sort, FeedSort::Alphabetical);
+        assert_eq!(
+            resolve(r#"feed_sort = "manual""#).unwrap().feed_sort,
+            FeedSort::Manual
+        );
+        assert!(resolve(r#"feed_sort = "random""#).is_err());
+    }
+
+    #[test]
+    fn max_entry_age_is_in_days() {
+        assert_eq!(
+            resolve("max_entry_age_days = 90").unwrap().retention_policy,
+            RetentionPolicy {
+                max_entries_per_feed: None,
+                max_age: Some(chrono::Duration::days(90)),
+            }
+        );
+    }
+
+    #[test]
+    fn highlight_keywords_are_regexes() {
+        let config = resolve(r#"highlight_keywords = ["(?i)\\brust\\b"]"#).unwrap();
+        assert!(config.highlight_keywords.is_match(&[], "Why Rust?"));
+        assert!(!config.highlight_keywords.is_match(&[], "Trustworthy"));
+    }
+
+    #[test]
+    fn colors_override_the_theme_they_start_from() {
+        let config = resolve(
+            r##"
+            theme = "light"
+            color_support = "truecolor"
+
+            [colors]
+            highlight = "#00ff00"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(config.theme.highlight, Color::Rgb(0, 255, 0));
+        assert_eq!(config.theme.title, Color::Rgb(20, 90, 180));
+    }
+
+    #[test]
+    fn sync_backends_are_read() {
+        let config = resolve(
+            r##"
+            [sync]
+            backend = "miniflux"
+            url = "https://miniflux.example.com"
+            password_command = "pass show miniflux"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.sync.map(|sync| sync.backend),
+            Some(crate::sync::SyncBackendKind::Miniflux)
+        );
+    }
+
+    #[test]
+    fn date_formats_are_checked() {
+        let options = crate::Options::from_iter(["russ", "-d", "cli.db"]);
+
+        let config_file: ConfigFile = toml::from_str(r#"date_format = "%d.%m.%Y %H:%M""#).unwrap();
+        let config = Config::resolve(&options, config_file).unwrap();
+        assert_eq!(config.date_format, "%d.%m.%Y %H:%M");
+        assert_eq!(config.day_format, DEFAULT_DAY_FORMAT);
+
+        assert!(toml::from_str::<ConfigFile>(r#"day_format = "%Y-%Q""#).is_err());
+    }
+
+    #[test]
+    fn pane_widths_depend_on_the_layout_and_are_kept_in_bounds() {
+        let options = crate::Options::from_iter(["russ", "-d", "cli.db"]);
+
+        let config = Config::resolve(&options, toml::from_str("").unwrap()).unwrap();
+        assert_eq!(config.feeds_pane_width, 30);
+
+        let config_file: ConfigFile = toml::from_str(r#"layout = "three_pane""#).unwrap();
+        let config = Config::resolve(&options, config_file).unwrap();
+        assert_eq!(config.feeds_pane_width, 20);
+        assert_eq!(config.entries_pane_width, DEFAULT_ENTRIES_PANE_WIDTH);
+
+        let config_file: ConfigFile =
+            toml::from_str("feeds_pane_width = 0\nentries_pane_width = 100").unwrap();
+        let config = Config::resolve(&options, config_file).unwrap();
+        assert_eq!(config.feeds_pane_width, MIN_PANE_WIDTH);
+        assert_eq!(config.entries_pane_width, 100 - MIN_PANE_WIDTH);
+    }
+
+    #[test]
+    fn unknown_keybinding_actions_are_errors() {
+        let options = crate::Options::from_iter(["russ", "-d", "cli.db"]);
+        let config_file: ConfigFile = toml::from_str(
+            r##"
+            [keybindings]
+            fly = "f"
+            "##,
+        )
+        .unwrap();
+
+        assert!(Config::resolve(&options, config_file).is_err());
+    }
+
+    #[test]
+    fn rules_need_a_pattern_and_valid_regexes() {
+        let options = crate::Options::from_iter(["russ", "-d", "cli.db"]);
+        let config_file: ConfigFile = toml::from_str(
+            r##"
+            [[rules]]
+            title = "(?i)^sponsored"
+            action = "mark_read"
+
+            [[rules]]
+            author = "Bot$"
+            feed = "example\\.com"
+            action = "hide"
+            "##,
+        )
+        .unwrap();
+        assert!(!Config::resolve(&options, config_file)
+            .unwrap()
+            .rules
+            .is_empty());
+
+        let config_file: ConfigFile = toml::from_str(
+            r##"
+            [[rules]]
+            action = "hide"
+            "##,
+        )
+        .unwrap();
+        assert!(Config::resolve(&options, config_file).is_err());
+
+        assert!(toml::from_str::<ConfigFile>(
+            r##"
+            [[rules]]
+            title = "(unclosed"
+            action = "hide"
+            "##
+        )
+        .is_err());
+    }
+}
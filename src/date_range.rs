@@ -0,0 +1,222 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+
+/// the span of time the entry list is narrowed to, by published date,
+/// or by when russ first saw an entry if it has none.
+/// `start` is inclusive and `end` is exclusive, and either may be open.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn is_unbounded(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
+
+    /// parses what the user typed, with days starting at midnight in `now`'s time zone:
+    /// `today`, `yesterday`, `this week`, `this month`, `this year`,
+    /// `last 7 days` (or `7d`, and likewise weeks and months),
+    /// a day like `2021-03-14`, a month like `2021-03`,
+    /// or `START..END` between two of those, where either can be left out
+    pub fn parse<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Result<DateRange> {
+        let input = input.trim().to_lowercase();
+        let today = now.naive_local().date();
+        let tz = now.timezone();
+
+        if let Some((start, end)) = input.split_once("..") {
+            let start = match start.trim() {
+                "" => None,
+                start => Some(parse_period(start, today)?.0),
+            };
+            let end = match end.trim() {
+                "" => None,
+                end => Some(parse_period(end, today)?.1),
+            };
+
+            if let (Some(start), Some(end)) = (start, end) {
+                if start >= end {
+                    return Err(anyhow::anyhow!("{} ends before it starts", input));
+                }
+            }
+
+            return Ok(DateRange {
+                start: start.map(|start| midnight(&tz, start)),
+                end: end.map(|end| midnight(&tz, end)),
+            });
+        }
+
+        if let Some(duration) = parse_last(&input) {
+            let start = match duration {
+                Last::Days(days) => now.with_timezone(&Utc) - Duration::days(days),
+                Last::Months(months) => midnight(&tz, add_months(today, -months)),
+            };
+
+            return Ok(DateRange {
+                start: Some(start),
+                end: None,
+            });
+        }
+
+        let open_ended = matches!(input.as_str(), "this week" | "this month" | "this year");
+        let (start, end) = parse_period(&input, today)?;
+
+        Ok(DateRange {
+            start: Some(midnight(&tz, start)),
+            end: if open_ended {
+                None
+            } else {
+                Some(midnight(&tz, end))
+            },
+        })
+    }
+}
+
+enum Last {
+    Days(i64),
+    Months(i32),
+}
+
+/// `last 7 days`, `last 2 weeks`, `last 3 months`, `7d`, `2w`, `3m`, and so on
+fn parse_last(input: &str) -> Option<Last> {
+    let input = input.strip_prefix("last").unwrap_or(input).trim();
+    let unit_idx = input.find(|c: char| !c.is_ascii_digit())?;
+    let (n, unit) = input.split_at(unit_idx);
+    let n = n.parse::<i32>().ok()?;
+
+    match unit.trim() {
+        "d" | "day" | "days" => Some(Last::Days(n.into())),
+        "w" | "week" | "weeks" => Some(Last::Days(i64::from(n) * 7)),
+        "m" | "month" | "months" => Some(Last::Months(n)),
+        _ => None,
+    }
+}
+
+/// the first day of a named period or date, and the first day after it
+fn parse_period(input: &str, today: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+    let first_of_month = today.with_day(1).unwrap();
+
+    let period = match input {
+        "today" => (today, today.succ_opt().unwrap()),
+        "yesterday" => (today.pred_opt().unwrap(), today),
+        "this week" => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+            (monday, monday + Duration::weeks(1))
+        }
+        "this month" => (first_of_month, add_months(first_of_month, 1)),
+        "this year" => {
+            let new_year = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+            (new_year, add_months(new_year, 12))
+        }
+        _ => {
+            if let Ok(day) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+                (day, day.succ_opt().unwrap())
+            } else if let Ok(first_of_month) =
+                NaiveDate::parse_from_str(&format!("{}-01", input), "%Y-%m-%d")
+            {
+                (first_of_month, add_months(first_of_month, 1))
+            } else {
+                return Err(anyhow::anyhow!(
+                    "{} is not a date range. Try \"last 7 days\", \"this month\", or \"2021-01-01..2021-02-01\"",
+                    input
+                ));
+            }
+        }
+    };
+
+    Ok(period)
+}
+
+fn midnight<Tz: TimeZone>(tz: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    let naive_midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+    // a midnight skipped by daylight saving time is not worth an error
+    tz.from_local_datetime(&naive_midnight)
+        .earliest()
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&naive_midnight))
+}
+
+/// the same day `months` later, or earlier if negative,
+/// or the last day of that month if it has fewer days
+fn add_months(date: NaiveDate, months: i32) -> NaiveDate {
+    let months = date.year() * 12 + date.month0() as i32 + months;
+    let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn it_parses_date_ranges() {
+        // a thursday
+        let now = utc("2021-03-18T15:30:00Z");
+        let range = |input| DateRange::parse(input, now).unwrap();
+
+        assert_eq!(
+            range("today"),
+            DateRange {
+                start: Some(utc("2021-03-18T00:00:00Z")),
+                end: Some(utc("2021-03-19T00:00:00Z")),
+            }
+        );
+        assert_eq!(range("This Week").start, Some(utc("2021-03-15T00:00:00Z")));
+        assert_eq!(range("this month").start, Some(utc("2021-03-01T00:00:00Z")));
+        assert_eq!(range("this month").end, None);
+        assert_eq!(
+            range("last 7 days").start,
+            Some(utc("2021-03-11T15:30:00Z"))
+        );
+        assert_eq!(range("2w").start, Some(utc("2021-03-04T15:30:00Z")));
+        assert_eq!(range("3m").start, Some(utc("2020-12-18T00:00:00Z")));
+        assert_eq!(
+            range("2021-02"),
+            DateRange {
+                start: Some(utc("2021-02-01T00:00:00Z")),
+                end: Some(utc("2021-03-01T00:00:00Z")),
+            }
+        );
+        assert_eq!(
+            range("2021-01-01..2021-01-31"),
+            DateRange {
+                start: Some(utc("2021-01-01T00:00:00Z")),
+                end: Some(utc("2021-02-01T00:00:00Z")),
+            }
+        );
+        assert_eq!(
+            range("..2020-12"),
+            DateRange {
+                start: None,
+                end: Some(utc("2021-01-01T00:00:00Z")),
+            }
+        );
+
+        assert!(DateRange::parse("2021-02..2021-01", now).is_err());
+        assert!(DateRange::parse("last tuesday", now).is_err());
+    }
+
+    #[test]
+    fn months_are_clamped_to_their_last_day() {
+        let may_31 = NaiveDate::from_ymd_opt(2021, 5, 31).unwrap();
+
+        assert_eq!(
+            add_months(may_31, -3),
+            NaiveDate::from_ymd_opt(2021, 2, 28).unwrap()
+        );
+        assert_eq!(
+            add_months(may_31, 9),
+            NaiveDate::from_ymd_opt(2022, 2, 28).unwrap()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::modes::EntrySort;
+use crate::rss::EntryMeta;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+
+/// a row of the entry list
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryRow {
+    /// the header over one day's entries, like "Today"
+    Day(String),
+    /// an index into the entries
+    Entry(usize),
+}
+
+/// the entries, with a header before each day's, by the day they are sorted by in `now`'s timezone.
+/// days before yesterday are shown in `day_format`.
+/// entries sorted by title get no headers, as their days are all mixed up
+pub fn entry_rows<Tz: TimeZone>(
+    entries: &[EntryMeta],
+    entry_sort: EntrySort,
+    now: DateTime<Tz>,
+    day_format: &str,
+) -> Vec<EntryRow> {
+    if entry_sort == EntrySort::Title {
+        return (0..entries.len()).map(EntryRow::Entry).collect();
+    }
+
+    let tz = now.timezone();
+    let today = now.naive_local().date();
+
+    let mut rows = vec![];
+    let mut last_day = None;
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let day = entry_date(entry, entry_sort)
+            .with_timezone(&tz)
+            .naive_local()
+            .date();
+
+        if last_day != Some(day) {
+            rows.push(EntryRow::Day(day_label(day, today, day_format)));
+            last_day = Some(day);
+        }
+
+        rows.push(EntryRow::Entry(idx));
+    }
+
+    rows
+}
+
+/// the date `entry_sort` sorts the entry by: when it was published,
+/// or when russ first saw it if it does not say or if entries are sorted that way
+pub fn entry_date(entry: &EntryMeta, entry_sort: EntrySort) -> DateTime<Utc> {
+    match entry_sort {
+        EntrySort::Inserted => entry.inserted_at,
+        _ => entry.pub_date.unwrap_or(entry.inserted_at),
+    }
+}
+
+/// "Today", "Yesterday", or the date, like "2024-05-12"
+fn day_label(day: NaiveDate, today: NaiveDate, day_format: &str) -> String {
+    if day == today {
+        "Today".to_string()
+    } else if day == today - Duration::days(1) {
+        "Yesterday".to_string()
+    } else {
+        day.format(day_format).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn entry(pub_date: &str) -> EntryMeta {
+        let utc = |date: &str| {
+            DateTime::parse_from_rfc3339(date)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        EntryMeta {
+            pub_date: Some(utc(pub_date)),
+            inserted_at: utc("2024-05-14T12:00:00Z"),
+            ..EntryMeta::for_test("Entry")
+        }
+    }
+
+    #[test]
+    fn entries_are_grouped_by_their_local_day() {
+        let entries = vec![
+            entry("2024-05-14T09:00:00Z"),
+            // after midnight two hours east of UTC, so today there
+            entry("2024-05-13T23:00:00Z"),
+            entry("2024-05-13T08:00:00Z"),
+            entry("2024-05-12T08:00:00Z"),
+        ];
+        let now = DateTime::parse_from_rfc3339("2024-05-14T18:00:00+02:00")
+            .unwrap()
+            .with_timezone(&FixedOffset::east(2 * 3600));
+
+        assert_eq!(
+            entry_rows(&entries, EntrySort::Newest, now, "%Y-%m-%d"),
+            vec![
+                EntryRow::Day("Today".to_string()),
+                EntryRow::Entry(0),
+                EntryRow::Entry(1),
+                EntryRow::Day("Yesterday".to_string()),
+                EntryRow::Entry(2),
+                EntryRow::Day("2024-05-12".to_string()),
+                EntryRow::Entry(3),
+            ]
+        );
+
+        assert_eq!(
+            entry_rows(&entries, EntrySort::Inserted, now, "%Y-%m-%d"),
+            vec![
+                EntryRow::Day("Today".to_string()),
+                EntryRow::Entry(0),
+                EntryRow::Entry(1),
+                EntryRow::Entry(2),
+                EntryRow::Entry(3),
+            ]
+        );
+
+        assert_eq!(
+            entry_rows(&entries, EntrySort::Title, now, "%Y-%m-%d"),
+            (0..4).map(EntryRow::Entry).collect::<Vec<_>>()
+        );
+    }
+}
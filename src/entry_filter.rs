@@ -0,0 +1,91 @@
+use crate::rss::EntryMeta;
+
+/// what part of an entry the filter looks at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Title,
+    Author,
+    Category,
+}
+
+/// the text the entry list is narrowed to, as typed after `/`.
+/// `author:` followed by text matches authors, `category:` followed by text matches categories,
+/// and anything else matches titles.
+/// case is ignored, and an empty filter matches every entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryFilter {
+    field: Field,
+    text: String,
+}
+
+impl EntryFilter {
+    pub fn parse(input: &str) -> EntryFilter {
+        let input = input.to_lowercase();
+
+        let prefixes = [("author:", Field::Author), ("category:", Field::Category)];
+
+        prefixes
+            .iter()
+            .find_map(|(prefix, field)| {
+                input.strip_prefix(prefix).map(|text| EntryFilter {
+                    field: *field,
+                    text: text.trim().to_owned(),
+                })
+            })
+            .unwrap_or(EntryFilter {
+                field: Field::Title,
+                text: input,
+            })
+    }
+
+    pub fn matches(&self, entry: &EntryMeta) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+
+        let matches = |value: &String| value.to_lowercase().contains(&self.text);
+
+        match self.field {
+            Field::Title => entry.title.as_ref().is_some_and(matches),
+            Field::Author => entry.author.as_ref().is_some_and(matches),
+            Field::Category => entry.categories.iter().any(matches),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, author: Option<&str>, categories: &[&str]) -> EntryMeta {
+        EntryMeta {
+            author: author.map(|author| author.to_string()),
+            categories: categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect(),
+            ..EntryMeta::for_test(title)
+        }
+    }
+
+    #[test]
+    fn it_matches_titles_authors_or_categories() {
+        let by_jane = entry("Async Rust", Some("Jane Doe"), &["Programming", "Rust"]);
+        let anonymous = entry("Jane's favourite crates", None, &[]);
+
+        let filter = EntryFilter::parse("RUST");
+        assert!(filter.matches(&by_jane));
+        assert!(!filter.matches(&anonymous));
+
+        let filter = EntryFilter::parse("author: jane d");
+        assert!(filter.matches(&by_jane));
+        assert!(!filter.matches(&anonymous));
+
+        let filter = EntryFilter::parse("Category:prog");
+        assert!(filter.matches(&by_jane));
+        assert!(!filter.matches(&anonymous));
+
+        assert!(EntryFilter::parse("author:").matches(&anonymous));
+        assert!(EntryFilter::parse("").matches(&anonymous));
+    }
+}
@@ -0,0 +1,346 @@
+use serde::Deserialize;
+use std::str::FromStr;
+use tui::style::Style;
+use tui::text::Span;
+use unicode_width::UnicodeWidthChar;
+
+/// how the entry list shows entries, from a format like Newsboat's `articlelist-format`,
+/// such as `%f %4D %?T?%-16T &?%t`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryFormat(Vec<Part>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// `%t`, or `%20t` padded to 20 columns on the left, or `%-20t` on the right.
+    /// longer values are cut to fit
+    Field {
+        field: Field,
+        width: Option<isize>,
+    },
+    /// `%?a? by %a&?`: what comes before `&` if the field is not empty, and what comes after it if it is
+    Conditional {
+        field: Field,
+        then: Vec<Part>,
+        otherwise: Vec<Part>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    /// `D`
+    Date,
+    /// `t`
+    Title,
+    /// `a`
+    Author,
+    /// `T`, the feed's title
+    Feed,
+    /// `f`, `N` if the entry is unread and `*` if it is starred
+    Flags,
+    /// `L`, like `3 min`
+    ReadingTime,
+    /// `n`, `[note]` if the entry has one
+    Note,
+    /// `u`, `[updated]` if the feed edited the entry since it was last read
+    Updated,
+}
+
+impl Field {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'D' => Some(Field::Date),
+            't' => Some(Field::Title),
+            'a' => Some(Field::Author),
+            'T' => Some(Field::Feed),
+            'f' => Some(Field::Flags),
+            'L' => Some(Field::ReadingTime),
+            'n' => Some(Field::Note),
+            'u' => Some(Field::Updated),
+            _ => None,
+        }
+    }
+}
+
+/// an entry's fields, as the entry list shows them. missing ones are empty
+#[derive(Clone, Debug, Default)]
+pub struct EntryFields {
+    pub date: String,
+    pub title: String,
+    pub author: String,
+    pub feed: String,
+    pub flags: String,
+    pub reading_time: String,
+    pub note: String,
+    pub updated: String,
+}
+
+impl EntryFields {
+    fn get(&self, field: Field) -> &str {
+        match field {
+            Field::Date => &self.date,
+            Field::Title => &self.title,
+            Field::Author => &self.author,
+            Field::Feed => &self.feed,
+            Field::Flags => &self.flags,
+            Field::ReadingTime => &self.reading_time,
+            Field::Note => &self.note,
+            Field::Updated => &self.updated,
+        }
+    }
+}
+
+impl EntryFormat {
+    /// the entry's row, with the title in `title_style` and everything else in `style`
+    pub fn spans(
+        &self,
+        fields: &EntryFields,
+        title_style: Style,
+        style: Style,
+    ) -> Vec<Span<'static>> {
+        let mut spans = vec![];
+        push_spans(&self.0, fields, title_style, style, &mut spans);
+        spans
+    }
+}
+
+fn push_spans(
+    parts: &[Part],
+    fields: &EntryFields,
+    title_style: Style,
+    style: Style,
+    spans: &mut Vec<Span<'static>>,
+) {
+    for part in parts {
+        match part {
+            Part::Text(text) => spans.push(Span::styled(text.clone(), style)),
+            Part::Field { field, width } => {
+                let value = fields.get(*field);
+                let value = match width {
+                    Some(width) => fit(value, *width),
+                    None => value.to_owned(),
+                };
+                let style = if *field == Field::Title {
+                    title_style
+                } else {
+                    style
+                };
+                spans.push(Span::styled(value, style));
+            }
+            Part::Conditional {
+                field,
+                then,
+                otherwise,
+            } => {
+                let parts = if fields.get(*field).trim().is_empty() {
+                    otherwise
+                } else {
+                    then
+                };
+                push_spans(parts, fields, title_style, style, spans);
+            }
+        }
+    }
+}
+
+/// `value` cut or padded to `width` columns, padded on the left if `width` is positive
+fn fit(value: &str, width: isize) -> String {
+    let columns = width.unsigned_abs();
+    let mut fitted = String::new();
+    let mut fitted_columns = 0;
+
+    for c in value.chars() {
+        let c_columns = c.width().unwrap_or(0);
+        if fitted_columns + c_columns > columns {
+            break;
+        }
+        fitted.push(c);
+        fitted_columns += c_columns;
+    }
+
+    let padding = " ".repeat(columns - fitted_columns);
+
+    if width > 0 {
+        padding + &fitted
+    } else {
+        fitted + &padding
+    }
+}
+
+impl FromStr for EntryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+        let (parts, end) = parse_parts(&mut chars, &[])?;
+        debug_assert!(end.is_none());
+        Ok(EntryFormat(parts))
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+/// the parts up to the first of `terminators`, which is returned if there was one
+fn parse_parts(
+    chars: &mut Chars,
+    terminators: &[char],
+) -> anyhow::Result<(Vec<Part>, Option<char>)> {
+    let mut parts = vec![];
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        if terminators.contains(&c) {
+            if !text.is_empty() {
+                parts.push(Part::Text(text));
+            }
+            return Ok((parts, Some(c)));
+        }
+
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('%') => {
+                chars.next();
+                text.push('%');
+                continue;
+            }
+            Some('?') => {
+                chars.next();
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(parse_conditional(chars)?);
+                continue;
+            }
+            _ => (),
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+        parts.push(parse_field(chars)?);
+    }
+
+    if !terminators.is_empty() {
+        return Err(anyhow::anyhow!(
+            "A %? in the entry format is missing its closing ?"
+        ));
+    }
+
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    Ok((parts, None))
+}
+
+/// what follows a `%`, like `-20t`
+fn parse_field(chars: &mut Chars) -> anyhow::Result<Part> {
+    let mut width = String::new();
+
+    if chars.peek() == Some(&'-') {
+        width.push('-');
+        chars.next();
+    }
+
+    while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        width.push(*digit);
+        chars.next();
+    }
+
+    let field = field(chars.next())?;
+
+    let width = match width.as_str() {
+        "" => None,
+        "-" => return Err(anyhow::anyhow!("%- in the entry format needs a width")),
+        width => Some(width.parse()?),
+    };
+
+    Ok(Part::Field { field, width })
+}
+
+/// what follows a `%?`, like `a? by %a&?`
+fn parse_conditional(chars: &mut Chars) -> anyhow::Result<Part> {
+    let field = field(chars.next())?;
+
+    if chars.next() != Some('?') {
+        return Err(anyhow::anyhow!(
+            "%? in the entry format needs a ? after its field, like %?a? by %a&?"
+        ));
+    }
+
+    let (then, end) = parse_parts(chars, &['&', '?'])?;
+    let otherwise = if end == Some('&') {
+        parse_parts(chars, &['?'])?.0
+    } else {
+        vec![]
+    };
+
+    Ok(Part::Conditional {
+        field,
+        then,
+        otherwise,
+    })
+}
+
+fn field(c: Option<char>) -> anyhow::Result<Field> {
+    match c {
+        Some(c) => Field::from_char(c)
+            .ok_or_else(|| anyhow::anyhow!("%{} is not a valid entry format field", c)),
+        None => Err(anyhow::anyhow!(
+            "The entry format ends in the middle of a field"
+        )),
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryFormat {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        EntryFormat::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(format: &str, fields: &EntryFields) -> String {
+        EntryFormat::from_str(format)
+            .unwrap()
+            .spans(fields, Style::default(), Style::default())
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn it_formats_fields_with_widths_and_conditionals() {
+        let fields = EntryFields {
+            date: "3h".to_string(),
+            title: "Rust 2024".to_string(),
+            author: "Ferris".to_string(),
+            feed: "This Week in Rust".to_string(),
+            flags: "N ".to_string(),
+            updated: "[updated]".to_string(),
+            ..EntryFields::default()
+        };
+
+        assert_eq!(
+            text("%f %4D %-8T|%t%?a? by %a&?%?n? %n&?", &fields),
+            "N    3h This Wee|Rust 2024 by Ferris"
+        );
+        assert_eq!(text("%?L?(%L)&no time? 100%%", &fields), "no time 100%");
+        assert_eq!(text("%t%?u? %u&?", &fields), "Rust 2024 [updated]");
+
+        assert!(EntryFormat::from_str("%x").is_err());
+        assert!(EntryFormat::from_str("%?a? by %a").is_err());
+        assert!(EntryFormat::from_str("%-t").is_err());
+        assert!(EntryFormat::from_str("title %").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// how many errors are kept, after which the oldest ones are dropped
+const MAX_LOGGED_ERRORS: usize = 1000;
+
+/// an error, with when it happened and the feed it happened to, if any
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedError {
+    pub at: DateTime<Utc>,
+    /// the feed's title, or its link if it has none
+    pub feed: Option<String>,
+    /// the error and its causes
+    pub message: String,
+}
+
+impl LoggedError {
+    /// the message, broken between words into lines at most `width` columns wide.
+    /// words wider than that are broken too
+    pub fn wrapped_message(&self, width: usize) -> Vec<String> {
+        let width = width.max(1);
+        let mut lines = vec![];
+
+        for message_line in self.message.lines() {
+            let mut line = String::new();
+            let mut line_width = 0;
+
+            for word in message_line.split(' ') {
+                if line_width > 0 && line_width + 1 + word.width() > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+
+                if line_width > 0 {
+                    line.push(' ');
+                    line_width += 1;
+                }
+
+                for c in word.chars() {
+                    let c_width = c.width().unwrap_or(0);
+                    if line_width > 0 && line_width + c_width > width {
+                        lines.push(std::mem::take(&mut line));
+                        line_width = 0;
+                    }
+                    line.push(c);
+                    line_width += c_width;
+                }
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+/// every error since russ started, which the error flash only shows until the next key.
+/// each one is also appended to `path`, if there is one
+#[derive(Debug)]
+pub struct ErrorLog {
+    errors: Vec<LoggedError>,
+    path: Option<PathBuf>,
+}
+
+impl ErrorLog {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        ErrorLog {
+            errors: vec![],
+            path,
+        }
+    }
+
+    /// oldest first
+    pub fn errors(&self) -> &[LoggedError] {
+        &self.errors
+    }
+
+    pub fn push(&mut self, e: &anyhow::Error, feed: Option<String>, at: DateTime<Utc>) {
+        let logged_error = LoggedError {
+            at,
+            feed,
+            message: format!("{:#}", e),
+        };
+
+        if let Some(path) = &self.path {
+            if let Err(write_error) = append_line(path, &log_line(&logged_error)) {
+                // only kept here, as writing it to the file would fail the same way
+                let message = format!(
+                    "Unable to write to the error log at {}: {}",
+                    path.display(),
+                    write_error
+                );
+                self.keep(LoggedError {
+                    at,
+                    feed: None,
+                    message,
+                });
+            }
+        }
+
+        self.keep(logged_error);
+    }
+
+    fn keep(&mut self, logged_error: LoggedError) {
+        if self.errors.len() >= MAX_LOGGED_ERRORS {
+            self.errors.remove(0);
+        }
+        self.errors.push(logged_error);
+    }
+}
+
+/// the error on one line, like `2024-05-14T12:00:00+00:00 [Example] Unable to fetch: timed out`
+fn log_line(logged_error: &LoggedError) -> String {
+    let message = logged_error
+        .message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let at = logged_error.at.to_rfc3339_opts(SecondsFormat::Secs, false);
+
+    match &logged_error.feed {
+        Some(feed) => format!("{} [{}] {}", at, feed, message),
+        None => format!("{} {}", at, message),
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn errors_are_kept_and_appended_to_the_file() {
+        let path = std::env::temp_dir().join(format!("russ-error-log-{}", std::process::id()));
+        let at = DateTime::parse_from_rfc3339("2024-05-14T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let mut error_log = ErrorLog::new(Some(path.clone()));
+
+        let e = Err::<(), _>(anyhow::anyhow!("timed out\nafter 10s"))
+            .context("Unable to fetch")
+            .unwrap_err();
+        error_log.push(&e, Some("Example".to_string()), at);
+        error_log.push(&anyhow::anyhow!("There is nothing to play"), None, at);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            written,
+            "2024-05-14T12:00:00+00:00 [Example] Unable to fetch: timed out after 10s\n\
+             2024-05-14T12:00:00+00:00 There is nothing to play\n"
+        );
+        assert_eq!(
+            error_log.errors()[0],
+            LoggedError {
+                at,
+                feed: Some("Example".to_string()),
+                message: "Unable to fetch: timed out\nafter 10s".to_string(),
+            }
+        );
+
+        let mut error_log = ErrorLog::new(None);
+        for i in 0..MAX_LOGGED_ERRORS + 1 {
+            error_log.push(&anyhow::anyhow!("error {}", i), None, at);
+        }
+        assert_eq!(error_log.errors().len(), MAX_LOGGED_ERRORS);
+        assert_eq!(error_log.errors()[0].message, "error 1");
+    }
+
+    #[test]
+    fn messages_are_wrapped_between_words() {
+        let logged_error = LoggedError {
+            at: Utc::now(),
+            feed: None,
+            message: "Unable to fetch https://example.com/feed.xml: timed out\nafter 10s"
+                .to_string(),
+        };
+
+        assert_eq!(
+            logged_error.wrapped_message(16),
+            vec![
+                "Unable to fetch",
+                "https://example.",
+                "com/feed.xml:",
+                "timed out",
+                "after 10s",
+            ]
+        );
+    }
+}
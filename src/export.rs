@@ -0,0 +1,468 @@
+use crate::rss::EntryMeta;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// how wide saved Markdown is wrapped
+const MARKDOWN_WIDTH: usize = 80;
+/// file names are cut off after this many characters of the title
+const MAX_FILE_NAME_LEN: usize = 60;
+
+/// what `w` saves entries as
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl SaveFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SaveFormat::Markdown => "md",
+            SaveFormat::Html => "html",
+        }
+    }
+}
+
+/// what `russ export-starred` and `russ publish` write entries as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Atom,
+    Rss,
+    Markdown,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "atom" => Ok(ExportFormat::Atom),
+            "rss" => Ok(ExportFormat::Rss),
+            "md" => Ok(ExportFormat::Markdown),
+            _ => Err(anyhow::anyhow!(
+                "{} is not a valid export format, use json, atom, rss, or md",
+                s
+            )),
+        }
+    }
+}
+
+/// an entry with everything that is saved or exported with it
+pub struct ExportedEntry<'a> {
+    pub meta: &'a EntryMeta,
+    pub feed_title: Option<&'a str>,
+    /// the entry's full article, content, or description, whichever it has first
+    pub html: &'a str,
+    /// what the user wrote about the entry. kept out of Atom and RSS, which are for publishing
+    pub note: Option<&'a str>,
+}
+
+/// an entry as the JSON export has it
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    title: String,
+    link: Option<&'a str>,
+    author: Option<&'a str>,
+    pub_date: Option<String>,
+    feed: Option<&'a str>,
+    note: Option<&'a str>,
+    content: &'a str,
+}
+
+impl ExportedEntry<'_> {
+    /// the title as russ shows it, with its entities decoded
+    fn title(&self) -> String {
+        self.meta
+            .title
+            .as_deref()
+            .map(crate::sanitize::decode_entities)
+            .unwrap_or_else(|| "Untitled".to_owned())
+    }
+
+    /// the entry's link, or a made-up one for entries without, as Atom entries need an id
+    fn id(&self) -> String {
+        self.meta
+            .link
+            .clone()
+            .unwrap_or_else(|| format!("urn:russ:entry:{}", self.meta.id))
+    }
+
+    /// when the entry was published, or when russ first saw it
+    fn date(&self) -> DateTime<Utc> {
+        self.meta.pub_date.unwrap_or(self.meta.inserted_at)
+    }
+
+    fn pub_date(&self) -> Option<String> {
+        self.meta
+            .pub_date
+            .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+    }
+
+    /// the title as a heading, what is known about the entry as a list, then the content
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", self.title());
+
+        let details = [
+            (
+                "link",
+                self.meta.link.as_ref().map(|link| format!("<{}>", link)),
+            ),
+            (
+                "feed",
+                self.feed_title.map(|feed_title| feed_title.to_owned()),
+            ),
+            ("author", self.meta.author.clone()),
+            ("published", self.pub_date()),
+            ("note", self.note.map(|note| note.to_owned())),
+        ];
+
+        for (name, value) in details {
+            if let Some(value) = value {
+                markdown.push_str(&format!("- {}: {}\n", name, value));
+            }
+        }
+
+        markdown.push('\n');
+        markdown.push_str(html2text::from_read(self.html.as_bytes(), MARKDOWN_WIDTH).trim_end());
+        markdown.push('\n');
+        markdown
+    }
+
+    /// a page of its own, with the title linking to the entry
+    pub fn to_html(&self) -> String {
+        let title = escape_html(&self.title());
+
+        let heading = match &self.meta.link {
+            Some(link) => format!("<a href=\"{}\">{}</a>", escape_html(link), title),
+            None => title.clone(),
+        };
+
+        let details = [
+            self.feed_title.map(|feed_title| feed_title.to_owned()),
+            self.meta
+                .author
+                .as_ref()
+                .map(|author| format!("by {}", author)),
+            self.pub_date(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|detail| escape_html(&detail))
+        .collect::<Vec<_>>()
+        .join(" &middot; ");
+
+        let note = self
+            .note
+            .map(|note| format!("<blockquote>{}</blockquote>\n", escape_html(note)))
+            .unwrap_or_default();
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n{}{}\n</body>\n</html>\n",
+            title, heading, details, note, self.html
+        )
+    }
+
+    /// writes the entry into `directory`, named after its title, and returns where it went.
+    /// an existing file is never overwritten.
+    pub fn save(&self, directory: &Path, format: SaveFormat) -> Result<PathBuf> {
+        let path = directory.join(format!(
+            "{}.{}",
+            file_name(&self.title(), self.meta.id),
+            format.extension()
+        ));
+
+        if path.exists() {
+            return Err(anyhow::anyhow!("{} already exists", path.display()));
+        }
+
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Unable to create {}", directory.display()))?;
+
+        let contents = match format {
+            SaveFormat::Markdown => self.to_markdown(),
+            SaveFormat::Html => self.to_html(),
+        };
+
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Unable to write {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    fn to_json(&self) -> JsonEntry<'_> {
+        JsonEntry {
+            title: self.title(),
+            link: self.meta.link.as_deref(),
+            author: self.meta.author.as_deref(),
+            pub_date: self.meta.pub_date.map(|date| date.to_rfc3339()),
+            feed: self.feed_title,
+            note: self.note,
+            content: self.html,
+        }
+    }
+
+    fn to_atom_entry(&self) -> String {
+        let mut atom = format!(
+            "  <entry>\n    <title>{}</title>\n    <id>{}</id>\n    <updated>{}</updated>\n",
+            escape_html(&self.title()),
+            escape_html(&self.id()),
+            self.date().to_rfc3339()
+        );
+
+        if let Some(link) = &self.meta.link {
+            atom.push_str(&format!("    <link href=\"{}\"/>\n", escape_html(link)));
+        }
+        if let Some(author) = &self.meta.author {
+            atom.push_str(&format!(
+                "    <author><name>{}</name></author>\n",
+                escape_html(author)
+            ));
+        }
+        if let Some(feed_title) = self.feed_title {
+            atom.push_str(&format!(
+                "    <source><title>{}</title></source>\n",
+                escape_html(feed_title)
+            ));
+        }
+
+        atom.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n  </entry>\n",
+            escape_html(self.html)
+        ));
+
+        atom
+    }
+
+    fn to_rss_item(&self) -> String {
+        let mut rss = format!(
+            "    <item>\n      <title>{}</title>\n",
+            escape_html(&self.title())
+        );
+
+        match &self.meta.link {
+            Some(link) => rss.push_str(&format!(
+                "      <link>{}</link>\n      <guid>{}</guid>\n",
+                escape_html(link),
+                escape_html(link)
+            )),
+            None => rss.push_str(&format!(
+                "      <guid isPermaLink=\"false\">{}</guid>\n",
+                self.id()
+            )),
+        }
+
+        if let Some(author) = &self.meta.author {
+            rss.push_str(&format!(
+                "      <dc:creator>{}</dc:creator>\n",
+                escape_html(author)
+            ));
+        }
+        if let Some(feed_title) = self.feed_title {
+            rss.push_str(&format!(
+                "      <category>{}</category>\n",
+                escape_html(feed_title)
+            ));
+        }
+
+        rss.push_str(&format!(
+            "      <pubDate>{}</pubDate>\n      <description>{}</description>\n    </item>\n",
+            self.date().to_rfc2822(),
+            escape_html(self.html)
+        ));
+
+        rss
+    }
+}
+
+/// `entries` as one JSON array, one Atom or RSS feed called `title`, or one Markdown document
+pub fn export_entries(entries: &[ExportedEntry], format: ExportFormat, title: &str) -> String {
+    match format {
+        ExportFormat::Json => {
+            let entries = entries
+                .iter()
+                .map(ExportedEntry::to_json)
+                .collect::<Vec<_>>();
+            format!(
+                "{}\n",
+                serde_json::to_string(&entries).expect("entries serialize to JSON")
+            )
+        }
+        ExportFormat::Atom => {
+            let mut atom = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                <feed xmlns=\"http://www.w3.org/2005/Atom\">\n  \
+                <title>{}</title>\n  \
+                <id>urn:russ:{}</id>\n  \
+                <updated>{}</updated>\n  \
+                <generator>russ</generator>\n",
+                escape_html(title),
+                file_name(title, 0),
+                updated(entries).to_rfc3339()
+            );
+
+            for entry in entries {
+                atom.push_str(&entry.to_atom_entry());
+            }
+
+            atom.push_str("</feed>\n");
+            atom
+        }
+        ExportFormat::Rss => {
+            let mut rss = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                <rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n  \
+                <channel>\n    \
+                <title>{}</title>\n    \
+                <description>{}</description>\n    \
+                <lastBuildDate>{}</lastBuildDate>\n    \
+                <generator>russ</generator>\n",
+                escape_html(title),
+                escape_html(title),
+                updated(entries).to_rfc2822()
+            );
+
+            for entry in entries {
+                rss.push_str(&entry.to_rss_item());
+            }
+
+            rss.push_str("  </channel>\n</rss>\n");
+            rss
+        }
+        ExportFormat::Markdown => entries
+            .iter()
+            .map(ExportedEntry::to_markdown)
+            .collect::<Vec<_>>()
+            .join("\n---\n\n"),
+    }
+}
+
+/// a feed changed when its newest entry did
+fn updated(entries: &[ExportedEntry]) -> DateTime<Utc> {
+    entries
+        .iter()
+        .map(ExportedEntry::date)
+        .max()
+        .unwrap_or_else(Utc::now)
+}
+
+/// the title in lowercase, with anything but letters and digits turned into dashes,
+/// like `why-rust-2021`. a title without any is named after the entry's id instead
+fn file_name(title: &str, entry_id: crate::rss::EntryId) -> String {
+    let file_name = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(MAX_FILE_NAME_LEN)
+        .collect::<String>();
+
+    let file_name = file_name.trim_end_matches('-');
+
+    if file_name.is_empty() {
+        format!("entry-{}", entry_id)
+    } else {
+        file_name.to_owned()
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn entry_meta(title: &str) -> EntryMeta {
+        EntryMeta {
+            id: 7,
+            author: Some("Jane".to_string()),
+            pub_date: Some(
+                DateTime::parse_from_rfc3339("2021-03-04T05:06:07Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            link: Some("https://example.com/a?b=1&c=2".to_string()),
+            ..EntryMeta::for_test(title)
+        }
+    }
+
+    #[test]
+    fn entries_are_saved_as_markdown_or_html() {
+        let meta = entry_meta("Tom & Jerry: <the> story!");
+        let entry = ExportedEntry {
+            meta: &meta,
+            feed_title: Some("Example"),
+            html: "<p>Some <b>bold</b> text</p>",
+            note: Some("read <later>"),
+        };
+
+        assert_eq!(
+            entry.to_markdown(),
+            "# Tom & Jerry: <the> story!\n\n- link: <https://example.com/a?b=1&c=2>\n- feed: Example\n- author: Jane\n- published: 2021-03-04 05:06 UTC\n- note: read <later>\n\nSome bold text\n"
+        );
+
+        let html = entry.to_html();
+        assert!(html.contains("<title>Tom &amp; Jerry: &lt;the&gt; story!</title>"));
+        assert!(html.contains("<h1><a href=\"https://example.com/a?b=1&amp;c=2\">"));
+        assert!(html.contains("<p>Example &middot; by Jane &middot; 2021-03-04 05:06 UTC</p>"));
+        assert!(html
+            .contains("<blockquote>read &lt;later&gt;</blockquote>\n<p>Some <b>bold</b> text</p>"));
+
+        let directory =
+            std::env::temp_dir().join(format!("russ-saved-entries-{}", std::process::id()));
+        let path = entry.save(&directory, SaveFormat::Markdown).unwrap();
+        assert_eq!(path, directory.join("tom-jerry-the-story.md"));
+        assert!(entry.save(&directory, SaveFormat::Markdown).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(file_name("!!!", 7), "entry-7");
+    }
+
+    #[test]
+    fn entries_are_exported_as_json_atom_or_markdown() {
+        let meta = entry_meta("Tom &amp; Jerry");
+        let entries = [ExportedEntry {
+            meta: &meta,
+            feed_title: Some("Example"),
+            html: "<p>Hi</p>",
+            note: Some("why"),
+        }];
+
+        assert_eq!(
+            export_entries(&entries, ExportFormat::Json, "starred"),
+            "[{\"title\":\"Tom & Jerry\",\"link\":\"https://example.com/a?b=1&c=2\",\"author\":\"Jane\",\"pub_date\":\"2021-03-04T05:06:07+00:00\",\"feed\":\"Example\",\"note\":\"why\",\"content\":\"<p>Hi</p>\"}]\n"
+        );
+
+        let atom = export_entries(&entries, ExportFormat::Atom, "russ starred entries");
+        assert!(atom.contains("<id>urn:russ:russ-starred-entries</id>"));
+        assert!(atom.contains("<updated>2021-03-04T05:06:07+00:00</updated>\n  <generator>"));
+        assert!(atom.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(atom.contains("<link href=\"https://example.com/a?b=1&amp;c=2\"/>"));
+        assert!(atom.contains("<content type=\"html\">&lt;p&gt;Hi&lt;/p&gt;</content>"));
+        assert!(!atom.contains("why"));
+
+        assert!(export_entries(&entries, ExportFormat::Markdown, "starred")
+            .starts_with("# Tom & Jerry\n"));
+        let rss = export_entries(&entries, ExportFormat::Rss, "russ: news");
+        assert!(rss.contains("<lastBuildDate>Thu, 04 Mar 2021 05:06:07 +0000</lastBuildDate>"));
+        assert!(rss.contains("<guid>https://example.com/a?b=1&amp;c=2</guid>"));
+        assert!(rss.contains("<dc:creator>Jane</dc:creator>"));
+        assert!(rss.contains("<description>&lt;p&gt;Hi&lt;/p&gt;</description>"));
+
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
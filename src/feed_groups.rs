@@ -0,0 +1,196 @@
+use crate::rss::{Feed, FeedId, VirtualFeed};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// a tag's group of feeds, or `None` for the feeds without tags
+pub type Group = Option<String>;
+
+/// a line in the feed pane
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeedRow {
+    Virtual(VirtualFeed),
+    Group {
+        group: Group,
+        feeds_len: usize,
+        collapsed: bool,
+    },
+    /// an index into the feeds list
+    Feed {
+        feed_idx: usize,
+    },
+}
+
+/// the indexes of the feeds in `group`, in feed list order
+pub fn group_feed_indexes(
+    feeds: &[Feed],
+    tags: &HashMap<FeedId, Vec<String>>,
+    group: &Group,
+) -> Vec<usize> {
+    feeds
+        .iter()
+        .enumerate()
+        .filter(|(_, feed)| {
+            let feed_tags = tags.get(&feed.id).map(|t| t.as_slice()).unwrap_or_default();
+            match group {
+                Some(tag) => feed_tags.contains(tag),
+                None => feed_tags.is_empty(),
+            }
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// lays out the feed pane. The virtual feeds come first, followed by the `smart_feeds_len` smart feeds.
+/// Without any tags the rest is the flat feed list.
+/// With tags, every tag gets a header followed by its feeds, sorted by tag,
+/// and feeds without tags come last under their own header.
+/// A feed with several tags appears under each of them.
+pub fn feed_rows(
+    feeds: &[Feed],
+    tags: &HashMap<FeedId, Vec<String>>,
+    collapsed: &HashSet<Group>,
+    smart_feeds_len: usize,
+) -> Vec<FeedRow> {
+    let mut tag_names = feeds
+        .iter()
+        .flat_map(|feed| tags.get(&feed.id).into_iter().flatten())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    tag_names.sort_by_key(|tag| tag.to_lowercase());
+
+    let mut rows = vec![];
+
+    if feeds.is_empty() {
+        return rows;
+    }
+
+    rows.push(FeedRow::Virtual(VirtualFeed::AllEntries));
+    rows.push(FeedRow::Virtual(VirtualFeed::Starred));
+    rows.push(FeedRow::Virtual(VirtualFeed::RecentlyRead));
+    rows.extend((0..smart_feeds_len).map(|idx| FeedRow::Virtual(VirtualFeed::Smart(idx))));
+
+    if tag_names.is_empty() {
+        rows.extend((0..feeds.len()).map(|feed_idx| FeedRow::Feed { feed_idx }));
+        return rows;
+    }
+
+    let groups = tag_names
+        .into_iter()
+        .map(|tag| Some(tag.to_owned()))
+        .chain(std::iter::once(None));
+
+    for group in groups {
+        let feed_indexes = group_feed_indexes(feeds, tags, &group);
+
+        if feed_indexes.is_empty() {
+            continue;
+        }
+
+        let is_collapsed = collapsed.contains(&group);
+
+        rows.push(FeedRow::Group {
+            group,
+            feeds_len: feed_indexes.len(),
+            collapsed: is_collapsed,
+        });
+
+        if !is_collapsed {
+            rows.extend(
+                feed_indexes
+                    .into_iter()
+                    .map(|feed_idx| FeedRow::Feed { feed_idx }),
+            );
+        }
+    }
+
+    rows
+}
+
+/// splits comma separated tags, dropping blanks and duplicates
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+
+    for tag in input.split(',').map(|tag| tag.trim()) {
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_owned());
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(id: FeedId) -> Feed {
+        Feed {
+            title: Some(format!("feed {}", id)),
+            ..Feed::for_test(id)
+        }
+    }
+
+    #[test]
+    fn feeds_without_tags_are_a_flat_list() {
+        let feeds = vec![feed(1), feed(2)];
+        let rows = feed_rows(&feeds, &HashMap::new(), &HashSet::new(), 1);
+
+        assert_eq!(
+            rows,
+            vec![
+                FeedRow::Virtual(VirtualFeed::AllEntries),
+                FeedRow::Virtual(VirtualFeed::Starred),
+                FeedRow::Virtual(VirtualFeed::RecentlyRead),
+                FeedRow::Virtual(VirtualFeed::Smart(0)),
+                FeedRow::Feed { feed_idx: 0 },
+                FeedRow::Feed { feed_idx: 1 }
+            ]
+        );
+        assert!(feed_rows(&[], &HashMap::new(), &HashSet::new(), 1).is_empty());
+    }
+
+    #[test]
+    fn feeds_are_grouped_by_tag_and_groups_collapse() {
+        let feeds = vec![feed(1), feed(2), feed(3)];
+        let mut tags = HashMap::new();
+        tags.insert(1, vec!["news".to_string()]);
+        tags.insert(2, vec!["Blogs".to_string(), "news".to_string()]);
+        let mut collapsed = HashSet::new();
+        collapsed.insert(Some("news".to_string()));
+
+        let rows = feed_rows(&feeds, &tags, &collapsed, 0);
+
+        assert_eq!(
+            rows,
+            vec![
+                FeedRow::Virtual(VirtualFeed::AllEntries),
+                FeedRow::Virtual(VirtualFeed::Starred),
+                FeedRow::Virtual(VirtualFeed::RecentlyRead),
+                FeedRow::Group {
+                    group: Some("Blogs".to_string()),
+                    feeds_len: 1,
+                    collapsed: false
+                },
+                FeedRow::Feed { feed_idx: 1 },
+                FeedRow::Group {
+                    group: Some("news".to_string()),
+                    feeds_len: 2,
+                    collapsed: true
+                },
+                FeedRow::Group {
+                    group: None,
+                    feeds_len: 1,
+                    collapsed: false
+                },
+                FeedRow::Feed { feed_idx: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_tags() {
+        assert_eq!(parse_tags(" News, blogs,,News ,"), vec!["News", "blogs"]);
+        assert!(parse_tags("  ").is_empty());
+    }
+}
@@ -0,0 +1,519 @@
+use crate::http::{Credentials, HttpClient};
+use crate::rss::Entry;
+use crate::sync::{first_enclosure, NumberOrString, RemoteEnclosure};
+use crate::sync::{EntryStates, RemoteEntry, RemoteFeed, StateChange, SyncBackend, SyncConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// how many items are asked for at a time
+const PAGE_SIZE: usize = 1000;
+
+/// how many item ids are asked for at a time
+const IDS_PAGE_SIZE: usize = 10000;
+
+/// how far back the first sync goes for entries that are neither unread nor starred
+const FIRST_SYNC_DAYS: i64 = 30;
+
+/// how many items are marked in one request
+const EDIT_BATCH_SIZE: usize = 100;
+
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+const READ: &str = "user/-/state/com.google/read";
+const STARRED: &str = "user/-/state/com.google/starred";
+
+const ITEM_ID_PREFIX: &str = "tag:google.com,2005:reader/item/";
+
+/// the Google Reader API, as FreshRSS and The Old Reader have it.
+/// `url` is where the API lives, like `https://freshrss.example.com/api/greader.php`.
+#[derive(Debug)]
+pub struct GoogleReader {
+    http_client: HttpClient,
+    /// without a trailing slash
+    url: String,
+    credentials: Credentials,
+    /// from logging in, kept until the server stops taking it
+    auth_token: Mutex<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionList {
+    #[serde(default)]
+    subscriptions: Vec<Subscription>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subscription {
+    id: String,
+    title: Option<String>,
+    url: Option<String>,
+    #[serde(rename = "htmlUrl")]
+    html_url: Option<String>,
+    #[serde(default)]
+    categories: Vec<Category>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Category {
+    label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamContents {
+    #[serde(default)]
+    items: Vec<Item>,
+    continuation: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamItemIds {
+    #[serde(default, rename = "itemRefs")]
+    item_refs: Vec<ItemRef>,
+    continuation: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemRef {
+    id: NumberOrString,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    id: String,
+    title: Option<String>,
+    author: Option<String>,
+    published: Option<i64>,
+    #[serde(default)]
+    canonical: Vec<Link>,
+    #[serde(default)]
+    alternate: Vec<Link>,
+    content: Option<Content>,
+    summary: Option<Content>,
+    origin: Origin,
+    #[serde(default)]
+    categories: Vec<String>,
+    enclosure: Option<Vec<RemoteEnclosure>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    href: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Origin {
+    #[serde(rename = "streamId")]
+    stream_id: String,
+}
+
+impl GoogleReader {
+    pub fn new(http_client: HttpClient, sync_config: &SyncConfig) -> GoogleReader {
+        GoogleReader {
+            http_client,
+            url: sync_config.url.trim_end_matches('/').to_owned(),
+            credentials: Credentials {
+                username: sync_config.username.clone().unwrap_or_default(),
+                password: sync_config.password.clone(),
+                password_command: sync_config.password_command.clone(),
+            },
+            auth_token: Mutex::new(None),
+        }
+    }
+
+    fn auth_token(&self) -> Result<String> {
+        let mut auth_token = self.auth_token.lock().unwrap();
+
+        if let Some(auth_token) = &*auth_token {
+            return Ok(auth_token.to_owned());
+        }
+
+        let password = self.http_client.password(&self.credentials)?;
+
+        let body = self
+            .http_client
+            .request("POST", &format!("{}/accounts/ClientLogin", self.url))
+            .send_form(&[("Email", &self.credentials.username), ("Passwd", &password)])
+            .context("Unable to log in to the Google Reader API")?
+            .into_string()?;
+
+        let new_auth_token = parse_auth_token(&body)
+            .ok_or_else(|| anyhow::anyhow!("The Google Reader API did not return an auth token"))?;
+
+        *auth_token = Some(new_auth_token.clone());
+
+        Ok(new_auth_token)
+    }
+
+    /// makes the request with the auth token, logging in again if the server no longer takes it.
+    /// a request with a form is a POST.
+    fn call(&self, path: &str, form: Option<&[(&str, &str)]>) -> Result<String> {
+        let url = format!("{}/reader/api/0{}", self.url, path);
+        let method = if form.is_some() { "POST" } else { "GET" };
+
+        for is_retry in [false, true] {
+            let request = self.http_client.request(method, &url).set(
+                "Authorization",
+                &format!("GoogleLogin auth={}", self.auth_token()?),
+            );
+
+            let response = match form {
+                Some(form) => request.send_form(form),
+                None => request.call(),
+            };
+
+            match response {
+                Err(ureq::Error::Status(401, _)) if !is_retry => {
+                    *self.auth_token.lock().unwrap() = None;
+                }
+                response => {
+                    return Ok(response
+                        .with_context(|| {
+                            format!("Unable to {} {} on the Google Reader API", method, path)
+                        })?
+                        .into_string()?)
+                }
+            }
+        }
+
+        unreachable!("the retry either returns or fails")
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self.call(path, None)?;
+        serde_json::from_str(&body)
+            .with_context(|| format!("Invalid JSON from the Google Reader API for {}", path))
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
+        // the token edits need, which is not the auth token
+        let token = self.call("/token", None)?;
+
+        let mut form = form.to_vec();
+        form.push(("T", token.trim()));
+
+        self.call(path, Some(&form))
+    }
+
+    /// every page of the stream's items, with `query` narrowing it
+    fn stream_items(&self, stream_id: &str, query: &str) -> Result<Vec<Item>> {
+        let mut items = vec![];
+        let mut continuation = None;
+
+        loop {
+            let page: StreamContents = self.get(&format!(
+                "/stream/contents/{}?output=json&n={}{}{}",
+                encode(stream_id),
+                PAGE_SIZE,
+                query,
+                continuation
+                    .map(|continuation: String| format!("&c={}", encode(&continuation)))
+                    .unwrap_or_default()
+            ))?;
+
+            items.extend(page.items);
+
+            continuation = page
+                .continuation
+                .filter(|continuation| !continuation.is_empty());
+
+            if continuation.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+
+    /// every page of the stream's item ids, with `query` narrowing it
+    fn stream_item_ids(&self, stream_id: &str, query: &str) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        let mut continuation = None;
+
+        loop {
+            let page: StreamItemIds = self.get(&format!(
+                "/stream/items/ids?output=json&s={}&n={}{}{}",
+                encode(stream_id),
+                IDS_PAGE_SIZE,
+                query,
+                continuation
+                    .map(|continuation: String| format!("&c={}", encode(&continuation)))
+                    .unwrap_or_default()
+            ))?;
+
+            ids.extend(page.item_refs.into_iter().map(|item_ref| item_ref.id.0));
+
+            continuation = page
+                .continuation
+                .filter(|continuation| !continuation.is_empty());
+
+            if continuation.is_none() {
+                return Ok(ids);
+            }
+        }
+    }
+
+    /// adds or removes `tag` on the items
+    fn edit_tag(&self, remote_ids: &[&str], tag: &str, add: bool) -> Result<()> {
+        for remote_ids in remote_ids.chunks(EDIT_BATCH_SIZE) {
+            let item_ids = remote_ids
+                .iter()
+                .map(|remote_id| long_item_id(remote_id))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut form = item_ids
+                .iter()
+                .map(|item_id| ("i", item_id.as_str()))
+                .collect::<Vec<_>>();
+            form.push((if add { "a" } else { "r" }, tag));
+
+            self.post("/edit-tag", &form)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SyncBackend for GoogleReader {
+    fn server(&self) -> String {
+        format!("google_reader {} {}", self.url, self.credentials.username)
+    }
+
+    fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        let subscription_list: SubscriptionList = self.get("/subscription/list?output=json")?;
+
+        subscription_list
+            .subscriptions
+            .into_iter()
+            .map(to_remote_feed)
+            .collect()
+    }
+
+    fn entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RemoteEntry>> {
+        let streams = match since {
+            Some(since) => vec![(READING_LIST, format!("&ot={}", since.timestamp()))],
+            None => vec![
+                (READING_LIST, format!("&xt={}", encode(READ))),
+                (STARRED, String::new()),
+                (
+                    READING_LIST,
+                    format!(
+                        "&ot={}",
+                        (Utc::now() - chrono::Duration::days(FIRST_SYNC_DAYS)).timestamp()
+                    ),
+                ),
+            ],
+        };
+
+        let mut seen_ids = HashSet::new();
+        let mut remote_entries = vec![];
+
+        for (stream_id, query) in streams {
+            for item in self.stream_items(stream_id, &query)? {
+                let remote_entry = to_remote_entry(item)?;
+                if seen_ids.insert(remote_entry.remote_id.clone()) {
+                    remote_entries.push(remote_entry);
+                }
+            }
+        }
+
+        Ok(remote_entries)
+    }
+
+    fn entry_states(&self) -> Result<Option<EntryStates>> {
+        Ok(Some(EntryStates {
+            unread: self.stream_item_ids(READING_LIST, &format!("&xt={}", encode(READ)))?,
+            starred: self.stream_item_ids(STARRED, "")?,
+        }))
+    }
+
+    fn push_state_changes(&self, state_changes: &[StateChange]) -> Result<()> {
+        for tag in [READ, STARRED] {
+            for add in [true, false] {
+                let remote_ids = state_changes
+                    .iter()
+                    .filter(|state_change| {
+                        let is_set = if tag == READ {
+                            state_change.read
+                        } else {
+                            state_change.starred
+                        };
+                        is_set == add
+                    })
+                    .map(|state_change| state_change.remote_id.as_str())
+                    .collect::<Vec<_>>();
+
+                if !remote_ids.is_empty() {
+                    self.edit_tag(&remote_ids, tag, add)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, feed_link: &str) -> Result<String> {
+        let body = self.post("/subscription/quickadd", &[("quickadd", feed_link)])?;
+
+        serde_json::from_str::<Origin>(&body)
+            .map(|subscribed| subscribed.stream_id)
+            .context("The Google Reader API did not say which feed it subscribed to")
+    }
+}
+
+/// `ClientLogin` answers with lines like `Auth=...`
+fn parse_auth_token(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("Auth="))
+        .map(|auth_token| auth_token.trim().to_owned())
+}
+
+/// items have ids like `tag:google.com,2005:reader/item/000000000000001f`,
+/// but are listed by id as decimal numbers like `31`, which is what russ keeps
+fn short_item_id(item_id: &str) -> Result<String> {
+    match item_id.strip_prefix(ITEM_ID_PREFIX) {
+        Some(hex) => u64::from_str_radix(hex, 16)
+            .map(|id| (id as i64).to_string())
+            .with_context(|| format!("Invalid Google Reader item id {}", item_id)),
+        None => Ok(item_id.to_owned()),
+    }
+}
+
+fn long_item_id(remote_id: &str) -> Result<String> {
+    let id: i64 = remote_id
+        .parse()
+        .with_context(|| format!("Invalid Google Reader item id {}", remote_id))?;
+
+    Ok(format!("{}{:016x}", ITEM_ID_PREFIX, id as u64))
+}
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+fn to_remote_feed(subscription: Subscription) -> Result<RemoteFeed> {
+    // without a url, the id is `feed/` and then the feed's URL
+    let feed_link = subscription
+        .url
+        .or_else(|| subscription.id.strip_prefix("feed/").map(str::to_owned))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Google Reader subscription {} without a url",
+                subscription.id
+            )
+        })?;
+
+    Ok(RemoteFeed {
+        remote_id: subscription.id,
+        title: subscription.title,
+        feed_link,
+        link: subscription.html_url,
+        tags: subscription
+            .categories
+            .into_iter()
+            .filter_map(|category| category.label)
+            .collect(),
+    })
+}
+
+fn to_remote_entry(item: Item) -> Result<RemoteEntry> {
+    // the user is `-` when asked for, but servers may answer with the user's id
+    let has_state = |state: &str| {
+        let suffix = state.trim_start_matches("user/-");
+        item.categories
+            .iter()
+            .any(|category| category.starts_with("user/") && category.ends_with(suffix))
+    };
+
+    let now = Utc::now();
+    let read_at = if has_state(READ) { Some(now) } else { None };
+    let starred = has_state(STARRED);
+
+    let link = [&item.canonical, &item.alternate]
+        .iter()
+        .find_map(|links| links.first()?.href.clone());
+
+    let content = [item.content, item.summary]
+        .into_iter()
+        .find_map(|content| content?.content);
+
+    Ok(RemoteEntry {
+        remote_id: short_item_id(&item.id)?,
+        remote_feed_id: item.origin.stream_id,
+        entry: Entry {
+            title: item.title,
+            author: item.author.filter(|author| !author.is_empty()),
+            pub_date: item
+                .published
+                .and_then(|published| Utc.timestamp_opt(published, 0).single()),
+            description: None,
+            content,
+            link,
+            base_url: None,
+            guid: None,
+            feed_updated_at: None,
+            categories: vec![],
+            enclosure: first_enclosure(&item.enclosure),
+            read_at,
+        },
+        starred,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_subscriptions_and_items() {
+        let subscription_list: SubscriptionList = serde_json::from_str(
+            r#"{"subscriptions": [{"id": "feed/7", "title": "Example", "url": "https://example.com/feed",
+            "htmlUrl": "https://example.com", "categories": [{"id": "user/-/label/News", "label": "News"}]}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_remote_feed(subscription_list.subscriptions.into_iter().next().unwrap()).unwrap(),
+            RemoteFeed {
+                remote_id: "feed/7".to_string(),
+                title: Some("Example".to_string()),
+                feed_link: "https://example.com/feed".to_string(),
+                link: Some("https://example.com".to_string()),
+                tags: vec!["News".to_string()],
+            }
+        );
+
+        let item: Item = serde_json::from_str(
+            r#"{"id": "tag:google.com,2005:reader/item/000000000000001f", "title": "Hi",
+            "published": 1622548800, "canonical": [{"href": "https://example.com/hi"}],
+            "summary": {"content": "<p>hi</p>"}, "author": "me", "origin": {"streamId": "feed/7"},
+            "categories": ["user/-/state/com.google/reading-list", "user/1/state/com.google/read"]}"#,
+        )
+        .unwrap();
+
+        let remote_entry = to_remote_entry(item).unwrap();
+        assert_eq!(remote_entry.remote_id, "31");
+        assert_eq!(remote_entry.remote_feed_id, "feed/7");
+        assert_eq!(
+            remote_entry.entry.link.as_deref(),
+            Some("https://example.com/hi")
+        );
+        assert_eq!(remote_entry.entry.content.as_deref(), Some("<p>hi</p>"));
+        assert!(remote_entry.entry.read_at.is_some());
+        assert!(!remote_entry.starred);
+
+        assert_eq!(
+            long_item_id("31").unwrap(),
+            "tag:google.com,2005:reader/item/000000000000001f"
+        );
+        assert_eq!(
+            parse_auth_token("SID=none\nLSID=none\nAuth=user/0123\n"),
+            Some("user/0123".to_string())
+        );
+    }
+}
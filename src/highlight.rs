@@ -0,0 +1,251 @@
+/// a code block's language is not known by the time it is rendered,
+/// so these are the keywords of the languages entries show the most
+const KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "crate",
+    "def",
+    "default",
+    "defer",
+    "do",
+    "dyn",
+    "elif",
+    "else",
+    "enum",
+    "except",
+    "export",
+    "extends",
+    "false",
+    "False",
+    "finally",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "go",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "lambda",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "module",
+    "move",
+    "mut",
+    "new",
+    "nil",
+    "None",
+    "not",
+    "null",
+    "or",
+    "package",
+    "pass",
+    "private",
+    "protected",
+    "pub",
+    "public",
+    "raise",
+    "ref",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "True",
+    "try",
+    "type",
+    "typeof",
+    "unsafe",
+    "use",
+    "var",
+    "void",
+    "where",
+    "while",
+    "with",
+    "yield",
+];
+
+/// what a piece of code is, for coloring it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    /// strings and numbers
+    Literal,
+    Comment,
+}
+
+/// carried from one line of a code block to the next
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct State {
+    in_block_comment: bool,
+}
+
+/// splits a line of code into runs of one kind of token
+pub fn highlight_line<'a>(line: &'a str, state: &mut State) -> Vec<(TokenKind, &'a str)> {
+    // the kind, start, and end of each run
+    let mut runs: Vec<(TokenKind, usize, usize)> = vec![];
+    let mut push = |kind: TokenKind, start: usize, len: usize| match runs.last_mut() {
+        // keywords next to each other are still separate words
+        Some((last_kind, _, end)) if *last_kind == kind && kind != TokenKind::Keyword => {
+            *end += len;
+        }
+        _ => runs.push((kind, start, start + len)),
+    };
+
+    let mut i = 0;
+
+    while i < line.len() {
+        let rest = &line[i..];
+
+        if state.in_block_comment {
+            let end = match rest.find("*/") {
+                Some(end) => {
+                    state.in_block_comment = false;
+                    end + 2
+                }
+                None => rest.len(),
+            };
+            push(TokenKind::Comment, i, end);
+            i += end;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        let previous = line[..i].chars().next_back();
+
+        let (kind, len) = if rest.starts_with("/*") {
+            state.in_block_comment = true;
+            (TokenKind::Comment, 2)
+        } else if is_line_comment(rest) {
+            (TokenKind::Comment, rest.len())
+        } else if let Some(len) = string_len(rest, previous) {
+            (TokenKind::Literal, len)
+        } else if c.is_ascii_digit() && !previous.is_some_and(is_identifier_char) {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            (TokenKind::Literal, len)
+        } else if is_identifier_char(c) {
+            let len = rest
+                .find(|c: char| !is_identifier_char(c))
+                .unwrap_or(rest.len());
+            let kind = if KEYWORDS.contains(&&rest[..len]) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Plain
+            };
+            (kind, len)
+        } else {
+            (TokenKind::Plain, c.len_utf8())
+        };
+
+        push(kind, i, len);
+        i += len;
+    }
+
+    runs.into_iter()
+        .map(|(kind, start, end)| (kind, &line[start..end]))
+        .collect()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `//`, `# ` and `-- ` comments, but not `#[attributes]` or `#include`s
+fn is_line_comment(rest: &str) -> bool {
+    rest.starts_with("//")
+        || rest == "#"
+        || rest.starts_with("# ")
+        || rest == "--"
+        || rest.starts_with("-- ")
+}
+
+/// how long the string `rest` starts with is, if it starts with one that ends on this line.
+/// a `'` after a letter, `&` or `<` is an apostrophe or a Rust lifetime, not a string
+fn string_len(rest: &str, previous: Option<char>) -> Option<usize> {
+    let quote = rest
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\'' | '`'))?;
+
+    if quote == '\'' && previous.is_some_and(|c| is_identifier_char(c) || c == '&' || c == '<') {
+        return None;
+    }
+
+    let mut is_escaped = false;
+
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' => is_escaped = true,
+            _ if c == quote => return Some(i + c.len_utf8()),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_highlights_keywords_strings_and_comments() {
+        let mut state = State::default();
+
+        assert_eq!(
+            highlight_line(
+                "fn f<'a>(s: &'a str) -> usize { \"a\\\"b\".len() + 10 } // hi",
+                &mut state
+            ),
+            vec![
+                (TokenKind::Keyword, "fn"),
+                (TokenKind::Plain, " f<'a>(s: &'a str) -> usize { "),
+                (TokenKind::Literal, "\"a\\\"b\""),
+                (TokenKind::Plain, ".len() + "),
+                (TokenKind::Literal, "10"),
+                (TokenKind::Plain, " } "),
+                (TokenKind::Comment, "// hi"),
+            ]
+        );
+
+        assert_eq!(
+            highlight_line("x = 1 /* a", &mut state),
+            vec![
+                (TokenKind::Plain, "x = "),
+                (TokenKind::Literal, "1"),
+                (TokenKind::Plain, " "),
+                (TokenKind::Comment, "/* a"),
+            ]
+        );
+        assert_eq!(
+            highlight_line("b */ #[derive(Debug)]", &mut state),
+            vec![
+                (TokenKind::Comment, "b */"),
+                (TokenKind::Plain, " #[derive(Debug)]"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::rss::{Entry, Feed};
+use crate::sanitize::decode_entities;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// runs `command` once for each of `entries`, which were just added to `feed`, and waits for it to exit.
+/// `{title}`, `{link}`, and `{feed}` in the command are replaced with the entry's,
+/// and the entry is written to its stdin as JSON
+pub fn run_new_entry_command(command: &str, feed: &Feed, entries: &[Entry]) -> Result<()> {
+    for entry in entries {
+        let entry_json = entry_json(feed, entry);
+
+        let args = command_args(
+            command,
+            &[
+                ("{title}", entry_json.title.as_deref()),
+                ("{link}", entry_json.link),
+                ("{feed}", entry_json.feed),
+            ],
+        );
+
+        let (program, args) = args
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("The new entry command is empty"))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Unable to run {}", program))?;
+
+        // a command that has no use for its stdin may close it before it is written
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(&serde_json::to_vec(&entry_json)?);
+        }
+
+        let status = child.wait()?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} failed with {}", command, status));
+        }
+    }
+
+    Ok(())
+}
+
+/// the entry as the new entry command gets it, with its title and author as russ shows them
+#[derive(Serialize)]
+struct EntryJson<'a> {
+    title: Option<String>,
+    link: Option<&'a str>,
+    author: Option<String>,
+    pub_date: Option<String>,
+    feed: Option<&'a str>,
+    feed_link: Option<&'a str>,
+}
+
+fn entry_json<'a>(feed: &'a Feed, entry: &'a Entry) -> EntryJson<'a> {
+    EntryJson {
+        title: entry.title.as_deref().map(decode_entities),
+        link: entry.link.as_deref(),
+        author: entry.author.as_deref().map(decode_entities),
+        pub_date: entry.pub_date.map(|date| date.to_rfc3339()),
+        feed: feed.title.as_deref(),
+        feed_link: feed.feed_link.as_deref(),
+    }
+}
+
+/// splits a command on whitespace and replaces the placeholders in each argument,
+/// so a title with spaces in it stays one argument. missing values are replaced with nothing
+fn command_args(command: &str, replacements: &[(&str, Option<&str>)]) -> Vec<String> {
+    command
+        .split_whitespace()
+        .map(|arg| {
+            replacements
+                .iter()
+                .fold(arg.to_owned(), |arg, (placeholder, value)| {
+                    arg.replace(placeholder, value.unwrap_or_default())
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn feed() -> Feed {
+        Feed {
+            title: Some("Example".to_string()),
+            feed_link: Some("https://example.com/feed.xml".to_string()),
+            ..Feed::for_test(1)
+        }
+    }
+
+    fn entry() -> Entry {
+        Entry {
+            pub_date: Some(
+                DateTime::parse_from_rfc3339("2021-03-04T05:06:07Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            link: Some("https://example.com/tom-and-jerry".to_string()),
+            ..Entry::for_test("Tom &amp; Jerry")
+        }
+    }
+
+    #[test]
+    fn it_passes_the_entry_as_arguments_and_as_json_on_stdin() {
+        let path = std::env::temp_dir().join(format!("russ-new-entry-{}", std::process::id()));
+
+        run_new_entry_command(&format!("tee {}", path.display()), &feed(), &[entry()]).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            written,
+            r#"{"title":"Tom & Jerry","link":"https://example.com/tom-and-jerry","author":null,"pub_date":"2021-03-04T05:06:07+00:00","feed":"Example","feed_link":"https://example.com/feed.xml"}"#
+        );
+
+        let (feed, entry) = (feed(), entry());
+        let entry_json = entry_json(&feed, &entry);
+        assert_eq!(
+            command_args(
+                "notify-send {feed}: {title} --url={link}{author}",
+                &[
+                    ("{title}", entry_json.title.as_deref()),
+                    ("{link}", entry_json.link),
+                    ("{feed}", entry_json.feed),
+                    ("{author}", entry_json.author.as_deref()),
+                ]
+            ),
+            vec![
+                "notify-send",
+                "Example:",
+                "Tom & Jerry",
+                "--url=https://example.com/tom-and-jerry",
+            ]
+        );
+
+        assert!(run_new_entry_command("false", &feed, &[entry]).is_err());
+    }
+}
@@ -0,0 +1,671 @@
+use crate::highlight::{self, TokenKind};
+use crate::images::ImageCache;
+use crate::theme::Theme;
+use html2text::render::text_renderer::{
+    TaggedLine, TaggedLineElement, TaggedString, TextDecorator,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans, Text};
+
+/// stands in for `<hr>`, which html2text drops, until it is drawn across the line
+const RULE_MARKER: char = '\u{e000}';
+/// followed by its index, stands in for an image drawn inline
+const IMAGE_MARKER: char = '\u{e001}';
+
+/// an entry's HTML, ready to draw
+#[derive(Clone, Debug, Default)]
+pub struct Rendered {
+    pub text: Text<'static>,
+    /// the entry's links, in the order they are numbered, from 1
+    pub links: Vec<String>,
+    /// where the links are in `text`
+    pub link_spans: Vec<LinkSpan>,
+    /// the entry's images, when they are drawn inline
+    pub image_urls: Vec<String>,
+    /// where the images that have been fetched go in `text`
+    pub image_placements: Vec<ImagePlacement>,
+}
+
+impl Rendered {
+    /// the text without its styles
+    pub fn plain_text(&self) -> String {
+        self.text
+            .lines
+            .iter()
+            .map(|line| {
+                line.0
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// a run of a link's text on one line
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkSpan {
+    pub line: usize,
+    /// in columns
+    pub column: usize,
+    pub text: String,
+    pub style: Style,
+    pub link_number: usize,
+}
+
+/// blank lines an image is drawn over
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub line: usize,
+    /// in columns
+    pub column: usize,
+    pub columns: u16,
+    pub rows: u16,
+    pub url: String,
+}
+
+/// what an entry's images are drawn with, when they are drawn inline
+pub struct InlineImages<'a> {
+    pub cache: &'a ImageCache,
+}
+
+/// renders an entry's HTML as styled text `width` columns wide,
+/// with a footnote number after each link, and the links listed at the end.
+/// relative links are made absolute against `base_url`.
+/// with `inline_images`, the entry's images get lines of their own,
+/// and room to be drawn once they are in the cache
+pub fn render(
+    html: &str,
+    base_url: Option<&str>,
+    width: usize,
+    theme: &Theme,
+    inline_images: Option<&InlineImages>,
+) -> Rendered {
+    let decorator = Decorator::default();
+    let links = decorator.links.clone();
+
+    let mut images = vec![];
+    let sanitized = crate::sanitize::sanitize(html, base_url);
+    let normalized = normalize(&sanitized, inline_images.map(|_| &mut images));
+
+    let lines = html2text::parse(normalized.as_bytes())
+        .render(width, decorator)
+        .into_lines();
+
+    let mut text_lines = vec![];
+    let mut link_spans = vec![];
+    let mut image_placements = vec![];
+    let mut highlight_state = highlight::State::default();
+
+    let image_urls = images
+        .iter()
+        .map(|image: &ImageTag| image.src.clone())
+        .collect::<Vec<_>>();
+
+    for line in &lines {
+        let plain = line.chars().collect::<String>();
+
+        let image = plain.split_once(IMAGE_MARKER).and_then(|(prefix, index)| {
+            // a link around the image can leave its number after it
+            let index = index
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse::<usize>()
+                .ok()?;
+            Some((prefix, &images[index], &image_urls[index]))
+        });
+
+        if let Some((prefix, image, url)) = image {
+            let prefix_width = Span::raw(prefix).width();
+            let size = inline_images
+                .and_then(|inline_images| inline_images.cache.get(url))
+                .map(|cached| cached.size_in_cells(width.saturating_sub(prefix_width) as u16));
+
+            match size {
+                Some((columns, rows)) => {
+                    image_placements.push(ImagePlacement {
+                        line: text_lines.len(),
+                        column: prefix_width,
+                        columns,
+                        rows,
+                        url: url.clone(),
+                    });
+                    for _ in 0..rows {
+                        text_lines.push(Spans::from(prefix.to_owned()));
+                    }
+                }
+                None => {
+                    // as html2text shows images, cut to fit on the line
+                    let alt = if image.alt.is_empty() {
+                        "image"
+                    } else {
+                        image.alt.as_str()
+                    };
+                    let alt = alt
+                        .chars()
+                        .take(width.saturating_sub(prefix_width + 2))
+                        .collect::<String>();
+                    text_lines.push(Spans::from(format!("{}[{}]", prefix, alt)));
+                }
+            }
+
+            continue;
+        }
+
+        let spans = render_line(line, width, theme, &mut highlight_state);
+        link_spans.extend(find_link_spans(text_lines.len(), line, theme));
+        text_lines.push(spans);
+    }
+
+    let links = links.borrow().clone();
+
+    Rendered {
+        text: text_lines.into(),
+        links,
+        link_spans,
+        image_urls,
+        image_placements,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+enum Annotation {
+    #[default]
+    Default,
+    /// the link's number
+    Link(usize),
+    Emphasis,
+    Strong,
+    Code,
+    Preformat,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Decorator {
+    /// shared with the decorators of lists, quotes and table cells,
+    /// so links are numbered across the whole entry and listed once at the end
+    links: Rc<RefCell<Vec<String>>>,
+    is_subblock: bool,
+}
+
+impl TextDecorator for Decorator {
+    type Annotation = Annotation;
+
+    fn decorate_link_start(&mut self, url: &str) -> (String, Annotation) {
+        let mut links = self.links.borrow_mut();
+        links.push(url.to_owned());
+        (String::new(), Annotation::Link(links.len()))
+    }
+
+    fn decorate_link_end(&mut self) -> String {
+        format!("[{}]", self.links.borrow().len())
+    }
+
+    fn decorate_em_start(&mut self) -> (String, Annotation) {
+        (String::new(), Annotation::Emphasis)
+    }
+
+    fn decorate_em_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_strong_start(&mut self) -> (String, Annotation) {
+        (String::new(), Annotation::Strong)
+    }
+
+    fn decorate_strong_end(&mut self) -> String {
+        String::new()
+    }
+
+    // html2text strikes the text out itself
+    fn decorate_strikeout_start(&mut self) -> (String, Annotation) {
+        (String::new(), Annotation::Default)
+    }
+
+    fn decorate_strikeout_end(&mut self) -> String {
+        String::new()
+    }
+
+    fn decorate_code_start(&mut self) -> (String, Annotation) {
+        ("`".to_owned(), Annotation::Code)
+    }
+
+    fn decorate_code_end(&mut self) -> String {
+        "`".to_owned()
+    }
+
+    fn decorate_preformat_first(&mut self) -> Annotation {
+        Annotation::Preformat
+    }
+
+    fn decorate_preformat_cont(&mut self) -> Annotation {
+        Annotation::Preformat
+    }
+
+    fn decorate_image(&mut self, title: &str) -> (String, Annotation) {
+        (format!("[{}]", title), Annotation::Default)
+    }
+
+    fn make_subblock_decorator(&self) -> Self {
+        Decorator {
+            links: self.links.clone(),
+            is_subblock: true,
+        }
+    }
+
+    fn finalise(self) -> Vec<TaggedLine<Annotation>> {
+        if self.is_subblock {
+            return vec![];
+        }
+
+        self.links
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(i, link)| {
+                TaggedLine::from_string(format!("[{}] {}", i + 1, link), &Annotation::Link(i + 1))
+            })
+            .collect()
+    }
+}
+
+/// `highlight_state` is carried from each line of a code block to the next,
+/// and starts over after it
+fn render_line(
+    line: &TaggedLine<Vec<Annotation>>,
+    width: usize,
+    theme: &Theme,
+    highlight_state: &mut highlight::State,
+) -> Spans<'static> {
+    let text = line.chars().collect::<String>();
+
+    // the rule keeps whatever the line is indented or quoted with
+    if let Some((prefix, _)) = text.split_once(RULE_MARKER) {
+        *highlight_state = highlight::State::default();
+        let rule = "─".repeat(width.saturating_sub(prefix.chars().count()));
+        return Spans::from(format!("{}{}", prefix, rule));
+    }
+
+    let is_code_block = tagged_strings(line).any(|s| s.tag.contains(&Annotation::Preformat));
+    if !is_code_block {
+        *highlight_state = highlight::State::default();
+    }
+
+    tagged_strings(line)
+        .flat_map(|s| {
+            let style = annotations_style(&s.tag, theme);
+
+            if !s.tag.contains(&Annotation::Preformat) {
+                return vec![Span::styled(s.s.clone(), style)];
+            }
+
+            highlight::highlight_line(&s.s, highlight_state)
+                .into_iter()
+                .map(|(kind, token)| {
+                    let token_style = match kind {
+                        TokenKind::Plain => Style::default(),
+                        TokenKind::Keyword => theme.code_keyword_style(),
+                        TokenKind::Literal => theme.code_literal_style(),
+                        TokenKind::Comment => theme.code_comment_style(),
+                    };
+                    Span::styled(token.to_owned(), style.patch(token_style))
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn tagged_strings(
+    line: &TaggedLine<Vec<Annotation>>,
+) -> impl Iterator<Item = &TaggedString<Vec<Annotation>>> {
+    line.iter().filter_map(|element| match element {
+        TaggedLineElement::Str(s) => Some(s),
+        TaggedLineElement::FragmentStart(_) => None,
+    })
+}
+
+fn find_link_spans(
+    line_index: usize,
+    line: &TaggedLine<Vec<Annotation>>,
+    theme: &Theme,
+) -> Vec<LinkSpan> {
+    let mut column = 0;
+    let mut link_spans = vec![];
+
+    for s in tagged_strings(line) {
+        let link_number = s.tag.iter().find_map(|annotation| match annotation {
+            Annotation::Link(link_number) => Some(*link_number),
+            _ => None,
+        });
+
+        if let Some(link_number) = link_number {
+            link_spans.push(LinkSpan {
+                line: line_index,
+                column,
+                text: s.s.clone(),
+                style: annotations_style(&s.tag, theme),
+                link_number,
+            });
+        }
+
+        column += Span::raw(s.s.as_str()).width();
+    }
+
+    link_spans
+}
+
+fn annotations_style(annotations: &[Annotation], theme: &Theme) -> Style {
+    annotations
+        .iter()
+        .fold(Style::default(), |style, annotation| match annotation {
+            Annotation::Default => style,
+            Annotation::Link(_) => style.add_modifier(Modifier::UNDERLINED),
+            Annotation::Emphasis => style.add_modifier(Modifier::ITALIC),
+            Annotation::Strong => style.add_modifier(Modifier::BOLD),
+            Annotation::Code | Annotation::Preformat => style.fg(theme.code),
+        })
+}
+
+/// an `<img>`, taken out of the HTML to be drawn inline
+struct ImageTag {
+    src: String,
+    alt: String,
+}
+
+/// rewrites the tags html2text does not know into ones it does,
+/// and makes headings bold. given `images`, images are taken out into it,
+/// each leaving a line of its own behind
+fn normalize(html: &str, mut images: Option<&mut Vec<ImageTag>>) -> String {
+    let mut normalized = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        normalized.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find('>') {
+            Some(end) => end + 1,
+            None => break,
+        };
+
+        let tag = &rest[..end];
+        let rewritten = match &mut images {
+            Some(images) if tag_name(tag) == (false, "img".to_owned()) => {
+                images.push(ImageTag {
+                    src: attribute(tag, "src").unwrap_or_default(),
+                    alt: attribute(tag, "alt").unwrap_or_default(),
+                });
+                Some(format!("<p>{}{}</p>", IMAGE_MARKER, images.len() - 1))
+            }
+            _ => rewrite_tag(tag),
+        };
+
+        match rewritten {
+            Some(rewritten) => normalized.push_str(&rewritten),
+            None => normalized.push_str(tag),
+        }
+
+        rest = &rest[end..];
+    }
+
+    normalized.push_str(rest);
+    normalized
+}
+
+/// how many words a reader reads in `html`, leaving out its tags, scripts, and styles
+pub fn count_words(html: &str) -> usize {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skipping = None;
+
+    while let Some(start) = rest.find('<') {
+        if skipping.is_none() {
+            text.push_str(&rest[..start]);
+        }
+        rest = &rest[start..];
+
+        let end = match rest.find('>') {
+            Some(end) => end + 1,
+            None => break,
+        };
+
+        match (tag_name(&rest[..end]), &skipping) {
+            ((false, name), None) if name == "script" || name == "style" => skipping = Some(name),
+            ((true, name), Some(skipped)) if &name == skipped => skipping = None,
+            _ => (),
+        }
+
+        // tags like `<br>` and `<p>` separate words
+        text.push(' ');
+        rest = &rest[end..];
+    }
+
+    if skipping.is_none() {
+        text.push_str(rest);
+    }
+
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// whether `tag` is a closing tag, and its lowercase name
+fn tag_name(tag: &str) -> (bool, String) {
+    let inner = &tag[1..tag.len() - 1];
+    let (is_closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+
+    let name_len = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+
+    (is_closing, inner[..name_len].to_ascii_lowercase())
+}
+
+/// the value of `tag`'s attribute `name`, quoted or not
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    // lowercasing ASCII keeps every index where it was
+    let lowercase = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+
+    while let Some(i) = lowercase[search_from..].find(name) {
+        let start = search_from + i;
+        search_from = start + name.len();
+
+        if !lowercase[..start].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+
+        let value = match tag[search_from..].trim_start().strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next()?,
+            _ => value
+                .split(|c: char| c.is_ascii_whitespace() || c == '>')
+                .next()?,
+        };
+
+        return Some(value.replace("&amp;", "&"));
+    }
+
+    None
+}
+
+fn rewrite_tag(tag: &str) -> Option<String> {
+    let (is_closing, name) = tag_name(tag);
+
+    let renamed = match name.as_str() {
+        "hr" if is_closing => return Some(String::new()),
+        "hr" => return Some(format!("<p>{}</p>", RULE_MARKER)),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            // html2text only knows four levels
+            let heading = name.replace(['5', '6'], "4");
+            return Some(if is_closing {
+                format!("</strong></{}>", heading)
+            } else {
+                format!("<{}><strong>", heading)
+            });
+        }
+        "b" => "strong",
+        "i" => "em",
+        "del" | "strike" => "s",
+        _ => return None,
+    };
+
+    Some(if is_closing {
+        format!("</{}>", renamed)
+    } else {
+        format!("<{}>", renamed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain_lines(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|spans| {
+                spans
+                    .0
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_renders_rules_headings_and_links() {
+        let rendered = render(
+            r#"<h5>Small</h5><blockquote><p>quote</p><hr></blockquote>
+            <ul><li><a href="https://a.example">a</a></li></ul>
+            <p><b>bold</b> and <a href="https://b.example">b</a></p>"#,
+            None,
+            30,
+            &Theme::default(),
+            None,
+        );
+
+        assert_eq!(
+            plain_lines(&rendered.text)
+                .iter()
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>(),
+            vec![
+                "#### Small",
+                "> quote",
+                "> ",
+                "> ────────────────────────────",
+                "* a[1]",
+                "bold and b[2]",
+                "[1] https://a.example",
+                "[2] https://b.example",
+            ]
+        );
+        assert_eq!(
+            rendered.links,
+            vec!["https://a.example", "https://b.example"]
+        );
+
+        let link_span = rendered.link_spans.last().unwrap();
+        assert_eq!(link_span.link_number, 2);
+        assert_eq!(link_span.text, "b[2]");
+        assert_eq!(
+            plain_lines(&rendered.text)[link_span.line][link_span.column..],
+            *"b[2]"
+        );
+    }
+
+    #[test]
+    fn headings_and_code_are_styled() {
+        let text = render(
+            "<h1>Title</h1><pre>let x = 1;</pre>",
+            None,
+            20,
+            &Theme::default(),
+            None,
+        )
+        .text;
+
+        let style_of = |content: &str| {
+            text.lines
+                .iter()
+                .flat_map(|spans| spans.0.iter())
+                .find(|span| span.content == content)
+                .map(|span| span.style)
+                .unwrap()
+        };
+
+        assert!(style_of("Title").add_modifier.contains(Modifier::BOLD));
+        assert_eq!(style_of("let").fg, Some(Theme::default().code_keyword));
+        assert_eq!(style_of(" x = ").fg, Some(Theme::default().code));
+        assert_eq!(style_of("1").fg, Some(Theme::default().code_literal));
+    }
+
+    #[test]
+    fn words_are_counted_without_tags_scripts_or_styles() {
+        assert_eq!(
+            count_words(
+                "<style>p { color: red }</style><p>Hello,&nbsp;<b>brave</b> new<br>world - again</p>\
+                <script>let a = '<p>';</script>"
+            ),
+            5
+        );
+        assert_eq!(count_words("no markup at all"), 4);
+        assert_eq!(crate::util::format_reading_time(5, 230), "1 min");
+        assert_eq!(crate::util::format_reading_time(461, 230), "3 min");
+    }
+
+    #[test]
+    fn fetched_images_get_room_to_be_drawn() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&160u32.to_be_bytes());
+        png.extend_from_slice(&48u32.to_be_bytes());
+
+        let mut cache = ImageCache::default();
+        cache.insert("https://a.example/i.png".to_owned(), Some(png));
+
+        let rendered = render(
+            r#"<p>before</p><img src="i.png" alt="pic"><img src='/gone.png'>
+            <p>after <a href="https://l.example">l</a></p>"#,
+            Some("https://a.example/post"),
+            30,
+            &Theme::default(),
+            Some(&InlineImages { cache: &cache }),
+        );
+
+        assert_eq!(
+            rendered.image_urls,
+            vec!["https://a.example/i.png", "https://a.example/gone.png"]
+        );
+        assert_eq!(
+            rendered.image_placements,
+            vec![ImagePlacement {
+                line: 2,
+                column: 0,
+                columns: 20,
+                rows: 3,
+                url: "https://a.example/i.png".to_owned(),
+            }]
+        );
+
+        let lines = plain_lines(&rendered.text);
+        assert_eq!(lines[2..5], ["", "", ""]);
+        assert_eq!(lines[6], "[image]");
+
+        let link_span = &rendered.link_spans[0];
+        assert_eq!(lines[link_span.line][link_span.column..], *"l[1]");
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// fetched images are forgotten past this many bytes, so reading goes on fetching them again
+const MAX_CACHED_BYTES: usize = 64 * 1024 * 1024;
+/// the terminal's cells are not asked for their size in pixels, so images are sized
+/// as if they were this wide and tall, which most fonts are close to
+const CELL_WIDTH_PX: u32 = 8;
+const CELL_HEIGHT_PX: u32 = 16;
+/// so one image does not take more than a screenful
+const MAX_ROWS: u32 = 20;
+/// the most base64 the kitty graphics protocol takes in one escape sequence
+const MAX_CHUNK_LEN: usize = 4096;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// how many levels of each of red, green, and blue sixel images are drawn with
+const SIXEL_LEVELS: u32 = 6;
+
+/// deletes every image drawn with the kitty graphics protocol, freeing their data
+pub const DELETE_ALL_IMAGES: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// how the terminal is told to draw images
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    /// the terminal decodes the PNG and scales it to the cells itself
+    Kitty,
+    /// russ decodes and scales the PNG, and sends its pixels.
+    /// sixel images stay on the screen until something is drawn over them
+    Sixel,
+}
+
+/// a PNG, which kitty graphics terminals draw themselves, and which russ decodes for sixel ones
+#[derive(Clone)]
+pub struct Image {
+    png: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl fmt::Debug for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("png", &format_args!("{} bytes", self.png.len()))
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Image {
+    pub fn from_png(png: Vec<u8>) -> Option<Image> {
+        let (width, height) = png_dimensions(&png)?;
+
+        Some(Image { png, width, height })
+    }
+
+    /// how many columns and rows the image is drawn across,
+    /// shrunk to fit in `max_columns`, and keeping its aspect ratio
+    pub fn size_in_cells(&self, max_columns: u16) -> (u16, u16) {
+        let columns = self.width.div_ceil(CELL_WIDTH_PX).max(1) as f64;
+        let rows = self.height.div_ceil(CELL_HEIGHT_PX).max(1) as f64;
+
+        let scale = (max_columns as f64 / columns)
+            .min(MAX_ROWS as f64 / rows)
+            .min(1.0);
+
+        (
+            ((columns * scale).round() as u16).max(1),
+            ((rows * scale).round() as u16).max(1),
+        )
+    }
+
+    /// draws the image at the cursor, scaled to `columns` and `rows`.
+    /// `None` if it cannot be decoded for a sixel terminal
+    pub fn sequence(&self, protocol: GraphicsProtocol, columns: u16, rows: u16) -> Option<String> {
+        match protocol {
+            GraphicsProtocol::Kitty => Some(self.kitty_sequence(columns, rows)),
+            GraphicsProtocol::Sixel => self.sixel_sequence(columns, rows),
+        }
+    }
+
+    /// draws the image at the cursor with the kitty graphics protocol,
+    /// scaled to `columns` and `rows`, without moving the cursor
+    fn kitty_sequence(&self, columns: u16, rows: u16) -> String {
+        let encoded = base64::encode(&self.png);
+        let chunks = encoded.as_bytes().chunks(MAX_CHUNK_LEN).collect::<Vec<_>>();
+
+        let mut sequence = String::with_capacity(encoded.len() + chunks.len() * 16);
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = if i + 1 < chunks.len() { 1 } else { 0 };
+
+            if i == 0 {
+                sequence.push_str(&format!(
+                    "\x1b_Ga=T,f=100,c={},r={},C=1,q=2,m={};",
+                    columns, rows, more
+                ));
+            } else {
+                sequence.push_str(&format!("\x1b_Gm={};", more));
+            }
+
+            // base64 is ASCII
+            sequence.push_str(std::str::from_utf8(chunk).unwrap());
+            sequence.push_str("\x1b\\");
+        }
+
+        sequence
+    }
+
+    /// draws the image at the cursor as sixels, scaled to `columns` and `rows`
+    /// of cells as big as russ takes them to be, in 216 colors.
+    /// transparent pixels are left as they are
+    fn sixel_sequence(&self, columns: u16, rows: u16) -> Option<String> {
+        let (pixels, width, height) = decode_png(&self.png)?;
+
+        let sixel_width = columns as usize * CELL_WIDTH_PX as usize;
+        let sixel_height = rows as usize * CELL_HEIGHT_PX as usize;
+
+        // the nearest pixel of the PNG to each sixel pixel, as a palette index
+        let colors = (0..sixel_height)
+            .flat_map(|y| {
+                let png_y = y * height / sixel_height;
+                let pixels = &pixels;
+                (0..sixel_width).map(move |x| pixels[png_y * width + x * width / sixel_width])
+            })
+            .collect::<Vec<_>>();
+
+        let mut sequence = format!("\x1bP0;1;0q\"1;1;{};{}", sixel_width, sixel_height);
+
+        let max_level = SIXEL_LEVELS - 1;
+        for color in 0..SIXEL_LEVELS.pow(3) {
+            let percent = |level: u32| level * 100 / max_level;
+            sequence.push_str(&format!(
+                "#{};2;{};{};{}",
+                color,
+                percent(color / (SIXEL_LEVELS * SIXEL_LEVELS)),
+                percent(color / SIXEL_LEVELS % SIXEL_LEVELS),
+                percent(color % SIXEL_LEVELS)
+            ));
+        }
+
+        // each sixel is a column of 6 pixels, and each band of them is drawn once per color in it
+        for band in colors.chunks(sixel_width * 6) {
+            let band_rows = band.len() / sixel_width;
+
+            let mut band_colors = band.iter().flatten().copied().collect::<Vec<_>>();
+            band_colors.sort_unstable();
+            band_colors.dedup();
+
+            for color in band_colors {
+                sequence.push_str(&format!("#{}", color));
+
+                let sixels = (0..sixel_width).map(|x| {
+                    let bits = (0..band_rows)
+                        .filter(|row| band[row * sixel_width + x] == Some(color))
+                        .fold(0, |bits, row| bits | 1 << row);
+                    (b'?' + bits) as char
+                });
+
+                push_run_length_encoded(&mut sequence, sixels);
+                sequence.push('$');
+            }
+
+            sequence.push('-');
+        }
+
+        sequence.push_str("\x1b\\");
+
+        Some(sequence)
+    }
+}
+
+/// the PNG's pixels, row by row, as the palette index of the nearest of the sixel colors,
+/// or `None` where they are mostly transparent, and its width and height
+fn decode_png(png: &[u8]) -> Option<(Vec<Option<u8>>, usize, usize)> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+
+    let level = |value: u8| (value as u32 * (SIXEL_LEVELS - 1) + 127) / 255;
+    let color = |r: u8, g: u8, b: u8, a: u8| {
+        (a >= 128).then(|| {
+            (level(r) * SIXEL_LEVELS * SIXEL_LEVELS + level(g) * SIXEL_LEVELS + level(b)) as u8
+        })
+    };
+
+    let pixels = buf[..info.buffer_size()]
+        .chunks(info.color_type.samples())
+        .map(|pixel| match *pixel {
+            [gray] => color(gray, gray, gray, 255),
+            [gray, a] => color(gray, gray, gray, a),
+            [r, g, b] => color(r, g, b, 255),
+            [r, g, b, a] => color(r, g, b, a),
+            _ => None,
+        })
+        .collect();
+
+    Some((pixels, info.width as usize, info.height as usize))
+}
+
+/// sixels, with runs of the same one shortened to `!`, how many, and the sixel
+fn push_run_length_encoded(sequence: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut sixels = sixels.peekable();
+
+    while let Some(sixel) = sixels.next() {
+        let mut run_len = 1;
+        while sixels.next_if_eq(&sixel).is_some() {
+            run_len += 1;
+        }
+
+        if run_len > 3 {
+            sequence.push_str(&format!("!{}{}", run_len, sixel));
+        } else {
+            sequence.extend(std::iter::repeat_n(sixel, run_len));
+        }
+    }
+}
+
+/// the width and height of a PNG, from its header
+pub fn png_dimensions(png: &[u8]) -> Option<(u32, u32)> {
+    if png.len() < 24 || !png.starts_with(PNG_SIGNATURE) || &png[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(png[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(png[20..24].try_into().ok()?);
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    Some((width, height))
+}
+
+/// entries' images, by URL, for as long as russ runs
+#[derive(Debug, Default)]
+pub struct ImageCache {
+    /// `None` for images that could not be fetched or drawn,
+    /// so they are not fetched again
+    images: HashMap<String, Option<Image>>,
+    being_fetched: HashSet<String>,
+    bytes_len: usize,
+}
+
+impl ImageCache {
+    pub fn get(&self, url: &str) -> Option<&Image> {
+        self.images.get(url).and_then(|image| image.as_ref())
+    }
+
+    /// the ones of `urls` that are neither cached nor being fetched,
+    /// which are then being fetched
+    pub fn start_fetching(&mut self, urls: &[String]) -> Vec<String> {
+        urls.iter()
+            .filter(|url| !self.images.contains_key(*url))
+            .filter(|url| self.being_fetched.insert(url.to_string()))
+            .cloned()
+            .collect()
+    }
+
+    /// `png` is `None` if the image could not be fetched
+    pub fn insert(&mut self, url: String, png: Option<Vec<u8>>) {
+        self.being_fetched.remove(&url);
+
+        let image = png.and_then(Image::from_png);
+        let image_len = image.as_ref().map_or(0, |image| image.png.len());
+
+        if self.bytes_len + image_len > MAX_CACHED_BYTES {
+            self.images.clear();
+            self.bytes_len = 0;
+        }
+
+        self.bytes_len += image_len;
+        self.images.insert(url, image);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&width.to_be_bytes());
+        png.extend_from_slice(&height.to_be_bytes());
+        png
+    }
+
+    #[test]
+    fn images_are_sized_from_their_png_header() {
+        assert_eq!(png_dimensions(&png(640, 320)), Some((640, 320)));
+        assert_eq!(png_dimensions(b"\xff\xd8\xff\xe0 a JPEG, say"), None);
+
+        let image = Image::from_png(png(640, 320)).unwrap();
+        assert_eq!(image.size_in_cells(100), (80, 20));
+        assert_eq!(image.size_in_cells(40), (40, 10));
+
+        let tall = Image::from_png(png(80, 1600)).unwrap();
+        assert_eq!(tall.size_in_cells(100), (2, 20));
+    }
+
+    #[test]
+    fn images_are_drawn_as_sixels_in_cells_of_8_by_16_pixels() {
+        // a red pixel next to a transparent one
+        let mut red_png = vec![];
+        let mut encoder = png::Encoder::new(&mut red_png, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[255, 0, 0, 255, 0, 0, 0, 0])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let image = Image::from_png(red_png).unwrap();
+        let sixels = image.sequence(GraphicsProtocol::Sixel, 1, 1).unwrap();
+
+        assert!(sixels.starts_with("\x1bP0;1;0q\"1;1;8;16#0;2;0;0;0"));
+        assert!(sixels.contains("#180;2;100;0;0#181;2;100;0;20"));
+        // two bands of 6 rows and one of 4, red on the left half
+        assert!(sixels.ends_with("#180!4~!4?$-#180!4~!4?$-#180!4N!4?$-\x1b\\"));
+
+        // kitty terminals decode PNGs themselves, so only sixels need it to be whole
+        let header_only = Image::from_png(png(2, 1)).unwrap();
+        assert!(header_only
+            .sequence(GraphicsProtocol::Sixel, 1, 1)
+            .is_none());
+        assert!(header_only
+            .sequence(GraphicsProtocol::Kitty, 1, 1)
+            .unwrap()
+            .starts_with("\x1b_Ga=T,f=100,c=1,r=1,"));
+    }
+
+    #[test]
+    fn images_are_only_fetched_once() {
+        let mut cache = ImageCache::default();
+        let urls = vec!["https://a.example/1.png".to_string()];
+
+        assert_eq!(cache.start_fetching(&urls), urls);
+        assert!(cache.start_fetching(&urls).is_empty());
+
+        cache.insert(urls[0].clone(), None);
+        assert!(cache.get(&urls[0]).is_none());
+        assert!(cache.start_fetching(&urls).is_empty());
+    }
+}
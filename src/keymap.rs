@@ -0,0 +1,722 @@
+use crate::modes::Selected;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// everything that can be bound to a key in normal mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Left,
+    Down,
+    Up,
+    Right,
+    /// focuses the feeds, the entries, and the entry in turn
+    NextPane,
+    /// puts the panes side by side or one above the other
+    ToggleSplit,
+    /// shows only the entry being read, centered, or everything again
+    ToggleZen,
+    WidenFeedsPane,
+    NarrowFeedsPane,
+    /// in the three-pane layout, where the entry list has a pane of its own
+    WidenEntriesPane,
+    NarrowEntriesPane,
+    /// puts the panes back to the widths in the config file
+    ResetPaneWidths,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    /// the first feed or entry, or the one a count gives
+    Top,
+    /// the last feed or entry, or the one a count gives
+    Bottom,
+    NextUnread,
+    PreviousUnread,
+    /// opens an unread entry from any feed, picked at random
+    RandomUnread,
+    Enter,
+    ToggleHelp,
+    ToggleReadMode,
+    ToggleUnreadOnly,
+    InsertMode,
+    /// starts subscribing to the URL in the clipboard
+    SubscribeFromClipboard,
+    Search,
+    /// lists the searches saved while searching, to run one again
+    SavedSearches,
+    Filter,
+    DateRange,
+    SwitchFeed,
+    /// lists the feeds whose last refresh failed, and why
+    FeedHealth,
+    FeedStats,
+    /// shows every error since russ started, which the error flash only shows until the next key
+    ErrorLog,
+    /// shows the feed as it was last fetched, to see why it reads oddly
+    RawFeed,
+    EditTags,
+    EditFeedUrl,
+    /// leaves the feed out of refreshing all feeds and of scheduled refreshes, or stops doing so
+    TogglePaused,
+    CopyLink,
+    OpenLink,
+    RefreshFeed,
+    RefreshAllFeeds,
+    ToggleRead,
+    ToggleStarred,
+    MoveFeedUp,
+    MoveFeedDown,
+    CycleEntrySort,
+    DownloadEnclosure,
+    Play,
+    FetchArticle,
+    /// asks for a command to pipe the entry's text or HTML to
+    PipeEntry,
+    /// writes the entry to a Markdown or HTML file
+    SaveEntry,
+    /// writes or changes the entry's note
+    EditNote,
+    /// marks or unmarks the selected entry for a bulk action
+    ToggleMark,
+    /// starts or ends marking every entry from here to the selected one
+    VisualMode,
+    /// deletes the marked entries, or the selected one
+    DeleteEntries,
+    /// marks every entry in the list read
+    MarkAllRead,
+    Undo,
+    /// asks whether to delete the selected feed
+    DeleteFeed,
+    CommandPalette,
+}
+
+impl Action {
+    /// every action, in the order the help lists them
+    pub const ALL: &'static [Action] = &[
+        Action::Left,
+        Action::Down,
+        Action::Up,
+        Action::Right,
+        Action::NextPane,
+        Action::ToggleSplit,
+        Action::ToggleZen,
+        Action::WidenFeedsPane,
+        Action::NarrowFeedsPane,
+        Action::WidenEntriesPane,
+        Action::NarrowEntriesPane,
+        Action::ResetPaneWidths,
+        Action::PageUp,
+        Action::PageDown,
+        Action::HalfPageUp,
+        Action::HalfPageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::NextUnread,
+        Action::PreviousUnread,
+        Action::RandomUnread,
+        Action::Enter,
+        Action::SwitchFeed,
+        Action::FeedHealth,
+        Action::FeedStats,
+        Action::ErrorLog,
+        Action::RawFeed,
+        Action::Search,
+        Action::SavedSearches,
+        Action::Filter,
+        Action::DateRange,
+        Action::ToggleReadMode,
+        Action::ToggleUnreadOnly,
+        Action::CycleEntrySort,
+        Action::ToggleMark,
+        Action::VisualMode,
+        Action::ToggleRead,
+        Action::ToggleStarred,
+        Action::DeleteEntries,
+        Action::MarkAllRead,
+        Action::Undo,
+        Action::CopyLink,
+        Action::OpenLink,
+        Action::FetchArticle,
+        Action::DownloadEnclosure,
+        Action::Play,
+        Action::PipeEntry,
+        Action::SaveEntry,
+        Action::EditNote,
+        Action::InsertMode,
+        Action::SubscribeFromClipboard,
+        Action::EditTags,
+        Action::EditFeedUrl,
+        Action::TogglePaused,
+        Action::MoveFeedUp,
+        Action::MoveFeedDown,
+        Action::DeleteFeed,
+        Action::RefreshFeed,
+        Action::RefreshAllFeeds,
+        Action::CommandPalette,
+        Action::ToggleHelp,
+        Action::Quit,
+    ];
+
+    /// what the action does, for the help
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Left => "go back to the feeds or entries",
+            Action::Down => "move down",
+            Action::Up => "move up",
+            Action::Right => "go to the entries",
+            Action::NextPane => "focus the next pane",
+            Action::ToggleSplit => "switch between side-by-side and stacked panes",
+            Action::ToggleZen => "read the entry without distractions, or stop",
+            Action::WidenFeedsPane => "make the feeds wider",
+            Action::NarrowFeedsPane => "make the feeds narrower",
+            Action::WidenEntriesPane => "make the entries wider (in the three-pane layout)",
+            Action::NarrowEntriesPane => "make the entries narrower (in the three-pane layout)",
+            Action::ResetPaneWidths => "put the panes back to their configured widths",
+            Action::PageUp => "move up a page",
+            Action::PageDown => "move down a page",
+            Action::HalfPageUp => "move up half a page",
+            Action::HalfPageDown => "move down half a page",
+            Action::Top => "go to the top",
+            Action::Bottom => "go to the bottom",
+            Action::NextUnread => "go to the next unread entry",
+            Action::PreviousUnread => "go to the previous unread entry",
+            Action::RandomUnread => "open a random unread entry from any feed",
+            Action::Enter => "open the entry, or open/close the group",
+            Action::ToggleHelp => "show/hide all keys",
+            Action::ToggleReadMode => "switch between read and unread entries",
+            Action::ToggleUnreadOnly => "toggle hiding read entries",
+            Action::InsertMode => "subscribe to a feed",
+            Action::SubscribeFromClipboard => "subscribe to the feed in the clipboard",
+            Action::Search => "search all entries",
+            Action::SavedSearches => "run a saved search again",
+            Action::Filter => "filter the entries",
+            Action::DateRange => "show entries from a date range",
+            Action::SwitchFeed => "go to a feed by name",
+            Action::FeedHealth => "show the feeds that fail to refresh",
+            Action::FeedStats => "show the feed's statistics",
+            Action::ErrorLog => "show every error so far",
+            Action::RawFeed => "show the feed as it was last fetched",
+            Action::EditTags => "edit the feed's tags",
+            Action::EditFeedUrl => "change the feed's URL",
+            Action::TogglePaused => "pause/unpause refreshing the feed",
+            Action::CopyLink => "copy the link",
+            Action::OpenLink => "open the link in the browser",
+            Action::RefreshFeed => "refresh the feed or group",
+            Action::RefreshAllFeeds => "refresh all feeds",
+            Action::ToggleRead => "mark the entry, or the marked entries, read/unread",
+            Action::ToggleStarred => "star/unstar the entry, or the marked entries",
+            Action::ToggleMark => "mark/unmark the entry",
+            Action::VisualMode => "mark every entry from here to the selected one",
+            Action::DeleteEntries => "delete the entry, or the marked entries",
+            Action::MarkAllRead => "mark every entry in the list read",
+            Action::Undo => "undo the last change to entries or feeds",
+            Action::DeleteFeed => "delete the feed, after asking",
+            Action::MoveFeedUp => "move the feed up",
+            Action::MoveFeedDown => "move the feed down",
+            Action::CycleEntrySort => "change how entries are sorted",
+            Action::DownloadEnclosure => "download the enclosure",
+            Action::Play => "play the enclosure or link",
+            Action::FetchArticle => "fetch the full article",
+            Action::PipeEntry => "pipe the entry to a command",
+            Action::SaveEntry => "save the entry to a file",
+            Action::EditNote => "write a note about the entry",
+            Action::CommandPalette => "run any of these by name",
+        }
+    }
+
+    /// some actions share a key and are told apart by what is selected,
+    /// like `r` refreshing a feed or marking an entry as read
+    pub fn applies_to(&self, pane: Pane) -> bool {
+        match self {
+            Action::RefreshFeed => pane == Pane::Feeds,
+            Action::ToggleRead | Action::ToggleStarred => pane != Pane::Feeds,
+            Action::Filter => pane != Pane::Entry,
+            Action::DownloadEnclosure
+            | Action::Play
+            | Action::FetchArticle
+            | Action::PipeEntry
+            | Action::SaveEntry
+            | Action::EditNote => pane != Pane::Feeds,
+            Action::EditTags
+            | Action::EditFeedUrl
+            | Action::TogglePaused
+            | Action::MoveFeedUp
+            | Action::MoveFeedDown => pane == Pane::Feeds,
+            Action::ToggleMark | Action::VisualMode => pane == Pane::Entries,
+            Action::DeleteEntries => pane != Pane::Feeds,
+            Action::DeleteFeed => pane == Pane::Feeds,
+            _ => true,
+        }
+    }
+}
+
+/// what is selected, as far as keys are concerned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Feeds,
+    Entries,
+    Entry,
+}
+
+impl From<&Selected> for Pane {
+    fn from(selected: &Selected) -> Self {
+        match selected {
+            Selected::Feeds => Pane::Feeds,
+            Selected::Entries => Pane::Entries,
+            Selected::Entry(_) => Pane::Entry,
+        }
+    }
+}
+
+impl Pane {
+    /// how it is stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Pane::Feeds => "feeds",
+            Pane::Entries => "entries",
+            Pane::Entry => "entry",
+        }
+    }
+}
+
+impl FromStr for Pane {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "feeds" => Ok(Pane::Feeds),
+            "entries" => Ok(Pane::Entries),
+            "entry" => Ok(Pane::Entry),
+            _ => Err(anyhow::anyhow!("{} is not a valid Pane", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // terminals disagree about whether `?` or `A` come with SHIFT,
+        // so shift is carried by the character itself
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        Key { code, modifiers }
+    }
+}
+
+impl std::fmt::Display for Key {
+    /// the key as it is written in the config, like `ctrl-d` or `pagedown`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Key::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    /// parses keys like `q`, `ctrl-c`, `alt-j`, `pagedown` or `esc`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+
+        // a lone `-` is a key, not a separator
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, tail)| !tail.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow::anyhow!("{} is not a valid key modifier", modifier)),
+            };
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(anyhow::anyhow!("{} is not a valid key", s)),
+                }
+            }
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+/// keys pressed one after another, like `g g`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeySequence(Vec<Key>);
+
+impl std::fmt::Display for KeySequence {
+    /// the keys run together as they are typed, like `gg`, if they are all single characters
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.0.iter().map(Key::to_string).collect::<Vec<_>>();
+
+        if keys.len() > 1 && keys.iter().all(|key| key.chars().count() == 1) {
+            f.write_str(&keys.concat())
+        } else {
+            f.write_str(&keys.join(" "))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        KeySequence::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = anyhow::Error;
+
+    /// parses keys separated by spaces, like `g g` or `ctrl-w j`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(Key::from_str)
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() {
+            return Err(anyhow::anyhow!("A keybinding needs at least one key"));
+        }
+
+        Ok(KeySequence(keys))
+    }
+}
+
+/// what the keys pressed so far are bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Action(Action),
+    /// the keys start a longer sequence, so the next key decides
+    Pending,
+    Unbound,
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeySequence, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+
+        let bindings = [
+            ("q", Quit),
+            ("esc", Quit),
+            ("ctrl-c", Quit),
+            ("h", Left),
+            ("left", Left),
+            ("j", Down),
+            ("down", Down),
+            ("k", Up),
+            ("up", Up),
+            ("l", Right),
+            ("right", Right),
+            ("ctrl-w w", NextPane),
+            ("ctrl-w s", ToggleSplit),
+            ("z", ToggleZen),
+            ("ctrl-w >", WidenFeedsPane),
+            ("ctrl-w <", NarrowFeedsPane),
+            ("ctrl-w +", WidenEntriesPane),
+            ("ctrl-w -", NarrowEntriesPane),
+            ("ctrl-w =", ResetPaneWidths),
+            ("pageup", PageUp),
+            ("pagedown", PageDown),
+            ("ctrl-u", HalfPageUp),
+            ("ctrl-d", HalfPageDown),
+            ("g g", Top),
+            ("home", Top),
+            ("G", Bottom),
+            ("end", Bottom),
+            ("n", NextUnread),
+            ("tab", NextUnread),
+            ("N", PreviousUnread),
+            ("R", RandomUnread),
+            ("enter", Enter),
+            ("?", ToggleHelp),
+            ("a", ToggleReadMode),
+            ("U", ToggleUnreadOnly),
+            ("e", InsertMode),
+            ("i", InsertMode),
+            ("ctrl-v", SubscribeFromClipboard),
+            ("s", Search),
+            ("g s", SavedSearches),
+            ("/", Filter),
+            ("D", DateRange),
+            ("f", SwitchFeed),
+            ("H", FeedHealth),
+            ("I", FeedStats),
+            ("L", ErrorLog),
+            ("g r", RawFeed),
+            ("t", EditTags),
+            ("E", EditFeedUrl),
+            ("P", TogglePaused),
+            ("c", CopyLink),
+            ("y", CopyLink),
+            ("o", OpenLink),
+            ("r", RefreshFeed),
+            ("x", RefreshAllFeeds),
+            ("r", ToggleRead),
+            ("*", ToggleStarred),
+            ("K", MoveFeedUp),
+            ("J", MoveFeedDown),
+            ("S", CycleEntrySort),
+            ("d", DownloadEnclosure),
+            ("p", Play),
+            ("F", FetchArticle),
+            ("|", PipeEntry),
+            ("w", SaveEntry),
+            ("m", EditNote),
+            (":", CommandPalette),
+            ("space", ToggleMark),
+            ("v", VisualMode),
+            ("delete", DeleteEntries),
+            ("delete", DeleteFeed),
+            ("A", MarkAllRead),
+            ("u", Undo),
+        ]
+        .iter()
+        .map(|(keys, action)| (KeySequence::from_str(keys).unwrap(), *action))
+        .collect();
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// replaces the default keys of every overridden action
+    pub fn with_overrides(overrides: &HashMap<Action, Vec<KeySequence>>) -> Self {
+        let mut keymap = Keymap::default();
+
+        keymap
+            .bindings
+            .retain(|(_, action)| !overrides.contains_key(action));
+
+        for (action, keys) in overrides {
+            for key_sequence in keys {
+                keymap.bindings.push((key_sequence.clone(), *action));
+            }
+        }
+
+        keymap
+    }
+
+    /// the keys of every bound action that applies to `pane`, in the order of `Action::ALL`
+    pub fn bound_keys(&self, pane: Pane) -> Vec<(Action, Vec<&KeySequence>)> {
+        Action::ALL
+            .iter()
+            .filter(|action| action.applies_to(pane))
+            .filter_map(|action| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, bound_action)| bound_action == action)
+                    .map(|(key_sequence, _)| key_sequence)
+                    .collect::<Vec<_>>();
+
+                (!keys.is_empty()).then_some((*action, keys))
+            })
+            .collect()
+    }
+
+    /// what `keys`, pressed one after another, are bound to
+    pub fn binding(&self, keys: &[Key], selected: &Selected) -> Binding {
+        let applicable = self
+            .bindings
+            .iter()
+            .filter(|(_, action)| action.applies_to(Pane::from(selected)));
+
+        let mut is_pending = false;
+
+        for (KeySequence(bound_keys), action) in applicable {
+            if bound_keys == keys {
+                return Binding::Action(*action);
+            }
+
+            is_pending =
+                is_pending || (bound_keys.len() > keys.len() && bound_keys.starts_with(keys));
+        }
+
+        if is_pending {
+            Binding::Pending
+        } else {
+            Binding::Unbound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_keys() {
+        assert_eq!(
+            Key::from_str("ctrl-c").unwrap(),
+            Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Key::from_str("-").unwrap(),
+            Key::new(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::from_str("PageDown").unwrap(),
+            Key::new(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        assert!(Key::from_str("hyper-x").is_err());
+        assert!(Key::from_str("xyz").is_err());
+    }
+
+    #[test]
+    fn overrides_replace_default_keys() {
+        let mut overrides = HashMap::new();
+        overrides.insert(Action::Down, vec![KeySequence::from_str("b").unwrap()]);
+        let keymap = Keymap::with_overrides(&overrides);
+
+        let b = Key::from_str("b").unwrap();
+        let j = Key::from_str("j").unwrap();
+        assert_eq!(
+            keymap.binding(&[b], &Selected::Feeds),
+            Binding::Action(Action::Down)
+        );
+        assert_eq!(keymap.binding(&[j], &Selected::Feeds), Binding::Unbound);
+    }
+
+    #[test]
+    fn shared_keys_depend_on_selection() {
+        let keymap = Keymap::default();
+        let r = Key::from_str("r").unwrap();
+
+        assert_eq!(
+            keymap.binding(&[r], &Selected::Feeds),
+            Binding::Action(Action::RefreshFeed)
+        );
+        assert_eq!(
+            keymap.binding(&[r], &Selected::Entries),
+            Binding::Action(Action::ToggleRead)
+        );
+    }
+
+    #[test]
+    fn sequences_wait_for_their_next_key() {
+        let keymap = Keymap::default();
+        let g = Key::from_str("g").unwrap();
+        let j = Key::from_str("j").unwrap();
+
+        assert_eq!(keymap.binding(&[g], &Selected::Feeds), Binding::Pending);
+        assert_eq!(
+            keymap.binding(&[g, g], &Selected::Feeds),
+            Binding::Action(Action::Top)
+        );
+        assert_eq!(keymap.binding(&[g, j], &Selected::Feeds), Binding::Unbound);
+        assert!(KeySequence::from_str(" ").is_err());
+    }
+
+    #[test]
+    fn the_help_shows_every_default_binding() {
+        let keymap = Keymap::default();
+
+        for (_, action) in &keymap.bindings {
+            assert!(
+                Action::ALL.contains(action),
+                "{:?} is not in the help",
+                action
+            );
+        }
+
+        let entry_keys = keymap.bound_keys(Pane::Entries);
+        let (_, top_keys) = entry_keys
+            .iter()
+            .find(|(action, _)| *action == Action::Top)
+            .unwrap();
+        assert_eq!(
+            top_keys.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["gg", "home"]
+        );
+        assert!(!entry_keys
+            .iter()
+            .any(|(action, _)| *action == Action::RefreshFeed));
+
+        for key in ["ctrl-d", "pagedown", "space", "G", "alt-j"] {
+            assert_eq!(Key::from_str(key).unwrap().to_string(), key);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::config::FeedSettings;
+use regex::Regex;
+use tui::style::Style;
+use tui::text::{Span, Spans, Text};
+
+/// the patterns that make entries, and the words in them, stand out
+#[derive(Clone, Debug, Default)]
+pub struct HighlightKeywords {
+    /// highlighted in every feed
+    pub global: Vec<Regex>,
+    /// highlighted only in the feeds with these feed links
+    pub feeds: FeedSettings<Vec<Regex>>,
+}
+
+impl HighlightKeywords {
+    /// for the feed with `feed_links`, as `Feed::feed_links` has them
+    fn for_feed<'a>(&'a self, feed_links: &[&str]) -> impl Iterator<Item = &'a Regex> {
+        let feed_keywords = self.feeds.get(feed_links).into_iter().flatten();
+
+        self.global.iter().chain(feed_keywords)
+    }
+
+    pub fn is_match(&self, feed_links: &[&str], text: &str) -> bool {
+        self.for_feed(feed_links)
+            .any(|keyword| keyword.is_match(text))
+    }
+
+    /// `text` with what the keywords match patched with `style`.
+    /// a match has to be inside one span, so one that crosses from plain into bold text is missed
+    pub fn highlight(
+        &self,
+        feed_links: &[&str],
+        text: Text<'static>,
+        style: Style,
+    ) -> Text<'static> {
+        let keywords = self.for_feed(feed_links).collect::<Vec<_>>();
+
+        if keywords.is_empty() {
+            return text;
+        }
+
+        let lines = text
+            .lines
+            .into_iter()
+            .map(|line| {
+                Spans::from(
+                    line.0
+                        .into_iter()
+                        .flat_map(|span| highlight_span(&keywords, span, style))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+
+        Text { lines }
+    }
+}
+
+fn highlight_span(keywords: &[&Regex], span: Span<'static>, style: Style) -> Vec<Span<'static>> {
+    let content = span.content.as_ref();
+
+    let mut matches = keywords
+        .iter()
+        .flat_map(|keyword| keyword.find_iter(content))
+        .filter(|found| !found.range().is_empty())
+        .map(|found| found.range())
+        .collect::<Vec<_>>();
+
+    if matches.is_empty() {
+        return vec![span];
+    }
+
+    matches.sort_by_key(|range| range.start);
+
+    let mut spans = vec![];
+    let mut end = 0;
+
+    for range in matches {
+        // overlapping matches are highlighted as far as the first one reaches
+        if range.start < end {
+            continue;
+        }
+        if range.start > end {
+            spans.push(Span::styled(
+                content[end..range.start].to_owned(),
+                span.style,
+            ));
+        }
+        spans.push(Span::styled(
+            content[range.clone()].to_owned(),
+            span.style.patch(style),
+        ));
+        end = range.end;
+    }
+
+    if end < content.len() {
+        spans.push(Span::styled(content[end..].to_owned(), span.style));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::{Color, Modifier};
+
+    #[test]
+    fn it_highlights_global_and_feed_keywords_inside_spans() {
+        let keywords = HighlightKeywords {
+            global: vec![Regex::new("(?i)\\brust\\b").unwrap()],
+            feeds: [(
+                "https://example.com/feed.xml".to_string(),
+                vec![Regex::new("sqlite").unwrap()],
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let feed_links = &["https://example.com/feed.xml"];
+
+        assert!(keywords.is_match(&[], "Why Rust?"));
+        assert!(!keywords.is_match(&[], "Trusty sqlite"));
+        assert!(keywords.is_match(feed_links, "Trusty sqlite"));
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let highlight = Style::default().fg(Color::Yellow);
+        let text = Text::from(Spans::from(vec![
+            Span::raw("rust and sqlite"),
+            Span::styled("no match", bold),
+        ]));
+
+        let highlighted = keywords.highlight(feed_links, text, highlight);
+
+        assert_eq!(
+            highlighted.lines[0].0,
+            vec![
+                Span::styled("rust", highlight),
+                Span::raw(" and "),
+                Span::styled("sqlite", highlight),
+                Span::styled("no match", bold),
+            ]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use crate::http::{Credentials, HttpClient};
+use crate::rss::Entry;
+use crate::sync::{first_enclosure, NumberOrString, RemoteEnclosure};
+use crate::sync::{RemoteEntry, RemoteFeed, StateChange, SyncBackend, SyncConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// how many entries are asked for at a time
+const PAGE_SIZE: usize = 250;
+
+/// how far back the first sync goes for entries that are neither unread nor starred
+const FIRST_SYNC_DAYS: i64 = 30;
+
+/// Miniflux's REST API, authenticated with an API key, or a username and password
+#[derive(Debug)]
+pub struct Miniflux {
+    http_client: HttpClient,
+    /// without a trailing slash
+    url: String,
+    username: Option<String>,
+    credentials: Credentials,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinifluxFeed {
+    id: NumberOrString,
+    title: Option<String>,
+    feed_url: String,
+    site_url: Option<String>,
+    category: Option<MinifluxCategory>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinifluxCategory {
+    id: i64,
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntriesPage {
+    #[serde(default)]
+    entries: Vec<MinifluxEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinifluxEntry {
+    id: NumberOrString,
+    feed_id: NumberOrString,
+    status: Option<String>,
+    title: Option<String>,
+    author: Option<String>,
+    content: Option<String>,
+    url: Option<String>,
+    published_at: Option<String>,
+    #[serde(default)]
+    starred: bool,
+    enclosures: Option<Vec<RemoteEnclosure>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subscribed {
+    feed_id: NumberOrString,
+}
+
+impl Miniflux {
+    pub fn new(http_client: HttpClient, sync_config: &SyncConfig) -> Miniflux {
+        Miniflux {
+            http_client,
+            url: sync_config.url.trim_end_matches('/').to_owned(),
+            username: sync_config.username.clone(),
+            credentials: Credentials {
+                username: sync_config.username.clone().unwrap_or_default(),
+                password: sync_config.password.clone(),
+                password_command: sync_config.password_command.clone(),
+            },
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> Result<ureq::Request> {
+        let request = self
+            .http_client
+            .request(method, &format!("{}/v1{}", self.url, path));
+
+        let password = self.http_client.password(&self.credentials)?;
+
+        Ok(match &self.username {
+            Some(username) => {
+                let basic_auth = base64::encode(format!("{}:{}", username, password));
+                request.set("Authorization", &format!("Basic {}", basic_auth))
+            }
+            None => request.set("X-Auth-Token", &password),
+        })
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self
+            .request("GET", path)?
+            .call()
+            .with_context(|| format!("Unable to get {} from Miniflux", path))?
+            .into_string()?;
+
+        serde_json::from_str(&body)
+            .with_context(|| format!("Invalid JSON from Miniflux for {}", path))
+    }
+
+    /// the body of the answer, which is empty for most changes
+    fn send(&self, method: &str, path: &str, body: &Value) -> Result<String> {
+        Ok(self
+            .request(method, path)?
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .with_context(|| format!("Unable to {} {} on Miniflux", method, path))?
+            .into_string()?)
+    }
+
+    /// every page of `/entries?{query}`
+    fn all_entries(&self, query: &str) -> Result<Vec<MinifluxEntry>> {
+        let mut entries = vec![];
+
+        loop {
+            let page: EntriesPage = self.get(&format!(
+                "/entries?{}&order=id&direction=asc&limit={}&offset={}",
+                query,
+                PAGE_SIZE,
+                entries.len()
+            ))?;
+
+            let page_len = page.entries.len();
+            entries.extend(page.entries);
+
+            if page_len < PAGE_SIZE {
+                return Ok(entries);
+            }
+        }
+    }
+}
+
+impl SyncBackend for Miniflux {
+    fn server(&self) -> String {
+        match &self.username {
+            Some(username) => format!("miniflux {} {}", self.url, username),
+            None => format!("miniflux {}", self.url),
+        }
+    }
+
+    fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        let feeds: Vec<MinifluxFeed> = self.get("/feeds")?;
+
+        Ok(feeds.into_iter().map(to_remote_feed).collect())
+    }
+
+    fn entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RemoteEntry>> {
+        let queries = match since {
+            Some(since) => vec![format!("changed_after={}", since.timestamp())],
+            None => vec![
+                "status=unread".to_string(),
+                "starred=true".to_string(),
+                format!(
+                    "changed_after={}",
+                    (Utc::now() - chrono::Duration::days(FIRST_SYNC_DAYS)).timestamp()
+                ),
+            ],
+        };
+
+        let mut seen_ids = HashSet::new();
+        let mut remote_entries = vec![];
+
+        for query in queries {
+            for entry in self.all_entries(&query)? {
+                if let Some(remote_entry) = to_remote_entry(entry) {
+                    if seen_ids.insert(remote_entry.remote_id.clone()) {
+                        remote_entries.push(remote_entry);
+                    }
+                }
+            }
+        }
+
+        Ok(remote_entries)
+    }
+
+    fn push_state_changes(&self, state_changes: &[StateChange]) -> Result<()> {
+        for read in [true, false] {
+            let entry_ids = state_changes
+                .iter()
+                .filter(|state_change| state_change.read == read)
+                .map(|state_change| remote_id_number(&state_change.remote_id))
+                .collect::<Result<Vec<_>>>()?;
+
+            if !entry_ids.is_empty() {
+                self.send(
+                    "PUT",
+                    "/entries",
+                    &json!({
+                        "entry_ids": entry_ids,
+                        "status": if read { "read" } else { "unread" },
+                    }),
+                )?;
+            }
+        }
+
+        // Miniflux can only toggle a bookmark, so russ needs to know which are set first
+        let starred_ids = self
+            .all_entries("starred=true")?
+            .into_iter()
+            .map(|entry| entry.id.0)
+            .collect::<HashSet<_>>();
+
+        for state_change in state_changes {
+            if starred_ids.contains(&state_change.remote_id) != state_change.starred {
+                self.send(
+                    "PUT",
+                    &format!("/entries/{}/bookmark", state_change.remote_id),
+                    &Value::Null,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, feed_link: &str) -> Result<String> {
+        // Miniflux needs a category for every feed, so it goes in the first one
+        let categories: Vec<MinifluxCategory> = self.get("/categories")?;
+        let category_id = categories
+            .first()
+            .map(|category| category.id)
+            .ok_or_else(|| anyhow::anyhow!("Miniflux has no category to subscribe in"))?;
+
+        let response = self.send(
+            "POST",
+            "/feeds",
+            &json!({ "feed_url": feed_link, "category_id": category_id }),
+        )?;
+
+        serde_json::from_str::<Subscribed>(&response)
+            .map(|subscribed| subscribed.feed_id.0)
+            .context("Miniflux did not say which feed it subscribed to")
+    }
+}
+
+fn remote_id_number(remote_id: &str) -> Result<i64> {
+    remote_id
+        .parse()
+        .with_context(|| format!("Invalid Miniflux entry id {}", remote_id))
+}
+
+fn to_remote_feed(feed: MinifluxFeed) -> RemoteFeed {
+    RemoteFeed {
+        remote_id: feed.id.0,
+        title: feed.title,
+        feed_link: feed.feed_url,
+        link: feed.site_url,
+        tags: feed
+            .category
+            .and_then(|category| category.title)
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// `None` for an entry Miniflux has removed
+fn to_remote_entry(entry: MinifluxEntry) -> Option<RemoteEntry> {
+    let status = entry.status.as_deref();
+    if status == Some("removed") {
+        return None;
+    }
+
+    let now = Utc::now();
+
+    Some(RemoteEntry {
+        remote_id: entry.id.0,
+        remote_feed_id: entry.feed_id.0,
+        entry: Entry {
+            title: entry.title,
+            author: entry.author.filter(|author| !author.is_empty()),
+            pub_date: entry
+                .published_at
+                .and_then(|published_at| DateTime::parse_from_rfc3339(&published_at).ok())
+                .map(|published_at| published_at.with_timezone(&Utc)),
+            description: None,
+            content: entry.content,
+            link: entry.url,
+            base_url: None,
+            guid: None,
+            feed_updated_at: None,
+            categories: vec![],
+            enclosure: first_enclosure(&entry.enclosures),
+            read_at: if status == Some("read") {
+                Some(now)
+            } else {
+                None
+            },
+        },
+        starred: entry.starred,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_feeds_and_entries() {
+        let feeds: Vec<MinifluxFeed> = serde_json::from_str(
+            r#"[{"id": 42, "title": "Example", "feed_url": "https://example.com/feed",
+            "site_url": "https://example.com", "category": {"id": 1, "title": "News"}}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_remote_feed(feeds.into_iter().next().unwrap()),
+            RemoteFeed {
+                remote_id: "42".to_string(),
+                title: Some("Example".to_string()),
+                feed_link: "https://example.com/feed".to_string(),
+                link: Some("https://example.com".to_string()),
+                tags: vec!["News".to_string()],
+            }
+        );
+
+        let page: EntriesPage = serde_json::from_str(
+            r#"{"total": 2, "entries": [
+            {"id": 7, "feed_id": 42, "status": "read", "title": "Hi", "url": "https://example.com/hi",
+            "author": "", "content": "<p>hi</p>", "published_at": "2021-06-01T12:00:00+02:00", "starred": true,
+            "enclosures": [{"url": "https://example.com/hi.mp3", "mime_type": "audio/mpeg", "size": 1234}]},
+            {"id": 8, "feed_id": 42, "status": "removed", "title": "Gone", "starred": false,
+            "enclosures": null}]}"#,
+        )
+        .unwrap();
+        let mut entries = page.entries.into_iter();
+
+        let remote_entry = to_remote_entry(entries.next().unwrap()).unwrap();
+        assert_eq!(remote_entry.remote_id, "7");
+        assert_eq!(remote_entry.remote_feed_id, "42");
+        assert!(remote_entry.starred);
+        assert!(remote_entry.entry.read_at.is_some());
+        assert_eq!(remote_entry.entry.author, None);
+        assert_eq!(
+            remote_entry.entry.pub_date.unwrap().to_rfc3339(),
+            "2021-06-01T10:00:00+00:00"
+        );
+
+        let enclosure = remote_entry.entry.enclosure.unwrap();
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(enclosure.length, Some(1234));
+
+        assert!(to_remote_entry(entries.next().unwrap()).is_none());
+    }
+}
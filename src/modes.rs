@@ -1,19 +1,196 @@
+use serde::Deserialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
 #[derive(Clone, Debug)]
 pub enum Selected {
     Feeds,
     Entries,
-    Entry(crate::rss::EntryMeta),
+    Entry(Box<crate::rss::EntryMeta>),
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum Mode {
     Editing,
+    EditingTags,
+    EditingFeedUrl,
+    /// asks for the command to pipe the entry to
+    PipeCommand,
+    /// the selected entry's note, over everything else
+    EditingNote,
     Normal,
+    Search,
+    /// asks for the name to save the search under, over the search
+    NamingSearch,
+    /// the saved searches, over everything else
+    SavedSearches,
+    Filter,
+    DateRange,
+    FeedSwitcher,
+    /// the feeds that fail to refresh, over everything else
+    FeedHealth,
+    /// the selected feed's statistics, over everything else
+    FeedStats,
+    /// every key, over everything else
+    Help,
+    /// every error since russ started, over everything else
+    ErrorLog,
+    /// the selected feed as it was last fetched, over everything else
+    RawFeed,
+    CommandPalette,
+    /// asks `y` or `n` before deleting a feed
+    ConfirmFeedDeletion,
+    /// shows the fetched feed and asks `y` or `n` before subscribing to it
+    ConfirmSubscription,
+    /// asks `y` or `n` before moving a feed to where it has permanently moved
+    ConfirmFeedMove,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ReadMode {
     ShowRead,
     ShowUnread,
     All,
 }
+
+impl ReadMode {
+    /// how it is stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadMode::ShowRead => "read",
+            ReadMode::ShowUnread => "unread",
+            ReadMode::All => "all",
+        }
+    }
+}
+
+impl FromStr for ReadMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(ReadMode::ShowRead),
+            "unread" => Ok(ReadMode::ShowUnread),
+            "all" => Ok(ReadMode::All),
+            _ => Err(anyhow::anyhow!("{} is not a valid ReadMode", s)),
+        }
+    }
+}
+
+/// the order of the feeds in the feed pane
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedSort {
+    #[default]
+    Alphabetical,
+    /// newest entry first
+    Updated,
+    /// most unread entries first
+    Unread,
+    /// the order feeds were moved into, by `Action::MoveFeedUp` and `Action::MoveFeedDown`
+    Manual,
+}
+
+/// how the entry list shows entries' dates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryDates {
+    /// how long ago, like `3h`
+    #[default]
+    Relative,
+    /// the day, like `2024-05-12`
+    Absolute,
+}
+
+/// which panes are on the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneLayout {
+    /// the feeds, and the entries or the entry being read in their place
+    #[default]
+    TwoPane,
+    /// the feeds, the entries, and the entry being read, side by side
+    ThreePane,
+}
+
+/// how the entries and the entry being read are split from what is beside them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Split {
+    /// next to each other, for wide terminals
+    #[default]
+    SideBySide,
+    /// one above the other, for tall terminals
+    Stacked,
+}
+
+impl Split {
+    pub fn toggled(self) -> Self {
+        match self {
+            Split::SideBySide => Split::Stacked,
+            Split::Stacked => Split::SideBySide,
+        }
+    }
+}
+
+/// the order of a feed's entries, remembered per feed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntrySort {
+    /// by published date
+    #[default]
+    Newest,
+    /// by published date
+    Oldest,
+    Title,
+    /// by when russ first saw them, newest first, for feeds with bogus published dates
+    Inserted,
+}
+
+impl EntrySort {
+    /// the sort after this one, for cycling through them with a key
+    pub fn next(self) -> Self {
+        match self {
+            EntrySort::Newest => EntrySort::Oldest,
+            EntrySort::Oldest => EntrySort::Title,
+            EntrySort::Title => EntrySort::Inserted,
+            EntrySort::Inserted => EntrySort::Newest,
+        }
+    }
+
+    /// how it is stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntrySort::Newest => "newest",
+            EntrySort::Oldest => "oldest",
+            EntrySort::Title => "title",
+            EntrySort::Inserted => "inserted",
+        }
+    }
+}
+
+impl Display for EntrySort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let out = match self {
+            EntrySort::Newest => "newest first",
+            EntrySort::Oldest => "oldest first",
+            EntrySort::Title => "title",
+            EntrySort::Inserted => "recently added",
+        };
+
+        write!(f, "{}", out)
+    }
+}
+
+impl FromStr for EntrySort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "newest" => Ok(EntrySort::Newest),
+            "oldest" => Ok(EntrySort::Oldest),
+            "title" => Ok(EntrySort::Title),
+            "inserted" => Ok(EntrySort::Inserted),
+            _ => Err(anyhow::anyhow!("{} is not a valid EntrySort", s)),
+        }
+    }
+}
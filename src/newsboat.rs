@@ -0,0 +1,182 @@
+use crate::rss::{Enclosure, Entry, ImportedEntry};
+use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// a line of Newsboat's `urls` file
+#[derive(Debug, PartialEq, Eq)]
+pub struct NewsboatFeed {
+    pub url: String,
+    pub tags: Vec<String>,
+}
+
+/// where Newsboat keeps its `urls` file and `cache.db`:
+/// `~/.newsboat` if it exists, like Newsboat itself prefers,
+/// or else `$XDG_CONFIG_HOME/newsboat` and `$XDG_DATA_HOME/newsboat`
+pub fn default_paths() -> Option<(PathBuf, PathBuf)> {
+    let home = directories::BaseDirs::new()?.home_dir().to_owned();
+    let dotdir = home.join(".newsboat");
+
+    if dotdir.is_dir() {
+        return Some((dotdir.join("urls"), dotdir.join("cache.db")));
+    }
+
+    let xdg_dir = |var: &str, default: &str| {
+        std::env::var_os(var)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(default))
+            .join("newsboat")
+    };
+
+    Some((
+        xdg_dir("XDG_CONFIG_HOME", ".config").join("urls"),
+        xdg_dir("XDG_DATA_HOME", ".local/share").join("cache.db"),
+    ))
+}
+
+pub fn read_urls(path: &Path) -> Result<Vec<NewsboatFeed>> {
+    let s = std::fs::read_to_string(path)?;
+    Ok(parse_urls(&s))
+}
+
+/// every line is a URL followed by its tags, where a tag starting with `~` is
+/// a custom title and one starting with `!` hides the feed, and neither is carried over.
+/// query feeds and feeds from `exec:` and `filter:` commands have no URL russ can fetch, so they are skipped.
+pub fn parse_urls(s: &str) -> Vec<NewsboatFeed> {
+    s.lines()
+        .filter_map(|line| {
+            let mut words = split_words(line.trim()).into_iter();
+            let url = words.next()?;
+
+            if url.starts_with('#')
+                || ["query:", "exec:", "filter:"]
+                    .iter()
+                    .any(|prefix| url.starts_with(prefix))
+            {
+                return None;
+            }
+
+            let tags = words
+                .filter(|word| !word.starts_with('~') && !word.starts_with('!'))
+                .collect();
+
+            Some(NewsboatFeed { url, tags })
+        })
+        .collect()
+}
+
+/// whitespace separated, except inside double quotes
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => word.extend(chars.next()),
+            c if c.is_whitespace() && !in_quotes => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// the articles in Newsboat's cache, by the URL of their feed.
+/// Newsboat has no stars, so an article with any flags counts as starred.
+pub fn read_cache(path: &Path) -> Result<HashMap<String, Vec<ImportedEntry>>> {
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Unable to open Newsboat cache {}", path.display()))?;
+
+    let mut statement = conn.prepare(
+        "SELECT feedurl, title, author, url, pubDate, content, unread, flags, enclosure_url, enclosure_type
+        FROM rss_item
+        WHERE deleted = 0",
+    )?;
+
+    let now = Utc::now();
+    let mut entries: HashMap<String, Vec<ImportedEntry>> = HashMap::new();
+
+    let rows = statement.query_map([], |row| {
+        let feed_url: String = row.get(0)?;
+        let pub_date: Option<i64> = row.get(4)?;
+        let unread: bool = row.get(6)?;
+        let flags: Option<String> = row.get(7)?;
+        let enclosure_url: Option<String> = row.get(8)?;
+        let enclosure_type: Option<String> = row.get(9)?;
+
+        let entry = Entry {
+            title: row.get(1)?,
+            author: row.get(2)?,
+            pub_date: pub_date.and_then(|pub_date| Utc.timestamp_opt(pub_date, 0).single()),
+            description: None,
+            content: row.get(5)?,
+            link: row.get(3)?,
+            base_url: None,
+            guid: None,
+            feed_updated_at: None,
+            categories: vec![],
+            enclosure: enclosure_url.and_then(|enclosure_url| {
+                Enclosure::new(&enclosure_url, enclosure_type.as_deref(), None)
+            }),
+            read_at: if unread { None } else { Some(now) },
+        };
+
+        Ok((
+            feed_url,
+            ImportedEntry {
+                entry,
+                starred: flags.map(|flags| !flags.is_empty()).unwrap_or(false),
+            },
+        ))
+    })?;
+
+    for row in rows {
+        let (feed_url, imported_entry) = row?;
+        entries.entry(feed_url).or_default().push(imported_entry);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_urls_files() {
+        let urls = r#"
+# news
+https://a.example/feed.xml news "long reads" ~"Custom title"
+https://b.example/atom.xml !hidden
+"query:Unread:unread = \"yes\""
+exec:~/bin/feed.sh
+"#;
+
+        assert_eq!(
+            parse_urls(urls),
+            vec![
+                NewsboatFeed {
+                    url: "https://a.example/feed.xml".to_string(),
+                    tags: vec!["news".to_string(), "long reads".to_string()],
+                },
+                NewsboatFeed {
+                    url: "https://b.example/atom.xml".to_string(),
+                    tags: vec![],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::rss::Feed;
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::path::Path;
+
+/// reads an OPML file and returns the `xmlUrl` of every outline in it,
+/// in document order. Nested outlines (folders) are flattened.
+pub fn read_feed_urls(path: &Path) -> Result<Vec<String>> {
+    let s = std::fs::read_to_string(path)?;
+    parse_feed_urls(&s)
+}
+
+pub fn parse_feed_urls(s: &str) -> Result<Vec<String>> {
+    let mut reader = Reader::from_str(s);
+    reader.trim_text(true);
+
+    let mut urls = vec![];
+    let mut buf = vec![];
+
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) if e.name() == b"outline" => {
+                for attribute in e.attributes() {
+                    let attribute = attribute?;
+                    if attribute.key == b"xmlUrl" {
+                        let url = attribute.unescape_and_decode_value(&reader)?;
+                        if !url.trim().is_empty() {
+                            urls.push(url.trim().to_string());
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(urls)
+}
+
+/// an OPML document with an outline for every feed
+pub fn write_opml(feeds: &[Feed]) -> String {
+    let mut opml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <opml version=\"2.0\">\n  \
+        <head><title>russ subscriptions</title></head>\n  \
+        <body>\n",
+    );
+
+    for feed in feeds {
+        let feed_link = match &feed.feed_link {
+            Some(feed_link) => feed_link,
+            None => continue,
+        };
+
+        let title = escape(feed.title.as_deref().unwrap_or(feed_link));
+
+        opml.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\"",
+            title,
+            title,
+            escape(feed_link)
+        ));
+
+        if let Some(link) = &feed.link {
+            opml.push_str(&format!(" htmlUrl=\"{}\"", escape(link)));
+        }
+
+        opml.push_str("/>\n");
+    }
+
+    opml.push_str("  </body>\n</opml>\n");
+
+    opml
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_nested_outlines() {
+        let opml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head><title>subscriptions</title></head>
+  <body>
+    <outline text="Blogs">
+      <outline type="rss" text="a" xmlUrl="https://a.example/feed.xml"/>
+      <outline type="rss" text="b" xmlUrl="https://b.example/atom.xml?x=1&amp;y=2"></outline>
+    </outline>
+    <outline type="rss" text="c" xmlUrl="https://c.example/rss"/>
+    <outline text="no url"/>
+  </body>
+</opml>"#;
+
+        let urls = parse_feed_urls(opml).unwrap();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.example/feed.xml",
+                "https://b.example/atom.xml?x=1&y=2",
+                "https://c.example/rss"
+            ]
+        );
+    }
+
+    #[test]
+    fn written_opml_can_be_read_back() {
+        let feed = |title: &str, feed_link: &str| Feed {
+            title: Some(title.to_string()),
+            feed_link: Some(feed_link.to_string()),
+            ..Feed::for_test(1)
+        };
+
+        let opml = write_opml(&[
+            feed("Tom & Jerry's \"blog\"", "https://a.example/feed?x=1&y=<2>"),
+            feed("b", "https://b.example/rss"),
+        ]);
+
+        assert_eq!(
+            parse_feed_urls(&opml).unwrap(),
+            vec!["https://a.example/feed?x=1&y=<2>", "https://b.example/rss"]
+        );
+    }
+}
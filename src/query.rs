@@ -0,0 +1,420 @@
+use serde::Deserialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// a condition on entries, like `title ~ "rust" AND unread`, that a smart feed shows the entries of.
+/// `field ~ "text"` matches entries whose field contains the text, and `field = "text"` the ones
+/// whose field is the text, ignoring case. the fields are `title`, `author`, `content`, `category`,
+/// `feed` (the feed's title), and `link`.
+/// `unread`, `read`, `starred`, and `updated` match entries that are.
+/// conditions combine with `AND`, `OR`, `NOT`, and parentheses, with `AND` binding tighter than `OR`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    expr: Expr,
+    /// as it was written
+    text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare { field: Field, op: Op, value: String },
+    Is(Flag),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Title,
+    Author,
+    /// the description and the content
+    Content,
+    Category,
+    /// the feed's title
+    Feed,
+    Link,
+}
+
+impl Field {
+    fn from_word(word: &str) -> Option<Self> {
+        match word.to_lowercase().as_str() {
+            "title" => Some(Field::Title),
+            "author" => Some(Field::Author),
+            "content" => Some(Field::Content),
+            "category" => Some(Field::Category),
+            "feed" => Some(Field::Feed),
+            "link" => Some(Field::Link),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    /// `~`
+    Contains,
+    /// `=`
+    Equals,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Flag {
+    Unread,
+    Read,
+    Starred,
+    /// edited by the feed since it was last opened or read
+    Updated,
+}
+
+impl Flag {
+    fn from_word(word: &str) -> Option<Self> {
+        match word.to_lowercase().as_str() {
+            "unread" => Some(Flag::Unread),
+            "read" => Some(Flag::Read),
+            "starred" => Some(Flag::Starred),
+            "updated" => Some(Flag::Updated),
+            _ => None,
+        }
+    }
+}
+
+impl Query {
+    /// the query as an SQL condition on `entries`. its values are added to `values`,
+    /// numbered as parameters after the `params_len` the statement already has
+    pub fn to_sql(&self, params_len: usize, values: &mut Vec<String>) -> String {
+        expr_to_sql(&self.expr, params_len, values)
+    }
+}
+
+fn expr_to_sql(expr: &Expr, params_len: usize, values: &mut Vec<String>) -> String {
+    match expr {
+        Expr::And(left, right) => format!(
+            "({} AND {})",
+            expr_to_sql(left, params_len, values),
+            expr_to_sql(right, params_len, values)
+        ),
+        Expr::Or(left, right) => format!(
+            "({} OR {})",
+            expr_to_sql(left, params_len, values),
+            expr_to_sql(right, params_len, values)
+        ),
+        Expr::Not(expr) => format!("(NOT {})", expr_to_sql(expr, params_len, values)),
+        Expr::Compare { field, op, value } => {
+            values.push(value.clone());
+            let param = format!("?{}", params_len + values.len());
+
+            let column = match field {
+                Field::Title => "coalesce(entries.title, '')",
+                Field::Author => "coalesce(entries.author, '')",
+                Field::Content => {
+                    "coalesce(entries.description, '') || char(10) || coalesce(entries.content, '')"
+                }
+                Field::Link => "coalesce(entries.link, '')",
+                // one category per line
+                Field::Category => {
+                    return match op {
+                        Op::Contains => format!(
+                            "(instr(lower(coalesce(entries.categories, '')), lower({})) > 0)",
+                            param
+                        ),
+                        Op::Equals => format!(
+                            "(instr(char(10) || lower(coalesce(entries.categories, '')) || char(10), char(10) || lower({}) || char(10)) > 0)",
+                            param
+                        ),
+                    };
+                }
+                Field::Feed => "coalesce(feeds.title, '')",
+            };
+
+            let condition = match op {
+                Op::Contains => format!("instr(lower({}), lower({})) > 0", column, param),
+                Op::Equals => format!("lower({}) = lower({})", column, param),
+            };
+
+            if *field == Field::Feed {
+                format!(
+                    "(entries.feed_id IN (SELECT feeds.id FROM feeds WHERE {}))",
+                    condition
+                )
+            } else {
+                format!("({})", condition)
+            }
+        }
+        Expr::Is(flag) => match flag {
+            Flag::Unread => "(entries.read_at IS NULL)",
+            Flag::Read => "(entries.read_at IS NOT NULL)",
+            Flag::Starred => "(entries.starred)",
+            // selected with the entries, in `rss::ENTRY_META_COLUMNS`
+            Flag::Updated => "(unseen_revised_at IS NOT NULL)",
+        }
+        .to_string(),
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Tilde,
+    Equals,
+    OpenParen,
+    CloseParen,
+}
+
+fn tokenize(s: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '~' => tokens.push(Token::Tilde),
+            '=' => tokens.push(Token::Equals),
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            '"' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek().is_some() => quoted.extend(chars.next()),
+                        Some(c) => quoted.push(c),
+                        None => {
+                            return Err(anyhow::anyhow!(
+                                "A \" in the query is missing its closing \""
+                            ))
+                        }
+                    }
+                }
+                tokens.push(Token::Quoted(quoted));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars
+                    .peek()
+                    .filter(|c| !c.is_whitespace() && !"~=()\"".contains(**c))
+                {
+                    word.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// whether the next token is the keyword, which it then skips
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> anyhow::Result<Expr> {
+        if self.keyword("not") {
+            Ok(Expr::Not(Box::new(self.not()?)))
+        } else {
+            self.condition()
+        }
+    }
+
+    fn condition(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::OpenParen) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(expr),
+                    _ => Err(anyhow::anyhow!("A ( in the query is missing its closing )")),
+                }
+            }
+            Some(Token::Word(word)) => {
+                if let Some(flag) = Flag::from_word(&word) {
+                    return Ok(Expr::Is(flag));
+                }
+
+                let field = Field::from_word(&word).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is not a field or flag a query can have, like title or unread",
+                        word
+                    )
+                })?;
+
+                let op = match self.next() {
+                    Some(Token::Tilde) => Op::Contains,
+                    Some(Token::Equals) => Op::Equals,
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "{} in the query needs ~ or = after it, like {} ~ \"rust\"",
+                            word,
+                            word
+                        ))
+                    }
+                };
+
+                let value = match self.next() {
+                    Some(Token::Quoted(value)) | Some(Token::Word(value)) => value,
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "{} in the query needs something to compare with",
+                            word
+                        ))
+                    }
+                };
+
+                Ok(Expr::Compare { field, op, value })
+            }
+            Some(token) => Err(anyhow::anyhow!(
+                "The query has {} where a condition should be",
+                describe(&token)
+            )),
+            None => Err(anyhow::anyhow!(
+                "The query ends where a condition should be"
+            )),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => word.clone(),
+        Token::Quoted(quoted) => format!("\"{}\"", quoted),
+        Token::Tilde => "~".to_string(),
+        Token::Equals => "=".to_string(),
+        Token::OpenParen => "(".to_string(),
+        Token::CloseParen => ")".to_string(),
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+
+        let expr = parser.or()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(anyhow::anyhow!(
+                "The query has {} where AND or OR should be",
+                describe(token)
+            ));
+        }
+
+        Ok(Query {
+            expr,
+            text: s.trim().to_owned(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Query::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// one of the `[[smart_feeds]]`, shown in the feed pane with the entries of every feed its query matches
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmartFeed {
+    pub name: String,
+    pub query: Query,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(field: Field, op: Op, value: &str) -> Expr {
+        Expr::Compare {
+            field,
+            op,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_parses_queries_with_and_binding_tighter_than_or() {
+        let query = Query::from_str(r#"title ~ "rust" AND unread or NOT (feed = HN)"#).unwrap();
+
+        assert_eq!(
+            query.expr,
+            Expr::Or(
+                Box::new(Expr::And(
+                    Box::new(compare(Field::Title, Op::Contains, "rust")),
+                    Box::new(Expr::Is(Flag::Unread)),
+                )),
+                Box::new(Expr::Not(Box::new(compare(Field::Feed, Op::Equals, "HN")))),
+            )
+        );
+
+        let mut values = vec![];
+        assert_eq!(
+            Query::from_str(r#"author = "A \"B\"" and starred"#)
+                .unwrap()
+                .to_sql(2, &mut values),
+            "((lower(coalesce(entries.author, '')) = lower(?3)) AND (entries.starred))"
+        );
+        assert_eq!(values, vec!["A \"B\"".to_string()]);
+
+        assert!(Query::from_str("").is_err());
+        assert!(Query::from_str("title").is_err());
+        assert!(Query::from_str("title ~").is_err());
+        assert!(Query::from_str("body ~ rust").is_err());
+        assert!(Query::from_str("unread starred").is_err());
+        assert!(Query::from_str("(unread").is_err());
+        assert!(Query::from_str(r#"title ~ "rust"#).is_err());
+    }
+}
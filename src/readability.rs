@@ -0,0 +1,252 @@
+use anyhow::Result;
+use html5ever::serialize::{SerializeOpts, TraversalScope};
+use html5ever::tendril::TendrilSink;
+use markup5ever_rcdom::{Handle, NodeData, RcDom, SerializableHandle};
+use std::collections::HashMap;
+
+/// never part of an article
+const DROPPED_TAGS: &[&str] = &[
+    "aside", "button", "footer", "form", "header", "iframe", "nav", "noscript", "script", "select",
+    "style", "svg", "template",
+];
+/// classes and ids that mark an article, or the parts of a page around one
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "blog", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+const NEGATIVE_HINTS: &[&str] = &[
+    "advert",
+    "comment",
+    "cookie",
+    "footer",
+    "menu",
+    "meta",
+    "nav",
+    "newsletter",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "subscribe",
+    "widget",
+];
+/// paragraphs shorter than this are captions and bylines, not the article
+const MIN_PARAGRAPH_LEN: usize = 25;
+
+/// the main content of the web page `html`, found the way reader modes do,
+/// by where most of the page's paragraphs are. its links are made absolute against `url`
+pub fn extract(html: &str, url: &str) -> Result<String> {
+    let dom = html5ever::parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())?;
+
+    drop_clutter(&dom.document);
+
+    let mut scores = HashMap::new();
+    score_paragraphs(&dom.document, &mut vec![], &mut scores);
+
+    let article = scores
+        .into_values()
+        .map(|(node, score)| {
+            let score = score * (1.0 - link_density(&node));
+            (node, score)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(node, _)| node)
+        .or_else(|| find_element(&dom.document, "body"))
+        .ok_or_else(|| anyhow::anyhow!("{} has no article in it", url))?;
+
+    if let Ok(base_url) = url::Url::parse(url) {
+        make_links_absolute(&article, &base_url);
+    }
+
+    let mut serialized = vec![];
+    html5ever::serialize(
+        &mut serialized,
+        &SerializableHandle::from(article),
+        SerializeOpts {
+            traversal_scope: TraversalScope::ChildrenOnly(None),
+            ..Default::default()
+        },
+    )?;
+
+    Ok(String::from_utf8(serialized)?)
+}
+
+pub(crate) fn element_name(node: &Handle) -> Option<&str> {
+    match &node.data {
+        NodeData::Element { name, .. } => Some(&*name.local),
+        _ => None,
+    }
+}
+
+pub(crate) fn attribute(node: &Handle, name: &str) -> Option<String> {
+    match &node.data {
+        NodeData::Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attr| &*attr.name.local == name)
+            .map(|attr| attr.value.to_string()),
+        _ => None,
+    }
+}
+
+/// how much a node's class and id say it is the article, or is not
+fn hint_weight(node: &Handle) -> f64 {
+    let hints = format!(
+        "{} {}",
+        attribute(node, "class").unwrap_or_default(),
+        attribute(node, "id").unwrap_or_default()
+    )
+    .to_lowercase();
+
+    let mut weight = 0.0;
+    if POSITIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight += 25.0;
+    }
+    if NEGATIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+/// removes navigation, scripts, comment sections and the like
+fn drop_clutter(node: &Handle) {
+    node.children
+        .borrow_mut()
+        .retain(|child| match &child.data {
+            NodeData::Comment { .. } => false,
+            NodeData::Element { .. } => {
+                let name = element_name(child).unwrap_or_default();
+                let is_clutter = DROPPED_TAGS.contains(&name)
+                    || (!matches!(name, "html" | "body") && hint_weight(child) < 0.0);
+                !is_clutter
+            }
+            _ => true,
+        });
+
+    for child in node.children.borrow().iter() {
+        drop_clutter(child);
+    }
+}
+
+/// gives each paragraph's parent a point for it, more for long paragraphs with many commas,
+/// and its grandparent half as many
+fn score_paragraphs(
+    node: &Handle,
+    ancestors: &mut Vec<Handle>,
+    scores: &mut HashMap<*const markup5ever_rcdom::Node, (Handle, f64)>,
+) {
+    if let Some("p" | "pre" | "td" | "blockquote") = element_name(node) {
+        let text = text_content(node);
+        let text_len = text.trim().chars().count();
+
+        if text_len >= MIN_PARAGRAPH_LEN {
+            let score = 1.0 + text.matches(',').count() as f64 + (text_len / 100).min(3) as f64;
+
+            for (ancestor, share) in ancestors.iter().rev().zip([1.0, 0.5]) {
+                let (_, ancestor_score) = scores
+                    .entry(std::rc::Rc::as_ptr(ancestor))
+                    .or_insert_with(|| (ancestor.clone(), hint_weight(ancestor)));
+                *ancestor_score += score * share;
+            }
+        }
+    }
+
+    ancestors.push(node.clone());
+    for child in node.children.borrow().iter() {
+        score_paragraphs(child, ancestors, scores);
+    }
+    ancestors.pop();
+}
+
+fn text_content(node: &Handle) -> String {
+    let mut text = String::new();
+    push_text(node, &mut text);
+    text
+}
+
+fn push_text(node: &Handle, text: &mut String) {
+    if let NodeData::Text { contents } = &node.data {
+        text.push_str(&contents.borrow());
+    }
+
+    for child in node.children.borrow().iter() {
+        push_text(child, text);
+    }
+}
+
+/// how much of a node's text is links, as lists of links are not articles
+fn link_density(node: &Handle) -> f64 {
+    let text_len = text_content(node).chars().count();
+    if text_len == 0 {
+        return 1.0;
+    }
+
+    let mut links_len = 0;
+    let mut nodes = vec![node.clone()];
+    while let Some(node) = nodes.pop() {
+        if element_name(&node) == Some("a") {
+            links_len += text_content(&node).chars().count();
+        } else {
+            nodes.extend(node.children.borrow().iter().cloned());
+        }
+    }
+
+    links_len as f64 / text_len as f64
+}
+
+pub(crate) fn find_element(node: &Handle, name: &str) -> Option<Handle> {
+    if element_name(node) == Some(name) {
+        return Some(node.clone());
+    }
+
+    node.children
+        .borrow()
+        .iter()
+        .find_map(|child| find_element(child, name))
+}
+
+/// the article is read away from its page, where relative links mean nothing
+pub(crate) fn make_links_absolute(node: &Handle, base_url: &url::Url) {
+    if let NodeData::Element { attrs, .. } = &node.data {
+        for attr in attrs.borrow_mut().iter_mut() {
+            if matches!(&*attr.name.local, "href" | "src") {
+                if let Ok(absolute) = base_url.join(&attr.value) {
+                    attr.value = absolute.as_str().into();
+                }
+            }
+        }
+    }
+
+    for child in node.children.borrow().iter() {
+        make_links_absolute(child, base_url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_extracts_the_article_from_its_page() {
+        let html = r#"<html><head><script>track()</script></head><body>
+            <nav><a href="/">Home</a>, <a href="/about">About</a>, and more links, many of them</nav>
+            <div class="post-body">
+              <p>The first paragraph of the article, which is long enough, and has commas.</p>
+              <p>A second one, with <a href="/elsewhere">a relative link</a>, also long enough.</p>
+            </div>
+            <div class="comments"><p>A comment, which is long enough to count, but is not the article.</p></div>
+            </body></html>"#;
+
+        let article = extract(html, "https://blog.example/posts/1").unwrap();
+
+        assert!(article.contains("The first paragraph of the article"));
+        assert!(article.contains(r#"href="https://blog.example/elsewhere""#));
+        assert!(!article.contains("About"));
+        assert!(!article.contains("A comment"));
+        assert!(!article.contains("track()"));
+    }
+}
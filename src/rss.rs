@@ -1,17 +1,84 @@
-use crate::modes::ReadMode;
+use crate::date_range::DateRange;
+use crate::http::HttpClient;
+use crate::keymap::Pane;
+use crate::modes::{EntrySort, FeedSort, ReadMode};
+use crate::query::Query;
+use crate::rules::{RuleAction, Rules};
+use crate::sync::{EntryStates, RemoteEntry, RemoteFeed, StateChange};
 use anyhow::{Context, Result};
 use atom_syndication as atom;
 use chrono::prelude::{DateTime, Utc};
 use rss::Channel;
-use rusqlite::params;
 use rusqlite::types::ToSqlOutput;
-use std::collections::HashSet;
+use rusqlite::{params, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::str::FromStr;
 
-type EntryId = i64;
+pub type EntryId = i64;
 pub type FeedId = i64;
 
+/// a feed that is not a subscription, but a view over the entries of every feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VirtualFeed {
+    AllEntries,
+    Starred,
+    /// the entries that were opened, last opened first
+    RecentlyRead,
+    /// the `idx`th of the config's `[[smart_feeds]]`
+    Smart(usize),
+}
+
+impl Display for VirtualFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VirtualFeed::AllEntries => write!(f, "All entries"),
+            VirtualFeed::Starred => write!(f, "Starred"),
+            VirtualFeed::RecentlyRead => write!(f, "Recently read"),
+            VirtualFeed::Smart(idx) => write!(f, "Smart feed {}", idx + 1),
+        }
+    }
+}
+
+impl VirtualFeed {
+    /// how it is stored in the database
+    fn to_db_string(self) -> String {
+        match self {
+            VirtualFeed::AllEntries => "all".to_string(),
+            VirtualFeed::Starred => "starred".to_string(),
+            VirtualFeed::RecentlyRead => "recent".to_string(),
+            VirtualFeed::Smart(idx) => format!("smart:{}", idx),
+        }
+    }
+}
+
+impl FromStr for VirtualFeed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(VirtualFeed::AllEntries),
+            "starred" => Ok(VirtualFeed::Starred),
+            "recent" => Ok(VirtualFeed::RecentlyRead),
+            _ if s.starts_with("smart:") => Ok(VirtualFeed::Smart(s["smart:".len()..].parse()?)),
+            _ => Err(anyhow::anyhow!("{} is not a valid VirtualFeed", s)),
+        }
+    }
+}
+
+impl rusqlite::types::FromSql for VirtualFeed {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        VirtualFeed::from_str(s).map_err(|e| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+impl rusqlite::types::ToSql for VirtualFeed {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_db_string()))
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum FeedKind {
     Atom,
@@ -58,6 +125,45 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
     }
 }
 
+impl rusqlite::types::FromSql for EntrySort {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        EntrySort::from_str(s).map_err(|e| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+impl rusqlite::types::ToSql for EntrySort {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl rusqlite::types::FromSql for ReadMode {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        ReadMode::from_str(s).map_err(|e| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+impl rusqlite::types::ToSql for ReadMode {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl rusqlite::types::FromSql for Pane {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let s = value.as_str()?;
+        Pane::from_str(s).map_err(|e| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
+impl rusqlite::types::ToSql for Pane {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Feed {
     pub id: FeedId,
@@ -66,63 +172,337 @@ pub struct Feed {
     pub link: Option<String>,
     pub feed_kind: FeedKind,
     pub refreshed_at: Option<chrono::DateTime<Utc>>,
-    pub inserted_at: chrono::DateTime<Utc>,
-    pub updated_at: chrono::DateTime<Utc>,
+    /// the feed's server asked not to be fetched again before this
+    pub retry_after: Option<chrono::DateTime<Utc>>,
+    pub entry_sort: EntrySort,
+    /// left out of refreshing all feeds and of scheduled refreshes
+    pub paused: bool,
+    pub health: FeedHealth,
+    /// the feed link it was subscribed at, if it has permanently moved since
+    pub original_feed_link: Option<String>,
+    /// where the feed has permanently moved, while that waits to be confirmed
+    pub moved_to: Option<String>,
+}
+
+impl Feed {
+    /// where the feed is, then where it was subscribed at if it has moved since,
+    /// which is what settings for the feed in the config may still be under
+    pub fn feed_links(&self) -> Vec<&str> {
+        self.feed_link
+            .iter()
+            .chain(&self.original_feed_link)
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn is_rate_limited(&self, now: DateTime<Utc>) -> bool {
+        self.retry_after
+            .map(|retry_after| retry_after > now)
+            .unwrap_or(false)
+    }
+
+    /// its last refresh failed
+    pub fn is_failing(&self) -> bool {
+        self.health.consecutive_failures > 0
+    }
+}
+
+#[cfg(test)]
+impl Feed {
+    /// a feed with nothing but an id, for tests to fill in the rest of
+    pub fn for_test(id: FeedId) -> Feed {
+        Feed {
+            id,
+            title: None,
+            feed_link: None,
+            link: None,
+            feed_kind: FeedKind::Rss,
+            refreshed_at: None,
+            retry_after: None,
+            entry_sort: EntrySort::default(),
+            paused: false,
+            health: FeedHealth::default(),
+            original_feed_link: None,
+            moved_to: None,
+        }
+    }
+}
+
+/// how a feed's refreshes have gone. the last success is its `refreshed_at`
+#[derive(Clone, Debug, Default)]
+pub struct FeedHealth {
+    /// kept after the feed recovers, to look back on
+    pub last_error: Option<String>,
+    pub last_error_at: Option<chrono::DateTime<Utc>>,
+    /// how many refreshes in a row failed, 0 once one succeeds
+    pub consecutive_failures: u32,
+}
+
+/// how long to leave a feed alone after a 429 without a usable Retry-After
+const DEFAULT_RATE_LIMIT_COOL_OFF_MINUTES: i64 = 60;
+
+/// a feed's server answered 429 Too Many Requests
+#[derive(Debug)]
+pub struct RateLimited {
+    pub until: DateTime<Utc>,
+}
+
+impl Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rate limited until {}", self.until)
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// a feed's server answered with something that is not a feed russ can read
+#[derive(Debug)]
+pub struct UnparsableFeed {
+    /// what the server answered, to see why
+    pub body: String,
+}
+
+impl Display for UnparsableFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "It is not an RSS or Atom feed")
+    }
+}
+
+impl std::error::Error for UnparsableFeed {}
+
+/// a feed that is already subscribed to, at the same URL once both are normalized
+#[derive(Debug)]
+pub struct AlreadySubscribed {
+    pub feed_id: FeedId,
+    pub title: Option<String>,
+    /// the URL it is subscribed to at
+    pub url: String,
+}
+
+impl Display for AlreadySubscribed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is already subscribed to as {}",
+            self.url,
+            self.title.as_deref().unwrap_or("a feed with no title")
+        )
+    }
+}
+
+impl std::error::Error for AlreadySubscribed {}
+
+/// Retry-After is either a number of seconds or an HTTP date
+fn parse_retry_after(retry_after: Option<&str>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let retry_after = retry_after.map(|retry_after| retry_after.trim());
+
+    retry_after
+        .and_then(|retry_after| retry_after.parse::<u32>().ok())
+        .map(|seconds| now + chrono::Duration::seconds(seconds.into()))
+        .or_else(|| retry_after.and_then(parse_datetime))
+        .unwrap_or_else(|| now + chrono::Duration::minutes(DEFAULT_RATE_LIMIT_COOL_OFF_MINUTES))
 }
 
 #[derive(Clone, Debug)]
 pub struct Entry {
-    pub id: EntryId,
-    pub feed_id: FeedId,
     pub title: Option<String>,
     pub author: Option<String>,
     pub pub_date: Option<chrono::DateTime<Utc>>,
     pub description: Option<String>,
     pub content: Option<String>,
     pub link: Option<String>,
+    /// what relative links in the entry's content are relative to
+    pub base_url: Option<String>,
+    /// the feed's id for the entry, which stays the same when the entry is edited
+    pub guid: Option<String>,
+    /// when the feed says the entry was last edited, from Atom's `<updated>`
+    pub feed_updated_at: Option<chrono::DateTime<Utc>>,
+    pub enclosure: Option<Enclosure>,
+    /// the feed's own topics for the entry, from its `<category>` elements
+    pub categories: Vec<String>,
     pub read_at: Option<chrono::DateTime<Utc>>,
-    pub inserted_at: chrono::DateTime<Utc>,
-    pub updated_at: chrono::DateTime<Utc>,
+}
+
+#[cfg(test)]
+impl Entry {
+    /// an entry with nothing but a title, as a feed would have it, for tests to fill in the rest of
+    pub fn for_test(title: &str) -> Entry {
+        Entry {
+            title: Some(title.to_owned()),
+            author: None,
+            pub_date: None,
+            description: None,
+            content: None,
+            link: None,
+            base_url: None,
+            guid: None,
+            feed_updated_at: None,
+            enclosure: None,
+            categories: vec![],
+            read_at: None,
+        }
+    }
+
+    /// an entry published at `pub_date`, linking to a page named after its title
+    pub fn for_test_at(title: &str, pub_date: &str) -> Entry {
+        Entry {
+            pub_date: parse_datetime(pub_date),
+            link: Some(format!("https://example.com/{}", title)),
+            ..Entry::for_test(title)
+        }
+    }
+}
+
+/// a media file attached to an entry, like a podcast episode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enclosure {
+    pub url: String,
+    pub mime_type: Option<String>,
+    /// in bytes
+    pub length: Option<u64>,
+}
+
+impl Enclosure {
+    /// `length` is text in feeds, and often `0` when the feed does not know it
+    pub fn new(url: &str, mime_type: Option<&str>, length: Option<&str>) -> Option<Enclosure> {
+        if url.trim().is_empty() {
+            return None;
+        }
+
+        Some(Enclosure {
+            url: url.trim().to_owned(),
+            mime_type: mime_type
+                .map(str::trim)
+                .filter(|mime_type| !mime_type.is_empty())
+                .map(str::to_owned),
+            length: length
+                .and_then(|length| length.trim().parse().ok())
+                .filter(|length| *length > 0),
+        })
+    }
+
+    /// a `SELECT`ed `enclosure_url, enclosure_mime_type, enclosure_length`, starting at `idx`
+    fn from_row(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Option<Enclosure>> {
+        let url: Option<String> = row.get(idx)?;
+
+        Ok(match url {
+            Some(url) => Some(Enclosure {
+                url,
+                mime_type: row.get(idx + 1)?,
+                length: row
+                    .get::<_, Option<i64>>(idx + 2)?
+                    .map(|length| length as u64),
+            }),
+            None => None,
+        })
+    }
 }
 
 impl From<&atom::Entry> for Entry {
     fn from(entry: &atom::Entry) -> Self {
         Self {
-            id: -1,
-            feed_id: -1,
             title: Some(entry.title().to_string()),
-            author: entry.authors().get(0).map(|author| author.name.to_owned()),
+            author: entry.authors().first().map(|author| author.name.to_owned()),
             pub_date: entry.published().map(|date| date.with_timezone(&Utc)),
             description: None,
             content: entry.content().and_then(|content| content.value.to_owned()),
-            link: entry.links().get(0).map(|link| link.href().to_string()),
+            link: entry
+                .links()
+                .iter()
+                .find(|link| link.rel() != "enclosure")
+                .or_else(|| entry.links().first())
+                .map(|link| link.href().to_string()),
+            // the content's `xml:base`, which may itself be relative to the feed
+            base_url: entry
+                .content()
+                .and_then(|content| content.base())
+                .map(|base| base.to_owned()),
+            guid: Some(entry.id().to_owned()).filter(|id| !id.trim().is_empty()),
+            // the parser's stand-in for a missing `<updated>` is the epoch
+            feed_updated_at: Some(entry.updated().with_timezone(&Utc))
+                .filter(|updated| updated.timestamp() != 0),
+            enclosure: entry
+                .links()
+                .iter()
+                .find(|link| link.rel() == "enclosure")
+                .and_then(|link| Enclosure::new(link.href(), link.mime_type(), link.length())),
+            categories: parse_categories(
+                entry
+                    .categories()
+                    .iter()
+                    .map(|category| category.label().unwrap_or_else(|| category.term())),
+            ),
             read_at: None,
-            inserted_at: Utc::now(),
-            updated_at: Utc::now(),
         }
     }
 }
 
+/// RSS 1.0 (RDF) items have their author and date as Dublin Core elements,
+/// which some RSS 2.0 feeds use as well
 impl From<&rss::Item> for Entry {
     fn from(entry: &rss::Item) -> Self {
+        let dublin_core = entry.dublin_core_ext();
+
         Self {
-            id: -1,
-            feed_id: -1,
             title: entry.title().map(|title| title.to_owned()),
-            author: entry.author().map(|author| author.to_owned()),
-            pub_date: entry.pub_date().and_then(parse_datetime),
+            author: entry
+                .author()
+                .or_else(|| dublin_core.and_then(|dc| dc.creators().first().map(|c| c.as_str())))
+                .map(|author| author.to_owned()),
+            pub_date: entry
+                .pub_date()
+                .or_else(|| dublin_core.and_then(|dc| dc.dates().first().map(|d| d.as_str())))
+                .and_then(parse_datetime),
             description: entry
                 .description()
                 .map(|description| description.to_owned()),
             content: entry.content().map(|content| content.to_owned()),
             link: entry.link().map(|link| link.to_owned()),
+            base_url: None,
+            guid: entry.guid().map(|guid| guid.value().to_owned()),
+            feed_updated_at: None,
+            enclosure: entry.enclosure().and_then(|enclosure| {
+                Enclosure::new(
+                    enclosure.url(),
+                    Some(enclosure.mime_type()),
+                    Some(enclosure.length()),
+                )
+            }),
+            categories: parse_categories(entry.categories().iter().map(|category| category.name())),
             read_at: None,
-            inserted_at: Utc::now(),
-            updated_at: Utc::now(),
         }
     }
 }
 
+/// trimmed, decoded, and without blanks or repeats
+fn parse_categories<'a>(categories: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut parsed: Vec<String> = vec![];
+
+    for category in categories {
+        let category = crate::sanitize::decode_entities(category.trim());
+        if !category.is_empty() && !parsed.contains(&category) {
+            parsed.push(category);
+        }
+    }
+
+    parsed
+}
+
+/// categories are kept in one column, a line each
+fn categories_to_sql(categories: &[String]) -> Option<String> {
+    if categories.is_empty() {
+        None
+    } else {
+        Some(categories.join("\n"))
+    }
+}
+
+fn categories_from_sql(categories: Option<String>) -> Vec<String> {
+    categories
+        .map(|categories| categories.lines().map(|line| line.to_owned()).collect())
+        .unwrap_or_default()
+}
+
 #[derive(Clone, Debug)]
 pub struct EntryMeta {
     pub id: EntryId,
@@ -133,10 +513,86 @@ pub struct EntryMeta {
     pub link: Option<String>,
     pub read_at: Option<chrono::DateTime<Utc>>,
     pub inserted_at: chrono::DateTime<Utc>,
-    pub updated_at: chrono::DateTime<Utc>,
+    pub starred: bool,
+    pub enclosure: Option<Enclosure>,
+    pub categories: Vec<String>,
+    /// of whichever of the full article, content, or description is shown
+    pub word_count: usize,
+    /// when a refresh found the feed had edited the entry, if it has not been opened or read since
+    pub revised_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl EntryMeta {
+    /// an unread entry of feed 1 with nothing but a title, for tests to fill in the rest of
+    pub fn for_test(title: &str) -> EntryMeta {
+        EntryMeta {
+            id: 1,
+            feed_id: 1,
+            title: Some(title.to_owned()),
+            author: None,
+            pub_date: None,
+            link: None,
+            read_at: None,
+            inserted_at: Utc::now(),
+            starred: false,
+            enclosure: None,
+            categories: vec![],
+            word_count: 0,
+            revised_at: None,
+        }
+    }
 }
 
+/// what `EntryMeta::from_row` reads, in order.
+/// an entry counts as updated while it was revised after it was last opened and last read,
+/// which `unseen_revised_at` is the revision of, so queries can filter on it too
+const ENTRY_META_COLUMNS: &str = "entries.id,
+    entries.feed_id,
+    entries.title,
+    entries.author,
+    entries.pub_date,
+    entries.link,
+    entries.read_at,
+    entries.inserted_at,
+    entries.starred,
+    entries.enclosure_url,
+    entries.enclosure_mime_type,
+    entries.enclosure_length,
+    entries.categories,
+    entries.word_count,
+    CASE WHEN entries.revised_at > coalesce(entries.opened_at, '')
+        AND entries.revised_at > coalesce(entries.read_at, '')
+        THEN entries.revised_at END AS unseen_revised_at";
+
 impl EntryMeta {
+    /// a row of `ENTRY_META_COLUMNS`
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<EntryMeta> {
+        Ok(EntryMeta {
+            id: row.get(0)?,
+            feed_id: row.get(1)?,
+            title: row.get(2)?,
+            author: row.get(3)?,
+            pub_date: row.get(4)?,
+            link: row.get(5)?,
+            read_at: row.get(6)?,
+            inserted_at: row.get(7)?,
+            starred: row.get(8)?,
+            enclosure: Enclosure::from_row(row, 9)?,
+            categories: categories_from_sql(row.get(12)?),
+            word_count: row.get(13)?,
+            revised_at: row.get(14)?,
+        })
+    }
+
+    pub fn toggle_starred(&self, conn: &rusqlite::Connection) -> Result<()> {
+        let mut statement = conn.prepare(
+            "UPDATE entries SET starred = ?2, sync_pending = remote_id IS NOT NULL WHERE id = ?1",
+        )?;
+        statement.execute(params![self.id, !self.starred])?;
+        Ok(())
+    }
+
     pub fn toggle_read(&self, conn: &rusqlite::Connection) -> Result<()> {
         if self.read_at.is_none() {
             self.mark_as_read(conn)
@@ -146,13 +602,17 @@ pub fn toggle_read(&self, conn: &rusqlite::Connection) -> Result<()> {
     }
 
     fn mark_as_read(&self, conn: &rusqlite::Connection) -> Result<()> {
-        let mut statement = conn.prepare("UPDATE entries SET read_at = ?2 WHERE id = ?1")?;
+        let mut statement = conn.prepare(
+            "UPDATE entries SET read_at = ?2, sync_pending = remote_id IS NOT NULL WHERE id = ?1",
+        )?;
         statement.execute(params![self.id, Utc::now()])?;
         Ok(())
     }
 
     fn mark_as_unread(&self, conn: &rusqlite::Connection) -> Result<()> {
-        let mut statement = conn.prepare("UPDATE entries SET read_at = NULL WHERE id = ?1")?;
+        let mut statement = conn.prepare(
+            "UPDATE entries SET read_at = NULL, sync_pending = remote_id IS NOT NULL WHERE id = ?1",
+        )?;
         statement.execute(params![self.id])?;
         Ok(())
     }
@@ -161,21 +621,87 @@ fn mark_as_unread(&self, conn: &rusqlite::Connection) -> Result<()> {
 pub struct EntryContent {
     pub content: Option<String>,
     pub description: Option<String>,
+    /// the article from the entry's web page, once it has been fetched
+    pub full_content: Option<String>,
+    pub base_url: Option<String>,
+}
+
+impl EntryContent {
+    /// the full article if it was fetched, otherwise the content, otherwise the description
+    pub fn html(&self) -> &str {
+        self.full_content
+            .as_deref()
+            .or(self.content.as_deref())
+            .or(self.description.as_deref())
+            .unwrap_or_default()
+    }
 }
 
 fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
     diligent_date_parser::parse_date(s).map(|dt| dt.with_timezone(&Utc))
 }
 
+#[derive(Debug)]
 struct FeedAndEntries {
     pub feed: Feed,
+    /// what the feed says it is about
+    pub description: Option<String>,
     pub entries: Vec<Entry>,
+    pub cache_validators: CacheValidators,
+    /// the feed's text, as it was parsed
+    pub body: String,
+}
+
+/// the response headers that let the next request for a feed
+/// be answered with 304 Not Modified if the feed has not changed
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
 }
 
 impl FeedAndEntries {
     pub fn set_feed_link(&mut self, url: &str) {
         self.feed.feed_link = Some(url.to_owned());
     }
+
+    /// makes the entries' links absolute against `url`, where the feed was fetched from,
+    /// and gives each entry the URL its content's relative links are relative to:
+    /// its content's `xml:base`, or else its link
+    fn resolve_relative_links(&mut self, url: &str) {
+        let feed_url = match url::Url::parse(url) {
+            Ok(feed_url) => feed_url,
+            Err(_) => return,
+        };
+
+        // RSS feeds' relative links are relative to their site more often than to the feed
+        let site_url = self
+            .feed
+            .link
+            .as_deref()
+            .and_then(|link| feed_url.join(link).ok())
+            .unwrap_or_else(|| feed_url.clone());
+
+        for entry in &mut self.entries {
+            if let Some(link) = entry
+                .link
+                .as_deref()
+                .and_then(|link| site_url.join(link).ok())
+            {
+                entry.link = Some(link.to_string());
+            }
+
+            let base_url = entry
+                .base_url
+                .as_deref()
+                .and_then(|base_url| feed_url.join(base_url).ok())
+                .map(|base_url| base_url.to_string())
+                .or_else(|| entry.link.clone())
+                .unwrap_or_else(|| site_url.to_string());
+
+            entry.base_url = Some(base_url);
+        }
+    }
 }
 
 impl FromStr for FeedAndEntries {
@@ -188,11 +714,15 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
                     id: 0,
                     title: Some(atom_feed.title.to_string()),
                     feed_link: None,
-                    link: atom_feed.links.get(0).map(|link| link.href().to_string()),
+                    link: atom_feed.links.first().map(|link| link.href().to_string()),
                     feed_kind: FeedKind::Atom,
                     refreshed_at: None,
-                    inserted_at: Utc::now(),
-                    updated_at: Utc::now(),
+                    retry_after: None,
+                    entry_sort: EntrySort::default(),
+                    paused: false,
+                    health: FeedHealth::default(),
+                    original_feed_link: None,
+                    moved_to: None,
                 };
 
                 let entries = atom_feed
@@ -201,7 +731,16 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
                     .map(|entry| entry.into())
                     .collect::<Vec<_>>();
 
-                Ok(FeedAndEntries { feed, entries })
+                Ok(FeedAndEntries {
+                    feed,
+                    description: atom_feed
+                        .subtitle
+                        .as_ref()
+                        .map(|subtitle| subtitle.to_string()),
+                    entries,
+                    cache_validators: CacheValidators::default(),
+                    body: s.to_owned(),
+                })
             }
 
             Err(_e) => match Channel::from_str(s) {
@@ -213,8 +752,12 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
                         link: Some(channel.link().to_string()),
                         feed_kind: FeedKind::Rss,
                         refreshed_at: None,
-                        inserted_at: Utc::now(),
-                        updated_at: Utc::now(),
+                        retry_after: None,
+                        entry_sort: EntrySort::default(),
+                        paused: false,
+                        health: FeedHealth::default(),
+                        original_feed_link: None,
+                        moved_to: None,
                     };
 
                     let entries = channel
@@ -223,7 +766,13 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
                         .map(|item| item.into())
                         .collect::<Vec<_>>();
 
-                    Ok(FeedAndEntries { feed, entries })
+                    Ok(FeedAndEntries {
+                        feed,
+                        description: Some(channel.description().to_string()),
+                        entries,
+                        cache_validators: CacheValidators::default(),
+                        body: s.to_owned(),
+                    })
                 }
                 Err(e) => Err(e.into()),
             },
@@ -232,51 +781,284 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
 }
 
 pub fn subscribe_to_feed(
-    http_client: &ureq::Agent,
+    http_client: &HttpClient,
     conn: &rusqlite::Connection,
     url: &str,
 ) -> Result<FeedId> {
-    let feed_and_entries: FeedAndEntries = fetch_feed(http_client, url)?;
+    subscribe_to_previewed_feed(conn, &preview_feed(http_client, url)?)
+}
+
+/// a fetched feed, to look at before subscribing to it
+#[derive(Debug)]
+pub struct FeedPreview {
+    /// what was asked for, which sync servers subscribe to themselves
+    pub url: String,
+    feed_and_entries: FeedAndEntries,
+}
+
+impl FeedPreview {
+    pub fn title(&self) -> Option<&str> {
+        self.feed_and_entries.feed.title.as_deref()
+    }
+
+    /// `None` if the feed has none, or an empty one
+    pub fn description(&self) -> Option<String> {
+        self.feed_and_entries
+            .description
+            .as_deref()
+            .map(|description| crate::sanitize::decode_entities(description.trim()))
+            .filter(|description| !description.is_empty())
+    }
+
+    pub fn entries_len(&self) -> usize {
+        self.feed_and_entries.entries.len()
+    }
+
+    /// the titles of the `len` newest entries, newest first
+    pub fn latest_entry_titles(&self, len: usize) -> Vec<String> {
+        let mut entries = self.feed_and_entries.entries.iter().collect::<Vec<_>>();
+        // entries without dates go last, in the order the feed has them
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.pub_date));
+
+        entries
+            .into_iter()
+            .take(len)
+            .map(|entry| {
+                entry
+                    .title
+                    .as_deref()
+                    .map(crate::sanitize::decode_entities)
+                    .unwrap_or_else(|| "No title".to_string())
+            })
+            .collect()
+    }
+}
+
+/// fetches the feed at `url` without subscribing to it
+pub fn preview_feed(http_client: &HttpClient, url: &str) -> Result<FeedPreview> {
+    Ok(FeedPreview {
+        url: url.to_owned(),
+        feed_and_entries: fetch_feed(http_client, url)?,
+    })
+}
+
+/// the feed already subscribed to at the URL the preview was asked for,
+/// or at the one it was fetched from after permanent redirects
+pub fn find_previewed_feed(
+    conn: &rusqlite::Connection,
+    feed_preview: &FeedPreview,
+) -> Result<Option<AlreadySubscribed>> {
+    if let Some(subscribed_feed) = find_subscribed_feed(conn, &feed_preview.url)? {
+        return Ok(Some(subscribed_feed));
+    }
+
+    match &feed_preview.feed_and_entries.feed.feed_link {
+        Some(feed_link) => find_subscribed_feed(conn, feed_link),
+        None => Ok(None),
+    }
+}
+
+/// subscribes to the feed as it was fetched for the preview, without fetching it again.
+/// fails with `AlreadySubscribed` rather than subscribing to the same feed twice
+pub fn subscribe_to_previewed_feed(
+    conn: &rusqlite::Connection,
+    feed_preview: &FeedPreview,
+) -> Result<FeedId> {
+    if let Some(subscribed_feed) = find_previewed_feed(conn, feed_preview)? {
+        return Err(subscribed_feed.into());
+    }
+
+    let feed_and_entries = &feed_preview.feed_and_entries;
     let feed_id = create_feed(conn, &feed_and_entries.feed)?;
     add_entries_to_feed(conn, feed_id, &feed_and_entries.entries)?;
+    update_feed_cache_validators(conn, feed_id, &feed_and_entries.cache_validators)?;
+    update_raw_feed(conn, feed_id, &feed_and_entries.body)?;
 
     Ok(feed_id)
 }
 
-fn fetch_feed(http_client: &ureq::Agent, url: &str) -> Result<FeedAndEntries> {
-    let resp = http_client.get(url).call()?.into_string()?;
-    let mut feed = FeedAndEntries::from_str(&resp)?;
-    feed.set_feed_link(url);
+/// how many redirects a feed request follows
+const MAX_REDIRECTS: usize = 5;
+
+struct FetchedFeed {
+    /// `None` if the server says the feed has not changed since the cache validators
+    feed_and_entries: Option<FeedAndEntries>,
+    /// the URL the feed has permanently moved to, if it has.
+    /// the last URL reached through nothing but 301 and 308 redirects.
+    moved_to: Option<String>,
+}
+
+fn fetch_feed(http_client: &HttpClient, url: &str) -> Result<FeedAndEntries> {
+    let fetched_feed = fetch_feed_if_modified(http_client, &[url], &CacheValidators::default())?;
+
+    let mut feed = fetched_feed
+        .feed_and_entries
+        .ok_or_else(|| anyhow::anyhow!("{} answered an unconditional request with 304", url))?;
+
+    if let Some(moved_to) = &fetched_feed.moved_to {
+        feed.set_feed_link(moved_to);
+    }
 
     Ok(feed)
 }
 
+/// fetches the feed at the first of `feed_links`, with the settings of any of them
+fn fetch_feed_if_modified(
+    http_client: &HttpClient,
+    feed_links: &[&str],
+    cache_validators: &CacheValidators,
+) -> Result<FetchedFeed> {
+    let url = feed_links[0];
+    let mut current_url = url.to_owned();
+    let mut moved_to = None;
+    let mut is_permanent = true;
+    let mut redirects_len = 0;
+
+    let resp = loop {
+        let mut request = http_client.get(feed_links, &current_url)?;
+
+        if let Some(etag) = &cache_validators.etag {
+            request = request.set("If-None-Match", etag);
+        }
+
+        if let Some(last_modified) = &cache_validators.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+
+        let resp = match request.call() {
+            Err(ureq::Error::Status(429, resp)) => {
+                let until = parse_retry_after(resp.header("Retry-After"), Utc::now());
+                return Err(RateLimited { until }.into());
+            }
+            result => result?,
+        };
+
+        if !matches!(resp.status(), 301 | 302 | 303 | 307 | 308) {
+            break resp;
+        }
+
+        redirects_len += 1;
+        if redirects_len > MAX_REDIRECTS {
+            return Err(anyhow::anyhow!("{} redirected too many times", url));
+        }
+
+        let location = resp.header("Location").ok_or_else(|| {
+            anyhow::anyhow!("{} redirected without a Location header", current_url)
+        })?;
+        let next_url = url::Url::parse(&current_url)?.join(location)?.to_string();
+
+        is_permanent = is_permanent && matches!(resp.status(), 301 | 308);
+        if is_permanent {
+            moved_to = Some(next_url.clone());
+        }
+
+        current_url = next_url;
+    };
+
+    if resp.status() == 304 {
+        return Ok(FetchedFeed {
+            feed_and_entries: None,
+            moved_to,
+        });
+    }
+
+    let cache_validators = CacheValidators {
+        etag: resp.header("ETag").map(|etag| etag.to_owned()),
+        last_modified: resp
+            .header("Last-Modified")
+            .map(|last_modified| last_modified.to_owned()),
+    };
+
+    let body = crate::http::read_text(resp)?;
+    let mut feed = match FeedAndEntries::from_str(&body) {
+        Ok(feed) => feed,
+        Err(e) => return Err(e.context(UnparsableFeed { body })),
+    };
+    feed.set_feed_link(url);
+    feed.resolve_relative_links(&current_url);
+    feed.cache_validators = cache_validators;
+
+    Ok(FetchedFeed {
+        feed_and_entries: Some(feed),
+        moved_to,
+    })
+}
+
 /// fetches the feed and stores the new entries
 /// uses the link as the uniqueness key.
-/// TODO hash the content to see if anything changed, and update that way.
+/// a feed the server reports as not modified is only marked as refreshed.
+/// a feed that has permanently moved gets its new URL,
+/// or with `confirm_moves`, waits for `update_feed_url` with it in `moved_to`.
+/// a feed that answers 429 gets a `retry_after`, and is left alone until then.
+/// new entries that `rules` match are added already read, or not at all.
+/// entries the feed has edited since are updated, and marked unread again if `mark_revised_unread`.
+/// returns the entries that were added.
 pub fn refresh_feed(
-    client: &ureq::Agent,
+    client: &HttpClient,
     conn: &rusqlite::Connection,
     feed_id: FeedId,
-) -> Result<()> {
-    let feed_url = get_feed_url(conn, feed_id).with_context(|| {
+    rules: &Rules,
+    mark_revised_unread: bool,
+    confirm_moves: bool,
+) -> Result<Vec<Entry>> {
+    let feed = get_feed(conn, feed_id).with_context(|| {
         format!(
             "Unable to get url for feed id {} from the database",
             feed_id
         )
     })?;
-    let remote_feed: FeedAndEntries = fetch_feed(client, &feed_url)
-        .with_context(|| format!("Failed to fetch feed {}", feed_url))?;
+    let feed_url = feed
+        .feed_link
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("Feed id {} has no url", feed_id))?;
+    let cache_validators = get_feed_cache_validators(conn, feed_id)?;
+    let fetched_feed = match fetch_feed_if_modified(client, &feed.feed_links(), &cache_validators) {
+        Ok(fetched_feed) => fetched_feed,
+        Err(e) => {
+            if let Some(rate_limited) = e.downcast_ref::<RateLimited>() {
+                update_feed_retry_after(conn, feed_id, rate_limited.until)?;
+            }
+            if let Some(unparsable_feed) = e.downcast_ref::<UnparsableFeed>() {
+                update_raw_feed(conn, feed_id, &unparsable_feed.body)?;
+            }
+            return Err(e.context(format!("Failed to fetch feed {}", feed_url)));
+        }
+    };
+
+    if let Some(moved_to) = &fetched_feed.moved_to {
+        if confirm_moves {
+            set_feed_moved_to(conn, feed_id, moved_to)?;
+        } else {
+            update_feed_url(conn, feed_id, moved_to)?;
+        }
+    }
+
+    let remote_feed: FeedAndEntries = match fetched_feed.feed_and_entries {
+        Some(remote_feed) => remote_feed,
+        None => {
+            update_feed_refreshed_at(conn, feed_id)?;
+            return Ok(vec![]);
+        }
+    };
+
     let remote_items = remote_feed.entries;
+
+    // an entry whose link changed is still the same entry, if the feed gave it an id
+    let stored_items = update_revised_entries(conn, feed_id, &remote_items, mark_revised_unread)?;
+
     let remote_items_links = remote_items
         .iter()
         .flat_map(|item| &item.link)
         .cloned()
         .collect::<HashSet<String>>();
 
+    let pruned_entries_links = get_pruned_entries_links(conn, feed_id)?;
+
+    // pruned entries count as local, so they do not come back
     let local_entries_links = get_entries_links(conn, &ReadMode::All, feed_id)?
         .into_iter()
         .flatten()
+        .chain(pruned_entries_links.iter().cloned())
         .collect::<HashSet<_>>();
 
     let difference = remote_items_links
@@ -286,416 +1068,4919 @@ pub fn refresh_feed(
 
     let items_to_add = remote_items
         .into_iter()
-        .filter(|item| match &item.link {
-            Some(link) => difference.contains(link.as_str()),
+        .enumerate()
+        .filter(|(idx, item)| match &item.link {
+            Some(link) => difference.contains(link.as_str()) && !stored_items.contains(idx),
             None => false,
         })
+        .map(|(_, item)| item)
         .collect::<Vec<_>>();
 
+    let (items_to_add, items_to_mark_read) = apply_rules(conn, feed_id, rules, items_to_add)?;
+
     add_entries_to_feed(conn, feed_id, &items_to_add)?;
 
+    let now = Utc::now();
+    for link in &items_to_mark_read {
+        conn.execute(
+            "UPDATE entries SET read_at = ?1 WHERE feed_id = ?2 AND link = ?3",
+            params![now, feed_id, link],
+        )?;
+    }
+
+    // an entry that is gone from the feed cannot come back, so its link can be forgotten
+    for link in pruned_entries_links.difference(&remote_items_links) {
+        conn.execute(
+            "DELETE FROM pruned_entries WHERE feed_id = ?1 AND link = ?2",
+            params![feed_id, link],
+        )?;
+    }
+
+    update_feed_cache_validators(conn, feed_id, &remote_feed.cache_validators)?;
+    update_raw_feed(conn, feed_id, &remote_feed.body)?;
     update_feed_refreshed_at(conn, feed_id)?;
 
-    Ok(())
+    Ok(items_to_add)
 }
 
-pub fn initialize_db(conn: &rusqlite::Connection) -> Result<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS feeds (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        title TEXT,
-        feed_link TEXT,
-        link TEXT,
-        feed_kind TEXT,
-        refreshed_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-    )",
-        [],
-    )?;
+/// an entry as it was stored, to compare with the feed's latest version of it
+struct StoredVersion {
+    id: EntryId,
+    guid: Option<String>,
+    link: Option<String>,
+    content_hash: Option<String>,
+    feed_updated_at: Option<chrono::DateTime<Utc>>,
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS entries (
-        id INTEGER PRIMARY KEY AUTOINCREMENT,
-        feed_id INTEGER,
-        title TEXT,
-        author TEXT,
-        pub_date TIMESTAMP,
-        description TEXT,
-        content TEXT,
-        link TEXT,
-        read_at TIMESTAMP,
-        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-        [],
-    )?;
+/// updates the stored entries the feed has edited since they were stored,
+/// matching them by the feed's id for them, or by link for entries without one.
+/// an entry counts as edited if its title, description, or content changed,
+/// or if the feed says it was updated later than before.
+/// returns the indexes of the remote items that matched a stored entry
+fn update_revised_entries(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    remote_items: &[Entry],
+    mark_unread: bool,
+) -> Result<HashSet<usize>> {
+    let stored_versions = conn
+        .prepare(
+            "SELECT id, guid, link, content_hash, feed_updated_at FROM entries WHERE feed_id = ?1",
+        )?
+        .query_map(params![feed_id], |row| {
+            Ok(StoredVersion {
+                id: row.get(0)?,
+                guid: row.get(1)?,
+                link: row.get(2)?,
+                content_hash: row.get(3)?,
+                feed_updated_at: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut by_guid = HashMap::new();
+    let mut by_link = HashMap::new();
+    for stored_version in &stored_versions {
+        if let Some(guid) = &stored_version.guid {
+            by_guid.insert(guid.as_str(), stored_version);
+        }
+        if let Some(link) = &stored_version.link {
+            by_link.entry(link.as_str()).or_insert(stored_version);
+        }
+    }
+
+    let now = Utc::now();
+    let mut matched = HashSet::new();
+
+    for (idx, item) in remote_items.iter().enumerate() {
+        let stored_version = match item.guid.as_deref().and_then(|guid| by_guid.get(guid)) {
+            Some(stored_version) => stored_version,
+            None => match item.link.as_deref().and_then(|link| by_link.get(link)) {
+                // a stored entry with a different id is a different entry that reused the link
+                Some(stored_version) if item.guid.is_none() || stored_version.guid.is_none() => {
+                    stored_version
+                }
+                _ => continue,
+            },
+        };
+        matched.insert(idx);
+
+        let title = item.title.as_deref().map(crate::sanitize::decode_entities);
+        let content_hash = content_hash(
+            title.as_deref(),
+            item.description.as_deref(),
+            item.content.as_deref(),
+        );
+
+        let content_changed = stored_version
+            .content_hash
+            .as_ref()
+            .is_some_and(|stored_hash| *stored_hash != content_hash);
+        let updated_later = matches!(
+            (stored_version.feed_updated_at, item.feed_updated_at),
+            (Some(stored), Some(remote)) if remote > stored
+        );
+
+        if content_changed || updated_later {
+            update_revised_entry(
+                conn,
+                stored_version.id,
+                item,
+                &content_hash,
+                mark_unread,
+                now,
+            )?;
+        } else if stored_version.guid.is_none() && item.guid.is_some()
+            || stored_version.content_hash.is_none()
+            || stored_version.feed_updated_at.is_none() && item.feed_updated_at.is_some()
+        {
+            // entries stored before russ kept these have nothing to compare with until now
+            conn.execute(
+                "UPDATE entries SET
+                guid = coalesce(guid, ?2),
+                content_hash = coalesce(content_hash, ?3),
+                feed_updated_at = coalesce(feed_updated_at, ?4)
+                WHERE id = ?1",
+                params![
+                    stored_version.id,
+                    item.guid,
+                    content_hash,
+                    item.feed_updated_at
+                ],
+            )?;
+        }
+    }
+
+    Ok(matched)
+}
+
+fn update_revised_entry(
+    conn: &rusqlite::Connection,
+    entry_id: EntryId,
+    item: &Entry,
+    content_hash: &str,
+    mark_unread: bool,
+    now: chrono::DateTime<Utc>,
+) -> Result<()> {
+    let title = item.title.as_deref().map(crate::sanitize::decode_entities);
+    let author = item.author.as_deref().map(crate::sanitize::decode_entities);
+    let content = item.content.as_deref().or(item.description.as_deref());
+    let word_count = crate::html::count_words(content.unwrap_or_default());
 
     conn.execute(
-        "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index 
-        ON entries (feed_id, pub_date, inserted_at)",
-        [],
+        "UPDATE entries SET
+        title = ?2,
+        author = ?3,
+        description = ?4,
+        content = ?5,
+        link = coalesce(?6, link),
+        categories = ?7,
+        word_count = CASE WHEN full_content IS NULL THEN ?8 ELSE word_count END,
+        duplicate_key = ?9,
+        guid = coalesce(?10, guid),
+        content_hash = ?11,
+        feed_updated_at = ?12,
+        updated_at = ?13,
+        revised_at = ?13
+        WHERE id = ?1",
+        params![
+            entry_id,
+            title,
+            author,
+            item.description,
+            item.content,
+            item.link,
+            categories_to_sql(&item.categories),
+            word_count,
+            duplicate_key(title.as_deref(), item.link.as_deref(), content),
+            item.guid,
+            content_hash,
+            item.feed_updated_at,
+            now
+        ],
     )?;
 
+    if mark_unread {
+        conn.execute(
+            "UPDATE entries SET read_at = NULL, sync_pending = remote_id IS NOT NULL
+            WHERE id = ?1 AND read_at IS NOT NULL",
+            params![entry_id],
+        )?;
+    }
+
     Ok(())
 }
 
-fn create_feed(conn: &rusqlite::Connection, feed: &Feed) -> Result<FeedId> {
-    let feed_id = conn.query_row::<FeedId, _, _>(
-        "INSERT INTO feeds (title, link, feed_link, feed_kind)
-        VALUES (?1, ?2, ?3, ?4)
-        RETURNING id",
-        params![feed.title, feed.link, feed.feed_link, feed.feed_kind],
-        |r| r.get(0),
-    )?;
+/// splits the new entries into the ones to add and the links of the ones among them to mark read.
+/// the links of hidden entries count as pruned, so they do not come back
+fn apply_rules(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    rules: &Rules,
+    entries: Vec<Entry>,
+) -> Result<(Vec<Entry>, Vec<String>)> {
+    if rules.is_empty() {
+        return Ok((entries, vec![]));
+    }
 
-    Ok(feed_id)
+    let feed = get_feed(conn, feed_id)?;
+    let mut entries_to_add = Vec::with_capacity(entries.len());
+    let mut links_to_mark_read = vec![];
+
+    for entry in entries {
+        match rules.action(&feed, &entry) {
+            Some(RuleAction::Hide) => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO pruned_entries (feed_id, link) VALUES (?1, ?2)",
+                    params![feed_id, entry.link],
+                )?;
+            }
+            Some(RuleAction::MarkRead) => {
+                links_to_mark_read.extend(entry.link.clone());
+                entries_to_add.push(entry);
+            }
+            None => entries_to_add.push(entry),
+        }
+    }
+
+    Ok((entries_to_add, links_to_mark_read))
 }
 
-fn add_entries_to_feed(
+/// an entry from another feed reader, with its state there
+#[derive(Clone, Debug)]
+pub struct ImportedEntry {
+    /// read if `read_at` is set
+    pub entry: Entry,
+    pub starred: bool,
+}
+
+/// adds the imported entries the feed does not have yet,
+/// then marks the entries that were read or starred as read or starred, matching them by link.
+/// returns how many entries were added.
+pub fn import_entries(
     conn: &rusqlite::Connection,
     feed_id: FeedId,
-    entries: &[Entry],
-) -> Result<()> {
-    if !entries.is_empty() {
-        let now = Utc::now();
+    imported_entries: &[ImportedEntry],
+) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
 
-        let columns = [
-            "feed_id",
-            "title",
-            "author",
-            "pub_date",
-            "description",
-            "content",
-            "link",
-            "updated_at",
-        ];
+    let mut known_links = get_entries_links(&tx, &ReadMode::All, feed_id)?
+        .into_iter()
+        .flatten()
+        .chain(get_pruned_entries_links(&tx, feed_id)?)
+        .collect::<HashSet<_>>();
 
-        let mut entries_values = Vec::with_capacity(entries.len() * columns.len());
+    let entries_to_add = imported_entries
+        .iter()
+        .filter(|imported_entry| match &imported_entry.entry.link {
+            Some(link) => known_links.insert(link.clone()),
+            None => false,
+        })
+        .map(|imported_entry| imported_entry.entry.clone())
+        .collect::<Vec<_>>();
 
-        for entry in entries {
-            let values = params![
-                feed_id,
-                entry.title,
-                entry.author,
-                entry.pub_date,
-                entry.description,
-                entry.content,
-                entry.link,
-                now,
-            ];
-            entries_values.extend_from_slice(values);
-        }
+    // the bulk insert is one statement, so it is kept under SQLite's limit on parameters
+    for entries in entries_to_add.chunks(100) {
+        add_entries_to_feed(&tx, feed_id, entries)?;
+    }
 
-        let query = build_bulk_insert_query("entries", &columns, entries);
+    for ImportedEntry { entry, starred } in imported_entries {
+        if let (Some(link), Some(read_at)) = (&entry.link, entry.read_at) {
+            tx.execute(
+                "UPDATE entries SET read_at = ?3 WHERE feed_id = ?1 AND link = ?2 AND read_at IS NULL",
+                params![feed_id, link, read_at],
+            )?;
+        }
 
-        conn.execute(&query, entries_values.as_slice())?;
+        if let (Some(link), true) = (&entry.link, starred) {
+            tx.execute(
+                "UPDATE entries SET starred = 1 WHERE feed_id = ?1 AND link = ?2",
+                params![feed_id, link],
+            )?;
+        }
     }
 
-    Ok(())
+    tx.commit()?;
+
+    Ok(entries_to_add.len())
 }
 
-fn build_bulk_insert_query<C: AsRef<str>, R>(table: &str, columns: &[C], rows: &[R]) -> String {
-    let idxs = (1..(rows.len() * columns.len() + 1)).collect::<Vec<_>>();
+/// the entries read, unread, starred, or unstarred since the last sync, as they are now
+pub fn get_pending_state_changes(conn: &rusqlite::Connection) -> Result<Vec<StateChange>> {
+    let mut statement = conn.prepare(
+        "SELECT remote_id, read_at IS NOT NULL, starred FROM entries
+        WHERE sync_pending AND remote_id IS NOT NULL",
+    )?;
 
-    let values_groups_string = idxs
-        .chunks(columns.len())
-        .map(|chunk| {
-            let values_string = chunk
-                .iter()
-                .map(|i| format!("?{}", i))
-                .collect::<Vec<_>>()
-                .join(", ");
-            ["(", &values_string, ")"].concat()
-        })
-        .collect::<Vec<_>>()
-        .join(", ");
+    let state_changes = statement
+        .query_map([], |row| {
+            Ok(StateChange {
+                remote_id: row.get(0)?,
+                read: row.get(1)?,
+                starred: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
 
-    let columns_strs = columns
-        .iter()
-        .map(|column| column.as_ref())
-        .collect::<Vec<&str>>();
+    Ok(state_changes)
+}
 
-    let columns_joined = columns_strs.join(", ");
+/// once the server has them. an entry changed again since is left pending.
+pub fn clear_pending_state_changes(
+    conn: &rusqlite::Connection,
+    state_changes: &[StateChange],
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
 
-    let mut query = String::with_capacity(
-        "INSERT INTO ".len()
-            + table.len()
-            + 1 // '(' is a char
-            + columns_joined.len()
-            + ") ".len()
-            + "VALUES ".len()
-            + values_groups_string.len(),
-    );
+    for state_change in state_changes {
+        tx.execute(
+            "UPDATE entries SET sync_pending = 0
+            WHERE remote_id = ?1 AND (read_at IS NOT NULL) = ?2 AND starred = ?3",
+            params![
+                state_change.remote_id,
+                state_change.read,
+                state_change.starred
+            ],
+        )?;
+    }
 
-    query.push_str("INSERT INTO ");
-    query.push_str(table);
-    query.push('(');
-    query.push_str(&columns_joined);
-    query.push_str(") ");
-    query.push_str("VALUES ");
-    query.push_str(&values_groups_string);
+    tx.commit()?;
 
-    query
+    Ok(())
 }
 
-pub fn get_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Feed> {
-    let s = conn.query_row(
-        "SELECT id, title, feed_link, link, feed_kind, refreshed_at, inserted_at, updated_at FROM feeds WHERE id=?1",
-        params![feed_id],
-        |row| {
-            let feed_kind_str: String = row.get(4)?;
-            let feed_kind: FeedKind = FeedKind::from_str(&feed_kind_str)
-                .unwrap_or_else(|_| panic!("FeedKind must be Atom or RSS, got {}", feed_kind_str));
+pub fn get_synced_at(conn: &rusqlite::Connection, server: &str) -> Result<Option<DateTime<Utc>>> {
+    let mut statement = conn.prepare("SELECT synced_at FROM syncs WHERE server = ?1")?;
+    let mut rows = statement.query_map([server], |row| row.get(0))?;
+    Ok(rows.next().transpose()?)
+}
 
-            Ok(Feed {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                feed_link: row.get(2)?,
-                link: row.get(3)?,
-                feed_kind,
-                refreshed_at: row.get(5)?,
-                inserted_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
+pub fn set_synced_at(
+    conn: &rusqlite::Connection,
+    server: &str,
+    synced_at: DateTime<Utc>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO syncs (server, synced_at) VALUES (?1, ?2)",
+        params![server, synced_at],
     )?;
 
-    Ok(s)
+    Ok(())
+}
+
+/// where russ was when it quit, so it starts there next time
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub feed_id: Option<FeedId>,
+    /// shown instead of the feed, if it was
+    pub virtual_feed: Option<VirtualFeed>,
+    pub entry_id: Option<EntryId>,
+    pub pane: Pane,
+    pub read_mode: ReadMode,
+    pub entry_filter: String,
+    /// as it was typed, so a range like "7d" stays relative to today
+    pub date_range: String,
+    /// how wide the panes were made, in percent of the screen, if they were resized
+    pub feeds_pane_width: Option<u16>,
+    pub entries_pane_width: Option<u16>,
+}
+
+pub fn get_session(conn: &rusqlite::Connection) -> Result<Option<Session>> {
+    Ok(conn
+        .query_row(
+            "SELECT feed_id, virtual_feed, entry_id, pane, read_mode, entry_filter, date_range,
+            feeds_pane_width, entries_pane_width
+            FROM session",
+            [],
+            |row| {
+                Ok(Session {
+                    feed_id: row.get(0)?,
+                    virtual_feed: row.get(1)?,
+                    entry_id: row.get(2)?,
+                    pane: row.get(3)?,
+                    read_mode: row.get(4)?,
+                    entry_filter: row.get(5)?,
+                    date_range: row.get(6)?,
+                    feeds_pane_width: row.get(7)?,
+                    entries_pane_width: row.get(8)?,
+                })
+            },
+        )
+        .optional()?)
 }
 
-fn update_feed_refreshed_at(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+pub fn set_session(conn: &rusqlite::Connection, session: &Session) -> Result<()> {
     conn.execute(
-        "UPDATE feeds SET refreshed_at = ?2 WHERE id = ?1",
-        params![feed_id, Utc::now()],
+        "INSERT OR REPLACE INTO session
+        (id, feed_id, virtual_feed, entry_id, pane, read_mode, entry_filter, date_range,
+        feeds_pane_width, entries_pane_width)
+        VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            session.feed_id,
+            session.virtual_feed,
+            session.entry_id,
+            session.pane,
+            session.read_mode,
+            session.entry_filter,
+            session.date_range,
+            session.feeds_pane_width,
+            session.entries_pane_width
+        ],
     )?;
 
     Ok(())
 }
 
-pub fn get_feed_url(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<String> {
-    let s: String = conn.query_row(
-        "SELECT feed_link FROM feeds WHERE id=?1",
-        params![feed_id],
-        |row| row.get(0),
-    )?;
-
-    Ok(s)
+pub fn get_feed_id_by_remote_id(
+    conn: &rusqlite::Connection,
+    remote_id: &str,
+) -> Result<Option<FeedId>> {
+    let mut statement = conn.prepare("SELECT id FROM feeds WHERE remote_id = ?1")?;
+    let mut rows = statement.query_map([remote_id], |row| row.get(0))?;
+    Ok(rows.next().transpose()?)
 }
 
-pub fn get_feeds(conn: &rusqlite::Connection) -> Result<Vec<Feed>> {
-    let mut statement = conn.prepare(
-        "SELECT 
-          id, 
-          title, 
-          feed_link, 
-          link, 
-          feed_kind, 
-          refreshed_at, 
-          inserted_at, 
-          updated_at 
-        FROM feeds ORDER BY lower(title) ASC",
-    )?;
-    let mut feeds = vec![];
-    for feed in statement.query_map([], |row| {
-        Ok(Feed {
-            id: row.get(0)?,
+/// the feed subscribed to at `url`, or at a URL that is the same once both are normalized,
+/// like its http version or one with a trailing slash
+pub fn find_subscribed_feed(
+    conn: &rusqlite::Connection,
+    url: &str,
+) -> Result<Option<AlreadySubscribed>> {
+    let normalized_url = crate::util::normalize_url(url);
+
+    let mut statement =
+        conn.prepare("SELECT id, title, feed_link FROM feeds WHERE feed_link IS NOT NULL")?;
+    let feeds = statement.query_map([], |row| {
+        Ok(AlreadySubscribed {
+            feed_id: row.get(0)?,
             title: row.get(1)?,
-            feed_link: row.get(2)?,
-            link: row.get(3)?,
-            feed_kind: row.get(4)?,
-            refreshed_at: row.get(5)?,
-            inserted_at: row.get(6)?,
-            updated_at: row.get(7)?,
+            url: row.get(2)?,
         })
-    })? {
-        feeds.push(feed?)
+    })?;
+
+    for feed in feeds {
+        let feed = feed?;
+        if crate::util::normalize_url(&feed.url) == normalized_url {
+            return Ok(Some(feed));
+        }
     }
 
-    Ok(feeds)
+    Ok(None)
 }
 
-pub fn get_feed_ids(conn: &rusqlite::Connection) -> Result<Vec<FeedId>> {
-    let mut statement = conn.prepare("SELECT id FROM feeds ORDER BY lower(title) ASC")?;
-    let mut ids = vec![];
-    for id in statement.query_map([], |row| row.get(0))? {
-        ids.push(id?)
+/// creates the feeds russ does not have yet, adopting a subscription with the same feed link if there is one,
+/// and adds the server's tags. returns the local id of every remote feed, by remote id.
+pub fn store_remote_feeds(
+    conn: &rusqlite::Connection,
+    remote_feeds: &[RemoteFeed],
+) -> Result<HashMap<String, FeedId>> {
+    // immediate, so two syncs at once do not both create the same feed
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+    let mut feed_ids = HashMap::new();
+
+    for remote_feed in remote_feeds {
+        let existing_feed_id = tx
+            .query_row(
+                "SELECT id FROM feeds
+                WHERE remote_id = ?1 OR (remote_id IS NULL AND feed_link = ?2)
+                ORDER BY remote_id IS NULL
+                LIMIT 1",
+                params![remote_feed.remote_id, remote_feed.feed_link],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let feed_id = match existing_feed_id {
+            Some(feed_id) => feed_id,
+            None => create_feed(
+                &tx,
+                &Feed {
+                    id: -1,
+                    title: remote_feed.title.clone(),
+                    feed_link: Some(remote_feed.feed_link.clone()),
+                    link: remote_feed.link.clone(),
+                    // sync servers do not say, and most feeds are RSS
+                    feed_kind: FeedKind::Rss,
+                    refreshed_at: None,
+                    retry_after: None,
+                    entry_sort: EntrySort::default(),
+                    paused: false,
+                    health: FeedHealth::default(),
+                    original_feed_link: None,
+                    moved_to: None,
+                },
+            )?,
+        };
+
+        tx.execute(
+            "UPDATE feeds SET remote_id = ?2, title = coalesce(?3, title), link = coalesce(?4, link)
+            WHERE id = ?1",
+            params![
+                feed_id,
+                remote_feed.remote_id,
+                remote_feed
+                    .title
+                    .as_deref()
+                    .map(crate::sanitize::decode_entities),
+                remote_feed.link
+            ],
+        )?;
+
+        for tag in &remote_feed.tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO feed_tags (feed_id, tag) VALUES (?1, ?2)",
+                params![feed_id, tag],
+            )?;
+        }
+
+        feed_ids.insert(remote_feed.remote_id.clone(), feed_id);
     }
 
-    Ok(ids)
+    tx.commit()?;
+
+    Ok(feed_ids)
 }
 
-pub fn get_entry_meta(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryMeta> {
-    let result = conn.query_row(
-        "SELECT 
-          id, 
-          feed_id, 
-          title, 
-          author, 
-          pub_date, 
-          link, 
-          read_at, 
-          inserted_at, 
-          updated_at 
-        FROM entries WHERE id=?1",
-        params![entry_id],
-        |row| {
-            Ok(EntryMeta {
-                id: row.get(0)?,
-                feed_id: row.get(1)?,
-                title: row.get(2)?,
-                author: row.get(3)?,
-                pub_date: row.get(4)?,
-                link: row.get(5)?,
-                read_at: row.get(6)?,
-                inserted_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        },
-    )?;
+/// adds the entries russ does not have yet, adopting an entry with the same link in the same feed if there is one,
+/// and takes the server's read and starred state, except for entries changed in russ since the last sync.
+/// `feed_ids` are the local ids of the remote feeds, by remote id.
+/// returns how many entries were added.
+pub fn store_remote_entries(
+    conn: &rusqlite::Connection,
+    feed_ids: &HashMap<String, FeedId>,
+    remote_entries: &[RemoteEntry],
+) -> Result<usize> {
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+    let mut pruned_entries_links = HashMap::new();
+    let mut added_len = 0;
 
-    Ok(result)
+    for remote_entry in remote_entries {
+        let feed_id = match feed_ids.get(&remote_entry.remote_feed_id) {
+            Some(feed_id) => *feed_id,
+            None => continue,
+        };
+
+        let existing_entry_id: Option<EntryId> = tx
+            .query_row(
+                "SELECT id FROM entries
+                WHERE remote_id = ?1 OR (remote_id IS NULL AND feed_id = ?2 AND link = ?3)
+                ORDER BY remote_id IS NULL
+                LIMIT 1",
+                params![remote_entry.remote_id, feed_id, remote_entry.entry.link],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let entry_id = match existing_entry_id {
+            Some(entry_id) => entry_id,
+            None => {
+                let feed_pruned_entries_links = match pruned_entries_links.entry(feed_id) {
+                    std::collections::hash_map::Entry::Occupied(links) => links.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(links) => {
+                        links.insert(get_pruned_entries_links(&tx, feed_id)?)
+                    }
+                };
+
+                let is_pruned = match &remote_entry.entry.link {
+                    Some(link) => feed_pruned_entries_links.contains(link),
+                    None => false,
+                };
+
+                if is_pruned {
+                    continue;
+                }
+
+                add_entries_to_feed(&tx, feed_id, std::slice::from_ref(&remote_entry.entry))?;
+                added_len += 1;
+                tx.last_insert_rowid()
+            }
+        };
+
+        tx.execute(
+            "UPDATE entries SET remote_id = ?2 WHERE id = ?1",
+            params![entry_id, remote_entry.remote_id],
+        )?;
+
+        tx.execute(
+            "UPDATE entries
+            SET read_at = CASE WHEN ?2 IS NULL THEN NULL ELSE coalesce(read_at, ?2) END, starred = ?3
+            WHERE id = ?1 AND NOT sync_pending",
+            params![entry_id, remote_entry.entry.read_at, remote_entry.starred],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(added_len)
 }
 
-pub fn get_entry_content(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryContent> {
-    let result = conn.query_row(
-        "SELECT content, description FROM entries WHERE id=?1",
-        params![entry_id],
-        |row| {
-            Ok(EntryContent {
-                content: row.get(0)?,
-                description: row.get(1)?,
-            })
-        },
-    )?;
+/// takes the server's read and starred state for every synced entry,
+/// except for entries changed in russ since the last sync
+pub fn store_remote_entry_states(
+    conn: &rusqlite::Connection,
+    entry_states: &EntryStates,
+) -> Result<()> {
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
 
-    Ok(result)
+    let entries = {
+        let mut statement = tx.prepare(
+            "SELECT id, remote_id, read_at IS NOT NULL, starred FROM entries
+            WHERE remote_id IS NOT NULL AND NOT sync_pending",
+        )?;
+
+        let entries = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, EntryId>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        entries
+    };
+
+    let now = Utc::now();
+
+    for (entry_id, remote_id, read, starred) in entries {
+        let remote_read = !entry_states.unread.contains(&remote_id);
+        let remote_starred = entry_states.starred.contains(&remote_id);
+
+        if read != remote_read {
+            tx.execute(
+                "UPDATE entries SET read_at = ?2 WHERE id = ?1",
+                params![entry_id, Some(now).filter(|_| remote_read)],
+            )?;
+        }
+
+        if starred != remote_starred {
+            tx.execute(
+                "UPDATE entries SET starred = ?2 WHERE id = ?1",
+                params![entry_id, remote_starred],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
 }
 
-pub fn get_entries_metas(
+/// which read entries are deleted. unread and starred entries are always kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// counting every entry of a feed, newest first
+    pub max_entries_per_feed: Option<usize>,
+    pub max_age: Option<chrono::Duration>,
+}
+
+impl RetentionPolicy {
+    pub fn keeps_everything(&self) -> bool {
+        self.max_entries_per_feed.is_none() && self.max_age.is_none()
+    }
+}
+
+/// deletes the read, unstarred entries that `retention_policy` does not keep,
+/// of one feed or of every feed if `feed_id` is `None`.
+/// returns how many were deleted.
+pub fn prune_entries(
     conn: &rusqlite::Connection,
-    read_mode: &ReadMode,
-    feed_id: FeedId,
-) -> Result<Vec<EntryMeta>> {
-    let read_at_predicate = match read_mode {
-        ReadMode::ShowUnread => "\nAND read_at IS NULL",
-        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
-        ReadMode::All => "\n",
+    retention_policy: &RetentionPolicy,
+    feed_id: Option<FeedId>,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    if retention_policy.keeps_everything() {
+        return Ok(0);
+    }
+
+    let prunable_entries_ids = "SELECT id FROM (
+          SELECT
+            id,
+            feed_id,
+            read_at,
+            starred,
+            sync_pending,
+            coalesce(pub_date, inserted_at) AS date,
+            ROW_NUMBER() OVER (
+              PARTITION BY feed_id
+              ORDER BY coalesce(pub_date, inserted_at) DESC, id DESC
+            ) AS newness
+          FROM entries
+        )
+        WHERE read_at IS NOT NULL
+        AND NOT starred
+        AND NOT sync_pending
+        AND id NOT IN (SELECT entry_id FROM entry_notes)
+        AND (:feed_id IS NULL OR feed_id = :feed_id)
+        AND (
+          (:cutoff IS NOT NULL AND date < :cutoff)
+          OR (:max_entries IS NOT NULL AND newness > :max_entries)
+        )";
+
+    let cutoff = retention_policy.max_age.map(|max_age| now - max_age);
+    let max_entries = retention_policy
+        .max_entries_per_feed
+        .map(|max_entries| max_entries as i64);
+    let params = rusqlite::named_params! {
+        ":feed_id": feed_id,
+        ":cutoff": cutoff,
+        ":max_entries": max_entries,
     };
 
-    // we get weird pubDate formats from feeds,
-    // so sort by inserted at as this as a stable order at least
-    let mut query = "SELECT 
-        id, 
-        feed_id, 
-        title, 
-        author, 
-        pub_date, 
-        link, 
-        read_at, 
-        inserted_at, 
-        updated_at 
-        FROM entries 
-        WHERE feed_id=?1"
-        .to_string();
+    let tx = conn.unchecked_transaction()?;
 
-    query.push_str(read_at_predicate);
-    query.push_str("\nORDER BY pub_date DESC, inserted_at DESC");
+    tx.execute(
+        &format!(
+            "INSERT OR IGNORE INTO pruned_entries (feed_id, link)
+            SELECT feed_id, link FROM entries
+            WHERE link IS NOT NULL AND id IN ({})",
+            prunable_entries_ids
+        ),
+        params,
+    )?;
 
-    let mut statement = conn.prepare(&query)?;
-    let mut entries = vec![];
-    for entry in statement.query_map(params![feed_id], |row| {
-        Ok(EntryMeta {
-            id: row.get(0)?,
-            feed_id: row.get(1)?,
-            title: row.get(2)?,
-            author: row.get(3)?,
-            pub_date: row.get(4)?,
-            link: row.get(5)?,
-            read_at: row.get(6)?,
-            inserted_at: row.get(7)?,
-            updated_at: row.get(8)?,
-        })
-    })? {
-        entries.push(entry?)
+    let pruned_len = tx.execute(
+        &format!("DELETE FROM entries WHERE id IN ({})", prunable_entries_ids),
+        params,
+    )?;
+
+    tx.commit()?;
+
+    Ok(pruned_len)
+}
+
+/// marks every one of `entry_ids` as read, or as unread.
+/// entries that are already read keep when they were read
+pub fn set_entries_read(
+    conn: &rusqlite::Connection,
+    entry_ids: &[EntryId],
+    is_read: bool,
+) -> Result<()> {
+    let read_at = if is_read { Some(Utc::now()) } else { None };
+
+    let tx = conn.unchecked_transaction()?;
+
+    {
+        let mut statement = tx.prepare(
+            "UPDATE entries
+            SET read_at = CASE WHEN ?2 IS NULL THEN NULL ELSE coalesce(read_at, ?2) END,
+            sync_pending = remote_id IS NOT NULL
+            WHERE id = ?1",
+        )?;
+
+        for entry_id in entry_ids {
+            statement.execute(params![entry_id, read_at])?;
+        }
     }
 
-    Ok(entries)
+    tx.commit()?;
+
+    Ok(())
 }
 
-pub fn get_entries_links(
+pub fn set_entries_starred(
     conn: &rusqlite::Connection,
-    read_mode: &ReadMode,
-    feed_id: FeedId,
-) -> Result<Vec<Option<String>>> {
-    let read_at_predicate = match read_mode {
-        ReadMode::ShowUnread => "\nAND read_at IS NULL",
-        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
-        ReadMode::All => "\n",
+    entry_ids: &[EntryId],
+    is_starred: bool,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    {
+        let mut statement = tx.prepare(
+            "UPDATE entries SET starred = ?2, sync_pending = remote_id IS NOT NULL WHERE id = ?1",
+        )?;
+
+        for entry_id in entry_ids {
+            statement.execute(params![entry_id, is_starred])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// an entry's read and starred state, kept to put it back
+#[derive(Clone, Debug)]
+pub struct SavedEntryState {
+    id: EntryId,
+    read_at: Option<DateTime<Utc>>,
+    starred: bool,
+}
+
+pub fn get_saved_entry_states(
+    conn: &rusqlite::Connection,
+    entry_ids: &[EntryId],
+) -> Result<Vec<SavedEntryState>> {
+    let mut statement = conn.prepare("SELECT id, read_at, starred FROM entries WHERE id = ?1")?;
+    let mut states = vec![];
+
+    for entry_id in entry_ids {
+        let state = statement
+            .query_row(params![entry_id], |row| {
+                Ok(SavedEntryState {
+                    id: row.get(0)?,
+                    read_at: row.get(1)?,
+                    starred: row.get(2)?,
+                })
+            })
+            .optional()?;
+
+        states.extend(state);
+    }
+
+    Ok(states)
+}
+
+pub fn restore_entry_states(conn: &rusqlite::Connection, states: &[SavedEntryState]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    {
+        let mut statement = tx.prepare(
+            "UPDATE entries SET read_at = ?2, starred = ?3, sync_pending = remote_id IS NOT NULL
+            WHERE id = ?1",
+        )?;
+
+        for state in states {
+            statement.execute(params![state.id, state.read_at, state.starred])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// rows of one table, whole, kept to put them back
+#[derive(Clone, Debug, Default)]
+struct Rows {
+    columns: Vec<String>,
+    values: Vec<Vec<rusqlite::types::Value>>,
+}
+
+impl Rows {
+    /// deletes the rows of `table` where `column` is `value`, returning them
+    fn take(conn: &rusqlite::Connection, table: &str, column: &str, value: i64) -> Result<Self> {
+        let mut statement =
+            conn.prepare(&format!("SELECT * FROM {} WHERE {} = ?1", table, column))?;
+        let columns = statement
+            .column_names()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        let values = statement
+            .query_map(params![value], |row| {
+                (0..columns.len()).map(|idx| row.get(idx)).collect()
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        conn.execute(
+            &format!("DELETE FROM {} WHERE {} = ?1", table, column),
+            params![value],
+        )?;
+
+        Ok(Rows { columns, values })
+    }
+
+    fn extend(&mut self, rows: Rows) {
+        self.columns = rows.columns;
+        self.values.extend(rows.values);
+    }
+
+    fn put_back(&self, conn: &rusqlite::Connection, table: &str) -> Result<()> {
+        if self.values.is_empty() {
+            return Ok(());
+        }
+
+        let mut statement = conn.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            self.columns.join(", "),
+            vec!["?"; self.columns.len()].join(", ")
+        ))?;
+
+        for values in &self.values {
+            statement.execute(rusqlite::params_from_iter(values))?;
+        }
+
+        Ok(())
+    }
+
+    fn column(&self, name: &str) -> Result<usize> {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| anyhow::anyhow!("The deleted rows have no {} column", name))
+    }
+}
+
+/// deleted entries and their notes, kept to put them back
+#[derive(Clone, Debug)]
+pub struct DeletedEntries {
+    entries: Rows,
+    entry_notes: Rows,
+}
+
+impl DeletedEntries {
+    pub fn len(&self) -> usize {
+        self.entries.values.len()
+    }
+}
+
+/// deletes `entry_ids`, remembering their links as pruning does
+/// so that refreshing their feeds does not bring them back
+pub fn delete_entries(
+    conn: &rusqlite::Connection,
+    entry_ids: &[EntryId],
+) -> Result<DeletedEntries> {
+    let tx = conn.unchecked_transaction()?;
+    let mut entries = Rows::default();
+    let mut entry_notes = Rows::default();
+
+    for entry_id in entry_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO pruned_entries (feed_id, link)
+            SELECT feed_id, link FROM entries
+            WHERE link IS NOT NULL AND id = ?1",
+            params![entry_id],
+        )?;
+        entries.extend(Rows::take(&tx, "entries", "id", *entry_id)?);
+        entry_notes.extend(Rows::take(&tx, "entry_notes", "entry_id", *entry_id)?);
+    }
+
+    tx.commit()?;
+
+    Ok(DeletedEntries {
+        entries,
+        entry_notes,
+    })
+}
+
+/// puts deleted entries back as they were, and lets refreshing their feeds add them again
+pub fn restore_entries(
+    conn: &rusqlite::Connection,
+    deleted_entries: &DeletedEntries,
+) -> Result<()> {
+    let DeletedEntries {
+        entries: rows,
+        entry_notes,
+    } = deleted_entries;
+
+    let tx = conn.unchecked_transaction()?;
+
+    rows.put_back(&tx, "entries")?;
+    entry_notes.put_back(&tx, "entry_notes")?;
+
+    if !rows.values.is_empty() {
+        let (feed_id_idx, link_idx) = (rows.column("feed_id")?, rows.column("link")?);
+
+        for values in &rows.values {
+            tx.execute(
+                "DELETE FROM pruned_entries WHERE feed_id = ?1 AND link = ?2",
+                params![values[feed_id_idx], values[link_idx]],
+            )?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// a deleted feed with its entries, their notes, and its tags, kept to put them back
+#[derive(Clone, Debug)]
+pub struct DeletedFeed {
+    feeds: Rows,
+    feed_tags: Rows,
+    entries: Rows,
+    entry_notes: Rows,
+    pruned_entries: Rows,
+    raw_feeds: Rows,
+}
+
+/// deletes a feed and everything of it
+pub fn delete_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<DeletedFeed> {
+    let tx = conn.unchecked_transaction()?;
+
+    let entry_ids = tx
+        .prepare("SELECT entry_id FROM entry_notes JOIN entries ON entries.id = entry_id WHERE feed_id = ?1")?
+        .query_map(params![feed_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<EntryId>>>()?;
+
+    let mut entry_notes = Rows::default();
+    for entry_id in entry_ids {
+        entry_notes.extend(Rows::take(&tx, "entry_notes", "entry_id", entry_id)?);
+    }
+
+    let deleted_feed = DeletedFeed {
+        entry_notes,
+        feed_tags: Rows::take(&tx, "feed_tags", "feed_id", feed_id)?,
+        entries: Rows::take(&tx, "entries", "feed_id", feed_id)?,
+        pruned_entries: Rows::take(&tx, "pruned_entries", "feed_id", feed_id)?,
+        raw_feeds: Rows::take(&tx, "raw_feeds", "feed_id", feed_id)?,
+        feeds: Rows::take(&tx, "feeds", "id", feed_id)?,
     };
 
-    // we get weird pubDate formats from feeds,
-    // so sort by inserted at as this as a stable order at least
-    let mut query = "SELECT link FROM entries WHERE feed_id=?1".to_string();
+    tx.commit()?;
 
-    query.push_str(read_at_predicate);
-    query.push_str("\nORDER BY pub_date DESC, inserted_at DESC");
+    Ok(deleted_feed)
+}
 
-    let mut links = vec![];
-    let mut statement = conn.prepare(&query)?;
+pub fn restore_feed(conn: &rusqlite::Connection, deleted_feed: &DeletedFeed) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    deleted_feed.feeds.put_back(&tx, "feeds")?;
+    deleted_feed.feed_tags.put_back(&tx, "feed_tags")?;
+    deleted_feed.entries.put_back(&tx, "entries")?;
+    deleted_feed.entry_notes.put_back(&tx, "entry_notes")?;
+    deleted_feed
+        .pruned_entries
+        .put_back(&tx, "pruned_entries")?;
+    deleted_feed.raw_feeds.put_back(&tx, "raw_feeds")?;
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn get_pruned_entries_links(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+) -> Result<HashSet<String>> {
+    let mut statement = conn.prepare("SELECT link FROM pruned_entries WHERE feed_id = ?1")?;
 
+    let mut links = HashSet::new();
     for link in statement.query_map(params![feed_id], |row| row.get(0))? {
-        links.push(link?);
+        links.insert(link?);
     }
 
     Ok(links)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    const ZCT: &str = "https://zeroclarkthirty.com/feed";
+/// how big the database was before and after `maintain_db`, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    pub size_before: u64,
+    pub size_after: u64,
+}
 
-    #[test]
-    fn it_fetches() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
-        let feed_and_entries = fetch_feed(&http_client, ZCT).unwrap();
-        assert!(feed_and_entries.entries.len() > 0)
+/// checks the database for corruption, then updates the query planner's statistics,
+/// compacts the search index, and rebuilds the file without its free pages.
+/// a corrupt database is left alone.
+pub fn maintain_db(conn: &rusqlite::Connection) -> Result<MaintenanceReport> {
+    let mut statement = conn.prepare("PRAGMA integrity_check")?;
+    let problems = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    if problems != ["ok"] {
+        return Err(anyhow::anyhow!(
+            "The database is corrupt, so it was not changed:\n{}",
+            problems.join("\n")
+        ));
     }
 
-    #[test]
-    fn it_subscribes_to_a_feed() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
-        let conn = rusqlite::Connection::open_in_memory().unwrap();
-        initialize_db(&conn).unwrap();
-        subscribe_to_feed(&http_client, &conn, ZCT).unwrap();
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
-            .unwrap();
+    let size_before = db_size(conn)?;
 
-        assert!(count > 50)
+    conn.execute_batch(
+        "ANALYZE;
+        INSERT INTO entries_fts (entries_fts) VALUES ('optimize');
+        VACUUM;",
+    )?;
+
+    let size_after = db_size(conn)?;
+
+    Ok(MaintenanceReport {
+        size_before,
+        size_after,
+    })
+}
+
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 1000;
+const BACKUP_PAUSE: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// copies the database to a new file at `path`, while other connections go on using it.
+/// the copy is encrypted with `passphrase` if there is one.
+pub fn backup_db(
+    conn: &rusqlite::Connection,
+    path: &std::path::Path,
+    passphrase: Option<&Passphrase>,
+) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow::anyhow!("{} already exists", path.display()));
     }
 
-    #[test]
-    fn refresh_feed_does_not_add_any_items_if_there_are_no_new_items() {
-        let http_client = ureq::AgentBuilder::new()
-            .timeout_read(std::time::Duration::from_secs(5))
-            .build();
-        let conn = rusqlite::Connection::open_in_memory().unwrap();
-        initialize_db(&conn).unwrap();
-        subscribe_to_feed(&http_client, &conn, ZCT).unwrap();
-        let feed_id = 1;
-        let old_entries = get_entries_metas(&conn, &ReadMode::ShowUnread, feed_id).unwrap();
-        refresh_feed(&http_client, &conn, feed_id).unwrap();
-        let e = get_entry_meta(&conn, 1).unwrap();
-        e.mark_as_read(&conn).unwrap();
-        let new_entries = get_entries_metas(&conn, &ReadMode::ShowUnread, feed_id).unwrap();
+    let mut backup_conn = open_db(path, passphrase)?;
+    let backup = rusqlite::backup::Backup::new(conn, &mut backup_conn)?;
+    backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_PAUSE, None)?;
 
-        assert_eq!(new_entries.len(), old_entries.len() - 1);
+    Ok(())
+}
+
+/// replaces everything in the database with the backup at `path`,
+/// migrating it if it came from an older version of russ.
+/// an encrypted backup must have the same passphrase as the database.
+pub fn restore_db(
+    conn: &mut rusqlite::Connection,
+    path: &std::path::Path,
+    passphrase: Option<&Passphrase>,
+) -> Result<()> {
+    let backup_conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Could not open {}", path.display()))?;
+
+    if let Some(Passphrase(passphrase)) = passphrase {
+        backup_conn.pragma_update(None, "key", passphrase)?;
+    }
+
+    let has_feeds: bool = backup_conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'feeds')",
+            [],
+            |row| row.get(0),
+        )
+        .with_context(|| {
+            format!(
+                "Could not read {}, is it encrypted with another passphrase?",
+                path.display()
+            )
+        })?;
+
+    if !has_feeds {
+        return Err(anyhow::anyhow!("{} is not a russ database", path.display()));
+    }
+
+    if schema_version(&backup_conn)? > MIGRATIONS.len() as u32 {
+        return Err(anyhow::anyhow!(
+            "{} is from a newer version of russ. Upgrade russ to restore it",
+            path.display()
+        ));
+    }
+
+    {
+        let backup = rusqlite::backup::Backup::new(&backup_conn, conn)?;
+        backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_PAUSE, None)?;
+    }
+
+    initialize_db(conn)
+}
+
+fn db_size(conn: &rusqlite::Connection) -> Result<u64> {
+    let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok(page_count * page_size)
+}
+
+/// how long a connection waits for another one's write to finish
+/// before giving up with "database is locked"
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// the key of a database encrypted with SQLCipher, kept out of `Debug` output
+#[derive(Clone)]
+pub struct Passphrase(pub String);
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Passphrase(..)")
+    }
+}
+
+/// opens the database the way every connection to it should be opened
+pub fn open_db<P: AsRef<std::path::Path>>(
+    path: P,
+    passphrase: Option<&Passphrase>,
+) -> Result<rusqlite::Connection> {
+    let path = path.as_ref();
+    let conn = rusqlite::Connection::open(path)?;
+
+    match configure_connection(&conn, passphrase) {
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::NotADatabase =>
+        {
+            Err(anyhow::anyhow!(match passphrase {
+                Some(_) => format!(
+                    "Unable to open {}, the passphrase is wrong or it is not encrypted",
+                    path.display()
+                ),
+                None => format!(
+                    "Unable to open {}, it is encrypted or not a database",
+                    path.display()
+                ),
+            }))
+        }
+        result => {
+            result?;
+            Ok(conn)
+        }
+    }
+}
+
+/// in WAL mode the UI can read while a refresh writes,
+/// and writers wait on each other for up to `BUSY_TIMEOUT` rather than failing
+pub fn configure_connection(
+    conn: &rusqlite::Connection,
+    passphrase: Option<&Passphrase>,
+) -> rusqlite::Result<()> {
+    // SQLCipher needs the key before anything else touches the database
+    if let Some(Passphrase(passphrase)) = passphrase {
+        conn.pragma_update(None, "key", passphrase)?;
+    }
+
+    // an in-memory database answers "memory" instead, which is fine
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_row| Ok(()))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+}
+
+type Migration = fn(&rusqlite::Connection) -> Result<()>;
+
+/// every change to the schema, in order, and a database's `user_version` is how many it has had.
+/// the first one brings databases from before migrations existed up to date, so it must stay idempotent.
+/// the rest run exactly once, so they can change tables however they need to.
+/// never change a migration that has been released, add another one instead.
+const MIGRATIONS: &[Migration] = &[
+    create_schema,
+    add_sync_columns,
+    add_enclosures,
+    add_full_content,
+    decode_titles,
+    add_base_url,
+    add_paused,
+    add_feed_health,
+    add_refresh_duration,
+    add_categories,
+    add_entry_notes,
+    add_word_counts,
+    add_scroll_positions,
+    add_session,
+    add_opened_at,
+    add_pane_widths,
+    add_raw_feeds,
+    add_duplicate_keys,
+    add_entry_revisions,
+    add_saved_searches,
+    add_original_feed_links,
+    add_feed_moves,
+];
+
+pub fn initialize_db(conn: &rusqlite::Connection) -> Result<()> {
+    migrate(conn, MIGRATIONS)
+}
+
+fn migrate(conn: &rusqlite::Connection, migrations: &[Migration]) -> Result<()> {
+    let latest_version = migrations.len() as u32;
+    let current_version = schema_version(conn)?;
+
+    if current_version > latest_version {
+        return Err(anyhow::anyhow!(
+            "The database is at schema version {}, but this version of russ only knows up to {}. Upgrade russ to open it",
+            current_version,
+            latest_version
+        ));
+    }
+
+    for (version, migration) in (1..=latest_version)
+        .zip(migrations)
+        .skip(current_version as usize)
+    {
+        // immediate, so a UI and a daemon starting at once do not both run the same migration
+        let tx =
+            rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+
+        if schema_version(&tx)? >= version {
+            continue;
+        }
+
+        migration(&tx)
+            .with_context(|| format!("Could not migrate the database to version {}", version))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+fn schema_version(conn: &rusqlite::Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// the schema as it was before migrations, built up from whatever parts of it already exist
+fn create_schema(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS feeds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT,
+        feed_link TEXT,
+        link TEXT,
+        feed_kind TEXT,
+        refreshed_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        etag TEXT,
+        last_modified TEXT,
+        retry_after TIMESTAMP,
+        position INTEGER,
+        entry_sort TEXT
+    )",
+        [],
+    )?;
+
+    add_column_if_missing(conn, "feeds", "etag", "TEXT")?;
+    add_column_if_missing(conn, "feeds", "last_modified", "TEXT")?;
+    add_column_if_missing(conn, "feeds", "retry_after", "TIMESTAMP")?;
+    add_column_if_missing(conn, "feeds", "position", "INTEGER")?;
+    add_column_if_missing(conn, "feeds", "entry_sort", "TEXT")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER,
+        title TEXT,
+        author TEXT,
+        pub_date TIMESTAMP,
+        description TEXT,
+        content TEXT,
+        link TEXT,
+        read_at TIMESTAMP,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        starred BOOLEAN NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    add_column_if_missing(conn, "entries", "starred", "BOOLEAN NOT NULL DEFAULT 0")?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS entries_feed_id_and_pub_date_and_inserted_at_index 
+        ON entries (feed_id, pub_date, inserted_at)",
+        [],
+    )?;
+
+    // the links of pruned entries, so refreshing does not add them again
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pruned_entries (
+        feed_id INTEGER NOT NULL,
+        link TEXT NOT NULL,
+        PRIMARY KEY (feed_id, link)
+    )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS feed_tags (
+        feed_id INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (feed_id, tag)
+        )",
+        [],
+    )?;
+
+    initialize_search_index(conn)?;
+
+    Ok(())
+}
+
+/// the ids a sync server knows feeds and entries by,
+/// and which entries were read or starred locally since the last sync
+fn add_sync_columns(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE feeds ADD COLUMN remote_id TEXT;
+        ALTER TABLE entries ADD COLUMN remote_id TEXT;
+        ALTER TABLE entries ADD COLUMN sync_pending BOOLEAN NOT NULL DEFAULT 0;
+        CREATE UNIQUE INDEX feeds_remote_id_index ON feeds (remote_id);
+        CREATE UNIQUE INDEX entries_remote_id_index ON entries (remote_id);
+
+        CREATE TABLE syncs (
+            server TEXT PRIMARY KEY,
+            synced_at TIMESTAMP NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// the media file of a podcast episode and the like
+fn add_enclosures(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE entries ADD COLUMN enclosure_url TEXT;
+        ALTER TABLE entries ADD COLUMN enclosure_mime_type TEXT;
+        ALTER TABLE entries ADD COLUMN enclosure_length INTEGER;",
+    )?;
+
+    Ok(())
+}
+
+/// the full articles of entries whose feeds only have summaries
+fn add_full_content(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE entries ADD COLUMN full_content TEXT;")?;
+
+    Ok(())
+}
+
+/// titles used to be kept with the entities feeds escape them with, like `&#8217;`
+fn decode_titles(conn: &rusqlite::Connection) -> Result<()> {
+    for table in ["feeds", "entries"] {
+        let mut statement = conn.prepare(&format!(
+            "SELECT id, title FROM {} WHERE title LIKE '%&%;%'",
+            table
+        ))?;
+        let titles = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (id, title) in titles {
+            conn.execute(
+                &format!("UPDATE {} SET title = ?2 WHERE id = ?1", table),
+                params![id, crate::sanitize::decode_entities(&title)],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_base_url(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE entries ADD COLUMN base_url TEXT;")?;
+
+    Ok(())
+}
+
+fn add_paused(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE feeds ADD COLUMN paused INTEGER NOT NULL DEFAULT 0;")?;
+
+    Ok(())
+}
+
+fn add_feed_health(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE feeds ADD COLUMN last_error TEXT;
+        ALTER TABLE feeds ADD COLUMN last_error_at TIMESTAMP;
+        ALTER TABLE feeds ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;",
+    )?;
+
+    Ok(())
+}
+
+fn add_refresh_duration(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE feeds ADD COLUMN last_refresh_duration_ms INTEGER;")?;
+
+    Ok(())
+}
+
+fn add_categories(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE entries ADD COLUMN categories TEXT;")?;
+
+    Ok(())
+}
+
+/// what the user wrote about an entry, like why they starred it
+fn add_entry_notes(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE entry_notes (
+        entry_id INTEGER PRIMARY KEY,
+        note TEXT NOT NULL,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// counted once, as entries are added or their articles fetched, so lists of them can show it
+fn add_word_counts(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE entries ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;")?;
+
+    let word_counts = conn
+        .prepare("SELECT id, coalesce(full_content, content, description, '') FROM entries")?
+        .query_map([], |row| {
+            let html: String = row.get(1)?;
+            Ok((row.get::<_, EntryId>(0)?, crate::html::count_words(&html)))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut statement = conn.prepare("UPDATE entries SET word_count = ?2 WHERE id = ?1")?;
+    for (entry_id, word_count) in word_counts {
+        statement.execute(params![entry_id, word_count])?;
+    }
+
+    Ok(())
+}
+
+/// how far down an entry was scrolled when it was last left, to pick up reading there
+fn add_scroll_positions(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE entries ADD COLUMN scroll_position INTEGER NOT NULL DEFAULT 0;",
+    )?;
+
+    Ok(())
+}
+
+/// one row, overwritten whenever russ quits
+fn add_session(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE session (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            feed_id INTEGER,
+            virtual_feed TEXT,
+            entry_id INTEGER,
+            pane TEXT NOT NULL,
+            read_mode TEXT NOT NULL,
+            entry_filter TEXT NOT NULL,
+            date_range TEXT NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// the pane widths the last session left, if they were resized
+fn add_pane_widths(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE session ADD COLUMN feeds_pane_width INTEGER;
+        ALTER TABLE session ADD COLUMN entries_pane_width INTEGER;",
+    )?;
+
+    Ok(())
+}
+
+/// each feed as it was last fetched, compressed, to see what russ made of it
+fn add_raw_feeds(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE raw_feeds (
+            feed_id INTEGER PRIMARY KEY,
+            body BLOB NOT NULL,
+            fetched_at TIMESTAMP NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// what the same article has in common across the feeds it appears in, to tell duplicates apart
+fn add_duplicate_keys(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE entries ADD COLUMN duplicate_key TEXT;
+        CREATE INDEX entries_duplicate_key_index ON entries (duplicate_key);",
+    )?;
+
+    let duplicate_keys = conn
+        .prepare("SELECT id, title, link, coalesce(content, description) FROM entries")?
+        .query_map([], |row| {
+            let title: Option<String> = row.get(1)?;
+            let link: Option<String> = row.get(2)?;
+            let content: Option<String> = row.get(3)?;
+            Ok((
+                row.get::<_, EntryId>(0)?,
+                duplicate_key(title.as_deref(), link.as_deref(), content.as_deref()),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut statement = conn.prepare("UPDATE entries SET duplicate_key = ?2 WHERE id = ?1")?;
+    for (entry_id, duplicate_key) in duplicate_keys {
+        statement.execute(params![entry_id, duplicate_key])?;
+    }
+
+    Ok(())
+}
+
+/// what refreshing needs to tell when a feed has edited an entry
+fn add_entry_revisions(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE entries ADD COLUMN guid TEXT;
+        ALTER TABLE entries ADD COLUMN content_hash TEXT;
+        ALTER TABLE entries ADD COLUMN feed_updated_at TIMESTAMP;
+        ALTER TABLE entries ADD COLUMN revised_at TIMESTAMP;",
+    )?;
+
+    let content_hashes = conn
+        .prepare("SELECT id, title, description, content FROM entries")?
+        .query_map([], |row| {
+            let title: Option<String> = row.get(1)?;
+            let description: Option<String> = row.get(2)?;
+            let content: Option<String> = row.get(3)?;
+            Ok((
+                row.get::<_, EntryId>(0)?,
+                content_hash(title.as_deref(), description.as_deref(), content.as_deref()),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut statement = conn.prepare("UPDATE entries SET content_hash = ?2 WHERE id = ?1")?;
+    for (entry_id, content_hash) in content_hashes {
+        statement.execute(params![entry_id, content_hash])?;
+    }
+
+    Ok(())
+}
+
+fn add_saved_searches(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE saved_searches (
+        name TEXT PRIMARY KEY,
+        query TEXT NOT NULL,
+        updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// the feed links feeds were subscribed at, before a permanent redirect moved them
+fn add_original_feed_links(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE feeds ADD COLUMN original_feed_link TEXT;")?;
+
+    Ok(())
+}
+
+/// where feeds have permanently moved, while that waits to be confirmed,
+/// and where they were not allowed to move, so that is not asked again
+fn add_feed_moves(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE feeds ADD COLUMN moved_to TEXT;
+        ALTER TABLE feeds ADD COLUMN declined_moved_to TEXT;",
+    )?;
+
+    Ok(())
+}
+
+/// when entries were last opened, for the reading history
+fn add_opened_at(conn: &rusqlite::Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE entries ADD COLUMN opened_at TIMESTAMP;
+        CREATE INDEX entries_opened_at_index ON entries (opened_at);",
+    )?;
+
+    Ok(())
+}
+
+/// for databases created before `column` existed
+fn add_column_if_missing(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut statement = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = statement
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// full text search over entries, kept in sync with the entries table by triggers
+fn initialize_search_index(conn: &rusqlite::Connection) -> Result<()> {
+    let search_index_exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'entries_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+        title,
+        description,
+        content,
+        content='entries',
+        content_rowid='id'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS entries_fts_after_insert AFTER INSERT ON entries BEGIN
+        INSERT INTO entries_fts (rowid, title, description, content)
+        VALUES (new.id, new.title, new.description, new.content);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS entries_fts_after_delete AFTER DELETE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, title, description, content)
+        VALUES ('delete', old.id, old.title, old.description, old.content);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS entries_fts_after_update
+    AFTER UPDATE OF title, description, content ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, title, description, content)
+        VALUES ('delete', old.id, old.title, old.description, old.content);
+        INSERT INTO entries_fts (rowid, title, description, content)
+        VALUES (new.id, new.title, new.description, new.content);
+    END;",
+    )?;
+
+    // databases from before search existed already have entries to index
+    if !search_index_exists {
+        conn.execute(
+            "INSERT INTO entries_fts (entries_fts) VALUES ('rebuild')",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn create_feed(conn: &rusqlite::Connection, feed: &Feed) -> Result<FeedId> {
+    // not `RETURNING id`, which the SQLite that SQLCipher bundles is too old for
+    conn.execute(
+        "INSERT INTO feeds (title, link, feed_link, feed_kind)
+        VALUES (?1, ?2, ?3, ?4)",
+        params![
+            feed.title.as_deref().map(crate::sanitize::decode_entities),
+            feed.link,
+            feed.feed_link,
+            feed.feed_kind
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+fn add_entries_to_feed(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    entries: &[Entry],
+) -> Result<()> {
+    if !entries.is_empty() {
+        let now = Utc::now();
+
+        let columns = [
+            "feed_id",
+            "title",
+            "author",
+            "pub_date",
+            "description",
+            "content",
+            "link",
+            "base_url",
+            "enclosure_url",
+            "enclosure_mime_type",
+            "enclosure_length",
+            "categories",
+            "word_count",
+            "duplicate_key",
+            "guid",
+            "content_hash",
+            "feed_updated_at",
+            "updated_at",
+        ];
+
+        let mut entries_values = Vec::with_capacity(entries.len() * columns.len());
+
+        // the values are borrowed until the query runs, so they cannot be temporaries.
+        // feeds escape titles and authors as if they were HTML, which they are not shown as
+        let titles_and_authors = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.title.as_deref().map(crate::sanitize::decode_entities),
+                    entry
+                        .author
+                        .as_deref()
+                        .map(crate::sanitize::decode_entities),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let enclosures = entries
+            .iter()
+            .map(|entry| {
+                let enclosure = entry.enclosure.as_ref();
+                (
+                    enclosure.map(|enclosure| &enclosure.url),
+                    enclosure.and_then(|enclosure| enclosure.mime_type.as_ref()),
+                    enclosure.and_then(|enclosure| enclosure.length.map(|length| length as i64)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let categories = entries
+            .iter()
+            .map(|entry| categories_to_sql(&entry.categories))
+            .collect::<Vec<_>>();
+
+        let word_counts = entries
+            .iter()
+            .map(|entry| {
+                let html = entry.content.as_deref().or(entry.description.as_deref());
+                crate::html::count_words(html.unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+
+        let duplicate_keys = entries
+            .iter()
+            .zip(&titles_and_authors)
+            .map(|(entry, (title, _))| {
+                let content = entry.content.as_deref().or(entry.description.as_deref());
+                duplicate_key(title.as_deref(), entry.link.as_deref(), content)
+            })
+            .collect::<Vec<_>>();
+
+        let content_hashes = entries
+            .iter()
+            .zip(&titles_and_authors)
+            .map(|(entry, (title, _))| {
+                content_hash(
+                    title.as_deref(),
+                    entry.description.as_deref(),
+                    entry.content.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (
+            (
+                ((((entry, title_and_author), enclosure), entry_categories), word_count),
+                duplicate_key,
+            ),
+            content_hash,
+        ) in entries
+            .iter()
+            .zip(&titles_and_authors)
+            .zip(&enclosures)
+            .zip(&categories)
+            .zip(&word_counts)
+            .zip(&duplicate_keys)
+            .zip(&content_hashes)
+        {
+            let values = params![
+                feed_id,
+                title_and_author.0,
+                title_and_author.1,
+                entry.pub_date,
+                entry.description,
+                entry.content,
+                entry.link,
+                entry.base_url,
+                enclosure.0,
+                enclosure.1,
+                enclosure.2,
+                *entry_categories,
+                *word_count,
+                *duplicate_key,
+                entry.guid,
+                *content_hash,
+                entry.feed_updated_at,
+                now,
+            ];
+            entries_values.extend_from_slice(values);
+        }
+
+        let query = build_bulk_insert_query("entries", &columns, entries);
+
+        conn.execute(&query, entries_values.as_slice())?;
+    }
+
+    Ok(())
+}
+
+/// what the same article has in common across the feeds it appears in:
+/// its link, normalized, or a hash of its title and content if it has no link.
+/// `None` for an entry with neither, which has nothing to tell it apart by
+fn duplicate_key(title: Option<&str>, link: Option<&str>, content: Option<&str>) -> Option<String> {
+    if let Some(link) = link.map(str::trim).filter(|link| !link.is_empty()) {
+        return Some(crate::util::normalize_url(link));
+    }
+
+    let title = title.map(str::trim).unwrap_or_default();
+    let content = content.map(str::trim).unwrap_or_default();
+    if title.is_empty() && content.is_empty() {
+        return None;
+    }
+
+    Some(format!("hash:{:016x}", fnv1a(&[title, "\n", content])))
+}
+
+/// a hash of what a feed can edit about an entry, to tell when it has.
+/// the title is the decoded one that is stored
+fn content_hash(title: Option<&str>, description: Option<&str>, content: Option<&str>) -> String {
+    let hash = fnv1a(&[
+        title.unwrap_or_default(),
+        "\n",
+        description.unwrap_or_default(),
+        "\n",
+        content.unwrap_or_default(),
+    ]);
+
+    format!("{:016x}", hash)
+}
+
+/// FNV-1a, rather than std's hasher, which may hash differently from one Rust to the next
+fn fnv1a(parts: &[&str]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|s| s.bytes())
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// the unread entries that are duplicates of any of `entry_ids` in other feeds, or in the same one
+pub fn get_unread_duplicate_entry_ids(
+    conn: &rusqlite::Connection,
+    entry_ids: &[EntryId],
+) -> Result<Vec<EntryId>> {
+    let mut statement = conn.prepare(
+        "SELECT duplicate.id
+        FROM entries AS entry
+        JOIN entries AS duplicate ON duplicate.duplicate_key = entry.duplicate_key
+        WHERE entry.id = ?1 AND duplicate.id != entry.id AND duplicate.read_at IS NULL",
+    )?;
+
+    let mut duplicate_entry_ids = vec![];
+
+    for entry_id in entry_ids {
+        for duplicate_entry_id in statement.query_map([entry_id], |row| row.get(0))? {
+            let duplicate_entry_id = duplicate_entry_id?;
+            if !entry_ids.contains(&duplicate_entry_id)
+                && !duplicate_entry_ids.contains(&duplicate_entry_id)
+            {
+                duplicate_entry_ids.push(duplicate_entry_id);
+            }
+        }
+    }
+
+    Ok(duplicate_entry_ids)
+}
+
+/// marks the feed's unread entries read if a duplicate of them is read already,
+/// like the entries a refresh adds that were read in another feed.
+/// returns how many were marked
+pub fn mark_read_duplicates_read(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE entries
+        SET read_at = ?2, sync_pending = remote_id IS NOT NULL
+        WHERE feed_id = ?1
+        AND read_at IS NULL
+        AND duplicate_key IS NOT NULL
+        AND EXISTS (
+            SELECT 1 FROM entries AS duplicate
+            WHERE duplicate.duplicate_key = entries.duplicate_key
+            AND duplicate.id != entries.id
+            AND duplicate.read_at IS NOT NULL
+        )",
+        params![feed_id, Utc::now()],
+    )?)
+}
+
+fn build_bulk_insert_query<C: AsRef<str>, R>(table: &str, columns: &[C], rows: &[R]) -> String {
+    let idxs = (1..(rows.len() * columns.len() + 1)).collect::<Vec<_>>();
+
+    let values_groups_string = idxs
+        .chunks(columns.len())
+        .map(|chunk| {
+            let values_string = chunk
+                .iter()
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ["(", &values_string, ")"].concat()
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let columns_strs = columns
+        .iter()
+        .map(|column| column.as_ref())
+        .collect::<Vec<&str>>();
+
+    let columns_joined = columns_strs.join(", ");
+
+    let mut query = String::with_capacity(
+        "INSERT INTO ".len()
+            + table.len()
+            + 1 // '(' is a char
+            + columns_joined.len()
+            + ") ".len()
+            + "VALUES ".len()
+            + values_groups_string.len(),
+    );
+
+    query.push_str("INSERT INTO ");
+    query.push_str(table);
+    query.push('(');
+    query.push_str(&columns_joined);
+    query.push_str(") ");
+    query.push_str("VALUES ");
+    query.push_str(&values_groups_string);
+
+    query
+}
+
+pub fn get_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Feed> {
+    let s = conn.query_row(
+        "SELECT id, title, feed_link, link, feed_kind, refreshed_at, retry_after, entry_sort, paused, last_error, last_error_at, consecutive_failures, original_feed_link, moved_to FROM feeds WHERE id=?1",
+        params![feed_id],
+        |row| {
+            let feed_kind_str: String = row.get(4)?;
+            let feed_kind: FeedKind = FeedKind::from_str(&feed_kind_str)
+                .unwrap_or_else(|_| panic!("FeedKind must be Atom or RSS, got {}", feed_kind_str));
+
+            Ok(Feed {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                feed_link: row.get(2)?,
+                link: row.get(3)?,
+                feed_kind,
+                refreshed_at: row.get(5)?,
+                retry_after: row.get(6)?,
+                entry_sort: row.get::<_, Option<_>>(7)?.unwrap_or_default(),
+                paused: row.get(8)?,
+                health: FeedHealth {
+                    last_error: row.get(9)?,
+                    last_error_at: row.get(10)?,
+                    consecutive_failures: row.get(11)?,
+                },
+                original_feed_link: row.get(12)?,
+                moved_to: row.get(13)?,
+            })
+        },
+    )?;
+
+    Ok(s)
+}
+
+pub fn update_feed_refreshed_at(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET refreshed_at = ?2, retry_after = NULL WHERE id = ?1",
+        params![feed_id, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+fn update_feed_retry_after(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    retry_after: DateTime<Utc>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET retry_after = ?2 WHERE id = ?1",
+        params![feed_id, retry_after],
+    )?;
+
+    Ok(())
+}
+
+fn get_feed_cache_validators(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+) -> Result<CacheValidators> {
+    let cache_validators = conn.query_row(
+        "SELECT etag, last_modified FROM feeds WHERE id=?1",
+        params![feed_id],
+        |row| {
+            Ok(CacheValidators {
+                etag: row.get(0)?,
+                last_modified: row.get(1)?,
+            })
+        },
+    )?;
+
+    Ok(cache_validators)
+}
+
+fn update_feed_cache_validators(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    cache_validators: &CacheValidators,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET etag = ?2, last_modified = ?3 WHERE id = ?1",
+        params![
+            feed_id,
+            cache_validators.etag,
+            cache_validators.last_modified
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// moves the feed to where it has permanently moved, remembering where it was subscribed at
+pub fn update_feed_url(conn: &rusqlite::Connection, feed_id: FeedId, url: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds
+        SET original_feed_link = coalesce(original_feed_link, feed_link), feed_link = ?2, moved_to = NULL, updated_at = ?3
+        WHERE id = ?1",
+
+        params![feed_id, url, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// remembers where the feed has permanently moved until `update_feed_url` or `decline_feed_move`,
+/// unless it is where the feed was already not allowed to move
+pub fn set_feed_moved_to(conn: &rusqlite::Connection, feed_id: FeedId, url: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds
+        SET moved_to = ?2
+        WHERE id = ?1 AND (declined_moved_to IS NULL OR declined_moved_to != ?2)",
+        params![feed_id, url],
+    )?;
+
+    Ok(())
+}
+
+/// keeps the feed where it is, and does not ask about moving it there again
+pub fn decline_feed_move(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds
+        SET declined_moved_to = moved_to, moved_to = NULL
+        WHERE id = ?1",
+        params![feed_id],
+    )?;
+
+    Ok(())
+}
+
+/// points the feed at a new URL, keeping its entries.
+/// the old URL's cache validators and `retry_after` do not apply to the new one
+pub fn change_feed_url(conn: &rusqlite::Connection, feed_id: FeedId, url: &str) -> Result<()> {
+    let url = url.trim();
+    url::Url::parse(url).with_context(|| format!("{} is not a URL", url))?;
+
+    if let Some(other_feed) =
+        find_subscribed_feed(conn, url)?.filter(|other_feed| other_feed.feed_id != feed_id)
+    {
+        return Err(other_feed.into());
+    }
+
+    conn.execute(
+        "UPDATE feeds
+        SET feed_link = ?2, updated_at = ?3, etag = NULL, last_modified = NULL, retry_after = NULL, moved_to = NULL
+        WHERE id = ?1",
+        params![feed_id, url, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// a failed refresh, after its retries, counts as one failure.
+/// `duration` is how long the refresh took, retries included
+pub fn record_refresh_result(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    duration: std::time::Duration,
+    error: Option<&anyhow::Error>,
+) -> Result<()> {
+    let duration_ms = duration.as_millis() as i64;
+
+    match error {
+        Some(error) => conn.execute(
+            "UPDATE feeds
+            SET last_error = ?2, last_error_at = ?3, consecutive_failures = consecutive_failures + 1,
+              last_refresh_duration_ms = ?4
+            WHERE id = ?1",
+            params![feed_id, format!("{:#}", error), Utc::now(), duration_ms],
+        )?,
+        None => conn.execute(
+            "UPDATE feeds SET consecutive_failures = 0, last_refresh_duration_ms = ?2 WHERE id = ?1",
+            params![feed_id, duration_ms],
+        )?,
+    };
+
+    Ok(())
+}
+
+pub fn set_feed_paused(conn: &rusqlite::Connection, feed_id: FeedId, paused: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET paused = ?2 WHERE id = ?1",
+        params![feed_id, paused],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_feeds(conn: &rusqlite::Connection) -> Result<Vec<Feed>> {
+    get_sorted_feeds(conn, FeedSort::Alphabetical)
+}
+
+/// every feed, in `feed_sort` order. ties are broken alphabetically.
+pub fn get_sorted_feeds(conn: &rusqlite::Connection, feed_sort: FeedSort) -> Result<Vec<Feed>> {
+    let order_by = match feed_sort {
+        FeedSort::Alphabetical => "lower(title) ASC",
+        FeedSort::Updated => {
+            "(SELECT MAX(COALESCE(pub_date, inserted_at)) FROM entries WHERE feed_id = feeds.id) DESC, lower(title) ASC"
+        }
+        FeedSort::Unread => {
+            "(SELECT COUNT(*) FROM entries WHERE feed_id = feeds.id AND read_at IS NULL) DESC, lower(title) ASC"
+        }
+        // feeds that were never moved go last
+        FeedSort::Manual => "position IS NULL, position ASC, lower(title) ASC",
+    };
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT 
+          id, 
+          title, 
+          feed_link, 
+          link, 
+          feed_kind, 
+          refreshed_at, 
+          retry_after,
+          entry_sort,
+          paused,
+          last_error,
+          last_error_at,
+          consecutive_failures,
+          original_feed_link,
+          moved_to
+        FROM feeds ORDER BY {}",
+        order_by
+    ))?;
+    let mut feeds = vec![];
+    for feed in statement.query_map([], |row| {
+        Ok(Feed {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            feed_link: row.get(2)?,
+            link: row.get(3)?,
+            feed_kind: row.get(4)?,
+            refreshed_at: row.get(5)?,
+            retry_after: row.get(6)?,
+            entry_sort: row.get::<_, Option<_>>(7)?.unwrap_or_default(),
+            paused: row.get(8)?,
+            health: FeedHealth {
+                last_error: row.get(9)?,
+                last_error_at: row.get(10)?,
+                consecutive_failures: row.get(11)?,
+            },
+            original_feed_link: row.get(12)?,
+            moved_to: row.get(13)?,
+        })
+    })? {
+        feeds.push(feed?)
+    }
+
+    Ok(feeds)
+}
+
+/// how many entries a feed has, and how many of them are unread
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntryCounts {
+    pub unread: usize,
+    pub total: usize,
+}
+
+/// every feed's entry counts. feeds without entries are left out
+pub fn get_entry_counts(conn: &rusqlite::Connection) -> Result<HashMap<FeedId, EntryCounts>> {
+    let mut statement = conn.prepare(
+        "SELECT
+          feed_id,
+          SUM(CASE WHEN read_at IS NULL THEN 1 ELSE 0 END),
+          COUNT(*)
+        FROM entries GROUP BY feed_id",
+    )?;
+
+    let mut counts = HashMap::new();
+    for row in statement.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            EntryCounts {
+                unread: row.get(1)?,
+                total: row.get(2)?,
+            },
+        ))
+    })? {
+        let (feed_id, entry_counts) = row?;
+        counts.insert(feed_id, entry_counts);
+    }
+
+    Ok(counts)
+}
+
+/// a feed as it was last fetched, whether russ could read it or not
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawFeed {
+    pub body: String,
+    pub fetched_at: DateTime<Utc>,
+    /// how much room it takes in the database
+    pub compressed_len: usize,
+}
+
+/// the first byte of a stored raw feed, saying how the rest is compressed, so that can change
+const RAW_FEED_ZLIB: u8 = 1;
+
+fn update_raw_feed(conn: &rusqlite::Connection, feed_id: FeedId, body: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO raw_feeds (feed_id, body, fetched_at) VALUES (?1, ?2, ?3)",
+        params![feed_id, compress_raw_feed(body)?, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+fn compress_raw_feed(body: &str) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![RAW_FEED_ZLIB], Default::default());
+    encoder.write_all(body.as_bytes())?;
+
+    Ok(encoder.finish()?)
+}
+
+fn decompress_raw_feed(compressed: &[u8]) -> Result<String> {
+    use std::io::Read;
+
+    match compressed.split_first() {
+        Some((&RAW_FEED_ZLIB, zlib)) => {
+            let mut body = vec![];
+            flate2::read::ZlibDecoder::new(zlib)
+                .read_to_end(&mut body)
+                .context("The raw feed is corrupt")?;
+
+            Ok(String::from_utf8_lossy(&body).into_owned())
+        }
+        _ => Err(anyhow::anyhow!(
+            "The raw feed was kept in a format this version of russ does not know, refresh the feed to replace it"
+        )),
+    }
+}
+
+/// `None` if russ has not fetched the feed itself since it started keeping them,
+/// like when it is synced with a server
+pub fn get_raw_feed(conn: &rusqlite::Connection, feed_id: FeedId) -> Result<Option<RawFeed>> {
+    let raw_feed = conn
+        .query_row(
+            "SELECT body, fetched_at FROM raw_feeds WHERE feed_id = ?1",
+            [feed_id],
+            |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    raw_feed
+        .map(|(compressed, fetched_at)| {
+            Ok(RawFeed {
+                body: decompress_raw_feed(&compressed)?,
+                fetched_at,
+                compressed_len: compressed.len(),
+            })
+        })
+        .transpose()
+}
+
+/// numbers about one feed, for deciding whether to keep it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeedStats {
+    pub entry_counts: EntryCounts,
+    /// by when entries were published, or pulled if they do not say
+    pub oldest_entry_at: Option<DateTime<Utc>>,
+    pub newest_entry_at: Option<DateTime<Utc>>,
+    /// from its oldest entry until now, so feeds that went quiet count as quiet
+    pub entries_per_week: Option<f64>,
+    pub last_refresh_duration: Option<std::time::Duration>,
+}
+
+pub fn get_feed_stats(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    now: DateTime<Utc>,
+) -> Result<FeedStats> {
+    let (unread, total, oldest_entry_at, newest_entry_at) = conn.query_row(
+        "SELECT
+          COALESCE(SUM(CASE WHEN read_at IS NULL THEN 1 ELSE 0 END), 0),
+          COUNT(*),
+          MIN(COALESCE(pub_date, inserted_at)),
+          MAX(COALESCE(pub_date, inserted_at))
+        FROM entries WHERE feed_id = ?1",
+        params![feed_id],
+        |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get::<_, Option<DateTime<Utc>>>(2)?,
+                row.get::<_, Option<DateTime<Utc>>>(3)?,
+            ))
+        },
+    )?;
+
+    let last_refresh_duration_ms: Option<u64> = conn.query_row(
+        "SELECT last_refresh_duration_ms FROM feeds WHERE id = ?1",
+        params![feed_id],
+        |row| row.get(0),
+    )?;
+
+    // a feed younger than a week has had one week to post in, not less
+    let entries_per_week = oldest_entry_at.map(|oldest_entry_at| {
+        let weeks = (now - oldest_entry_at).num_seconds() as f64 / (7 * 24 * 60 * 60) as f64;
+        total as f64 / weeks.max(1.0)
+    });
+
+    Ok(FeedStats {
+        entry_counts: EntryCounts { unread, total },
+        oldest_entry_at,
+        newest_entry_at,
+        entries_per_week,
+        last_refresh_duration: last_refresh_duration_ms.map(std::time::Duration::from_millis),
+    })
+}
+
+pub fn set_feed_entry_sort(
+    conn: &rusqlite::Connection,
+    feed_id: FeedId,
+    entry_sort: EntrySort,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE feeds SET entry_sort = ?2 WHERE id = ?1",
+        params![feed_id, entry_sort],
+    )?;
+
+    Ok(())
+}
+
+/// stores the manual order of the feeds, first to last
+pub fn set_feed_positions(conn: &rusqlite::Connection, feed_ids: &[FeedId]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    {
+        let mut statement = tx.prepare("UPDATE feeds SET position = ?2 WHERE id = ?1")?;
+        for (position, feed_id) in feed_ids.iter().enumerate() {
+            statement.execute(params![feed_id, position])?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// every feed's tags, sorted by tag
+pub fn get_feed_tags(conn: &rusqlite::Connection) -> Result<HashMap<FeedId, Vec<String>>> {
+    let mut statement =
+        conn.prepare("SELECT feed_id, tag FROM feed_tags ORDER BY lower(tag) ASC")?;
+
+    let mut tags: HashMap<FeedId, Vec<String>> = HashMap::new();
+    for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (feed_id, tag) = row?;
+        tags.entry(feed_id).or_default().push(tag);
+    }
+
+    Ok(tags)
+}
+
+/// the notes of every entry that has one
+pub fn get_entry_notes(conn: &rusqlite::Connection) -> Result<HashMap<EntryId, String>> {
+    let mut statement = conn.prepare("SELECT entry_id, note FROM entry_notes")?;
+
+    let mut notes = HashMap::new();
+    for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (entry_id, note) = row?;
+        notes.insert(entry_id, note);
+    }
+
+    Ok(notes)
+}
+
+/// replaces an entry's note, or removes it if `note` is blank
+pub fn set_entry_note(conn: &rusqlite::Connection, entry_id: EntryId, note: &str) -> Result<()> {
+    let note = note.trim();
+
+    if note.is_empty() {
+        conn.execute(
+            "DELETE FROM entry_notes WHERE entry_id = ?1",
+            params![entry_id],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT INTO entry_notes (entry_id, note) VALUES (?1, ?2)
+            ON CONFLICT (entry_id) DO UPDATE SET note = ?2, updated_at = CURRENT_TIMESTAMP",
+            params![entry_id, note],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// replaces a feed's tags
+pub fn set_feed_tags(conn: &rusqlite::Connection, feed_id: FeedId, tags: &[String]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    tx.execute("DELETE FROM feed_tags WHERE feed_id = ?1", params![feed_id])?;
+
+    for tag in tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO feed_tags (feed_id, tag) VALUES (?1, ?2)",
+            params![feed_id, tag],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// the feeds refreshing all feeds refreshes, which paused feeds are not
+pub fn get_feed_ids(conn: &rusqlite::Connection) -> Result<Vec<FeedId>> {
+    let mut statement =
+        conn.prepare("SELECT id FROM feeds WHERE NOT paused ORDER BY lower(title) ASC")?;
+    let mut ids = vec![];
+    for id in statement.query_map([], |row| row.get(0))? {
+        ids.push(id?)
+    }
+
+    Ok(ids)
+}
+
+pub fn get_entry_meta(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryMeta> {
+    let result = conn.query_row(
+        &format!("SELECT {} FROM entries WHERE id=?1", ENTRY_META_COLUMNS),
+        params![entry_id],
+        EntryMeta::from_row,
+    )?;
+
+    Ok(result)
+}
+
+pub fn get_entry_content(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<EntryContent> {
+    let result = conn.query_row(
+        "SELECT content, description, full_content, base_url FROM entries WHERE id=?1",
+        params![entry_id],
+        |row| {
+            Ok(EntryContent {
+                content: row.get(0)?,
+                description: row.get(1)?,
+                full_content: row.get(2)?,
+                base_url: row.get(3)?,
+            })
+        },
+    )?;
+
+    Ok(result)
+}
+
+/// keeps the article fetched from an entry's web page, to be read instead of the feed's content,
+/// and returns the entry's new word count
+pub fn set_entry_full_content(
+    conn: &rusqlite::Connection,
+    entry_id: EntryId,
+    full_content: &str,
+) -> Result<usize> {
+    let word_count = crate::html::count_words(full_content);
+
+    conn.execute(
+        "UPDATE entries SET full_content = ?2, word_count = ?3 WHERE id = ?1",
+        params![entry_id, full_content, word_count],
+    )?;
+
+    Ok(word_count)
+}
+
+/// the line an entry was left at, or 0 if it was read to the end or never opened
+pub fn get_entry_scroll_position(conn: &rusqlite::Connection, entry_id: EntryId) -> Result<u16> {
+    Ok(conn.query_row(
+        "SELECT scroll_position FROM entries WHERE id = ?1",
+        params![entry_id],
+        |row| row.get(0),
+    )?)
+}
+
+pub fn set_entry_scroll_position(
+    conn: &rusqlite::Connection,
+    entry_id: EntryId,
+    scroll_position: u16,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE entries SET scroll_position = ?2 WHERE id = ?1",
+        params![entry_id, scroll_position],
+    )?;
+
+    Ok(())
+}
+
+/// puts the entry at the top of the reading history
+pub fn set_entry_opened_at(
+    conn: &rusqlite::Connection,
+    entry_id: EntryId,
+    opened_at: DateTime<Utc>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE entries SET opened_at = ?2 WHERE id = ?1",
+        params![entry_id, opened_at],
+    )?;
+
+    Ok(())
+}
+
+/// any unread entry in `date_range` but `except`, or `None` if there is none
+pub fn get_random_unread_entry_id(
+    conn: &rusqlite::Connection,
+    date_range: &DateRange,
+    except: Option<EntryId>,
+) -> Result<Option<EntryId>> {
+    Ok(conn
+        .query_row(
+            "SELECT id FROM entries
+            WHERE read_at IS NULL
+            AND id IS NOT ?1
+            AND (?2 IS NULL OR coalesce(pub_date, inserted_at) >= ?2)
+            AND (?3 IS NULL OR coalesce(pub_date, inserted_at) < ?3)
+            ORDER BY random()
+            LIMIT 1",
+            params![except, date_range.start, date_range.end],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+pub fn get_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    date_range: &DateRange,
+    feed_id: FeedId,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        date_range,
+        EntriesOf::Feed(feed_id),
+        false,
+        None,
+    )
+}
+
+/// with `collapse_duplicates`, an article that appears in more than one feed is only there once,
+/// as the first of its duplicates russ saw
+pub fn get_virtual_feed_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    date_range: &DateRange,
+    virtual_feed: VirtualFeed,
+    collapse_duplicates: bool,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        date_range,
+        EntriesOf::Virtual(virtual_feed),
+        collapse_duplicates,
+        None,
+    )
+}
+
+/// the newest `limit` entries of every feed, or of the feeds tagged `tag`, for publishing
+pub fn get_published_entries_metas(
+    conn: &rusqlite::Connection,
+    tag: Option<&str>,
+    limit: usize,
+    collapse_duplicates: bool,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        &ReadMode::All,
+        EntrySort::Newest,
+        &DateRange::default(),
+        match tag {
+            Some(tag) => EntriesOf::Tagged(tag),
+            None => EntriesOf::Virtual(VirtualFeed::AllEntries),
+        },
+        collapse_duplicates,
+        Some(limit),
+    )
+}
+
+/// the entries of every feed that `query` matches, for a smart feed
+pub fn get_smart_feed_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    date_range: &DateRange,
+    query: &Query,
+) -> Result<Vec<EntryMeta>> {
+    query_entries_metas(
+        conn,
+        read_mode,
+        entry_sort,
+        date_range,
+        EntriesOf::Query(query),
+        false,
+        None,
+    )
+}
+
+/// whose entries `query_entries_metas` gets
+#[derive(Clone, Copy)]
+enum EntriesOf<'a> {
+    Feed(FeedId),
+    /// every feed's, narrowed down to the virtual feed's
+    Virtual(VirtualFeed),
+    /// every feed's that the query matches
+    Query(&'a Query),
+    /// every feed's with the tag
+    Tagged(&'a str),
+}
+
+fn query_entries_metas(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    entry_sort: EntrySort,
+    date_range: &DateRange,
+    entries_of: EntriesOf,
+    collapse_duplicates: bool,
+    limit: Option<usize>,
+) -> Result<Vec<EntryMeta>> {
+    let is_history = matches!(entries_of, EntriesOf::Virtual(VirtualFeed::RecentlyRead));
+
+    // the reading history has what was opened, whether or not it was marked read since
+    let read_at_predicate = match read_mode {
+        _ if is_history => "\n",
+        ReadMode::ShowUnread => "\nAND read_at IS NULL",
+        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
+        ReadMode::All => "\n",
+    };
+
+    // we get weird pubDate formats from feeds,
+    // so sort by inserted at as this as a stable order at least
+    let mut query = format!("SELECT {} FROM entries WHERE 1=1", ENTRY_META_COLUMNS);
+
+    let mut query_values = vec![];
+    let mut params: Vec<&dyn rusqlite::ToSql> = vec![];
+
+    match &entries_of {
+        EntriesOf::Feed(feed_id) => {
+            params.push(feed_id);
+            query.push_str(&format!("\nAND feed_id=?{}", params.len()));
+        }
+        EntriesOf::Virtual(VirtualFeed::Starred) => query.push_str("\nAND starred"),
+        EntriesOf::Virtual(VirtualFeed::RecentlyRead) => {
+            query.push_str("\nAND opened_at IS NOT NULL")
+        }
+        EntriesOf::Virtual(VirtualFeed::AllEntries | VirtualFeed::Smart(_)) => (),
+        EntriesOf::Query(entries_query) => {
+            let condition = entries_query.to_sql(params.len(), &mut query_values);
+            params.extend(
+                query_values
+                    .iter()
+                    .map(|value| value as &dyn rusqlite::ToSql),
+            );
+            query.push_str("\nAND ");
+            query.push_str(&condition);
+        }
+        EntriesOf::Tagged(tag) => {
+            params.push(tag);
+            query.push_str(&format!(
+                "\nAND feed_id IN (SELECT feed_id FROM feed_tags WHERE tag = ?{})",
+                params.len()
+            ));
+        }
+    }
+
+    if collapse_duplicates {
+        query.push_str(
+            "\nAND NOT EXISTS (
+                SELECT 1 FROM entries AS original
+                WHERE original.duplicate_key = entries.duplicate_key
+                AND original.id < entries.id
+            )",
+        );
+    }
+
+    if let Some(start) = &date_range.start {
+        params.push(start);
+        query.push_str(&format!(
+            "\nAND coalesce(pub_date, inserted_at) >= ?{}",
+            params.len()
+        ));
+    }
+
+    if let Some(end) = &date_range.end {
+        params.push(end);
+        query.push_str(&format!(
+            "\nAND coalesce(pub_date, inserted_at) < ?{}",
+            params.len()
+        ));
+    }
+
+    query.push_str(read_at_predicate);
+
+    // entries from different feeds are merged by whichever date they have
+    let order_by = match entry_sort {
+        _ if is_history => "opened_at DESC",
+        EntrySort::Newest if matches!(entries_of, EntriesOf::Feed(_)) => {
+            "pub_date DESC, inserted_at DESC"
+        }
+        EntrySort::Newest => "coalesce(pub_date, inserted_at) DESC, inserted_at DESC",
+        EntrySort::Oldest => "coalesce(pub_date, inserted_at) ASC, inserted_at ASC",
+        EntrySort::Title => "lower(title) ASC, inserted_at DESC",
+        EntrySort::Inserted => "inserted_at DESC, id DESC",
+    };
+    query.push_str("\nORDER BY ");
+    query.push_str(order_by);
+
+    if let Some(limit) = limit {
+        query.push_str(&format!("\nLIMIT {}", limit));
+    }
+
+    let mut statement = conn.prepare(&query)?;
+    let mut entries = vec![];
+    for entry in statement.query_map(params.as_slice(), EntryMeta::from_row)? {
+        entries.push(entry?)
+    }
+
+    Ok(entries)
+}
+
+/// searches the title, description, and content of every entry,
+/// best matches first
+pub fn search_entries(conn: &rusqlite::Connection, input: &str) -> Result<Vec<EntryMeta>> {
+    let query = fts_query(input);
+
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT {}
+        FROM entries_fts
+        JOIN entries ON entries.id = entries_fts.rowid
+        WHERE entries_fts MATCH ?1
+        ORDER BY entries_fts.rank
+        LIMIT 200",
+        ENTRY_META_COLUMNS
+    ))?;
+
+    let mut entries = vec![];
+    for entry in statement.query_map(params![query], EntryMeta::from_row)? {
+        entries.push(entry?)
+    }
+
+    Ok(entries)
+}
+
+/// a search kept under a name, to run again later
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedSearch {
+    pub name: String,
+    /// as it was typed
+    pub query: String,
+}
+
+/// by name
+pub fn get_saved_searches(conn: &rusqlite::Connection) -> Result<Vec<SavedSearch>> {
+    let mut statement =
+        conn.prepare("SELECT name, query FROM saved_searches ORDER BY lower(name) ASC")?;
+
+    let saved_searches = statement
+        .query_map([], |row| {
+            Ok(SavedSearch {
+                name: row.get(0)?,
+                query: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(saved_searches)
+}
+
+/// saves the search under `name`, replacing the one saved under it before, if any
+pub fn save_search(conn: &rusqlite::Connection, name: &str, query: &str) -> Result<()> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("A saved search needs a name"));
+    }
+
+    conn.execute(
+        "INSERT INTO saved_searches (name, query) VALUES (?1, ?2)
+        ON CONFLICT (name) DO UPDATE SET query = ?2, updated_at = CURRENT_TIMESTAMP",
+        params![name, query.trim()],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_saved_search(conn: &rusqlite::Connection, name: &str) -> Result<()> {
+    conn.execute("DELETE FROM saved_searches WHERE name = ?1", params![name])?;
+    Ok(())
+}
+
+/// turns what the user typed into an FTS5 query that cannot be a syntax error:
+/// every word is quoted, so `c++` or `AND` are searched for literally,
+/// and the last word matches as a prefix so results show up while typing
+fn fts_query(input: &str) -> String {
+    let words = input.split_whitespace().collect::<Vec<_>>();
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let quoted = format!("\"{}\"", word.replace('"', "\"\""));
+            if i == words.len() - 1 {
+                quoted + "*"
+            } else {
+                quoted
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn get_entries_links(
+    conn: &rusqlite::Connection,
+    read_mode: &ReadMode,
+    feed_id: FeedId,
+) -> Result<Vec<Option<String>>> {
+    let read_at_predicate = match read_mode {
+        ReadMode::ShowUnread => "\nAND read_at IS NULL",
+        ReadMode::ShowRead => "\nAND read_at IS NOT NULL",
+        ReadMode::All => "\n",
+    };
+
+    // we get weird pubDate formats from feeds,
+    // so sort by inserted at as this as a stable order at least
+    let mut query = "SELECT link FROM entries WHERE feed_id=?1".to_string();
+
+    query.push_str(read_at_predicate);
+    query.push_str("\nORDER BY pub_date DESC, inserted_at DESC");
+
+    let mut links = vec![];
+    let mut statement = conn.prepare(&query)?;
+
+    for link in statement.query_map(params![feed_id], |row| row.get(0))? {
+        links.push(link?);
+    }
+
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const ZCT: &str = "https://zeroclarkthirty.com/feed";
+
+    #[test]
+    fn feeds_in_other_encodings_are_parsed_once_decoded() {
+        let rss = "<?xml version='1.0' encoding='Shift_JIS'?>\
+                   <rss version=\"2.0\"><channel><title>\u{65e5}\u{672c}</title><link>https://example.com/</link>\
+                   <description>d</description><item><title>\u{201c}caf\u{e9}\u{201d}</title></item></channel></rss>";
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode(rss);
+
+        let feed = FeedAndEntries::from_str(&crate::http::decode_text(&shift_jis, None)).unwrap();
+        assert_eq!(feed.feed.title.as_deref(), Some("\u{65e5}\u{672c}"));
+        assert_eq!(
+            feed.entries[0].title.as_deref(),
+            Some("\u{201c}caf\u{e9}\u{201d}")
+        );
+
+        let atom = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\
+                     <feed xmlns=\"http://www.w3.org/2005/Atom\"><title>caf\xe9</title>\
+                     <entry><title>cr\xe8me</title></entry></feed>";
+
+        let feed =
+            FeedAndEntries::from_str(&crate::http::decode_text(atom, Some("text/xml"))).unwrap();
+        assert_eq!(feed.feed.title.as_deref(), Some("caf\u{e9}"));
+        assert_eq!(feed.entries[0].title.as_deref(), Some("cr\u{e8}me"));
+    }
+
+    #[test]
+    fn it_fetches() {
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let feed_and_entries = fetch_feed(&http_client, ZCT).unwrap();
+        assert!(!feed_and_entries.entries.is_empty())
+    }
+
+    #[test]
+    fn it_subscribes_to_a_feed() {
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        subscribe_to_feed(&http_client, &conn, ZCT).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .unwrap();
+
+        assert!(count > 50)
+    }
+
+    #[test]
+    fn refresh_feed_does_not_add_any_items_if_there_are_no_new_items() {
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        subscribe_to_feed(&http_client, &conn, ZCT).unwrap();
+        let feed_id = 1;
+        let old_entries = get_entries_metas(
+            &conn,
+            &ReadMode::ShowUnread,
+            EntrySort::Newest,
+            &DateRange::default(),
+            feed_id,
+        )
+        .unwrap();
+        refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        let e = get_entry_meta(&conn, 1).unwrap();
+        e.mark_as_read(&conn).unwrap();
+        let new_entries = get_entries_metas(
+            &conn,
+            &ReadMode::ShowUnread,
+            EntrySort::Newest,
+            &DateRange::default(),
+            feed_id,
+        )
+        .unwrap();
+
+        assert_eq!(new_entries.len(), old_entries.len() - 1);
+    }
+
+    #[test]
+    fn fts_query_quotes_every_word() {
+        assert_eq!(fts_query("  "), "");
+        assert_eq!(fts_query("c++ AND"), "\"c++\" \"AND\"*");
+        assert_eq!(fts_query("say \"hi\""), "\"say\" \"\"\"hi\"\"\"*");
+    }
+
+    #[test]
+    fn search_entries_finds_entries_inserted_before_and_after_the_index() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        // the schema as it was before migrations existed
+        migrate(&conn, &MIGRATIONS[..1]).unwrap();
+
+        let entry = |title: &str, content: &str| Entry {
+            content: Some(content.to_string()),
+            link: Some(format!("https://example.com/{}", title)),
+            ..Entry::for_test(title)
+        };
+
+        conn.execute(
+            "INSERT INTO entries (feed_id, title, content, link) VALUES (1, 'rust', 'ownership and borrowing', 'https://example.com/rust')",
+            [],
+        )
+        .unwrap();
+
+        // simulate a database from before the search index and migrations existed
+        conn.execute_batch("DROP TABLE entries_fts; PRAGMA user_version = 0")
+            .unwrap();
+        initialize_db(&conn).unwrap();
+
+        add_entries_to_feed(&conn, 1, &[entry("zig", "comptime and borrowing")]).unwrap();
+
+        assert_eq!(search_entries(&conn, "owner").unwrap().len(), 1);
+        assert_eq!(search_entries(&conn, "borrowing").unwrap().len(), 2);
+        assert_eq!(search_entries(&conn, "haskell").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn searches_are_saved_by_name() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        save_search(&conn, "rust", "rust async").unwrap();
+        save_search(&conn, " Borrowing ", "borrow").unwrap();
+        save_search(&conn, "rust", "rust ownership").unwrap();
+        assert!(save_search(&conn, "  ", "zig").is_err());
+
+        let saved_search = |name: &str, query: &str| SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+        };
+        assert_eq!(
+            get_saved_searches(&conn).unwrap(),
+            vec![
+                saved_search("Borrowing", "borrow"),
+                saved_search("rust", "rust ownership")
+            ]
+        );
+
+        delete_saved_search(&conn, "rust").unwrap();
+        assert_eq!(
+            get_saved_searches(&conn).unwrap(),
+            vec![saved_search("Borrowing", "borrow")]
+        );
+    }
+
+    #[test]
+    fn migrations_run_once_in_order() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+
+        let migrations: &[Migration] = &[
+            |conn| Ok(conn.execute_batch("CREATE TABLE t (a TEXT)")?),
+            |conn| Ok(conn.execute_batch("ALTER TABLE t ADD COLUMN b TEXT")?),
+        ];
+
+        migrate(&conn, &migrations[..1]).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+
+        migrate(&conn, migrations).unwrap();
+        migrate(&conn, migrations).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 2);
+
+        // a failed migration leaves the database as it was
+        let failing: &[Migration] = &[migrations[0], migrations[1], |conn| {
+            conn.execute_batch("ALTER TABLE t ADD COLUMN c TEXT")?;
+            Err(anyhow::anyhow!("oops"))
+        }];
+        assert!(migrate(&conn, failing).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), 2);
+        assert!(conn.prepare("SELECT c FROM t").is_err());
+
+        // a database from a newer russ is not touched
+        assert!(migrate(&conn, &migrations[..1]).is_err());
+    }
+
+    #[test]
+    fn all_entries_merges_every_feed_newest_first() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        add_entries_to_feed(&conn, 1, &[Entry::for_test_at("a", "2021-01-01T00:00:00Z")]).unwrap();
+        add_entries_to_feed(&conn, 2, &[Entry::for_test_at("b", "2021-03-01T00:00:00Z")]).unwrap();
+        add_entries_to_feed(&conn, 1, &[Entry::for_test_at("c", "2021-02-01T00:00:00Z")]).unwrap();
+
+        let titles = get_virtual_feed_entries_metas(
+            &conn,
+            &ReadMode::All,
+            EntrySort::Newest,
+            &DateRange::default(),
+            VirtualFeed::AllEntries,
+            false,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.title.unwrap())
+        .collect::<Vec<_>>();
+
+        assert_eq!(titles, vec!["b", "c", "a"]);
+        assert_eq!(
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Newest,
+                &DateRange::default(),
+                1
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn published_entries_are_the_newest_of_the_tagged_feeds() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        add_entries_to_feed(
+            &conn,
+            1,
+            &[
+                Entry::for_test_at("a", "2021-01-01T00:00:00Z"),
+                Entry::for_test_at("c", "2021-03-01T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+        add_entries_to_feed(&conn, 2, &[Entry::for_test_at("b", "2021-02-01T00:00:00Z")]).unwrap();
+        set_feed_tags(&conn, 1, &["rust".to_string()]).unwrap();
+
+        let titles = |tag, limit| {
+            get_published_entries_metas(&conn, tag, limit, false)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.title.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(None, 2), vec!["c", "b"]);
+        assert_eq!(titles(Some("rust"), 10), vec!["c", "a"]);
+        assert!(titles(Some("go"), 10).is_empty());
+    }
+
+    #[test]
+    fn it_counts_unread_and_total_entries_per_feed() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let entry = |title: &str| Entry {
+            link: Some(format!("https://example.com/{}", title)),
+            ..Entry::for_test(title)
+        };
+
+        add_entries_to_feed(&conn, 1, &[entry("a"), entry("b"), entry("c")]).unwrap();
+        add_entries_to_feed(&conn, 2, &[entry("d")]).unwrap();
+
+        let a = get_entries_metas(
+            &conn,
+            &ReadMode::All,
+            EntrySort::Newest,
+            &DateRange::default(),
+            1,
+        )
+        .unwrap()
+        .into_iter()
+        .find(|entry| entry.title.as_deref() == Some("a"))
+        .unwrap();
+        a.toggle_read(&conn).unwrap();
+
+        let counts = get_entry_counts(&conn).unwrap();
+
+        assert_eq!(
+            counts[&1],
+            EntryCounts {
+                unread: 2,
+                total: 3
+            }
+        );
+        assert_eq!(
+            counts[&2],
+            EntryCounts {
+                unread: 1,
+                total: 1
+            }
+        );
+        assert!(!counts.contains_key(&3));
+    }
+
+    #[test]
+    fn duplicates_across_feeds_are_collapsed_and_read_together() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let entry = |title: &str, link: Option<&str>| Entry {
+            content: Some("<p>Hello</p>".to_string()),
+            link: link.map(|link| link.to_string()),
+            ..Entry::for_test(title)
+        };
+
+        add_entries_to_feed(
+            &conn,
+            1,
+            &[
+                entry("a", Some("https://example.com/a")),
+                entry("no link", None),
+                entry("b", Some("https://example.com/b")),
+            ],
+        )
+        .unwrap();
+        add_entries_to_feed(
+            &conn,
+            2,
+            &[
+                entry("a again", Some("http://example.com/a/?utm_source=rss")),
+                entry("no link", None),
+                entry("other", None),
+            ],
+        )
+        .unwrap();
+
+        let titles = |collapse_duplicates: bool| {
+            get_virtual_feed_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Inserted,
+                &DateRange::default(),
+                VirtualFeed::AllEntries,
+                collapse_duplicates,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.title.unwrap())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(false).len(), 6);
+        assert_eq!(titles(true), vec!["other", "b", "no link", "a"]);
+
+        let entry_id = |feed_id: FeedId, title: &str| -> EntryId {
+            conn.query_row(
+                "SELECT id FROM entries WHERE feed_id = ?1 AND title = ?2",
+                params![feed_id, title],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            get_unread_duplicate_entry_ids(&conn, &[entry_id(1, "a"), entry_id(1, "b")]).unwrap(),
+            vec![entry_id(2, "a again")]
+        );
+
+        set_entries_read(&conn, &[entry_id(1, "no link")], true).unwrap();
+        assert_eq!(mark_read_duplicates_read(&conn, 2).unwrap(), 1);
+        assert!(
+            get_unread_duplicate_entry_ids(&conn, &[entry_id(1, "no link")])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn smart_feeds_have_the_entries_their_query_matches() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = create_feed(
+            &conn,
+            &Feed {
+                title: Some("This Week in Rust".to_string()),
+                ..Feed::for_test(-1)
+            },
+        )
+        .unwrap();
+
+        let entry = |title: &str, categories: &[&str]| Entry {
+            description: Some(format!("<p>About {}</p>", title)),
+            link: Some(format!("https://example.com/{}", title)),
+            categories: categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect(),
+            ..Entry::for_test(title)
+        };
+
+        add_entries_to_feed(
+            &conn,
+            feed_id,
+            &[
+                entry("Rust 2024", &["Rust"]),
+                entry("Async rust", &["Rust", "async"]),
+                entry("Zig", &["Zig"]),
+            ],
+        )
+        .unwrap();
+
+        let async_rust = conn
+            .query_row(
+                "SELECT id FROM entries WHERE title = 'Async rust'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        set_entries_read(&conn, &[async_rust], true).unwrap();
+
+        let titles = |query: &str| {
+            get_smart_feed_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Title,
+                &DateRange::default(),
+                &Query::from_str(query).unwrap(),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.title.unwrap())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(r#"title ~ "RUST" AND unread"#), vec!["Rust 2024"]);
+        assert_eq!(
+            titles("category = async OR content ~ zig"),
+            vec!["Async rust", "Zig"]
+        );
+        assert_eq!(
+            titles(r#"feed = "this week in rust" AND NOT category = rust"#),
+            vec!["Zig"]
+        );
+        assert!(titles("feed ~ python").is_empty());
+    }
+
+    #[test]
+    fn it_sorts_feeds() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed = |title: &str| Feed {
+            title: Some(title.to_string()),
+            ..Feed::for_test(-1)
+        };
+
+        let a = create_feed(&conn, &feed("a")).unwrap();
+        let b = create_feed(&conn, &feed("B")).unwrap();
+        let c = create_feed(&conn, &feed("c")).unwrap();
+
+        add_entries_to_feed(
+            &conn,
+            a,
+            &[Entry::for_test_at("a1", "2021-01-01T00:00:00Z")],
+        )
+        .unwrap();
+        add_entries_to_feed(
+            &conn,
+            c,
+            &[
+                Entry::for_test_at("c1", "2021-02-01T00:00:00Z"),
+                Entry::for_test_at("c2", "2021-02-02T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+
+        let sorted_ids = |feed_sort| {
+            get_sorted_feeds(&conn, feed_sort)
+                .unwrap()
+                .into_iter()
+                .map(|feed| feed.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted_ids(FeedSort::Alphabetical), vec![a, b, c]);
+        assert_eq!(sorted_ids(FeedSort::Updated), vec![c, a, b]);
+        assert_eq!(sorted_ids(FeedSort::Unread), vec![c, a, b]);
+        assert_eq!(sorted_ids(FeedSort::Manual), vec![a, b, c]);
+
+        set_feed_positions(&conn, &[b, c]).unwrap();
+        assert_eq!(sorted_ids(FeedSort::Manual), vec![b, c, a]);
+    }
+
+    #[test]
+    fn entry_sorts_are_remembered_per_feed() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed = Feed {
+            title: Some("feed".to_string()),
+            ..Feed::for_test(-1)
+        };
+
+        let feed_id = create_feed(&conn, &feed).unwrap();
+        add_entries_to_feed(
+            &conn,
+            feed_id,
+            &[
+                Entry::for_test_at("b", "2021-01-01T00:00:00Z"),
+                Entry::for_test_at("c", "2021-03-01T00:00:00Z"),
+                Entry::for_test_at("a", "2021-02-01T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+
+        let sorted_titles = |entry_sort| {
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                entry_sort,
+                &DateRange::default(),
+                feed_id,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.title.unwrap())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted_titles(EntrySort::Newest), vec!["c", "a", "b"]);
+        assert_eq!(sorted_titles(EntrySort::Oldest), vec!["b", "a", "c"]);
+        assert_eq!(sorted_titles(EntrySort::Title), vec!["a", "b", "c"]);
+
+        assert_eq!(
+            get_feed(&conn, feed_id).unwrap().entry_sort,
+            EntrySort::Newest
+        );
+        set_feed_entry_sort(&conn, feed_id, EntrySort::Title).unwrap();
+        assert_eq!(
+            get_feed(&conn, feed_id).unwrap().entry_sort,
+            EntrySort::Title
+        );
+    }
+
+    #[test]
+    fn date_ranges_narrow_the_entries() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        add_entries_to_feed(
+            &conn,
+            1,
+            &[
+                Entry::for_test_at("january", "2021-01-31T23:59:59Z"),
+                Entry::for_test_at("february", "2021-02-14T12:00:00Z"),
+                Entry::for_test_at("march", "2021-03-01T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+
+        let titles = |date_range| {
+            get_entries_metas(&conn, &ReadMode::All, EntrySort::Oldest, &date_range, 1)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.title.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let february = DateRange {
+            start: parse_datetime("2021-02-01T00:00:00Z"),
+            end: parse_datetime("2021-03-01T00:00:00Z"),
+        };
+        let since_february = DateRange {
+            end: None,
+            ..february
+        };
+
+        assert_eq!(titles(february), vec!["february"]);
+        assert_eq!(titles(since_february), vec!["february", "march"]);
+        assert_eq!(titles(DateRange::default()).len(), 3);
+    }
+
+    #[test]
+    fn pruning_keeps_unread_and_starred_entries() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        add_entries_to_feed(
+            &conn,
+            1,
+            &[
+                Entry::for_test_at("a", "2021-01-01T00:00:00Z"),
+                Entry::for_test_at("b", "2021-02-01T00:00:00Z"),
+                Entry::for_test_at("c", "2021-03-01T00:00:00Z"),
+                Entry::for_test_at("d", "2021-04-01T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+
+        let entries = || {
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Oldest,
+                &DateRange::default(),
+                1,
+            )
+            .unwrap()
+        };
+
+        for entry in entries()
+            .iter()
+            .filter(|entry| entry.title.as_deref() != Some("d"))
+        {
+            entry.toggle_read(&conn).unwrap();
+        }
+        entries()[1].toggle_starred(&conn).unwrap();
+
+        let now = parse_datetime("2021-04-15T00:00:00Z").unwrap();
+        let titles = || {
+            entries()
+                .into_iter()
+                .map(|entry| entry.title.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let keep_two_months = RetentionPolicy {
+            max_entries_per_feed: None,
+            max_age: Some(chrono::Duration::days(60)),
+        };
+        assert_eq!(
+            prune_entries(&conn, &keep_two_months, None, now).unwrap(),
+            1
+        );
+        assert_eq!(titles(), vec!["b", "c", "d"]);
+
+        let keep_one = RetentionPolicy {
+            max_entries_per_feed: Some(1),
+            max_age: None,
+        };
+        assert_eq!(prune_entries(&conn, &keep_one, Some(1), now).unwrap(), 1);
+        assert_eq!(titles(), vec!["b", "d"]);
+
+        assert_eq!(
+            get_pruned_entries_links(&conn, 1).unwrap(),
+            ["https://example.com/a", "https://example.com/c"]
+                .iter()
+                .map(|link| link.to_string())
+                .collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn bulk_changes_apply_to_every_entry_given() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let entries = ["a", "b", "c"]
+            .iter()
+            .map(|title| Entry {
+                link: Some(format!("https://example.com/{}", title)),
+                ..Entry::for_test(title)
+            })
+            .collect::<Vec<_>>();
+        add_entries_to_feed(&conn, 1, &entries).unwrap();
+
+        let metas = || {
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Oldest,
+                &DateRange::default(),
+                1,
+            )
+            .unwrap()
+        };
+        let ids = metas().iter().map(|entry| entry.id).collect::<Vec<_>>();
+
+        set_entries_read(&conn, &ids[..2], true).unwrap();
+        set_entries_starred(&conn, &ids[1..], true).unwrap();
+        assert_eq!(
+            metas()
+                .iter()
+                .map(|entry| (entry.read_at.is_some(), entry.starred))
+                .collect::<Vec<_>>(),
+            vec![(true, false), (true, true), (false, true)]
+        );
+
+        let saved_states = get_saved_entry_states(&conn, &ids).unwrap();
+        set_entries_read(&conn, &ids, false).unwrap();
+        restore_entry_states(&conn, &saved_states).unwrap();
+        assert_eq!(
+            metas()
+                .iter()
+                .map(|entry| entry.read_at.is_some())
+                .collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+
+        let deleted_entries = delete_entries(&conn, &ids[..2]).unwrap();
+        assert_eq!(deleted_entries.len(), 2);
+        assert_eq!(metas().len(), 1);
+        assert_eq!(get_pruned_entries_links(&conn, 1).unwrap().len(), 2);
+
+        restore_entries(&conn, &deleted_entries).unwrap();
+        assert_eq!(
+            metas()
+                .iter()
+                .map(|entry| (entry.id, entry.starred))
+                .collect::<Vec<_>>(),
+            vec![(ids[0], false), (ids[1], true), (ids[2], true)]
+        );
+        assert!(get_pruned_entries_links(&conn, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleted_feeds_are_restored_with_their_entries_and_tags() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (7, 'a feed', 'https://example.com/feed', 'RSS');
+            INSERT INTO entries (feed_id, title, link, starred) VALUES (7, 'a', 'https://example.com/a', 1);
+            INSERT INTO entries (feed_id, title, link) VALUES (7, 'b', 'https://example.com/b');
+            INSERT INTO pruned_entries (feed_id, link) VALUES (7, 'https://example.com/c');",
+        )
+        .unwrap();
+        set_feed_tags(&conn, 7, &["news".to_string()]).unwrap();
+        set_entry_note(&conn, 1, "why").unwrap();
+
+        let counts = || {
+            [
+                "feeds",
+                "entries",
+                "pruned_entries",
+                "feed_tags",
+                "entry_notes",
+            ]
+            .iter()
+            .map(|table| {
+                conn.query_row(&format!("SELECT count(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+            })
+            .collect::<Vec<i64>>()
+        };
+
+        let deleted_feed = delete_feed(&conn, 7).unwrap();
+        assert_eq!(counts(), vec![0, 0, 0, 0, 0]);
+
+        restore_feed(&conn, &deleted_feed).unwrap();
+        assert_eq!(counts(), vec![1, 2, 1, 1, 1]);
+        assert_eq!(get_feed(&conn, 7).unwrap().title.as_deref(), Some("a feed"));
+        assert!(search_entries(&conn, "a").unwrap()[0].starred);
+    }
+
+    #[test]
+    fn entry_notes_outlive_pruning_and_come_back_with_their_entries() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO entries (id, feed_id, title, link, read_at) VALUES (1, 7, 'a', 'https://example.com/a', CURRENT_TIMESTAMP);
+            INSERT INTO entries (id, feed_id, title, link, read_at) VALUES (2, 7, 'b', 'https://example.com/b', CURRENT_TIMESTAMP);",
+        )
+        .unwrap();
+
+        set_entry_note(&conn, 1, "first").unwrap();
+        set_entry_note(&conn, 1, " why I kept this ").unwrap();
+        set_entry_note(&conn, 2, "gone soon").unwrap();
+        set_entry_note(&conn, 2, "  ").unwrap();
+
+        let notes = get_entry_notes(&conn).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[&1], "why I kept this");
+
+        let keep_none = RetentionPolicy {
+            max_entries_per_feed: Some(0),
+            max_age: None,
+        };
+        assert_eq!(
+            prune_entries(&conn, &keep_none, None, Utc::now()).unwrap(),
+            1
+        );
+
+        let deleted_entries = delete_entries(&conn, &[1]).unwrap();
+        assert!(get_entry_notes(&conn).unwrap().is_empty());
+
+        restore_entries(&conn, &deleted_entries).unwrap();
+        assert_eq!(get_entry_notes(&conn).unwrap(), notes);
+    }
+
+    #[test]
+    fn changing_a_feeds_url_keeps_its_entries_and_forgets_its_validators() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind, etag) VALUES (7, 'a feed', 'https://example.com/rss', 'RSS', 'abc');
+            INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (8, 'another', 'https://example.com/other', 'RSS');
+            INSERT INTO entries (feed_id, title, link, read_at) VALUES (7, 'a', 'https://example.com/a', CURRENT_TIMESTAMP);",
+        )
+        .unwrap();
+
+        assert!(change_feed_url(&conn, 7, "not a url").is_err());
+        assert!(change_feed_url(&conn, 7, "https://example.com/other").is_err());
+        assert!(change_feed_url(&conn, 7, "http://example.com/other/").is_err());
+
+        change_feed_url(&conn, 7, " https://example.com/atom.xml ").unwrap();
+        assert_eq!(
+            get_feed(&conn, 7).unwrap().feed_link.as_deref(),
+            Some("https://example.com/atom.xml")
+        );
+        assert_eq!(get_feed_cache_validators(&conn, 7).unwrap().etag, None);
+        assert!(search_entries(&conn, "a").unwrap()[0].read_at.is_some());
+    }
+
+    #[test]
+    fn paused_feeds_are_left_out_of_refreshing_all_feeds() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (1, 'a', 'https://example.com/a', 'RSS');
+            INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (2, 'b', 'https://example.com/b', 'RSS');",
+        )
+        .unwrap();
+
+        set_feed_paused(&conn, 2, true).unwrap();
+        assert!(get_feed(&conn, 2).unwrap().paused);
+        assert_eq!(get_feed_ids(&conn).unwrap(), vec![1]);
+
+        set_feed_paused(&conn, 2, false).unwrap();
+        assert_eq!(get_feed_ids(&conn).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn failures_are_counted_until_a_refresh_succeeds() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (1, 'a', 'https://example.com/a', 'RSS')",
+            [],
+        )
+        .unwrap();
+
+        let error = anyhow::anyhow!("status code 404").context("Failed to fetch feed");
+        let duration = std::time::Duration::from_millis(30);
+        record_refresh_result(&conn, 1, duration, Some(&error)).unwrap();
+        record_refresh_result(&conn, 1, duration, Some(&error)).unwrap();
+
+        let feed = get_feed(&conn, 1).unwrap();
+        assert!(feed.is_failing());
+        assert_eq!(feed.health.consecutive_failures, 2);
+        assert_eq!(
+            feed.health.last_error.as_deref(),
+            Some("Failed to fetch feed: status code 404")
+        );
+
+        record_refresh_result(&conn, 1, duration, None).unwrap();
+
+        let feed = get_feed(&conn, 1).unwrap();
+        assert!(!feed.is_failing());
+        assert!(feed.health.last_error_at.is_some());
+    }
+
+    #[test]
+    fn feed_stats_count_entries_per_week_since_the_oldest_one() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let now = Utc::now();
+        let pub_dates = [28, 14, 0]
+            .iter()
+            .map(|days_ago| now - chrono::Duration::days(*days_ago))
+            .collect::<Vec<_>>();
+
+        conn.execute(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (1, 'a', 'https://example.com/a', 'RSS')",
+            [],
+        )
+        .unwrap();
+        for (i, pub_date) in pub_dates.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO entries (feed_id, link, pub_date) VALUES (1, ?1, ?2)",
+                params![format!("https://example.com/{}", i), pub_date],
+            )
+            .unwrap();
+        }
+        set_entries_read(&conn, &[1], true).unwrap();
+        record_refresh_result(&conn, 1, std::time::Duration::from_millis(250), None).unwrap();
+
+        let stats = get_feed_stats(&conn, 1, now).unwrap();
+        assert_eq!(
+            stats.entry_counts,
+            EntryCounts {
+                unread: 2,
+                total: 3
+            }
+        );
+        assert_eq!(stats.oldest_entry_at, Some(pub_dates[0]));
+        assert_eq!(stats.newest_entry_at, Some(pub_dates[2]));
+        assert_eq!(stats.entries_per_week, Some(0.75));
+        assert_eq!(
+            stats.last_refresh_duration,
+            Some(std::time::Duration::from_millis(250))
+        );
+
+        conn.execute(
+            "INSERT INTO feeds (id, title, feed_link, feed_kind) VALUES (2, 'b', 'https://example.com/b', 'RSS')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(get_feed_stats(&conn, 2, now).unwrap(), FeedStats::default());
+    }
+
+    #[test]
+    fn maintenance_reclaims_the_space_of_deleted_entries() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let entries = (0..200)
+            .map(|i| Entry {
+                content: Some("lorem ipsum ".repeat(100)),
+                link: Some(format!("https://example.com/{}", i)),
+                ..Entry::for_test(&format!("entry {}", i))
+            })
+            .collect::<Vec<_>>();
+
+        for entries in entries.chunks(50) {
+            add_entries_to_feed(&conn, 1, entries).unwrap();
+        }
+        conn.execute("DELETE FROM entries", []).unwrap();
+
+        let report = maintain_db(&conn).unwrap();
+
+        assert!(report.size_after < report.size_before);
+    }
+
+    #[test]
+    fn full_content_is_kept_alongside_the_feeds_content() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO entries (feed_id, title, content, link) VALUES (1, 'a', 'a summary', 'https://example.com/a')",
+            [],
+        )
+        .unwrap();
+        let entry_id = conn.last_insert_rowid();
+
+        assert_eq!(
+            get_entry_content(&conn, entry_id).unwrap().full_content,
+            None
+        );
+
+        assert_eq!(
+            set_entry_full_content(&conn, entry_id, "<p>the whole article</p>").unwrap(),
+            3
+        );
+        assert_eq!(get_entry_meta(&conn, entry_id).unwrap().word_count, 3);
+
+        let entry_content = get_entry_content(&conn, entry_id).unwrap();
+        assert_eq!(entry_content.content.as_deref(), Some("a summary"));
+        assert_eq!(
+            entry_content.full_content.as_deref(),
+            Some("<p>the whole article</p>")
+        );
+    }
+
+    #[test]
+    fn entries_remember_where_they_were_left() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO entries (id, feed_id, title) VALUES (1, 1, 'a');
+            INSERT INTO entries (id, feed_id, title) VALUES (2, 1, 'b');",
+        )
+        .unwrap();
+
+        assert_eq!(get_entry_scroll_position(&conn, 1).unwrap(), 0);
+
+        set_entry_scroll_position(&conn, 1, 42).unwrap();
+        assert_eq!(get_entry_scroll_position(&conn, 1).unwrap(), 42);
+        assert_eq!(get_entry_scroll_position(&conn, 2).unwrap(), 0);
+    }
+
+    #[test]
+    fn recently_read_entries_are_the_opened_ones_last_opened_first() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO entries (id, feed_id, title, read_at) VALUES (1, 1, 'a', CURRENT_TIMESTAMP);
+            INSERT INTO entries (id, feed_id, title) VALUES (2, 2, 'b');
+            INSERT INTO entries (id, feed_id, title) VALUES (3, 1, 'c');",
+        )
+        .unwrap();
+
+        let now = Utc::now();
+        set_entry_opened_at(&conn, 2, now - chrono::Duration::days(1)).unwrap();
+        set_entry_opened_at(&conn, 1, now).unwrap();
+
+        let history = get_virtual_feed_entries_metas(
+            &conn,
+            &ReadMode::ShowUnread,
+            EntrySort::Title,
+            &DateRange::default(),
+            VirtualFeed::RecentlyRead,
+            false,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|entry_meta| entry_meta.id)
+        .collect::<Vec<_>>();
+
+        assert_eq!(history, vec![1, 2]);
+    }
+
+    #[test]
+    fn random_unread_entries_are_unread_and_not_the_one_being_read() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO entries (id, feed_id, title, read_at) VALUES (1, 1, 'a', CURRENT_TIMESTAMP);
+            INSERT INTO entries (id, feed_id, title) VALUES (2, 2, 'b');
+            INSERT INTO entries (id, feed_id, title) VALUES (3, 1, 'c');",
+        )
+        .unwrap();
+
+        for _ in 0..10 {
+            let entry_id = get_random_unread_entry_id(&conn, &DateRange::default(), None)
+                .unwrap()
+                .unwrap();
+            assert!(entry_id == 2 || entry_id == 3);
+        }
+
+        assert_eq!(
+            get_random_unread_entry_id(&conn, &DateRange::default(), Some(2)).unwrap(),
+            Some(3)
+        );
+
+        conn.execute("UPDATE entries SET read_at = CURRENT_TIMESTAMP", [])
+            .unwrap();
+        assert_eq!(
+            get_random_unread_entry_id(&conn, &DateRange::default(), None).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn the_last_session_is_remembered() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        assert_eq!(get_session(&conn).unwrap(), None);
+
+        let mut session = Session {
+            feed_id: Some(3),
+            virtual_feed: None,
+            entry_id: Some(7),
+            pane: Pane::Entry,
+            read_mode: ReadMode::All,
+            entry_filter: "rust".to_string(),
+            date_range: "7d".to_string(),
+            feeds_pane_width: None,
+            entries_pane_width: None,
+        };
+        set_session(&conn, &session).unwrap();
+        assert_eq!(get_session(&conn).unwrap(), Some(session.clone()));
+
+        session.virtual_feed = Some(VirtualFeed::Starred);
+        session.pane = Pane::Feeds;
+        session.feeds_pane_width = Some(25);
+        set_session(&conn, &session).unwrap();
+        assert_eq!(get_session(&conn).unwrap(), Some(session));
+    }
+
+    #[test]
+    fn escaped_titles_are_stored_decoded() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        let entry = Entry {
+            author: Some("Zo&euml;".to_string()),
+            link: Some("https://example.com/its".to_string()),
+            ..Entry::for_test("It&#8217;s &amp; more")
+        };
+        add_entries_to_feed(&conn, 1, &[entry]).unwrap();
+
+        let (title, author) = conn
+            .query_row("SELECT title, author FROM entries", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .unwrap();
+
+        assert_eq!(title, "It\u{2019}s & more");
+        assert_eq!(author, "Zo\u{eb}");
+    }
+
+    #[test]
+    fn backups_restore_into_another_database() {
+        let path = std::env::temp_dir().join(format!("russ-backup-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        let entry = Entry {
+            link: Some("https://example.com/backed-up".to_string()),
+            ..Entry::for_test("backed up")
+        };
+        add_entries_to_feed(&conn, 1, &[entry]).unwrap();
+
+        backup_db(&conn, &path, None).unwrap();
+        assert!(backup_db(&conn, &path, None).is_err());
+
+        let mut restored_conn = rusqlite::Connection::open_in_memory().unwrap();
+        restore_db(&mut restored_conn, &path, None).unwrap();
+
+        let titles = restored_conn
+            .query_row("SELECT title FROM entries", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .unwrap();
+        assert_eq!(titles, "backed up");
+
+        // anything else is refused rather than wiping the database
+        let not_russ = rusqlite::Connection::open(&path).unwrap();
+        not_russ.execute_batch("DROP TABLE feeds").unwrap();
+        drop(not_russ);
+        assert!(restore_db(&mut restored_conn, &path, None).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn encrypted_databases_need_their_passphrase() {
+        let path = std::env::temp_dir().join(format!("russ-encrypted-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let passphrase = Passphrase("correct horse".to_string());
+
+        let conn = open_db(&path, Some(&passphrase)).unwrap();
+        initialize_db(&conn).unwrap();
+        drop(conn);
+
+        assert!(open_db(&path, None).is_err());
+        assert!(open_db(&path, Some(&Passphrase("wrong".to_string()))).is_err());
+        assert!(open_db(&path, Some(&passphrase)).is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn imported_entries_bring_their_read_and_starred_state() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let entry = |title: &str, read: bool| Entry {
+            link: Some(format!("https://example.com/{}", title)),
+            read_at: if read { Some(Utc::now()) } else { None },
+            ..Entry::for_test(title)
+        };
+
+        add_entries_to_feed(&conn, 1, &[entry("fetched", false)]).unwrap();
+
+        let imported_entries = [
+            ImportedEntry {
+                entry: entry("fetched", true),
+                starred: false,
+            },
+            ImportedEntry {
+                entry: entry("old", false),
+                starred: true,
+            },
+        ];
+
+        assert_eq!(import_entries(&conn, 1, &imported_entries).unwrap(), 1);
+        assert_eq!(import_entries(&conn, 1, &imported_entries).unwrap(), 0);
+
+        let state = |title: &str| {
+            conn.query_row(
+                "SELECT read_at IS NOT NULL, starred FROM entries WHERE title = ?1",
+                [title],
+                |row| Ok((row.get::<_, bool>(0)?, row.get::<_, bool>(1)?)),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(state("fetched"), (true, false));
+        assert_eq!(state("old"), (false, true));
+    }
+
+    #[test]
+    fn starred_entries_survive_an_old_database_and_toggle() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+
+        // an entries table from before starring existed
+        conn.execute_batch(
+            "CREATE TABLE entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feed_id INTEGER,
+            title TEXT,
+            author TEXT,
+            pub_date TIMESTAMP,
+            description TEXT,
+            content TEXT,
+            link TEXT,
+            read_at TIMESTAMP,
+            inserted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO entries (feed_id, title) VALUES (1, 'a'), (2, 'b');",
+        )
+        .unwrap();
+
+        initialize_db(&conn).unwrap();
+
+        let starred = || {
+            get_virtual_feed_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Newest,
+                &DateRange::default(),
+                VirtualFeed::Starred,
+                false,
+            )
+            .unwrap()
+        };
+
+        assert!(starred().is_empty());
+
+        let entry = get_entry_meta(&conn, 2).unwrap();
+        entry.toggle_starred(&conn).unwrap();
+        assert_eq!(starred().len(), 1);
+        assert!(starred()[0].starred);
+
+        starred()[0].toggle_starred(&conn).unwrap();
+        assert!(starred().is_empty());
+    }
+
+    #[test]
+    fn set_feed_tags_replaces_a_feeds_tags() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        set_feed_tags(&conn, 1, &["News".to_string(), "blogs".to_string()]).unwrap();
+        set_feed_tags(&conn, 2, &["News".to_string()]).unwrap();
+        set_feed_tags(&conn, 1, &["blogs".to_string(), "Podcasts".to_string()]).unwrap();
+
+        let tags = get_feed_tags(&conn).unwrap();
+
+        assert_eq!(tags[&1], vec!["blogs", "Podcasts"]);
+        assert_eq!(tags[&2], vec!["News"]);
+    }
+
+    const LOCAL_FEED: &str = r#"<?xml version="1.0"?><rss version="2.0"><channel>
+        <title>local</title><link>http://localhost</link><description>d</description>
+        <item><title>one</title><link>http://localhost/one</link></item>
+        </channel></rss>"#;
+
+    /// answers `requests_len` requests on localhost with `respond(request head)`.
+    /// returns the server's URL and the head of every request.
+    fn serve_http<F>(
+        requests_len: usize,
+        respond: F,
+    ) -> (String, std::thread::JoinHandle<Vec<String>>)
+    where
+        F: Fn(&str) -> String + Send + 'static,
+    {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let mut heads = vec![];
+
+            for stream in listener.incoming().take(requests_len) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    head.push_str(&line.to_lowercase());
+                }
+
+                stream.write_all(respond(&head).as_bytes()).unwrap();
+                heads.push(head);
+            }
+
+            heads
+        });
+
+        (url, server)
+    }
+
+    fn ok_response(headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn refresh_feed_sends_the_etag_and_skips_unmodified_feeds() {
+        let (url, server) = serve_http(2, |head| {
+            if head.contains("if-none-match: \"v1\"") {
+                "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+            } else {
+                ok_response("ETag: \"v1\"\r\n", LOCAL_FEED)
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &url).unwrap();
+        assert_eq!(
+            get_feed_cache_validators(&conn, feed_id).unwrap().etag,
+            Some("\"v1\"".to_string())
+        );
+
+        refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap();
+
+        let heads = server.join().unwrap();
+        assert!(heads[0].contains("user-agent: russ/"));
+        assert!(heads[1].contains("if-none-match: \"v1\""));
+        assert!(get_feed(&conn, feed_id).unwrap().refreshed_at.is_some());
+        assert_eq!(
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Newest,
+                &DateRange::default(),
+                feed_id
+            )
+            .unwrap()
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn the_last_fetched_feed_is_kept_even_if_it_cannot_be_read() {
+        let requests = std::sync::atomic::AtomicUsize::new(0);
+        let (url, server) = serve_http(2, move |_| {
+            if requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                ok_response("", LOCAL_FEED)
+            } else {
+                ok_response("", "<html>Moved to /atom.xml</html>")
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &url).unwrap();
+        let raw_feed = get_raw_feed(&conn, feed_id).unwrap().unwrap();
+        assert_eq!(raw_feed.body, LOCAL_FEED);
+        assert!(raw_feed.compressed_len > 0);
+
+        let e = refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap_err();
+        assert!(e.downcast_ref::<UnparsableFeed>().is_some());
+        assert_eq!(
+            get_raw_feed(&conn, feed_id).unwrap().unwrap().body,
+            "<html>Moved to /atom.xml</html>"
+        );
+
+        server.join().unwrap();
+
+        delete_feed(&conn, feed_id).unwrap();
+        assert_eq!(get_raw_feed(&conn, feed_id).unwrap(), None);
+    }
+
+    #[test]
+    fn raw_feeds_are_compressed_behind_a_format_version() {
+        let feed =
+            "<item><title>Entry</title><link>https://example.com/</link></item>\n".repeat(200);
+
+        for body in ["", "abc", feed.as_str()] {
+            let compressed = compress_raw_feed(body).unwrap();
+            assert_eq!(compressed[0], RAW_FEED_ZLIB);
+            assert_eq!(decompress_raw_feed(&compressed).unwrap(), body);
+        }
+
+        assert!(compress_raw_feed(&feed).unwrap().len() < feed.len() / 10);
+
+        assert!(decompress_raw_feed(&[]).is_err());
+        assert!(decompress_raw_feed(&[0x30, b'a']).is_err());
+        assert!(decompress_raw_feed(&[RAW_FEED_ZLIB, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn refresh_feed_marks_read_or_hides_the_new_entries_rules_match() {
+        let requests = std::sync::atomic::AtomicUsize::new(0);
+        let (url, server) = serve_http(3, move |_| {
+            if requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                ok_response("", LOCAL_FEED)
+            } else {
+                ok_response(
+                    "",
+                    r#"<?xml version="1.0"?><rss version="2.0"><channel>
+                    <title>local</title><link>http://localhost</link><description>d</description>
+                    <item><title>one</title><link>http://localhost/one</link></item>
+                    <item><title>Sponsored: two</title><link>http://localhost/two</link></item>
+                    <item><title>three</title><link>http://localhost/three</link><author>bot</author></item>
+                    <item><title>four</title><link>http://localhost/four</link></item>
+                    </channel></rss>"#,
+                )
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &url).unwrap();
+        let rules = Rules::new(vec![
+            crate::rules::Rule {
+                title: Some(regex::Regex::new("^Sponsored").unwrap()),
+                author: None,
+                feed: Some(regex::Regex::new("^local$").unwrap()),
+                action: RuleAction::MarkRead,
+            },
+            crate::rules::Rule {
+                title: None,
+                author: Some(regex::Regex::new("^bot$").unwrap()),
+                feed: None,
+                action: RuleAction::Hide,
+            },
+        ]);
+
+        // a hidden entry stays hidden when the feed is refreshed again
+        refresh_feed(&http_client, &conn, feed_id, &rules, false, false).unwrap();
+        refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        server.join().unwrap();
+
+        let entries = get_entries_metas(
+            &conn,
+            &ReadMode::All,
+            EntrySort::Oldest,
+            &DateRange::default(),
+            feed_id,
+        )
+        .unwrap();
+        let titles_and_read = entries
+            .iter()
+            .map(|entry| (entry.title.as_deref().unwrap(), entry.read_at.is_some()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(titles_and_read.len(), 3);
+        assert!(titles_and_read.contains(&("one", false)));
+        assert!(titles_and_read.contains(&("Sponsored: two", true)));
+        assert!(titles_and_read.contains(&("four", false)));
+    }
+
+    #[test]
+    fn entries_the_feed_edits_are_updated_and_marked_revised() {
+        let requests = std::sync::atomic::AtomicUsize::new(0);
+        let (url, server) = serve_http(2, move |_| {
+            let (one_link, one_description) =
+                if requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    ("http://localhost/one", "first")
+                } else {
+                    ("http://localhost/one-renamed", "edited")
+                };
+            ok_response(
+                "",
+                &format!(
+                    r#"<?xml version="1.0"?><rss version="2.0"><channel>
+                    <title>local</title><link>http://localhost</link><description>d</description>
+                    <item><guid>one</guid><title>one</title><link>{}</link><description>{}</description></item>
+                    <item><guid>two</guid><title>two</title><link>http://localhost/two</link></item>
+                    </channel></rss>"#,
+                    one_link, one_description
+                ),
+            )
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &url).unwrap();
+        let get_entries = || {
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Title,
+                &DateRange::default(),
+                feed_id,
+            )
+            .unwrap()
+        };
+        let entry_ids = get_entries()
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        set_entries_read(&conn, &entry_ids, true).unwrap();
+
+        // the renamed link is the same entry, as its guid is the same
+        refresh_feed(&http_client, &conn, feed_id, &Rules::default(), true, false).unwrap();
+        server.join().unwrap();
+
+        let entries = get_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].link.as_deref(),
+            Some("http://localhost/one-renamed")
+        );
+        assert!(entries[0].read_at.is_none());
+        assert!(entries[0].revised_at.is_some());
+        assert_eq!(
+            get_entry_content(&conn, entries[0].id)
+                .unwrap()
+                .description
+                .as_deref(),
+            Some("edited")
+        );
+        assert!(entries[1].read_at.is_some());
+        assert!(entries[1].revised_at.is_none());
+
+        let updated = || {
+            get_smart_feed_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Title,
+                &DateRange::default(),
+                &Query::from_str("updated").unwrap(),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            updated().iter().map(|entry| entry.id).collect::<Vec<_>>(),
+            vec![entries[0].id]
+        );
+
+        // opening it shows the edit was seen
+        set_entry_opened_at(&conn, entries[0].id, Utc::now()).unwrap();
+        assert!(get_entry_meta(&conn, entries[0].id)
+            .unwrap()
+            .revised_at
+            .is_none());
+        assert!(updated().is_empty());
+    }
+
+    #[test]
+    fn refresh_feed_follows_and_stores_permanent_redirects_only() {
+        let (url, server) = serve_http(6, |head| {
+            if head.starts_with("get /permanent ") {
+                "HTTP/1.1 308 Permanent Redirect\r\nLocation: /temporary\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            } else if head.starts_with("get /temporary ") {
+                "HTTP/1.1 302 Found\r\nLocation: /feed\r\nConnection: close\r\n\r\n".to_string()
+            } else {
+                ok_response("", LOCAL_FEED)
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &format!("{}/feed", url)).unwrap();
+        update_feed_url(&conn, feed_id, &format!("{}/permanent", url)).unwrap();
+
+        // 308 then 302, so only the first hop is permanent
+        refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.feed_link, Some(format!("{}/temporary", url)));
+        // where it was subscribed at is kept through every move
+        assert_eq!(feed.original_feed_link, Some(format!("{}/feed", url)));
+
+        // a temporary redirect is followed but not stored
+        refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            get_feed(&conn, feed_id).unwrap().feed_link,
+            Some(format!("{}/temporary", url))
+        );
+
+        server.join().unwrap();
+        assert_eq!(
+            get_entries_metas(
+                &conn,
+                &ReadMode::All,
+                EntrySort::Newest,
+                &DateRange::default(),
+                feed_id
+            )
+            .unwrap()
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn refresh_feed_waits_for_moves_to_be_confirmed_and_does_not_ask_again_once_declined() {
+        let (url, server) = serve_http(5, |head| {
+            if head.starts_with("get /permanent ") {
+                "HTTP/1.1 308 Permanent Redirect\r\nLocation: /feed\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            } else {
+                ok_response("", LOCAL_FEED)
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &format!("{}/feed", url)).unwrap();
+        update_feed_url(&conn, feed_id, &format!("{}/permanent", url)).unwrap();
+
+        refresh_feed(&http_client, &conn, feed_id, &Rules::default(), false, true).unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.feed_link, Some(format!("{}/permanent", url)));
+        assert_eq!(feed.moved_to, Some(format!("{}/feed", url)));
+
+        decline_feed_move(&conn, feed_id).unwrap();
+        refresh_feed(&http_client, &conn, feed_id, &Rules::default(), false, true).unwrap();
+        server.join().unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.feed_link, Some(format!("{}/permanent", url)));
+        assert_eq!(feed.moved_to, None);
+
+        // somewhere else is asked about
+        set_feed_moved_to(&conn, feed_id, &format!("{}/elsewhere", url)).unwrap();
+        assert_eq!(
+            get_feed(&conn, feed_id).unwrap().moved_to,
+            Some(format!("{}/elsewhere", url))
+        );
+        update_feed_url(&conn, feed_id, &format!("{}/elsewhere", url)).unwrap();
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.feed_link, Some(format!("{}/elsewhere", url)));
+        assert_eq!(feed.moved_to, None);
+    }
+
+    #[test]
+    fn refresh_feed_leaves_rate_limited_feeds_alone_until_retry_after() {
+        let (url, server) = serve_http(2, |head| {
+            if head.starts_with("get /limited ") {
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            } else {
+                ok_response("", LOCAL_FEED)
+            }
+        });
+        let http_client = HttpClient::new(std::time::Duration::from_secs(5));
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_feed(&http_client, &conn, &format!("{}/feed", url)).unwrap();
+        update_feed_url(&conn, feed_id, &format!("{}/limited", url)).unwrap();
+
+        let e = refresh_feed(
+            &http_client,
+            &conn,
+            feed_id,
+            &Rules::default(),
+            false,
+            false,
+        )
+        .unwrap_err();
+        server.join().unwrap();
+
+        let until = e.downcast_ref::<RateLimited>().unwrap().until;
+        let feed = get_feed(&conn, feed_id).unwrap();
+        assert_eq!(feed.retry_after, Some(until));
+        assert!(feed.is_rate_limited(Utc::now() + chrono::Duration::seconds(100)));
+        assert!(!feed.is_rate_limited(Utc::now() + chrono::Duration::seconds(130)));
+    }
+
+    #[test]
+    fn it_parses_retry_after() {
+        let now = chrono::TimeZone::ymd(&Utc, 2015, 10, 21).and_hms(7, 0, 0);
+
+        assert_eq!(
+            parse_retry_after(Some("90"), now),
+            now + chrono::Duration::seconds(90)
+        );
+        assert_eq!(
+            parse_retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT"), now),
+            chrono::TimeZone::ymd(&Utc, 2015, 10, 21).and_hms(7, 28, 0)
+        );
+        assert_eq!(
+            parse_retry_after(None, now),
+            now + chrono::Duration::minutes(DEFAULT_RATE_LIMIT_COOL_OFF_MINUTES)
+        );
+    }
+
+    #[test]
+    fn feed_previews_list_the_newest_entries_first() {
+        let s = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <title>Example</title>
+  <link>https://example.com/</link>
+  <description> News &amp; notes </description>
+  <item><title>Older</title><link>https://example.com/1</link><pubDate>Tue, 01 Jun 2021 12:00:00 GMT</pubDate></item>
+  <item><title>Undated</title><link>https://example.com/3</link></item>
+  <item><title>Newer</title><link>https://example.com/2</link><pubDate>Wed, 02 Jun 2021 12:00:00 GMT</pubDate></item>
+</channel></rss>"#;
+
+        let feed_preview = FeedPreview {
+            url: "https://example.com/feed.xml".to_string(),
+            feed_and_entries: FeedAndEntries::from_str(s).unwrap(),
+        };
+
+        assert_eq!(feed_preview.title(), Some("Example"));
+        assert_eq!(feed_preview.description().as_deref(), Some("News & notes"));
+        assert_eq!(feed_preview.entries_len(), 3);
+        assert_eq!(
+            feed_preview.latest_entry_titles(2),
+            vec!["Newer".to_string(), "Older".to_string()]
+        );
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        let feed_id = subscribe_to_previewed_feed(&conn, &feed_preview).unwrap();
+        assert_eq!(get_raw_feed(&conn, feed_id).unwrap().unwrap().body, s);
+    }
+
+    #[test]
+    fn the_same_feed_is_not_subscribed_to_twice() {
+        let s = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Example</title></channel></rss>"#;
+
+        let feed_preview = |url: &str, feed_link: Option<&str>| {
+            let mut feed_and_entries = FeedAndEntries::from_str(s).unwrap();
+            feed_and_entries.feed.feed_link = feed_link.map(|feed_link| feed_link.to_owned());
+            FeedPreview {
+                url: url.to_string(),
+                feed_and_entries,
+            }
+        };
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+
+        let feed_id = subscribe_to_previewed_feed(
+            &conn,
+            &feed_preview(
+                "https://example.com/feed/",
+                Some("https://example.com/feed/"),
+            ),
+        )
+        .unwrap();
+
+        for feed_preview in [
+            feed_preview(
+                "http://example.com/feed?utm_source=rss",
+                Some("http://example.com/feed?utm_source=rss"),
+            ),
+            // redirected to the feed already subscribed to
+            feed_preview("https://example.org/feed", Some("https://example.com/feed")),
+        ] {
+            let e = subscribe_to_previewed_feed(&conn, &feed_preview).unwrap_err();
+            let subscribed_feed = e.downcast_ref::<AlreadySubscribed>().unwrap();
+            assert_eq!(subscribed_feed.feed_id, feed_id);
+            assert_eq!(subscribed_feed.title.as_deref(), Some("Example"));
+        }
+
+        assert_eq!(get_feeds(&conn).unwrap().len(), 1);
+        assert!(find_subscribed_feed(&conn, "https://example.com/other")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn it_parses_rdf_feeds() {
+        let s = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel rdf:about="https://example.com/">
+    <title>Example RDF</title>
+    <link>https://example.com/</link>
+    <description>d</description>
+    <items><rdf:Seq><rdf:li rdf:resource="https://example.com/1"/></rdf:Seq></items>
+  </channel>
+  <item rdf:about="https://example.com/1">
+    <title>First</title>
+    <link>https://example.com/1</link>
+    <description>hello</description>
+    <dc:creator>alice</dc:creator>
+    <dc:date>2021-06-01T12:00:00+00:00</dc:date>
+  </item>
+</rdf:RDF>"#;
+
+        let feed_and_entries = FeedAndEntries::from_str(s).unwrap();
+        assert_eq!(feed_and_entries.feed.title.as_deref(), Some("Example RDF"));
+        assert_eq!(feed_and_entries.entries.len(), 1);
+
+        let entry = &feed_and_entries.entries[0];
+        assert_eq!(entry.title.as_deref(), Some("First"));
+        assert_eq!(entry.link.as_deref(), Some("https://example.com/1"));
+        assert_eq!(entry.author.as_deref(), Some("alice"));
+        assert_eq!(
+            entry.pub_date,
+            Some(chrono::TimeZone::ymd(&Utc, 2021, 6, 1).and_hms(12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn relative_links_are_resolved() {
+        let s = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Relative</title>
+  <link href="/blog/"/>
+  <entry>
+    <title>Based</title>
+    <link href="posts/1"/>
+    <content type="html" xml:base="/media/">&lt;img src="a.png"&gt;</content>
+  </entry>
+  <entry>
+    <title>Linked</title>
+    <link href="posts/2"/>
+    <content type="html">&lt;a href="3"&gt;3&lt;/a&gt;</content>
+  </entry>
+</feed>"#;
+
+        let mut feed_and_entries = FeedAndEntries::from_str(s).unwrap();
+        feed_and_entries.resolve_relative_links("https://example.com/feeds/atom.xml");
+
+        let based = &feed_and_entries.entries[0];
+        assert_eq!(
+            based.link.as_deref(),
+            Some("https://example.com/blog/posts/1")
+        );
+        assert_eq!(
+            based.base_url.as_deref(),
+            Some("https://example.com/media/")
+        );
+
+        let linked = &feed_and_entries.entries[1];
+        assert_eq!(
+            linked.base_url.as_deref(),
+            Some("https://example.com/blog/posts/2")
+        );
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        add_entries_to_feed(&conn, 1, &feed_and_entries.entries).unwrap();
+        assert_eq!(
+            get_entry_content(&conn, 1).unwrap().base_url.as_deref(),
+            Some("https://example.com/media/")
+        );
+    }
+
+    #[test]
+    fn categories_are_parsed_and_stored() {
+        let rss = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Mixed</title>
+    <link>https://example.com</link>
+    <description>a bit of everything</description>
+    <item>
+      <title>Both</title>
+      <link>https://example.com/1</link>
+      <category>Rust</category>
+      <category domain="https://example.com/tags"> Cooking &amp;amp; Food </category>
+      <category>Rust</category>
+      <category> </category>
+    </item>
+  </channel>
+</rss>"#;
+        let atom = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Mixed</title>
+  <entry>
+    <title>Labeled</title>
+    <link href="https://example.com/2"/>
+    <category term="rust-lang" label="Rust"/>
+    <category term="travel"/>
+  </entry>
+</feed>"#;
+
+        let rss_entries = FeedAndEntries::from_str(rss).unwrap().entries;
+        assert_eq!(rss_entries[0].categories, vec!["Rust", "Cooking & Food"]);
+
+        let atom_entry = &FeedAndEntries::from_str(atom).unwrap().entries[0];
+        assert_eq!(atom_entry.categories, vec!["Rust", "travel"]);
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        add_entries_to_feed(&conn, 1, &rss_entries).unwrap();
+        assert_eq!(
+            get_entry_meta(&conn, 1).unwrap().categories,
+            vec!["Rust", "Cooking & Food"]
+        );
+    }
+
+    #[test]
+    fn atom_enclosures_are_parsed_and_stored() {
+        let s = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Example Podcast</title>
+  <id>https://example.com/</id>
+  <updated>2021-06-01T12:00:00Z</updated>
+  <entry>
+    <title>Episode 1</title>
+    <id>https://example.com/1</id>
+    <updated>2021-06-01T12:00:00Z</updated>
+    <link rel="enclosure" href="https://example.com/1.mp3" type="audio/mpeg" length="1234"/>
+    <link href="https://example.com/1"/>
+  </entry>
+</feed>"#;
+
+        let feed_and_entries = FeedAndEntries::from_str(s).unwrap();
+        let entry = &feed_and_entries.entries[0];
+        assert_eq!(entry.link.as_deref(), Some("https://example.com/1"));
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        initialize_db(&conn).unwrap();
+        add_entries_to_feed(&conn, 1, &feed_and_entries.entries).unwrap();
+
+        let entry_id = conn
+            .query_row("SELECT id FROM entries", [], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(
+            get_entry_meta(&conn, entry_id).unwrap().enclosure,
+            Some(Enclosure {
+                url: "https://example.com/1.mp3".to_string(),
+                mime_type: Some("audio/mpeg".to_string()),
+                length: Some(1234),
+            })
+        );
     }
 
     #[test]
@@ -0,0 +1,126 @@
+use crate::rss::{Entry, Feed};
+use regex::Regex;
+use serde::Deserialize;
+
+/// what happens to the new entries a rule matches.
+/// when more than one rule matches, hiding wins over marking read
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// added, but already read
+    MarkRead,
+    /// never added, and remembered like a deleted entry so it does not come back
+    Hide,
+}
+
+/// a rule from the `[[rules]]` in the config file.
+/// it matches the new entries that every one of its patterns matches
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub title: Option<Regex>,
+    pub author: Option<Regex>,
+    /// matches the feed's title or its URL
+    pub feed: Option<Regex>,
+    pub action: RuleAction,
+}
+
+impl Rule {
+    fn matches(&self, feed: &Feed, entry: &Entry) -> bool {
+        // titles and authors are matched as they are shown, with their entities decoded
+        let title = entry.title.as_deref().map(crate::sanitize::decode_entities);
+        let author = entry
+            .author
+            .as_deref()
+            .map(crate::sanitize::decode_entities);
+
+        is_match(self.title.as_ref(), &[title.as_deref()])
+            && is_match(self.author.as_ref(), &[author.as_deref()])
+            && is_match(
+                self.feed.as_ref(),
+                &[feed.title.as_deref(), feed.feed_link.as_deref()],
+            )
+    }
+}
+
+/// a missing pattern matches anything, and a missing value matches no pattern
+fn is_match(pattern: Option<&Regex>, values: &[Option<&str>]) -> bool {
+    match pattern {
+        Some(pattern) => values.iter().flatten().any(|value| pattern.is_match(value)),
+        None => true,
+    }
+}
+
+/// the rules new entries are checked against as feeds are refreshed
+#[derive(Clone, Debug, Default)]
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    pub fn new(rules: Vec<Rule>) -> Rules {
+        Rules(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// what to do with a new entry of `feed`, if any rule matches it
+    pub fn action(&self, feed: &Feed, entry: &Entry) -> Option<RuleAction> {
+        self.0
+            .iter()
+            .filter(|rule| rule.matches(feed, entry))
+            .map(|rule| rule.action)
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(title: Option<&str>, feed: Option<&str>, action: RuleAction) -> Rule {
+        Rule {
+            title: title.map(|title| Regex::new(title).unwrap()),
+            author: None,
+            feed: feed.map(|feed| Regex::new(feed).unwrap()),
+            action,
+        }
+    }
+
+    fn feed(feed_link: &str) -> Feed {
+        Feed {
+            title: Some("Example".to_string()),
+            feed_link: Some(feed_link.to_string()),
+            ..Feed::for_test(1)
+        }
+    }
+
+    fn entry(title: &str) -> Entry {
+        Entry {
+            ..Entry::for_test(title)
+        }
+    }
+
+    #[test]
+    fn hiding_wins_over_marking_read() {
+        let rules = Rules::new(vec![
+            rule(Some("(?i)^sponsored(:| &)"), None, RuleAction::MarkRead),
+            rule(
+                Some("(?i)giveaway"),
+                Some("example\\.com"),
+                RuleAction::Hide,
+            ),
+        ]);
+        let example_com = feed("https://example.com/feed.xml");
+        let example_org = feed("https://example.org/feed.xml");
+
+        assert_eq!(
+            rules.action(&example_com, &entry("Sponsored: a giveaway")),
+            Some(RuleAction::Hide)
+        );
+        assert_eq!(
+            rules.action(&example_com, &entry("Sponsored &amp; more")),
+            Some(RuleAction::MarkRead)
+        );
+        assert_eq!(rules.action(&example_org, &entry("A giveaway")), None);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::readability::{attribute, element_name, find_element, make_links_absolute};
+use html5ever::serialize::{SerializeOpts, TraversalScope};
+use html5ever::tendril::TendrilSink;
+use markup5ever_rcdom::{Handle, NodeData, RcDom, SerializableHandle};
+
+/// never worth showing, and scripts and styles show as their source
+const DROPPED_TAGS: &[&str] = &[
+    "button", "embed", "form", "iframe", "input", "link", "meta", "noscript", "object", "script",
+    "select", "style", "svg", "template", "textarea",
+];
+/// images from these only count who read the entry
+const TRACKERS: &[&str] = &[
+    "feeds.feedburner.com/~r/",
+    "feeds.feedburner.com/~ff/",
+    "google-analytics.com/",
+    "pixel.wp.com/",
+    "stats.wordpress.com/",
+];
+/// text in these is shown as it is, entities and all
+const VERBATIM_TAGS: &[&str] = &["code", "pre"];
+
+/// an entry's HTML without scripts, styles, forms, and tracking pixels,
+/// with the entities that feeds escape twice decoded,
+/// and with its links made absolute against `base_url`
+pub fn sanitize(html: &str, base_url: Option<&str>) -> String {
+    let dom = match html5ever::parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+    {
+        Ok(dom) => dom,
+        Err(_) => return html.to_owned(),
+    };
+
+    let body = match find_element(&dom.document, "body") {
+        Some(body) => body,
+        None => return html.to_owned(),
+    };
+
+    clean(&body);
+
+    if let Some(base_url) = base_url.and_then(|base_url| url::Url::parse(base_url).ok()) {
+        make_links_absolute(&body, &base_url);
+    }
+
+    let mut serialized = vec![];
+    let serialize_result = html5ever::serialize(
+        &mut serialized,
+        &SerializableHandle::from(body),
+        SerializeOpts {
+            traversal_scope: TraversalScope::ChildrenOnly(None),
+            ..Default::default()
+        },
+    );
+
+    match serialize_result.map(|_| String::from_utf8(serialized)) {
+        Ok(Ok(sanitized)) => sanitized,
+        _ => html.to_owned(),
+    }
+}
+
+fn clean(node: &Handle) {
+    node.children
+        .borrow_mut()
+        .retain(|child| match &child.data {
+            NodeData::Comment { .. } | NodeData::ProcessingInstruction { .. } => false,
+            NodeData::Element { .. } => {
+                let name = element_name(child).unwrap_or_default();
+                let is_dropped =
+                    DROPPED_TAGS.contains(&name) || (name == "img" && is_tracking_pixel(child));
+                !is_dropped
+            }
+            _ => true,
+        });
+
+    for child in node.children.borrow().iter() {
+        match &child.data {
+            // the parser decoded the entities once, so any left were escaped twice
+            NodeData::Text { contents } => {
+                let decoded = decode_entities(&contents.borrow());
+                *contents.borrow_mut() = decoded.into();
+            }
+            NodeData::Element { .. }
+                if VERBATIM_TAGS.contains(&element_name(child).unwrap_or_default()) => {}
+            _ => clean(child),
+        }
+    }
+}
+
+fn is_tracking_pixel(img: &Handle) -> bool {
+    let is_tiny = ["width", "height"].iter().any(|dimension| {
+        attribute(img, dimension)
+            .and_then(|size| size.trim().trim_end_matches("px").parse::<u32>().ok())
+            .is_some_and(|size| size <= 1)
+    });
+
+    let is_tracker = attribute(img, "src")
+        .is_some_and(|src| TRACKERS.iter().any(|tracker| src.contains(tracker)));
+
+    is_tiny || is_tracker
+}
+
+/// `text` with its named and numeric character references, like `&rsquo;` and `&#8217;`, decoded.
+/// anything that only looks like one is left as it is
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        match decode_entity(rest) {
+            Some((entity, len)) => {
+                decoded.push_str(&entity);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// what the entity `rest` starts with stands for, and how long the entity is
+fn decode_entity(rest: &str) -> Option<(String, usize)> {
+    let end = rest
+        .char_indices()
+        .take(40)
+        .find(|(_, c)| *c == ';')
+        .map(|(i, _)| i)?;
+    let name = &rest[1..end];
+
+    let decoded = match name.strip_prefix('#') {
+        Some(number) => {
+            let code_point = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code_point)
+                .filter(|c| *c != '\0')?
+                .to_string()
+        }
+        None => {
+            let (first, second) = html5ever::data::NAMED_ENTITIES.get(&rest[1..=end])?;
+            [*first, *second]
+                .iter()
+                .filter(|code_point| **code_point != 0)
+                .filter_map(|code_point| char::from_u32(*code_point))
+                .collect()
+        }
+    };
+
+    Some((decoded, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_decodes_named_and_numeric_entities() {
+        assert_eq!(
+            decode_entities("It&#8217;s &lt;b&gt; &amp;&#x1F600; &rsquo;"),
+            "It\u{2019}s <b> &\u{1F600} \u{2019}"
+        );
+        assert_eq!(
+            decode_entities("AT&T & &nosuch; &#xZZ; &#0;"),
+            "AT&T & &nosuch; &#xZZ; &#0;"
+        );
+    }
+
+    #[test]
+    fn it_drops_scripts_and_tracking_pixels() {
+        let sanitized = sanitize(
+            r#"<p>Don&amp;#8217;t <script>alert(1)</script>panic</p><style>p {}</style>
+            <!-- a comment --><img src="https://example.com/a.png" alt="a">
+            <img src="https://example.com/t.gif" width="1" height="1">
+            <img src="https://feeds.feedburner.com/~r/blog/~4/abc">
+            <pre>&amp;#8217;</pre>"#,
+            None,
+        );
+
+        assert!(sanitized.contains("<p>Don\u{2019}t panic</p>"));
+        assert!(sanitized.contains(r#"<img src="https://example.com/a.png" alt="a">"#));
+        assert!(sanitized.contains("<pre>&amp;#8217;</pre>"));
+        assert!(!sanitized.contains("script"));
+        assert!(!sanitized.contains("style"));
+        assert!(!sanitized.contains("comment"));
+        assert!(!sanitized.contains("t.gif"));
+        assert!(!sanitized.contains("feedburner"));
+    }
+}
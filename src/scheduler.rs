@@ -0,0 +1,136 @@
+use crate::config::FeedSettings;
+use crate::rss::{Feed, FeedId};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time;
+
+/// how often the scheduler looks for feeds that are due
+pub const CHECK_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+/// decides which feeds are due for an automatic refresh.
+/// a zero interval means never.
+#[derive(Debug)]
+pub struct RefreshSchedule {
+    refresh_interval: time::Duration,
+    /// per-feed intervals, by feed link
+    feed_refresh_intervals: FeedSettings<time::Duration>,
+    /// when each feed was last handed out,
+    /// so feeds that fail to refresh are not retried on every check
+    last_scheduled_at: HashMap<FeedId, DateTime<Utc>>,
+}
+
+impl RefreshSchedule {
+    /// `None` if no feed would ever be refreshed automatically
+    pub fn new(config: &crate::config::Config) -> Option<RefreshSchedule> {
+        let is_enabled = !config.refresh_interval.is_zero()
+            || config
+                .feed_refresh_intervals
+                .values()
+                .any(|interval| !interval.is_zero());
+
+        if is_enabled {
+            Some(RefreshSchedule {
+                refresh_interval: config.refresh_interval,
+                feed_refresh_intervals: config.feed_refresh_intervals.clone(),
+                last_scheduled_at: HashMap::new(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn refresh_interval(&self, feed: &Feed) -> Option<chrono::Duration> {
+        let interval = self
+            .feed_refresh_intervals
+            .get(&feed.feed_links())
+            .unwrap_or(&self.refresh_interval);
+
+        if interval.is_zero() {
+            None
+        } else {
+            chrono::Duration::from_std(*interval).ok()
+        }
+    }
+
+    /// the feeds that have not been refreshed or scheduled within their interval,
+    /// leaving out paused feeds. they count as scheduled from `now` on.
+    pub fn due_feed_ids(&mut self, feeds: &[Feed], now: DateTime<Utc>) -> Vec<FeedId> {
+        let mut due = vec![];
+
+        for feed in feeds.iter().filter(|feed| !feed.paused) {
+            let interval = match self.refresh_interval(feed) {
+                Some(interval) => interval,
+                None => continue,
+            };
+
+            let last_refreshed_at = feed
+                .refreshed_at
+                .into_iter()
+                .chain(self.last_scheduled_at.get(&feed.id).copied())
+                .max();
+
+            let is_due = match last_refreshed_at {
+                Some(last_refreshed_at) => now - last_refreshed_at >= interval,
+                None => true,
+            };
+
+            if is_due {
+                self.last_scheduled_at.insert(feed.id, now);
+                due.push(feed.id);
+            }
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(id: FeedId, feed_link: &str, refreshed_minutes_ago: Option<i64>) -> Feed {
+        let now = Utc::now();
+        Feed {
+            feed_link: Some(feed_link.to_string()),
+            refreshed_at: refreshed_minutes_ago.map(|m| now - chrono::Duration::minutes(m)),
+            ..Feed::for_test(id)
+        }
+    }
+
+    #[test]
+    fn feeds_are_due_after_their_interval_and_only_once() {
+        let minutes = |m: u64| time::Duration::from_secs(m * 60);
+        let feed_refresh_intervals = [
+            ("https://fast.example/feed".to_string(), minutes(5)),
+            ("http://never.example/feed/".to_string(), minutes(0)),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut schedule = RefreshSchedule {
+            refresh_interval: minutes(60),
+            feed_refresh_intervals,
+            last_scheduled_at: HashMap::new(),
+        };
+
+        let feeds = vec![
+            feed(1, "https://slow.example/feed", Some(30)),
+            feed(2, "https://fast.example/feed", Some(30)),
+            feed(3, "https://never.example/feed", None),
+            feed(4, "https://new.example/feed", None),
+            Feed {
+                paused: true,
+                ..feed(5, "https://paused.example/feed", None)
+            },
+            // its interval is under the link it was subscribed at
+            Feed {
+                original_feed_link: Some("https://fast.example/feed".to_string()),
+                ..feed(6, "https://fast.example/moved", Some(30))
+            },
+        ];
+
+        let now = Utc::now();
+        assert_eq!(schedule.due_feed_ids(&feeds, now), vec![2, 4, 6]);
+        assert!(schedule.due_feed_ids(&feeds, now).is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::http::HttpClient;
+use crate::rss::{Enclosure, Entry, FeedId};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// the `[sync]` table of the config file
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SyncConfig {
+    pub backend: SyncBackendKind,
+    /// where the server is, like `https://miniflux.example.com`
+    pub url: String,
+    /// Miniflux takes an API key as the password if there is no username.
+    /// the others always need one.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub password_command: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncBackendKind {
+    Miniflux,
+    /// the Google Reader API, as FreshRSS and The Old Reader have it
+    GoogleReader,
+    #[serde(rename = "ttrss")]
+    TinyTinyRss,
+}
+
+impl SyncBackendKind {
+    /// whether the server takes an API key without a username
+    pub fn needs_username(&self) -> bool {
+        match self {
+            SyncBackendKind::Miniflux => false,
+            SyncBackendKind::GoogleReader | SyncBackendKind::TinyTinyRss => true,
+        }
+    }
+}
+
+/// a feed as the sync server has it
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteFeed {
+    pub remote_id: String,
+    pub title: Option<String>,
+    pub feed_link: String,
+    pub link: Option<String>,
+    /// the server's categories or labels for the feed
+    pub tags: Vec<String>,
+}
+
+/// an entry as the sync server has it
+#[derive(Clone, Debug)]
+pub struct RemoteEntry {
+    pub remote_id: String,
+    pub remote_feed_id: String,
+    /// read if `read_at` is set
+    pub entry: Entry,
+    pub starred: bool,
+}
+
+/// every unread and every starred entry on the server, by remote id
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntryStates {
+    pub unread: HashSet<String>,
+    pub starred: HashSet<String>,
+}
+
+/// an entry read, unread, starred, or unstarred in russ since the last sync
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateChange {
+    pub remote_id: String,
+    pub read: bool,
+    pub starred: bool,
+}
+
+/// a server that fetches feeds for russ, and keeps the entries' state in sync with other clients.
+/// with one configured, russ never fetches feeds itself.
+pub trait SyncBackend: std::fmt::Debug + Send + Sync {
+    /// the server and account, so switching either starts over with a full sync
+    fn server(&self) -> String;
+
+    fn feeds(&self) -> Result<Vec<RemoteFeed>>;
+
+    /// the entries that changed since `since`,
+    /// or the unread, starred, and recent entries if this is the first sync
+    fn entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RemoteEntry>>;
+
+    /// for servers that only say which entries are new, not which changed:
+    /// the state of every entry, applied to the entries russ already has
+    fn entry_states(&self) -> Result<Option<EntryStates>> {
+        Ok(None)
+    }
+
+    fn push_state_changes(&self, state_changes: &[StateChange]) -> Result<()>;
+
+    /// subscribes on the server, returning the new feed's remote id
+    fn subscribe(&self, feed_link: &str) -> Result<String>;
+}
+
+pub fn backend_from_config(
+    sync_config: &SyncConfig,
+    http_client: &HttpClient,
+) -> Arc<dyn SyncBackend> {
+    match sync_config.backend {
+        SyncBackendKind::Miniflux => Arc::new(crate::miniflux::Miniflux::new(
+            http_client.clone(),
+            sync_config,
+        )),
+        SyncBackendKind::GoogleReader => Arc::new(crate::greader::GoogleReader::new(
+            http_client.clone(),
+            sync_config,
+        )),
+        SyncBackendKind::TinyTinyRss => Arc::new(crate::ttrss::TinyTinyRss::new(
+            http_client.clone(),
+            sync_config,
+        )),
+    }
+}
+
+/// an id or a number that servers give either as a JSON number or as a string, kept as text.
+/// numbers keep every digit, so ids too big for an `f64` stay exact
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NumberOrString(pub String);
+
+impl<'de> Deserialize<'de> for NumberOrString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(s) => Ok(NumberOrString(s)),
+            serde_json::Value::Number(n) => Ok(NumberOrString(n.to_string())),
+            value => Err(serde::de::Error::custom(format!(
+                "expected a number or a string, found {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// a media file a server gives with an entry.
+/// Miniflux calls its fields `url`, `mime_type`, and `size`,
+/// the Google Reader API `href`, `type`, and `length`,
+/// and TT-RSS `content_url`, `content_type`, and `length`
+#[derive(Clone, Debug, Deserialize)]
+pub struct RemoteEnclosure {
+    #[serde(alias = "href", alias = "content_url")]
+    url: String,
+    #[serde(alias = "type", alias = "content_type")]
+    mime_type: Option<String>,
+    #[serde(alias = "size")]
+    length: Option<NumberOrString>,
+}
+
+/// the first of the media files a server gives with an entry, which may be `null`
+pub fn first_enclosure(enclosures: &Option<Vec<RemoteEnclosure>>) -> Option<Enclosure> {
+    let enclosure = enclosures.as_deref()?.first()?;
+
+    Enclosure::new(
+        &enclosure.url,
+        enclosure.mime_type.as_deref(),
+        enclosure.length.as_ref().map(|length| length.0.as_str()),
+    )
+}
+
+/// what a sync did
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub pushed_len: usize,
+    pub feeds_len: usize,
+    pub added_entries_len: usize,
+}
+
+/// how far before a sync starts the next one asks for changes from,
+/// so entries that change while it runs are not missed
+const SYNC_OVERLAP_MINUTES: i64 = 1;
+
+/// pushes russ's state changes to the server, then brings in the server's feeds and entries
+pub fn sync(conn: &rusqlite::Connection, backend: &dyn SyncBackend) -> Result<SyncReport> {
+    let server = backend.server();
+
+    // pushed first, so the entries that come back already have them
+    let state_changes = crate::rss::get_pending_state_changes(conn)?;
+    if !state_changes.is_empty() {
+        backend.push_state_changes(&state_changes)?;
+        crate::rss::clear_pending_state_changes(conn, &state_changes)?;
+    }
+
+    let started_at = Utc::now() - chrono::Duration::minutes(SYNC_OVERLAP_MINUTES);
+    let synced_at = crate::rss::get_synced_at(conn, &server)?;
+
+    let remote_feeds = backend.feeds()?;
+    let remote_entries = backend.entries(synced_at)?;
+    let entry_states = backend.entry_states()?;
+
+    let feed_ids = crate::rss::store_remote_feeds(conn, &remote_feeds)?;
+    let added_entries_len = crate::rss::store_remote_entries(conn, &feed_ids, &remote_entries)?;
+
+    if let Some(entry_states) = entry_states {
+        crate::rss::store_remote_entry_states(conn, &entry_states)?;
+    }
+
+    for feed_id in feed_ids.values() {
+        crate::rss::update_feed_refreshed_at(conn, *feed_id)?;
+    }
+
+    crate::rss::set_synced_at(conn, &server, started_at)?;
+
+    Ok(SyncReport {
+        pushed_len: state_changes.len(),
+        feeds_len: remote_feeds.len(),
+        added_entries_len,
+    })
+}
+
+/// subscribes on the server, then syncs to bring the new feed in.
+/// fails with `AlreadySubscribed` if the feed is already subscribed to
+pub fn subscribe(
+    conn: &rusqlite::Connection,
+    backend: &dyn SyncBackend,
+    feed_link: &str,
+) -> Result<FeedId> {
+    if let Some(subscribed_feed) = crate::rss::find_subscribed_feed(conn, feed_link)? {
+        return Err(subscribed_feed.into());
+    }
+
+    let remote_id = backend.subscribe(feed_link)?;
+
+    sync(conn, backend)?;
+
+    crate::rss::get_feed_id_by_remote_id(conn, &remote_id)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Subscribed to {} on the server, but it did not come back in the sync",
+            feed_link
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct FakeBackend {
+        feeds: Vec<RemoteFeed>,
+        entries: Mutex<Vec<RemoteEntry>>,
+        entry_states: Option<EntryStates>,
+        pushed: Mutex<Vec<StateChange>>,
+    }
+
+    impl SyncBackend for FakeBackend {
+        fn server(&self) -> String {
+            "fake".to_string()
+        }
+
+        fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+            Ok(self.feeds.clone())
+        }
+
+        fn entries(&self, _since: Option<DateTime<Utc>>) -> Result<Vec<RemoteEntry>> {
+            Ok(self.entries.lock().unwrap().clone())
+        }
+
+        fn entry_states(&self) -> Result<Option<EntryStates>> {
+            Ok(self.entry_states.clone())
+        }
+
+        fn push_state_changes(&self, state_changes: &[StateChange]) -> Result<()> {
+            for state_change in state_changes {
+                for remote_entry in self.entries.lock().unwrap().iter_mut() {
+                    if remote_entry.remote_id == state_change.remote_id {
+                        remote_entry.entry.read_at = Some(Utc::now()).filter(|_| state_change.read);
+                        remote_entry.starred = state_change.starred;
+                    }
+                }
+            }
+
+            self.pushed.lock().unwrap().extend_from_slice(state_changes);
+            Ok(())
+        }
+
+        fn subscribe(&self, _feed_link: &str) -> Result<String> {
+            Err(anyhow::anyhow!("not supported"))
+        }
+    }
+
+    fn remote_entry(remote_id: &str, read: bool, starred: bool) -> RemoteEntry {
+        RemoteEntry {
+            remote_id: remote_id.to_string(),
+            remote_feed_id: "f1".to_string(),
+            entry: Entry {
+                link: Some(format!("https://example.com/{}", remote_id)),
+                read_at: if read { Some(Utc::now()) } else { None },
+                ..Entry::for_test(remote_id)
+            },
+            starred,
+        }
+    }
+
+    #[test]
+    fn syncing_brings_in_the_servers_state_and_pushes_russs() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::rss::initialize_db(&conn).unwrap();
+
+        let backend = FakeBackend {
+            feeds: vec![RemoteFeed {
+                remote_id: "f1".to_string(),
+                title: Some("remote feed".to_string()),
+                feed_link: "https://example.com/feed".to_string(),
+                link: None,
+                tags: vec!["news".to_string()],
+            }],
+            entries: Mutex::new(vec![
+                remote_entry("e1", false, false),
+                remote_entry("e2", true, true),
+            ]),
+            ..FakeBackend::default()
+        };
+
+        let report = sync(&conn, &backend).unwrap();
+        assert_eq!(report.added_entries_len, 2);
+
+        let feed_id = crate::rss::get_feed_id_by_remote_id(&conn, "f1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            crate::rss::get_feed_tags(&conn).unwrap()[&feed_id],
+            vec!["news".to_string()]
+        );
+
+        let entries = crate::rss::get_entries_metas(
+            &conn,
+            &crate::modes::ReadMode::All,
+            crate::modes::EntrySort::Title,
+            &crate::date_range::DateRange::default(),
+            feed_id,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].read_at.is_none() && !entries[0].starred);
+        assert!(entries[1].read_at.is_some() && entries[1].starred);
+
+        // read in russ, while the server still has it unread
+        entries[0].toggle_read(&conn).unwrap();
+        let report = sync(&conn, &backend).unwrap();
+
+        assert_eq!(report.added_entries_len, 0);
+        assert_eq!(
+            *backend.pushed.lock().unwrap(),
+            vec![StateChange {
+                remote_id: "e1".to_string(),
+                read: true,
+                starred: false,
+            }]
+        );
+        assert!(crate::rss::get_pending_state_changes(&conn)
+            .unwrap()
+            .is_empty());
+
+        // unstarred on the server
+        *backend.entries.lock().unwrap() = vec![remote_entry("e2", true, false)];
+        sync(&conn, &backend).unwrap();
+
+        let entry = crate::rss::get_entry_meta(&conn, entries[1].id).unwrap();
+        assert!(!entry.starred);
+    }
+
+    #[test]
+    fn entry_states_apply_to_entries_the_server_did_not_send_again() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::rss::initialize_db(&conn).unwrap();
+
+        let mut backend = FakeBackend {
+            feeds: vec![RemoteFeed {
+                remote_id: "f1".to_string(),
+                title: None,
+                feed_link: "https://example.com/feed".to_string(),
+                link: None,
+                tags: vec![],
+            }],
+            entries: Mutex::new(vec![remote_entry("e1", false, false)]),
+            ..FakeBackend::default()
+        };
+        sync(&conn, &backend).unwrap();
+
+        // read and starred on the server, which only says so in the states
+        backend.entries = Mutex::new(vec![]);
+        backend.entry_states = Some(EntryStates {
+            unread: HashSet::new(),
+            starred: ["e1".to_string()].into_iter().collect(),
+        });
+        sync(&conn, &backend).unwrap();
+
+        let feed_id = crate::rss::get_feed_id_by_remote_id(&conn, "f1")
+            .unwrap()
+            .unwrap();
+        let entries = crate::rss::get_entries_metas(
+            &conn,
+            &crate::modes::ReadMode::All,
+            crate::modes::EntrySort::Title,
+            &crate::date_range::DateRange::default(),
+            feed_id,
+        )
+        .unwrap();
+        assert!(entries[0].read_at.is_some() && entries[0].starred);
+    }
+
+    #[test]
+    fn numbers_and_strings_from_servers_are_kept_exactly() {
+        let ids: Vec<NumberOrString> =
+            serde_json::from_str(r#"[12, "12", 123456789012345678901234567890, 1.50]"#).unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                NumberOrString("12".to_string()),
+                NumberOrString("12".to_string()),
+                NumberOrString("123456789012345678901234567890".to_string()),
+                NumberOrString("1.50".to_string()),
+            ]
+        );
+
+        assert!(serde_json::from_str::<NumberOrString>("null").is_err());
+        assert!(serde_json::from_str::<NumberOrString>("[12]").is_err());
+    }
+}
@@ -0,0 +1,465 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::str::FromStr;
+use tui::style::{Color, Modifier, Style};
+
+/// the colors of every themeable element in the UI
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// block titles
+    pub title: Color,
+    /// the selected feed or entry, and the entry progress gauge
+    pub highlight: Color,
+    /// the feed subscription input
+    pub input: Color,
+    /// entries that have been read
+    pub read: Color,
+    /// entries that have not been read
+    pub unread: Color,
+    /// transient status messages, like "Refreshing feed..."
+    pub flash: Color,
+    /// the error pane
+    pub error: Color,
+    /// block borders
+    pub border: Color,
+    /// code and preformatted text in entries
+    pub code: Color,
+    /// keywords in code blocks, like `fn` and `return`
+    pub code_keyword: Color,
+    /// strings and numbers in code blocks
+    pub code_literal: Color,
+    /// comments in code blocks
+    pub code_comment: Color,
+    /// what the highlight keywords match, in entry titles and entries
+    pub highlight_keyword: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub const PRESETS: &'static [&'static str] = &["auto", "dark", "light", "monochrome"];
+
+    pub fn preset(name: &str) -> Result<Theme> {
+        match name {
+            // dark, unless the terminal says its background is light
+            "auto" => match Background::detect() {
+                Some(Background::Light) => Ok(Theme::light()),
+                Some(Background::Dark) | None => Ok(Theme::dark()),
+            },
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            "monochrome" => Ok(Theme::monochrome()),
+            _ => Err(anyhow::anyhow!(
+                "{} is not a theme, expected one of: {}",
+                name,
+                Theme::PRESETS.join(", ")
+            )),
+        }
+    }
+
+    /// in 24-bit colors, which `with_color_support` brings down to what the terminal has
+    fn dark() -> Theme {
+        Theme {
+            title: Color::Rgb(95, 205, 228),
+            highlight: Color::Rgb(255, 150, 167),
+            input: Color::Rgb(240, 200, 90),
+            read: Color::Rgb(118, 118, 118),
+            unread: Color::Reset,
+            flash: Color::Rgb(240, 200, 90),
+            error: Color::Rgb(255, 107, 107),
+            border: Color::Reset,
+            code: Color::Rgb(135, 215, 135),
+            code_keyword: Color::Rgb(215, 135, 235),
+            code_literal: Color::Rgb(235, 215, 120),
+            code_comment: Color::Rgb(118, 118, 118),
+            highlight_keyword: Color::Rgb(120, 170, 255),
+        }
+    }
+
+    fn light() -> Theme {
+        Theme {
+            title: Color::Rgb(20, 90, 180),
+            highlight: Color::Rgb(176, 30, 90),
+            input: Color::Rgb(135, 85, 0),
+            read: Color::Rgb(150, 150, 150),
+            unread: Color::Reset,
+            flash: Color::Rgb(135, 85, 0),
+            error: Color::Rgb(190, 30, 30),
+            border: Color::Rgb(110, 110, 110),
+            code: Color::Rgb(0, 110, 60),
+            code_keyword: Color::Rgb(150, 30, 150),
+            code_literal: Color::Rgb(160, 90, 0),
+            code_comment: Color::Rgb(140, 140, 140),
+            highlight_keyword: Color::Rgb(0, 90, 170),
+        }
+    }
+
+    /// no colors at all, only the terminal's own foreground
+    fn monochrome() -> Theme {
+        Theme {
+            title: Color::Reset,
+            highlight: Color::Reset,
+            input: Color::Reset,
+            read: Color::Reset,
+            unread: Color::Reset,
+            flash: Color::Reset,
+            error: Color::Reset,
+            border: Color::Reset,
+            code: Color::Reset,
+            code_keyword: Color::Reset,
+            code_literal: Color::Reset,
+            code_comment: Color::Reset,
+            highlight_keyword: Color::Reset,
+        }
+    }
+
+    /// the theme with every color the terminal cannot show replaced with the closest one it can
+    pub fn with_color_support(self, color_support: ColorSupport) -> Theme {
+        let convert = |color| color_support.convert(color);
+
+        Theme {
+            title: convert(self.title),
+            highlight: convert(self.highlight),
+            input: convert(self.input),
+            read: convert(self.read),
+            unread: convert(self.unread),
+            flash: convert(self.flash),
+            error: convert(self.error),
+            border: convert(self.border),
+            code: convert(self.code),
+            code_keyword: convert(self.code_keyword),
+            code_literal: convert(self.code_literal),
+            code_comment: convert(self.code_comment),
+            highlight_keyword: convert(self.highlight_keyword),
+        }
+    }
+
+    /// keywords are bold and comments italic too, so code is highlighted without colors
+    pub fn code_keyword_style(&self) -> Style {
+        Style::default()
+            .fg(self.code_keyword)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    pub fn code_literal_style(&self) -> Style {
+        Style::default().fg(self.code_literal)
+    }
+
+    pub fn code_comment_style(&self) -> Style {
+        Style::default()
+            .fg(self.code_comment)
+            .add_modifier(Modifier::ITALIC)
+    }
+
+    /// bold too, so highlighted keywords stand out without colors
+    pub fn highlight_keyword_style(&self) -> Style {
+        Style::default()
+            .fg(self.highlight_keyword)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    pub fn title_style(&self) -> Style {
+        Style::default().fg(self.title).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn highlight_style(&self) -> Style {
+        let style = Style::default()
+            .fg(self.highlight)
+            .add_modifier(Modifier::BOLD);
+
+        if self.highlight == Color::Reset {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    }
+
+    pub fn border_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    pub fn flash_style(&self) -> Style {
+        Style::default().fg(self.flash).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn error_style(&self) -> Style {
+        Style::default().fg(self.error).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn entry_style(&self, is_read: bool) -> Style {
+        if is_read {
+            Style::default().fg(self.read)
+        } else {
+            Style::default().fg(self.unread)
+        }
+    }
+
+    /// marked entries are reversed, so they stand out in any theme
+    pub fn marked_entry_style(&self, is_read: bool) -> Style {
+        self.entry_style(is_read).add_modifier(Modifier::REVERSED)
+    }
+}
+
+/// whether the terminal's background is dark or light
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// from `COLORFGBG`, which rxvt, Konsole, and others set, like `15;0`
+    pub fn detect() -> Option<Background> {
+        background(|name| std::env::var(name).ok())
+    }
+}
+
+fn background(var: impl Fn(&str) -> Option<String>) -> Option<Background> {
+    let colorfgbg = var("COLORFGBG")?;
+    // the background is last, after the foreground and sometimes a default
+    let background = colorfgbg.rsplit(';').next()?.parse::<u8>().ok()?;
+
+    // white and light gray, and the bright colors besides dark gray
+    if background == 7 || (9..=15).contains(&background) {
+        Some(Background::Light)
+    } else {
+        Some(Background::Dark)
+    }
+}
+
+/// how many colors the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ColorSupport {
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Indexed256,
+    /// the 16 named colors, which the terminal's own palette decides
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+impl ColorSupport {
+    /// from `COLORTERM` and `TERM`, like the `truecolor` or `xterm-256color` in them
+    pub fn detect() -> ColorSupport {
+        color_support(|name| std::env::var(name).ok())
+    }
+
+    fn convert(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, _) => color,
+            (ColorSupport::Indexed256, Color::Rgb(r, g, b)) => {
+                Color::Indexed(nearest_indexed(r, g, b))
+            }
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi(r, g, b),
+            (ColorSupport::Ansi16, Color::Indexed(i)) if i >= 16 => {
+                let (r, g, b) = indexed_rgb(i);
+                nearest_ansi(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+fn color_support(var: impl Fn(&str) -> Option<String>) -> ColorSupport {
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+
+    if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("direct") {
+        ColorSupport::TrueColor
+    } else if term.contains("256color") {
+        ColorSupport::Indexed256
+    } else {
+        ColorSupport::Ansi16
+    }
+}
+
+/// the levels of each channel in the 6x6x6 color cube of 256-color terminals
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// the usual xterm values of the 16 named colors, as terminals do not tell theirs
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// the closest of the color cube and the gray ramp of 256-color terminals
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |channel: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - channel as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+
+    let gray_step = ((r as usize + g as usize + b as usize) / 3).saturating_sub(3) / 10;
+    let gray = 232 + gray_step.min(23);
+
+    if distance((r, g, b), indexed_rgb(gray as u8)) < distance((r, g, b), indexed_rgb(cube as u8)) {
+        gray as u8
+    } else {
+        cube as u8
+    }
+}
+
+fn nearest_ansi(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// what a 256-color index above the named colors looks like
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => ANSI_COLORS[i as usize].1,
+        16..=231 => {
+            let i = (i - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        232..=255 => {
+            let level = 8 + 10 * (i - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// parses named colors (`cyan`, `lightred`), 256-color indexes (`208`),
+/// and hex colors (`#ff96a7`)
+pub fn parse_color(s: &str) -> Result<Color> {
+    let color = match s.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        // only ASCII can be sliced two bytes at a time
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let channel = |i: usize| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .with_context(|| format!("{} is not a valid hex color", s))
+            };
+            Color::Rgb(channel(1)?, channel(3)?, channel(5)?)
+        }
+        indexed => match u8::from_str(indexed) {
+            Ok(i) => Color::Indexed(i),
+            Err(_) => return Err(anyhow::anyhow!("{} is not a valid color", s)),
+        },
+    };
+
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_colors() {
+        assert_eq!(parse_color("Light-Red").unwrap(), Color::LightRed);
+        assert_eq!(parse_color("208").unwrap(), Color::Indexed(208));
+        assert_eq!(parse_color("#FF96a7").unwrap(), Color::Rgb(255, 150, 167));
+        assert!(parse_color("#ff96").is_err());
+        // seven bytes, but not seven characters
+        assert!(parse_color("#1é234").is_err());
+        assert!(parse_color("chartreuse").is_err());
+    }
+
+    #[test]
+    fn colors_are_brought_down_to_what_the_terminal_shows() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            color_support(env(&[("COLORTERM", "truecolor")])),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            color_support(env(&[("TERM", "xterm-256color")])),
+            ColorSupport::Indexed256
+        );
+        assert_eq!(
+            color_support(env(&[("TERM", "linux")])),
+            ColorSupport::Ansi16
+        );
+
+        assert_eq!(
+            background(env(&[("COLORFGBG", "0;15")])),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            background(env(&[("COLORFGBG", "15;default;0")])),
+            Some(Background::Dark)
+        );
+        assert_eq!(background(env(&[])), None);
+
+        let pink = Color::Rgb(255, 150, 167);
+        assert_eq!(ColorSupport::TrueColor.convert(pink), pink);
+        assert_eq!(ColorSupport::Indexed256.convert(pink), Color::Indexed(211));
+        assert_eq!(
+            ColorSupport::Indexed256.convert(Color::Rgb(118, 118, 118)),
+            Color::Indexed(243)
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.convert(Color::Rgb(20, 90, 180)),
+            Color::LightBlue
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.convert(Color::Indexed(231)),
+            Color::White
+        );
+        assert_eq!(ColorSupport::Ansi16.convert(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn unknown_presets_are_errors() {
+        assert!(Theme::preset("light").is_ok());
+        assert!(Theme::preset("solarized-ish").is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use crate::http::{Credentials, HttpClient};
+use crate::rss::Entry;
+use crate::sync::{first_enclosure, NumberOrString, RemoteEnclosure};
+use crate::sync::{EntryStates, RemoteEntry, RemoteFeed, StateChange, SyncBackend, SyncConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// the most headlines TT-RSS gives at a time
+const PAGE_SIZE: i64 = 200;
+
+/// how far back the first sync goes for entries that are neither unread nor starred
+const FIRST_SYNC_DAYS: i64 = 30;
+
+/// TT-RSS's virtual feed of every article
+const ALL_ARTICLES: i64 = -4;
+
+/// TT-RSS's category of every feed that is not virtual
+const ALL_FEEDS: i64 = -3;
+
+/// `updateArticle`'s fields
+const STARRED_FIELD: i64 = 0;
+const UNREAD_FIELD: i64 = 2;
+
+/// Tiny Tiny RSS's JSON API.
+/// `url` is where TT-RSS lives, like `https://example.com/tt-rss`, and the API must be enabled in its preferences.
+#[derive(Debug)]
+pub struct TinyTinyRss {
+    http_client: HttpClient,
+    /// without a trailing slash
+    url: String,
+    credentials: Credentials,
+    /// from logging in, kept until the server stops taking it
+    session_id: Mutex<Option<String>>,
+}
+
+/// what every call answers with
+#[derive(Debug, Deserialize)]
+struct Response {
+    status: Option<i64>,
+    #[serde(default)]
+    content: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Session {
+    session_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Category {
+    id: NumberOrString,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TtrssFeed {
+    id: NumberOrString,
+    title: Option<String>,
+    feed_url: String,
+    cat_id: Option<NumberOrString>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Headline {
+    id: NumberOrString,
+    feed_id: NumberOrString,
+    #[serde(default = "default_unread")]
+    unread: bool,
+    #[serde(default)]
+    marked: bool,
+    title: Option<String>,
+    link: Option<String>,
+    author: Option<String>,
+    updated: Option<i64>,
+    content: Option<String>,
+    attachments: Option<Vec<RemoteEnclosure>>,
+}
+
+fn default_unread() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct Subscribed {
+    status: SubscribeStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscribeStatus {
+    code: Option<i64>,
+    feed_id: Option<NumberOrString>,
+}
+
+impl TinyTinyRss {
+    pub fn new(http_client: HttpClient, sync_config: &SyncConfig) -> TinyTinyRss {
+        TinyTinyRss {
+            http_client,
+            url: sync_config.url.trim_end_matches('/').to_owned(),
+            credentials: Credentials {
+                username: sync_config.username.clone().unwrap_or_default(),
+                password: sync_config.password.clone(),
+                password_command: sync_config.password_command.clone(),
+            },
+            session_id: Mutex::new(None),
+        }
+    }
+
+    /// the `content` of the answer, or the error TT-RSS gave
+    fn post<T: DeserializeOwned>(&self, op: &str, params: Vec<(&str, Value)>) -> Result<T> {
+        let mut body = serde_json::Map::new();
+        body.insert("op".to_owned(), Value::from(op));
+        body.extend(
+            params
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value)),
+        );
+
+        let response = self
+            .http_client
+            .request("POST", &format!("{}/api/", self.url))
+            .set("Content-Type", "application/json")
+            .send_string(&Value::Object(body).to_string())
+            .with_context(|| format!("Unable to call {} on TT-RSS", op))?
+            .into_string()?;
+
+        let response: Response = serde_json::from_str(&response)
+            .with_context(|| format!("Invalid JSON from TT-RSS for {}", op))?;
+
+        match response.status {
+            Some(0) => serde_json::from_value(response.content)
+                .with_context(|| format!("Invalid JSON from TT-RSS for {}", op)),
+            _ => Err(anyhow::anyhow!(
+                "TT-RSS failed to {}: {}",
+                op,
+                response
+                    .content
+                    .get("error")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            )),
+        }
+    }
+
+    fn session_id(&self) -> Result<String> {
+        let mut session_id = self.session_id.lock().unwrap();
+
+        if let Some(session_id) = &*session_id {
+            return Ok(session_id.to_owned());
+        }
+
+        let password = self.http_client.password(&self.credentials)?;
+
+        let session: Session = self
+            .post(
+                "login",
+                vec![
+                    ("user", self.credentials.username.as_str().into()),
+                    ("password", password.as_str().into()),
+                ],
+            )
+            .context("TT-RSS did not return a session id")?;
+        let new_session_id = session.session_id;
+
+        *session_id = Some(new_session_id.clone());
+
+        Ok(new_session_id)
+    }
+
+    /// `op` in the session, logging in again if the session has expired
+    fn call<T: DeserializeOwned>(&self, op: &str, params: Vec<(&str, Value)>) -> Result<T> {
+        let mut session_params = params.clone();
+        session_params.push(("sid", self.session_id()?.as_str().into()));
+
+        match self.post(op, session_params) {
+            Err(e) if e.to_string().ends_with("NOT_LOGGED_IN") => {
+                *self.session_id.lock().unwrap() = None;
+
+                let mut session_params = params;
+                session_params.push(("sid", self.session_id()?.as_str().into()));
+                self.post(op, session_params)
+            }
+            result => result,
+        }
+    }
+
+    /// every page of headlines in `view_mode`, newest first, until `keep_going` says to stop
+    fn headlines(
+        &self,
+        view_mode: &str,
+        show_content: bool,
+        mut keep_going: impl FnMut(&Headline) -> bool,
+    ) -> Result<Vec<Headline>> {
+        let mut headlines = vec![];
+
+        loop {
+            let page: Vec<Headline> = self.call(
+                "getHeadlines",
+                vec![
+                    ("feed_id", ALL_ARTICLES.into()),
+                    ("view_mode", view_mode.into()),
+                    // newest first, by when the feed says they were updated
+                    ("order_by", "feed_dates".into()),
+                    ("show_content", show_content.into()),
+                    ("limit", PAGE_SIZE.into()),
+                    ("skip", (headlines.len() as i64).into()),
+                ],
+            )?;
+
+            let page_len = page.len();
+
+            for headline in page {
+                if !keep_going(&headline) {
+                    return Ok(headlines);
+                }
+                headlines.push(headline);
+            }
+
+            if (page_len as i64) < PAGE_SIZE {
+                return Ok(headlines);
+            }
+        }
+    }
+
+    fn headline_ids(&self, view_mode: &str) -> Result<HashSet<String>> {
+        Ok(self
+            .headlines(view_mode, false, |_| true)?
+            .into_iter()
+            .map(|headline| headline.id.0)
+            .collect())
+    }
+
+    /// sets `field` on the articles, or unsets it
+    fn update_articles(&self, remote_ids: &[&str], field: i64, set: bool) -> Result<()> {
+        if remote_ids.is_empty() {
+            return Ok(());
+        }
+
+        let _: Value = self.call(
+            "updateArticle",
+            vec![
+                ("article_ids", remote_ids.join(",").as_str().into()),
+                ("mode", i64::from(set).into()),
+                ("field", field.into()),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+impl SyncBackend for TinyTinyRss {
+    fn server(&self) -> String {
+        format!("ttrss {} {}", self.url, self.credentials.username)
+    }
+
+    fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        let categories: Vec<Category> = self.call("getCategories", vec![])?;
+        let categories = categories
+            .into_iter()
+            .map(|category| (category.id.0, category.title))
+            .collect::<HashMap<_, _>>();
+
+        let feeds: Vec<TtrssFeed> = self.call("getFeeds", vec![("cat_id", ALL_FEEDS.into())])?;
+
+        Ok(feeds
+            .into_iter()
+            .map(|feed| to_remote_feed(feed, &categories))
+            .collect())
+    }
+
+    fn entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RemoteEntry>> {
+        let is_since = |since: DateTime<Utc>| {
+            move |headline: &Headline| {
+                headline
+                    .updated
+                    .map(|updated| updated >= since.timestamp())
+                    .unwrap_or(true)
+            }
+        };
+
+        // TT-RSS cannot say what changed, so new headlines are the ones updated since,
+        // and the read and starred state of the rest comes from `entry_states`
+        let headlines = match since {
+            Some(since) => self.headlines("all_articles", true, is_since(since))?,
+            None => {
+                let mut headlines = self.headlines("unread", true, |_| true)?;
+                headlines.extend(self.headlines("marked", true, |_| true)?);
+                headlines.extend(self.headlines(
+                    "all_articles",
+                    true,
+                    is_since(Utc::now() - chrono::Duration::days(FIRST_SYNC_DAYS)),
+                )?);
+                headlines
+            }
+        };
+
+        let mut seen_ids = HashSet::new();
+        let mut remote_entries = vec![];
+
+        for headline in headlines {
+            let remote_entry = to_remote_entry(headline);
+            if seen_ids.insert(remote_entry.remote_id.clone()) {
+                remote_entries.push(remote_entry);
+            }
+        }
+
+        Ok(remote_entries)
+    }
+
+    fn entry_states(&self) -> Result<Option<EntryStates>> {
+        Ok(Some(EntryStates {
+            unread: self.headline_ids("unread")?,
+            starred: self.headline_ids("marked")?,
+        }))
+    }
+
+    fn push_state_changes(&self, state_changes: &[StateChange]) -> Result<()> {
+        for set in [true, false] {
+            let unread_ids = state_changes
+                .iter()
+                .filter(|state_change| state_change.read != set)
+                .map(|state_change| state_change.remote_id.as_str())
+                .collect::<Vec<_>>();
+            self.update_articles(&unread_ids, UNREAD_FIELD, set)?;
+
+            let starred_ids = state_changes
+                .iter()
+                .filter(|state_change| state_change.starred == set)
+                .map(|state_change| state_change.remote_id.as_str())
+                .collect::<Vec<_>>();
+            self.update_articles(&starred_ids, STARRED_FIELD, set)?;
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, feed_link: &str) -> Result<String> {
+        let subscribed: Subscribed = self.call(
+            "subscribeToFeed",
+            vec![("feed_url", feed_link.into()), ("category_id", 0.into())],
+        )?;
+        let status = subscribed.status;
+
+        // 0 is already subscribed and 1 is subscribed, which both come with the feed's id
+        match status.code {
+            Some(0) | Some(1) => status
+                .feed_id
+                .map(|feed_id| feed_id.0)
+                .ok_or_else(|| anyhow::anyhow!("TT-RSS did not say which feed it subscribed to")),
+            code => Err(anyhow::anyhow!(
+                "TT-RSS could not subscribe to {} (status code {:?})",
+                feed_link,
+                code
+            )),
+        }
+    }
+}
+
+/// `categories` are category titles by id
+fn to_remote_feed(feed: TtrssFeed, categories: &HashMap<String, String>) -> RemoteFeed {
+    RemoteFeed {
+        remote_id: feed.id.0,
+        title: feed.title,
+        feed_link: feed.feed_url,
+        link: None,
+        tags: feed
+            .cat_id
+            .and_then(|cat_id| categories.get(&cat_id.0))
+            .cloned()
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn to_remote_entry(headline: Headline) -> RemoteEntry {
+    let now = Utc::now();
+
+    RemoteEntry {
+        remote_id: headline.id.0,
+        remote_feed_id: headline.feed_id.0,
+        entry: Entry {
+            title: headline.title,
+            author: headline.author.filter(|author| !author.is_empty()),
+            pub_date: headline
+                .updated
+                .and_then(|updated| Utc.timestamp_opt(updated, 0).single()),
+            description: None,
+            content: headline.content,
+            link: headline.link,
+            base_url: None,
+            guid: None,
+            feed_updated_at: None,
+            categories: vec![],
+            enclosure: first_enclosure(&headline.attachments),
+            read_at: if headline.unread { None } else { Some(now) },
+        },
+        starred: headline.marked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_feeds_and_headlines() {
+        let categories = [("3".to_string(), "News".to_string())]
+            .into_iter()
+            .collect();
+
+        let feed: TtrssFeed = serde_json::from_str(
+            r#"{"id": 12, "title": "Example", "feed_url": "https://example.com/feed", "cat_id": 3, "unread": 4}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_remote_feed(feed, &categories),
+            RemoteFeed {
+                remote_id: "12".to_string(),
+                title: Some("Example".to_string()),
+                feed_link: "https://example.com/feed".to_string(),
+                link: None,
+                tags: vec!["News".to_string()],
+            }
+        );
+
+        let headline: Headline = serde_json::from_str(
+            r#"{"id": 99, "unread": false, "marked": true, "title": "Hi", "link": "https://example.com/hi",
+            "author": "", "updated": 1622548800, "feed_id": "12", "content": "<p>hi</p>"}"#,
+        )
+        .unwrap();
+
+        let remote_entry = to_remote_entry(headline);
+        assert_eq!(remote_entry.remote_id, "99");
+        assert_eq!(remote_entry.remote_feed_id, "12");
+        assert!(remote_entry.entry.read_at.is_some());
+        assert!(remote_entry.starred);
+        assert_eq!(remote_entry.entry.author, None);
+        assert_eq!(
+            remote_entry.entry.pub_date,
+            Utc.timestamp_opt(1622548800, 0).single()
+        );
+    }
+}
@@ -1,51 +1,196 @@
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
-use tui::style::{Color, Modifier, Style};
-use tui::text::{Span, Text};
-use tui::widgets::{Block, Borders, LineGauge, List, ListItem, Paragraph, Wrap};
+use tui::style::Style;
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{Block, Borders, Clear, LineGauge, List, ListItem, ListState, Paragraph, Wrap};
 use tui::Frame;
 
-use crate::app::AppImpl;
-use crate::modes::{Mode, ReadMode, Selected};
-use crate::rss::EntryMeta;
+use crate::app::{AppImpl, RefreshProgress};
+use crate::days::EntryRow;
+use crate::entry_format::EntryFields;
+use crate::feed_groups::FeedRow;
+use crate::images::GraphicsProtocol;
+use crate::keymap::Pane;
+use crate::modes::{EntryDates, EntrySort, FeedSort, Mode, PaneLayout, ReadMode, Selected, Split};
+use crate::rss::{EntryMeta, VirtualFeed};
+use unicode_width::UnicodeWidthChar;
 
-pub fn predraw<B: Backend>(f: &Frame<B>) -> Vec<Rect> {
-    Layout::default()
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .direction(Direction::Horizontal)
-        .split(f.size())
+/// how many of a feed's latest entries its preview lists before subscribing
+const FEED_PREVIEW_ENTRIES_LEN: usize = 5;
+
+/// the panes: the feeds, and the entries or the entry being read,
+/// or in the three-pane layout, the feeds, the entries, and the entry being read.
+/// stacked, the entries or the entry go below the feeds,
+/// or in the three-pane layout, the entry goes below the entries.
+/// in zen mode, only the entry, centered
+pub fn predraw<B: Backend>(f: &Frame<B>, app: &AppImpl) -> Vec<Rect> {
+    if app.is_zen_reading() {
+        let size = f.size();
+        // the borders and the space inside them take 4 columns
+        let width = (app.config.zen_line_width + 4).min(size.width);
+        return vec![Rect::new(
+            size.x + (size.width - width) / 2,
+            size.y,
+            width,
+            size.height,
+        )];
+    }
+
+    let feeds_width = app.feeds_pane_width;
+    let entries_width = app.entries_pane_width;
+
+    match (app.config.layout, app.split) {
+        (PaneLayout::TwoPane, Split::SideBySide) => Layout::default()
+            .constraints(
+                [
+                    Constraint::Percentage(feeds_width),
+                    Constraint::Percentage(100 - feeds_width),
+                ]
+                .as_ref(),
+            )
+            .direction(Direction::Horizontal)
+            .split(f.size()),
+        (PaneLayout::TwoPane, Split::Stacked) => Layout::default()
+            .constraints(
+                [
+                    Constraint::Percentage(feeds_width),
+                    Constraint::Percentage(100 - feeds_width),
+                ]
+                .as_ref(),
+            )
+            .direction(Direction::Vertical)
+            .split(f.size()),
+        (PaneLayout::ThreePane, Split::SideBySide) => Layout::default()
+            .constraints(
+                [
+                    Constraint::Percentage(feeds_width),
+                    Constraint::Percentage(entries_width),
+                    Constraint::Percentage(100u16.saturating_sub(feeds_width + entries_width)),
+                ]
+                .as_ref(),
+            )
+            .direction(Direction::Horizontal)
+            .split(f.size()),
+        // the entries' width is their share of the height here
+        (PaneLayout::ThreePane, Split::Stacked) => {
+            let columns = Layout::default()
+                .constraints(
+                    [
+                        Constraint::Percentage(feeds_width),
+                        Constraint::Percentage(100 - feeds_width),
+                    ]
+                    .as_ref(),
+                )
+                .direction(Direction::Horizontal)
+                .split(f.size());
+
+            let rows = Layout::default()
+                .constraints(
+                    [
+                        Constraint::Percentage(entries_width),
+                        Constraint::Percentage(100 - entries_width),
+                    ]
+                    .as_ref(),
+                )
+                .direction(Direction::Vertical)
+                .split(columns[1]);
+
+            vec![columns[0], rows[0], rows[1]]
+        }
+    }
 }
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, chunks: Vec<Rect>, app: &mut AppImpl) {
+    app.entry_text_area = None;
+    app.feeds_area = None;
+    app.entries_area = None;
+
+    if app.is_zen_reading() {
+        draw_entry(f, chunks[0], app);
+    } else {
+        draw_panes(f, chunks, app);
+    }
+
+    match app.mode {
+        Mode::FeedSwitcher => draw_feed_switcher(f, f.size(), app),
+        Mode::FeedHealth => draw_feed_health(f, f.size(), app),
+        Mode::FeedStats => draw_feed_stats(f, f.size(), app),
+        Mode::CommandPalette => draw_command_palette(f, f.size(), app),
+        Mode::ConfirmFeedDeletion => draw_feed_deletion_confirmation(f, f.size(), app),
+        Mode::ConfirmSubscription => draw_subscription_confirmation(f, f.size(), app),
+        Mode::ConfirmFeedMove => draw_feed_move_confirmation(f, f.size(), app),
+        Mode::EditingNote => draw_entry_note_input(f, f.size(), app),
+        Mode::NamingSearch => draw_saved_search_name_input(f, f.size(), app),
+        Mode::SavedSearches => draw_saved_searches(f, f.size(), app),
+        Mode::Help => draw_key_help(f, f.size(), app),
+        Mode::ErrorLog => draw_error_log(f, f.size(), app),
+        Mode::RawFeed => draw_raw_feed(f, f.size(), app),
+        _ => (),
+    }
+}
+
+fn draw_panes<B: Backend>(f: &mut Frame<B>, chunks: Vec<Rect>, app: &mut AppImpl) {
     draw_info_column(f, chunks[0], app);
 
+    let reading_area = chunks.get(2).copied();
+
     match &app.selected {
+        _ if matches!(app.mode, Mode::Search | Mode::NamingSearch) => {
+            draw_search_results(f, chunks[1], app);
+        }
         Selected::Feeds | Selected::Entries => {
             draw_entries(f, chunks[1], app);
         }
+        Selected::Entry(_entry_meta) if reading_area.is_some() => {
+            draw_entries(f, chunks[1], app);
+        }
         Selected::Entry(_entry_meta) => {
             draw_entry(f, chunks[1], app);
         }
     }
+
+    if let Some(reading_area) = reading_area {
+        draw_entry(f, reading_area, app);
+    }
 }
 
 fn draw_info_column<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let mut constraints = match &app.mode {
-        Mode::Normal => vec![Constraint::Percentage(70), Constraint::Percentage(20)],
-        Mode::Editing => vec![
+    let constraints = match &app.mode {
+        Mode::Normal
+        | Mode::FeedSwitcher
+        | Mode::FeedHealth
+        | Mode::FeedStats
+        | Mode::Help
+        | Mode::ErrorLog
+        | Mode::RawFeed
+        | Mode::CommandPalette
+        | Mode::ConfirmFeedDeletion
+        | Mode::ConfirmSubscription
+        | Mode::ConfirmFeedMove
+        | Mode::EditingNote
+        | Mode::SavedSearches => vec![
+            Constraint::Percentage(70),
+            Constraint::Percentage(20),
+            Constraint::Percentage(10),
+        ],
+        Mode::Editing
+        | Mode::EditingTags
+        | Mode::EditingFeedUrl
+        | Mode::PipeCommand
+        | Mode::Search
+        | Mode::NamingSearch
+        | Mode::Filter
+        | Mode::DateRange => vec![
             Constraint::Percentage(60),
             Constraint::Percentage(20),
             Constraint::Percentage(10),
+            Constraint::Percentage(10),
         ],
     };
 
-    if app.show_help {
-        constraints.push(Constraint::Percentage(10));
-    }
-
     let chunks = Layout::default()
         .constraints(constraints)
         .direction(Direction::Vertical)
@@ -56,41 +201,75 @@ fn draw_info_column<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 
         // INFO
         match &app.selected {
-            Selected::Entry(entry) => draw_entry_info(f, chunks[1], entry),
+            Selected::Entry(entry) => draw_entry_info(f, chunks[1], entry, app),
             Selected::Entries => {
                 if let Some(entry_meta) = &app.current_entry_meta {
-                    draw_entry_info(f, chunks[1], entry_meta);
+                    draw_entry_info(f, chunks[1], entry_meta, app);
                 } else {
                     draw_feed_info(f, chunks[1], app);
                 }
             }
             _ => {
-                if app.current_feed.is_some() {
+                if app.current_feed.is_some() || app.current_virtual_feed.is_some() {
                     draw_feed_info(f, chunks[1], app);
                 }
             }
         }
 
-        match (app.mode, app.show_help) {
-            (Mode::Editing, true) => {
+        match app.mode {
+            Mode::Editing => {
                 draw_new_feed_input(f, chunks[2], app);
                 draw_help(f, chunks[3], app);
             }
-            (Mode::Editing, false) => {
-                draw_new_feed_input(f, chunks[2], app);
+            Mode::EditingTags => {
+                draw_feed_tags_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
             }
-            (_, true) => {
+            Mode::EditingFeedUrl => {
+                draw_feed_url_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            Mode::PipeCommand => {
+                draw_pipe_command_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            Mode::Search | Mode::NamingSearch => {
+                draw_search_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            Mode::Filter => {
+                draw_filter_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            Mode::DateRange => {
+                draw_date_range_input(f, chunks[2], app);
+                draw_help(f, chunks[3], app);
+            }
+            Mode::Normal
+            | Mode::FeedSwitcher
+            | Mode::FeedHealth
+            | Mode::FeedStats
+            | Mode::Help
+            | Mode::ErrorLog
+            | Mode::RawFeed
+            | Mode::CommandPalette
+            | Mode::ConfirmFeedDeletion
+            | Mode::ConfirmSubscription
+            | Mode::ConfirmFeedMove
+            | Mode::EditingNote
+            | Mode::SavedSearches => {
                 draw_help(f, chunks[2], app);
             }
-            _ => (),
         }
     }
 }
 
-fn draw_entry_info<B>(f: &mut Frame<B>, area: Rect, entry_meta: &EntryMeta)
+fn draw_entry_info<B>(f: &mut Frame<B>, area: Rect, entry_meta: &EntryMeta, app: &AppImpl)
 where
     B: Backend,
 {
+    let theme = &app.config.theme;
+
     let mut text = String::new();
     if let Some(item) = &entry_meta.title {
         text.push_str("Title: ");
@@ -98,36 +277,85 @@ fn draw_entry_info<B>(f: &mut Frame<B>, area: Rect, entry_meta: &EntryMeta)
         text.push('\n');
     };
 
+    if let Some(author) = &entry_meta.author {
+        text.push_str("Author: ");
+        text.push_str(author);
+        text.push('\n');
+    }
+
+    if !entry_meta.categories.is_empty() {
+        text.push_str("Categories: ");
+        text.push_str(&entry_meta.categories.join(", "));
+        text.push('\n');
+    }
+
     if let Some(item) = &entry_meta.link {
         text.push_str("Link: ");
         text.push_str(item);
         text.push('\n');
     }
 
-    if let Some(pub_date) = &entry_meta.pub_date {
+    if let Some(enclosure) = &entry_meta.enclosure {
+        text.push_str("Enclosure: ");
+        text.push_str(&enclosure.url);
+        let details = enclosure
+            .mime_type
+            .iter()
+            .cloned()
+            .chain(enclosure.length.map(crate::util::format_bytes))
+            .collect::<Vec<_>>();
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        text.push('\n');
+    }
+
+    if let Some(pub_date) = entry_meta.pub_date {
         text.push_str("Pub. date: ");
-        text.push_str(pub_date.to_string().as_str());
+        text.push_str(&app.format_date(pub_date));
     } else {
         // TODO this should probably pull the <updated> tag
         // and use that
         let inserted_at = entry_meta.inserted_at;
         text.push_str("Pulled date: ");
-        text.push_str(inserted_at.to_string().as_str());
+        text.push_str(&app.format_date(inserted_at));
     }
     text.push('\n');
 
-    if let Some(read_at) = &entry_meta.read_at {
+    if let Some(revised_at) = entry_meta.revised_at {
+        text.push_str("Updated: ");
+        text.push_str(&app.format_date(revised_at));
+        text.push('\n');
+    }
+
+    if let Some(read_at) = entry_meta.read_at {
         text.push_str("Read at: ");
-        text.push_str(read_at.to_string().as_str());
+        text.push_str(&app.format_date(read_at));
         text.push('\n');
     }
 
-    let block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "Info",
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD),
-    ));
+    if entry_meta.starred {
+        text.push_str("Starred\n");
+    }
+
+    if entry_meta.word_count > 0 {
+        text.push_str(&format!(
+            "Length: {} words, {} read\n",
+            entry_meta.word_count,
+            crate::util::format_reading_time(entry_meta.word_count, app.config.words_per_minute)
+        ));
+    }
+
+    if let Some(note) = app.entry_notes.get(&entry_meta.id) {
+        text.push_str("Note: ");
+        text.push_str(note);
+        text.push('\n');
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border_style())
+        .title(Span::styled("Info", theme.title_style()));
 
     let paragraph = Paragraph::new(Text::from(text.as_str()))
         .block(block)
@@ -136,43 +364,125 @@ fn draw_entry_info<B>(f: &mut Frame<B>, area: Rect, entry_meta: &EntryMeta)
     f.render_widget(paragraph, area);
 }
 
+/// a spinner, how many feeds are done, and the feeds being fetched
+fn refresh_progress_text(refresh_progress: &RefreshProgress, feeds: &[crate::rss::Feed]) -> String {
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+    let spinner_idx = refresh_progress.started_at.elapsed().as_millis() / 250;
+    let spinner = SPINNER[spinner_idx as usize % SPINNER.len()];
+
+    let in_flight = refresh_progress
+        .in_flight
+        .iter()
+        .filter_map(|feed_id| feeds.iter().find(|feed| feed.id == *feed_id))
+        .map(|feed| feed.title.as_deref().unwrap_or("No feed title"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut text = format!(
+        "{} Refreshing {}/{}",
+        spinner, refresh_progress.done_len, refresh_progress.feeds_len
+    );
+
+    if !in_flight.is_empty() {
+        text.push_str(": ");
+        text.push_str(&in_flight);
+    }
+
+    text
+}
+
 fn draw_feeds<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
+    let default_feed_title = "No feed title".to_string();
+    let now = chrono::Utc::now();
+    let is_grouped = app
+        .feed_rows
+        .items
+        .iter()
+        .any(|row| matches!(row, FeedRow::Group { .. }));
+
     let feeds = app
-        .feeds
+        .feed_rows
         .items
         .iter()
-        .flat_map(|feed| feed.title.as_ref())
-        .map(Span::raw)
-        .map(ListItem::new)
+        .map(|row| match row {
+            FeedRow::Virtual(virtual_feed) => {
+                ListItem::new(Span::raw(app.virtual_feed_title(*virtual_feed)))
+            }
+            FeedRow::Group {
+                group,
+                feeds_len,
+                collapsed,
+            } => {
+                let marker = if *collapsed { "+" } else { "-" };
+                let name = group.as_deref().unwrap_or("Untagged");
+                ListItem::new(Span::styled(
+                    format!("{} {} ({})", marker, name, feeds_len),
+                    app.config.theme.title_style(),
+                ))
+            }
+            FeedRow::Feed { feed_idx } => {
+                let feed = &app.feeds.items[*feed_idx];
+                let title = feed.title.as_ref().unwrap_or(&default_feed_title);
+                let indent = if is_grouped { "  " } else { "" };
+                let entry_counts = app
+                    .feed_entry_counts
+                    .get(&feed.id)
+                    .copied()
+                    .unwrap_or_default();
+                let rate_limited = if feed.is_rate_limited(now) {
+                    " (rate limited)"
+                } else {
+                    ""
+                };
+                let paused = if feed.paused { " (paused)" } else { "" };
+                let text = format!(
+                    "{}{} ({}/{}){}{}",
+                    indent, title, entry_counts.unread, entry_counts.total, rate_limited, paused
+                );
+
+                if feed.is_failing() {
+                    ListItem::new(Spans::from(vec![
+                        Span::raw(text),
+                        Span::styled(" (failing)", app.config.theme.error_style()),
+                    ]))
+                } else {
+                    ListItem::new(Span::raw(text))
+                }
+            }
+        })
         .collect::<Vec<ListItem>>();
 
-    let default_title = String::from("Feeds");
-    let title = app.flash.as_ref().unwrap_or(&default_title);
+    let title = match (&app.refresh_progress, &app.flash) {
+        (Some(refresh_progress), _) => Span::styled(
+            refresh_progress_text(refresh_progress, &app.feeds.items),
+            app.config.theme.flash_style(),
+        ),
+        (None, Some(flash)) => Span::styled(flash.as_str(), app.config.theme.flash_style()),
+        (None, None) => Span::styled("Feeds", app.config.theme.title_style()),
+    };
 
     let feeds = List::new(feeds).block(
-        Block::default().borders(Borders::ALL).title(Span::styled(
-            title,
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )),
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.config.theme.border_style())
+            .title(title),
     );
 
     let feeds = match app.selected {
         Selected::Feeds => feeds
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Rgb(255, 150, 167))
-                    .add_modifier(Modifier::BOLD),
-            )
+            .highlight_style(app.config.theme.highlight_style())
             .highlight_symbol("> "),
         _ => feeds,
     };
 
-    f.render_stateful_widget(feeds, area, &mut app.feeds.state);
+    f.render_stateful_widget(feeds, area, &mut app.feed_rows.state);
+    app.feed_rows
+        .scroll_to_selected(area.height.saturating_sub(2) as usize);
+    app.feeds_area = Some(area);
 }
 
 fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
@@ -180,6 +490,20 @@ fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
     B: Backend,
 {
     let mut text = String::new();
+    if let Some(virtual_feed) = app.current_virtual_feed {
+        text.push_str("Title: ");
+        text.push_str(&app.virtual_feed_title(virtual_feed));
+        text.push('\n');
+
+        if let VirtualFeed::Smart(idx) = virtual_feed {
+            if let Some(smart_feed) = app.config.smart_feeds.get(idx) {
+                text.push_str("Query: ");
+                text.push_str(&smart_feed.query.to_string());
+                text.push('\n');
+            }
+        }
+    }
+
     if let Some(item) = app
         .current_feed
         .as_ref()
@@ -210,10 +534,10 @@ fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
         text.push('\n');
     }
 
-    if let Some(item) = app.entries.items.get(0) {
-        if let Some(pub_date) = &item.pub_date {
+    if let Some(item) = app.entries.items.first() {
+        if let Some(pub_date) = item.pub_date {
             text.push_str("Most recent entry at: ");
-            text.push_str(pub_date.to_string().as_str());
+            text.push_str(&app.format_date(pub_date));
             text.push('\n');
         }
     }
@@ -222,7 +546,7 @@ fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
         .current_feed
         .as_ref()
         .and_then(|feed| feed.refreshed_at)
-        .map(|timestamp| timestamp.to_string())
+        .map(|timestamp| app.format_date(timestamp))
         .or_else(|| Some("Never refreshed".to_string()))
     {
         text.push_str("Refreshed at: ");
@@ -230,10 +554,43 @@ fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
         text.push('\n');
     }
 
+    if let Some(retry_after) = app
+        .current_feed
+        .as_ref()
+        .filter(|feed| feed.is_rate_limited(chrono::Utc::now()))
+        .and_then(|feed| feed.retry_after)
+    {
+        text.push_str("Rate limited until: ");
+        text.push_str(&app.format_date(retry_after));
+        text.push('\n');
+    }
+
+    if let Some(health) = app
+        .current_feed
+        .as_ref()
+        .filter(|feed| feed.is_failing())
+        .map(|feed| &feed.health)
+    {
+        text.push_str(&format!(
+            "Failing refreshes in a row: {}\nLast error: ",
+            health.consecutive_failures
+        ));
+        text.push_str(health.last_error.as_deref().unwrap_or_default());
+        text.push('\n');
+    }
+
+    if app.current_feed.as_ref().is_some_and(|feed| feed.paused) {
+        text.push_str("Paused: left out of refreshing all feeds\n");
+    }
+
+    // the reading history has read and unread entries alike
     match app.read_mode {
+        _ if app.current_virtual_feed == Some(VirtualFeed::RecentlyRead) => {
+            text.push_str("Entries: ")
+        }
         ReadMode::ShowUnread => text.push_str("Unread entries: "),
         ReadMode::ShowRead => text.push_str("Read entries: "),
-        ReadMode::All => unreachable!("ReadMode::All should never be possible from the UI!"),
+        ReadMode::All => text.push_str("Entries: "),
     }
     text.push_str(app.entries.items.len().to_string().as_str());
     text.push('\n');
@@ -244,12 +601,10 @@ fn draw_feed_info<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
         text.push('\n');
     }
 
-    let block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "Info",
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD),
-    ));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.config.theme.border_style())
+        .title(Span::styled("Info", app.config.theme.title_style()));
 
     let paragraph = Paragraph::new(Text::from(text.as_str()))
         .block(block)
@@ -265,23 +620,72 @@ fn draw_help<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
     let mut text = String::new();
     match app.selected {
         Selected::Feeds => {
-            text.push_str("r - refresh selected feed; x - refresh all feeds\n");
-            text.push_str("c - copy link\n")
+            text.push_str("r - refresh selected feed/group; x - refresh all feeds\n");
+            text.push_str("t - edit tags; enter - open/close group\n");
+            if app.config.feed_sort == FeedSort::Manual {
+                text.push_str("K/J - move feed up/down\n");
+            }
+            text.push_str("c - copy link; o - open link\n")
         }
         _ => {
             text.push_str("r - mark entry read/un; a - toggle view read/un\n");
-            text.push_str("c - copy link\n")
+            text.push_str("U - toggle hiding read entries; / - filter\n");
+            text.push_str("* - star/unstar entry; S - change sort\n");
+            text.push_str("d - download enclosure; p - play enclosure/link\n");
+            text.push_str("F - fetch full article\n");
+            text.push_str("n/N - next/previous unread entry\n");
+            text.push_str("1-9 - open the entry's link with that number\n");
+            text.push_str("c - copy link; o - open link\n")
         }
     }
     match app.mode {
-        Mode::Normal => text.push_str("i - edit mode; q - exit\n"),
-        Mode::Editing => text.push_str("esc - normal mode; enter - fetch feed\n"),
+        Mode::Normal => {
+            text.push_str("gg/G - top/bottom; ctrl-u/ctrl-d - half page\n");
+            text.push_str("f - switch feed; s - search; : - commands\n");
+            text.push_str("i - edit mode; q - exit\n")
+        }
+        Mode::Editing => {
+            text.push_str("esc - normal mode; enter - preview feed\n");
+            text.push_str("ctrl-o - import OPML file at input path\n")
+        }
+        Mode::Search => {
+            text.push_str("esc - normal mode; enter - open entry; ctrl-s - save search\n");
+            text.push_str("up/down, ctrl-p/ctrl-n - select result\n")
+        }
+        Mode::NamingSearch => {
+            text.push_str("esc - cancel; enter - save the search under this name\n")
+        }
+        Mode::SavedSearches => {
+            text.push_str("j/k - select; enter - search; d - delete; esc - close\n")
+        }
+        Mode::EditingTags => text.push_str("esc - cancel; enter - save comma separated tags\n"),
+        Mode::EditingFeedUrl => text.push_str("esc - cancel; enter - save the URL and refresh\n"),
+        Mode::PipeCommand => {
+            text.push_str("esc - cancel; enter - pipe the entry\n");
+            text.push_str("tab - switch between text and HTML\n")
+        }
+        Mode::Filter => text.push_str("esc - clear filter; enter - keep filter\n"),
+        Mode::DateRange => text.push_str("esc - clear date range; enter - apply date range\n"),
+        Mode::FeedSwitcher => text.push_str("esc - cancel; enter - go to feed\n"),
+        Mode::FeedHealth => text.push_str("j/k - select; enter - go to feed; esc - close\n"),
+        Mode::FeedStats => text.push_str("esc - close\n"),
+        Mode::Help | Mode::ErrorLog | Mode::RawFeed => text.push_str("j/k - scroll; esc - close\n"),
+        Mode::CommandPalette => text.push_str("esc - cancel; enter - run command\n"),
+        Mode::ConfirmFeedDeletion => text.push_str("y - delete feed; n - keep it\n"),
+        Mode::ConfirmSubscription => text.push_str("y - subscribe; n - change the URL\n"),
+        Mode::ConfirmFeedMove => text.push_str("y - use the new URL; n - keep the old one\n"),
+        Mode::EditingNote => {
+            text.push_str("esc - cancel; enter - save the note, empty to remove it\n")
+        }
     }
 
-    text.push_str("? - show/hide help");
+    text.push_str("? - all keys");
 
-    let help_message =
-        Paragraph::new(Text::from(text.as_str())).block(Block::default().borders(Borders::ALL));
+    let help_message = Paragraph::new(Text::from(text.as_str())).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.config.theme.border_style()),
+    );
     f.render_widget(help_message, area);
 }
 
@@ -292,136 +696,1271 @@ fn draw_new_feed_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
     let text = &app.feed_subscription_input;
     let text = Text::from(text.as_str());
     let input = Paragraph::new(text)
-        .style(Style::default().fg(Color::Yellow))
+        .style(Style::default().fg(app.config.theme.input))
         .block(
-            Block::default().borders(Borders::ALL).title(Span::styled(
-                "Add a feed",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )),
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled("Add a feed", app.config.theme.title_style())),
         );
     f.render_widget(input, area);
 }
 
-fn draw_entries<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+fn draw_search_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let entries = app
-        .entries
-        .items
-        .iter()
-        .map(|entry| {
-            ListItem::new(Span::raw(entry.title.as_ref().unwrap_or_else(|| {
-                panic!("Unable to get title for entry id {}", entry.id)
-            })))
-        })
-        .collect::<Vec<ListItem>>();
-
-    let default_title = "Entries".to_string();
+    let text = &app.search_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled("Search", app.config.theme.title_style())),
+        );
+    f.render_widget(input, area);
+}
 
-    let title = app
+fn draw_feed_tags_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed_title = app
         .current_feed
         .as_ref()
-        .and_then(|feed| feed.title.as_ref())
-        .unwrap_or(&default_title);
-
-    let entries_titles = List::new(entries).block(
-        Block::default().borders(Borders::ALL).title(Span::styled(
-            title,
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )),
-    );
+        .and_then(|feed| feed.title.as_deref())
+        .unwrap_or("No feed title");
 
-    let entries_titles = match app.selected {
-        Selected::Entries => entries_titles
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Rgb(255, 150, 167))
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol("> "),
-        _ => entries_titles,
-    };
+    let title = format!("Tags for {}", feed_title);
 
-    if !&app.error_flash.is_empty() {
-        let chunks = Layout::default()
-            .constraints([Constraint::Percentage(60), Constraint::Percentage(30)].as_ref())
-            .direction(Direction::Vertical)
-            .split(area);
-        {
-            let error_text = error_text(&app.error_flash);
+    let text = &app.feed_tags_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled(title, app.config.theme.title_style())),
+        );
+    f.render_widget(input, area);
+}
 
-            let block = Block::default().borders(Borders::ALL).title(Span::styled(
-                "Error - press 'q' to close",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            ));
+fn draw_feed_url_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed_title = app
+        .current_feed
+        .as_ref()
+        .and_then(|feed| feed.title.as_deref())
+        .unwrap_or("No feed title");
 
-            let error_widget = Paragraph::new(error_text)
-                .block(block)
-                .wrap(Wrap { trim: false })
-                .scroll((0, 0));
+    let title = format!("URL of {}", feed_title);
 
-            f.render_stateful_widget(entries_titles, chunks[0], &mut app.entries.state);
-            f.render_widget(error_widget, chunks[1]);
-        }
-    } else {
-        f.render_stateful_widget(entries_titles, area, &mut app.entries.state);
-    }
+    let text = &app.feed_url_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled(title, app.config.theme.title_style())),
+        );
+    f.render_widget(input, area);
 }
 
-fn draw_entry<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+fn draw_pipe_command_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
 where
     B: Backend,
 {
-    let scroll = app.entry_scroll_position;
-    let entry_meta = if let Selected::Entry(e) = &app.selected {
-        e
+    let title = if app.pipe_html {
+        "Pipe the entry's HTML to"
     } else {
-        panic!("draw_entry should only be called when app.selected was Selected::Entry")
+        "Pipe the entry's text to"
     };
-    let default_entry_title = "No entry title".to_string();
-    let default_feed_title = "No feed title".to_string();
 
-    let entry_title = entry_meta.title.as_ref().unwrap_or(&default_entry_title);
+    let text = &app.pipe_command_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled(title, app.config.theme.title_style())),
+        );
+    f.render_widget(input, area);
+}
 
-    let feed_title = app
-        .current_feed
-        .as_ref()
-        .and_then(|feed| feed.title.as_ref())
-        .unwrap_or(&default_feed_title);
+fn draw_filter_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let text = &app.entry_filter;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled(
+                    "Filter entries (author: or category: to filter by those)",
+                    app.config.theme.title_style(),
+                )),
+        );
+    f.render_widget(input, area);
+}
 
-    let mut title = entry_title.to_owned();
-    title.push_str(" - ");
-    title.push_str(feed_title);
+fn draw_date_range_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let text = &app.date_range_input;
+    let text = Text::from(text.as_str());
+    let input = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style())
+                .title(Span::styled(
+                    "Date range (last 7 days, this month, 2021-01-01..2021-01-31)",
+                    app.config.theme.title_style(),
+                )),
+        );
+    f.render_widget(input, area);
+}
 
-    let block = Block::default().borders(Borders::ALL).title(Span::styled(
-        &title,
-        Style::default()
-            .add_modifier(Modifier::BOLD)
-            .fg(Color::Cyan),
-    ));
+/// a popup over the middle of `area` with the switcher input above the matching feeds
+fn draw_feed_switcher<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(60, 60, area);
 
-    let paragraph = Paragraph::new(app.current_entry_text.as_str())
-        .block(block)
-        .wrap(Wrap { trim: false })
-        .scroll((scroll, 0));
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .direction(Direction::Vertical)
+        .split(popup);
 
-    let entry_chunk_height = area.height - 2;
+    let theme = &app.config.theme;
 
-    let progress_gauge_chunk_percent = 3;
+    let input = Paragraph::new(Text::from(app.feed_switcher_input.as_str()))
+        .style(Style::default().fg(theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled("Go to feed", theme.title_style())),
+        );
 
-    let entry_percent = 100.0 - progress_gauge_chunk_percent as f32;
+    let default_feed_title = "No feed title".to_string();
+    let feeds = &app.feeds.items;
 
-    let real_entry_chunk_height =
-        (entry_chunk_height as f32 * (entry_percent / 100.0)).floor() as u16;
+    let results = app
+        .feed_switcher_results
+        .items
+        .iter()
+        .filter_map(|idx| feeds.get(*idx))
+        .map(|feed| {
+            ListItem::new(Span::raw(
+                feed.title.as_ref().unwrap_or(&default_feed_title),
+            ))
+        })
+        .collect::<Vec<ListItem>>();
 
-    app.entry_lines_rendered_len = real_entry_chunk_height;
+    let title = format!(
+        "Feeds: {}/{}",
+        app.feed_switcher_results.items.len(),
+        feeds.len()
+    );
+
+    let results = List::new(results)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("> ");
+
+    f.render_widget(Clear, popup);
+    f.render_widget(input, chunks[0]);
+    f.render_stateful_widget(results, chunks[1], &mut app.feed_switcher_results.state);
+}
+
+/// a popup over the middle of `area` listing the failing feeds,
+/// each with when it last refreshed and its last error
+fn draw_feed_health<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(80, 60, area);
+    let theme = &app.config.theme;
+
+    let results = app
+        .feed_health_results
+        .items
+        .iter()
+        .filter_map(|feed_id| app.feeds.items.iter().find(|feed| feed.id == *feed_id))
+        .map(|feed| {
+            let last_success = feed
+                .refreshed_at
+                .map(|refreshed_at| format!("last refreshed {}", refreshed_at))
+                .unwrap_or_else(|| "never refreshed".to_string());
+
+            ListItem::new(vec![
+                Spans::from(vec![
+                    Span::raw(feed.title.as_deref().unwrap_or("No feed title")),
+                    Span::raw(format!(
+                        " - failing refreshes in a row: {}, {}",
+                        feed.health.consecutive_failures, last_success
+                    )),
+                ]),
+                Spans::from(Span::styled(
+                    format!(
+                        "  {}: {}",
+                        feed.health
+                            .last_error_at
+                            .map(|last_error_at| app.format_date(last_error_at))
+                            .unwrap_or_default(),
+                        feed.health.last_error.as_deref().unwrap_or_default()
+                    ),
+                    theme.error_style(),
+                )),
+            ])
+        })
+        .collect::<Vec<ListItem>>();
+
+    let title = if results.is_empty() {
+        "Every feed refreshed fine - esc to close".to_string()
+    } else {
+        format!(
+            "Failing feeds: {} - enter to go to one, esc to close",
+            results.len()
+        )
+    };
+
+    let results = List::new(results)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("> ");
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(results, popup, &mut app.feed_health_results.state);
+}
+
+fn draw_feed_stats<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let stats = match &app.feed_stats {
+        Some(stats) => stats,
+        None => return,
+    };
+
+    let feed_title = app
+        .current_feed
+        .as_ref()
+        .and_then(|feed| feed.title.as_deref())
+        .unwrap_or("No feed title");
+
+    let format_date = |date: Option<chrono::DateTime<chrono::Utc>>| {
+        date.map(|date| app.format_day(date))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let text = [
+        ("Entries", stats.entry_counts.total.to_string()),
+        ("Unread", stats.entry_counts.unread.to_string()),
+        (
+            "Entries per week",
+            stats
+                .entries_per_week
+                .map(|entries_per_week| format!("{:.1}", entries_per_week))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+        ("Oldest entry", format_date(stats.oldest_entry_at)),
+        ("Newest entry", format_date(stats.newest_entry_at)),
+        (
+            "Last refresh took",
+            stats
+                .last_refresh_duration
+                .map(|duration| format!("{:?}", duration))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| {
+        Spans::from(vec![
+            Span::styled(format!("{:<18}", name), app.config.theme.title_style()),
+            Span::raw(value),
+        ])
+    })
+    .collect::<Vec<_>>();
+
+    let popup = centered_rect(50, 40, area);
+    let stats = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.config.theme.border_style())
+            .title(Span::styled(
+                format!("{} - esc to close", feed_title),
+                app.config.theme.title_style(),
+            )),
+    );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(stats, popup);
+}
+
+/// a popup over the middle of `area` with the palette input above the matching actions and their keys
+fn draw_command_palette<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(60, 60, area);
+
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .direction(Direction::Vertical)
+        .split(popup);
+
+    let theme = &app.config.theme;
+
+    let input = Paragraph::new(Text::from(app.command_palette_input.as_str()))
+        .style(Style::default().fg(theme.input))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled("Command", theme.title_style())),
+        );
+
+    let bound_keys = app.config.keymap.bound_keys(Pane::from(&app.selected));
+
+    let results = app
+        .command_palette_results
+        .items
+        .iter()
+        .map(|action| {
+            let keys = bound_keys
+                .iter()
+                .find(|(bound_action, _)| bound_action == action)
+                .map(|(_, keys)| {
+                    keys.iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+
+            ListItem::new(Spans::from(vec![
+                Span::raw(format!("{:<42}", action.description())),
+                Span::styled(keys, theme.border_style()),
+            ]))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let title = format!("Commands: {}", app.command_palette_results.items.len());
+
+    let results = List::new(results)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("> ");
+
+    f.render_widget(Clear, popup);
+    f.render_widget(input, chunks[0]);
+    f.render_stateful_widget(results, chunks[1], &mut app.command_palette_results.state);
+}
+
+/// asks in the middle of `area` whether to delete the feed, saying how much goes with it
+fn draw_feed_deletion_confirmation<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed_id = match app.feed_to_delete {
+        Some(feed_id) => feed_id,
+        None => return,
+    };
+
+    let feed_title = app
+        .feeds
+        .items
+        .iter()
+        .find(|feed| feed.id == feed_id)
+        .and_then(|feed| feed.title.as_deref())
+        .unwrap_or("No feed title");
+    let entries_len = app
+        .feed_entry_counts
+        .get(&feed_id)
+        .map(|entry_counts| entry_counts.total)
+        .unwrap_or_default();
+
+    let theme = &app.config.theme;
+
+    let text = vec![
+        Spans::from(format!(
+            "Delete \"{}\" and its {} entries?",
+            feed_title, entries_len
+        )),
+        Spans::default(),
+        Spans::from(vec![
+            Span::styled("y", theme.highlight_style()),
+            Span::raw(" - delete, "),
+            Span::styled("n", theme.highlight_style()),
+            Span::raw(" - keep it"),
+        ]),
+    ];
+
+    let popup = centered_rect(50, 20, area);
+    let confirmation = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.error))
+                .title(Span::styled("Delete feed", theme.error_style())),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(confirmation, popup);
+}
+
+/// asks in the middle of `area` whether to move the feed to where it has permanently moved
+fn draw_feed_move_confirmation<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed = match app
+        .feed_to_move
+        .and_then(|feed_id| app.feeds.items.iter().find(|feed| feed.id == feed_id))
+    {
+        Some(feed) => feed,
+        None => return,
+    };
+
+    let theme = &app.config.theme;
+
+    let text = vec![
+        Spans::from(format!(
+            "\"{}\" has permanently moved",
+            feed.title.as_deref().unwrap_or("No feed title")
+        )),
+        Spans::default(),
+        Spans::from(format!(
+            "from {}",
+            feed.feed_link.as_deref().unwrap_or_default()
+        )),
+        Spans::from(format!(
+            "to {}",
+            feed.moved_to.as_deref().unwrap_or_default()
+        )),
+        Spans::default(),
+        Spans::from(vec![
+            Span::styled("y", theme.highlight_style()),
+            Span::raw(" - use the new URL, "),
+            Span::styled("n", theme.highlight_style()),
+            Span::raw(" - keep the old one"),
+        ]),
+    ];
+
+    let popup = centered_rect(60, 30, area);
+    let confirmation = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled("Feed moved", theme.title_style())),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(confirmation, popup);
+}
+
+fn draw_subscription_confirmation<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let feed_preview = match &app.feed_preview {
+        Some(feed_preview) => feed_preview,
+        None => return,
+    };
+
+    let theme = &app.config.theme;
+
+    let mut text = vec![
+        Spans::from(Span::styled(
+            feed_preview.title().unwrap_or("No feed title").to_string(),
+            theme.title_style(),
+        )),
+        Spans::from(feed_preview.url.clone()),
+    ];
+
+    if let Some(description) = feed_preview.description() {
+        text.push(Spans::default());
+        text.push(Spans::from(description));
+    }
+
+    text.push(Spans::default());
+    text.push(Spans::from(format!(
+        "{} entries, the latest:",
+        feed_preview.entries_len()
+    )));
+    for title in feed_preview.latest_entry_titles(FEED_PREVIEW_ENTRIES_LEN) {
+        text.push(Spans::from(format!("  {}", title)));
+    }
+
+    text.push(Spans::default());
+    text.push(Spans::from(vec![
+        Span::styled("y", theme.highlight_style()),
+        Span::raw(" - subscribe, "),
+        Span::styled("n", theme.highlight_style()),
+        Span::raw(" - change the URL"),
+    ]));
+
+    let popup = centered_rect(60, 50, area);
+    let confirmation = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled("Subscribe to this feed?", theme.title_style())),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(confirmation, popup);
+}
+
+/// a scrollable popup with every key the keymap has, for what is selected first
+fn draw_key_help<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(70, 80, area);
+    let theme = &app.config.theme;
+
+    let mut panes = vec![
+        (Pane::Feeds, "Feeds"),
+        (Pane::Entries, "Entries"),
+        (Pane::Entry, "Reading an entry"),
+    ];
+    let current_pane = Pane::from(&app.selected);
+    panes.sort_by_key(|(pane, _)| *pane != current_pane);
+
+    let mut lines = vec![];
+
+    for (pane, heading) in panes {
+        if !lines.is_empty() {
+            lines.push(Spans::default());
+        }
+        lines.push(Spans::from(Span::styled(heading, theme.title_style())));
+
+        for (action, keys) in app.config.keymap.bound_keys(pane) {
+            let keys = keys
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(Spans::from(vec![
+                Span::styled(format!("  {:<16}", keys), theme.highlight_style()),
+                Span::raw(action.description()),
+            ]));
+        }
+    }
+
+    // inside the block's borders
+    let max_scroll = (lines.len() as u16).saturating_sub(popup.height.saturating_sub(2));
+    app.popup_scroll = app.popup_scroll.min(max_scroll);
+
+    let help = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(
+                    "Keys - j/k to scroll, esc to close",
+                    theme.title_style(),
+                )),
+        )
+        .scroll((app.popup_scroll, 0));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(help, popup);
+}
+
+fn draw_error_log<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(80, 80, area);
+    let theme = &app.config.theme;
+    // inside the block's borders, and indented
+    let message_width = popup.width.saturating_sub(4) as usize;
+
+    let mut lines = vec![];
+
+    for logged_error in app.error_log.errors().iter().rev() {
+        let mut heading = vec![Span::styled(
+            app.format_date(logged_error.at),
+            theme.title_style(),
+        )];
+        if let Some(feed) = &logged_error.feed {
+            heading.push(Span::raw(format!(" - {}", feed)));
+        }
+        lines.push(Spans::from(heading));
+
+        for line in logged_error.wrapped_message(message_width) {
+            lines.push(Spans::from(Span::styled(
+                format!("  {}", line),
+                theme.error_style(),
+            )));
+        }
+    }
+
+    let title = if lines.is_empty() {
+        "No errors - esc to close".to_string()
+    } else {
+        format!(
+            "Errors: {}, newest first - j/k to scroll, esc to close",
+            app.error_log.errors().len()
+        )
+    };
+
+    let max_scroll = (lines.len() as u16).saturating_sub(popup.height.saturating_sub(2));
+    app.popup_scroll = app.popup_scroll.min(max_scroll);
+
+    let error_log = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .scroll((app.popup_scroll, 0));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(error_log, popup);
+}
+
+fn draw_raw_feed<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let raw_feed = match &app.raw_feed {
+        Some(raw_feed) => raw_feed,
+        None => return,
+    };
+
+    let popup = centered_rect(90, 90, area);
+    let theme = &app.config.theme;
+    // inside the block's borders
+    let width = popup.width.saturating_sub(2) as usize;
+    let height = popup.height.saturating_sub(2);
+
+    // feeds are often one long line, so they are broken wherever they reach the edge
+    let lines = raw_feed
+        .body
+        .lines()
+        .flat_map(|line| wrap_columns(&line.replace('\t', "    "), width))
+        .collect::<Vec<_>>();
+
+    let max_scroll = (lines.len() as u16).saturating_sub(height);
+    app.popup_scroll = app.popup_scroll.min(max_scroll);
+
+    let text = lines
+        .into_iter()
+        .skip(app.popup_scroll as usize)
+        .take(height as usize)
+        .map(Spans::from)
+        .collect::<Vec<_>>();
+
+    let title = format!(
+        "Fetched {}, {} bytes ({} in the database) - j/k to scroll, esc to close",
+        app.format_date(raw_feed.fetched_at),
+        raw_feed.body.len(),
+        raw_feed.compressed_len
+    );
+
+    let raw_feed = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_style())
+            .title(Span::styled(title, theme.title_style())),
+    );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(raw_feed, popup);
+}
+
+/// `line` broken into lines `width` columns wide, the last one narrower
+fn wrap_columns(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![String::new()];
+    let mut line_width = 0;
+
+    for c in line.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if line_width + c_width > width {
+            lines.push(String::new());
+            line_width = 0;
+        }
+        lines.last_mut().unwrap().push(c);
+        line_width += c_width;
+    }
+
+    lines
+}
+
+fn draw_entry_note_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let entry_title = match &app.selected {
+        Selected::Entry(entry_meta) => entry_meta.title.as_deref(),
+        _ => app
+            .current_entry_meta
+            .as_ref()
+            .and_then(|entry_meta| entry_meta.title.as_deref()),
+    }
+    .unwrap_or("No entry title");
+
+    let theme = &app.config.theme;
+    let title = format!("Note on {} - enter to save, esc to cancel", entry_title);
+
+    let popup = centered_rect(60, 20, area);
+    let input = Paragraph::new(app.entry_note_input.as_str())
+        .style(Style::default().fg(theme.input))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(input, popup);
+}
+
+fn draw_saved_search_name_input<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let theme = &app.config.theme;
+    let title = format!(
+        "Save the search for {} as - enter to save, esc to cancel",
+        app.search_input.trim()
+    );
+
+    let popup = centered_rect(60, 20, area);
+    let input = Paragraph::new(app.saved_search_name_input.as_str())
+        .style(Style::default().fg(theme.input))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        );
+
+    f.render_widget(Clear, popup);
+    f.render_widget(input, popup);
+}
+
+/// a popup over the middle of `area` listing the saved searches, each with its query
+fn draw_saved_searches<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let popup = centered_rect(60, 50, area);
+    let theme = &app.config.theme;
+
+    let saved_searches = app
+        .saved_searches
+        .items
+        .iter()
+        .map(|saved_search| {
+            ListItem::new(Spans::from(vec![
+                Span::raw(saved_search.name.as_str()),
+                Span::styled(format!(" - {}", saved_search.query), theme.border_style()),
+            ]))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let title = if saved_searches.is_empty() {
+        "No saved searches, ctrl-s saves one while searching - esc to close"
+    } else {
+        "Saved searches - enter to search, d to delete, esc to close"
+    };
+
+    let saved_searches = List::new(saved_searches)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("> ");
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(saved_searches, popup, &mut app.saved_searches.state);
+}
+
+/// a rect of the given percentages of `area`, centered in it
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
+fn draw_search_results<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let default_entry_title = "No entry title".to_string();
+    let default_feed_title = "No feed title".to_string();
+
+    let feeds = &app.feeds.items;
+    let theme = &app.config.theme;
+
+    let results = app
+        .search_results
+        .items
+        .iter()
+        .map(|entry| {
+            let entry_title = entry.title.as_ref().unwrap_or(&default_entry_title);
+            let feed_title = feeds
+                .iter()
+                .find(|feed| feed.id == entry.feed_id)
+                .and_then(|feed| feed.title.as_ref())
+                .unwrap_or(&default_feed_title);
+
+            ListItem::new(Spans::from(vec![
+                Span::styled(entry_title, theme.entry_style(entry.read_at.is_some())),
+                Span::styled(" - ", theme.border_style()),
+                Span::styled(feed_title, theme.border_style()),
+            ]))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let title = format!("Search results: {}", app.search_results.items.len());
+
+    let results = List::new(results)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_style())
+                .title(Span::styled(title, theme.title_style())),
+        )
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("> ");
+
+    f.render_stateful_widget(results, area, &mut app.search_results.state);
+}
+
+fn draw_entries<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let marked_entry_ids = app.marked_entry_ids();
+    let entry_sort = app.entry_sort();
+    let now = chrono::Utc::now();
+
+    let entries = app
+        .entries
+        .items
+        .iter()
+        .map(|entry| {
+            let entry_title = entry
+                .title
+                .as_ref()
+                .unwrap_or_else(|| panic!("Unable to get title for entry id {}", entry.id));
+
+            let feed_links = app
+                .feeds
+                .items
+                .iter()
+                .find(|feed| feed.id == entry.feed_id)
+                .map(|feed| feed.feed_links())
+                .unwrap_or_default();
+
+            let mut title_style = if marked_entry_ids.contains(&entry.id) {
+                app.config.theme.marked_entry_style(entry.read_at.is_some())
+            } else {
+                app.config.theme.entry_style(entry.read_at.is_some())
+            };
+
+            if app
+                .config
+                .highlight_keywords
+                .is_match(&feed_links, entry_title)
+            {
+                title_style = title_style.patch(app.config.theme.highlight_keyword_style());
+            }
+
+            let date = crate::days::entry_date(entry, entry_sort);
+            let date = match app.config.entry_dates {
+                EntryDates::Relative => crate::util::format_age(date, now),
+                EntryDates::Absolute => {
+                    crate::util::format_date(date, &chrono::Local, &app.config.day_format)
+                }
+            };
+
+            let feed_title = app
+                .feeds
+                .items
+                .iter()
+                .find(|feed| feed.id == entry.feed_id)
+                .and_then(|feed| feed.title.as_deref())
+                .unwrap_or("No feed title");
+
+            if let Some(entry_format) = &app.config.entry_format {
+                let fields = EntryFields {
+                    date,
+                    title: entry_title.to_owned(),
+                    author: entry.author.clone().unwrap_or_default(),
+                    feed: feed_title.to_owned(),
+                    flags: format!(
+                        "{}{}",
+                        if entry.read_at.is_none() { 'N' } else { ' ' },
+                        if entry.starred { '*' } else { ' ' }
+                    ),
+                    reading_time: if entry.word_count > 0 {
+                        crate::util::format_reading_time(
+                            entry.word_count,
+                            app.config.words_per_minute,
+                        )
+                    } else {
+                        String::new()
+                    },
+                    note: if app.entry_notes.contains_key(&entry.id) {
+                        "[note]".to_owned()
+                    } else {
+                        String::new()
+                    },
+                    updated: if entry.revised_at.is_some() {
+                        "[updated]".to_owned()
+                    } else {
+                        String::new()
+                    },
+                };
+
+                return ListItem::new(Spans::from(entry_format.spans(
+                    &fields,
+                    title_style,
+                    app.config.theme.border_style(),
+                )));
+            }
+
+            let title = Span::styled(
+                if entry.starred {
+                    format!("* {}", entry_title)
+                } else {
+                    entry_title.to_owned()
+                },
+                title_style,
+            );
+
+            // padded, so the titles line up
+            let date = match app.config.entry_dates {
+                EntryDates::Relative => format!("{:>3} ", date),
+                EntryDates::Absolute => format!("{} ", date),
+            };
+
+            let mut spans = vec![Span::styled(date, app.config.theme.border_style()), title];
+
+            if let Some(author) = &entry.author {
+                spans.push(Span::styled(
+                    format!(" by {}", author),
+                    app.config.theme.border_style(),
+                ));
+            }
+
+            if entry.word_count > 0 {
+                spans.push(Span::styled(
+                    format!(
+                        " ({})",
+                        crate::util::format_reading_time(
+                            entry.word_count,
+                            app.config.words_per_minute
+                        )
+                    ),
+                    app.config.theme.border_style(),
+                ));
+            }
+
+            if app.entry_notes.contains_key(&entry.id) {
+                spans.push(Span::styled(" [note]", app.config.theme.border_style()));
+            }
+
+            if entry.revised_at.is_some() {
+                spans.push(Span::styled(" [updated]", app.config.theme.border_style()));
+            }
+
+            // entries from every feed are labeled with the feed they came from
+            if app.current_virtual_feed.is_some() {
+                spans.push(Span::styled(" - ", app.config.theme.border_style()));
+                spans.push(Span::styled(feed_title, app.config.theme.border_style()));
+            }
+
+            ListItem::new(Spans::from(spans))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let default_title = "Entries".to_string();
+
+    let mut title = match app.current_virtual_feed {
+        Some(virtual_feed) => app.virtual_feed_title(virtual_feed),
+        None => app
+            .current_feed
+            .as_ref()
+            .and_then(|feed| feed.title.as_ref())
+            .unwrap_or(&default_title)
+            .to_owned(),
+    };
+
+    if entry_sort != EntrySort::default() {
+        title.push_str(" (sort: ");
+        title.push_str(&entry_sort.to_string());
+        title.push(')');
+    }
+
+    if !app.date_range.is_unbounded() {
+        title.push_str(" (dates: ");
+        title.push_str(app.date_range_input.trim());
+        title.push(')');
+    }
+
+    if !app.entry_filter.is_empty() {
+        title.push_str(" (filter: ");
+        title.push_str(&app.entry_filter);
+        title.push(')');
+    }
+
+    if app.visual_anchor.is_some() || !marked_entry_ids.is_empty() {
+        title.push_str(&format!(" ({} marked", marked_entry_ids.len()));
+        if app.visual_anchor.is_some() {
+            title.push_str(", visual");
+        }
+        title.push(')');
+    }
+
+    let entries_area = if !&app.error_flash.is_empty() {
+        let chunks = Layout::default()
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(30)].as_ref())
+            .direction(Direction::Vertical)
+            .split(area);
+        {
+            let error_text = error_text(&app.error_flash);
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.config.theme.error))
+                .title(Span::styled(
+                    "Error - press 'q' to close",
+                    app.config.theme.error_style(),
+                ));
+
+            let error_widget = Paragraph::new(error_text)
+                .block(block)
+                .wrap(Wrap { trim: false })
+                .scroll((0, 0));
+
+            f.render_widget(error_widget, chunks[1]);
+        }
+        chunks[0]
+    } else {
+        area
+    };
+
+    // scrolled here rather than by tui, as the day headers are rows but not entries
+    let rows = app.entry_rows();
+    let height = entries_area.height.saturating_sub(2) as usize;
+    let selected_row = app
+        .entries
+        .state
+        .selected()
+        .and_then(|idx| rows.iter().position(|row| *row == EntryRow::Entry(idx)));
+
+    if let Some(selected_row) = selected_row {
+        // a day's first entry brings its header into view with it
+        if let Some(header_row) = selected_row
+            .checked_sub(1)
+            .filter(|row| matches!(rows[*row], EntryRow::Day(_)))
+        {
+            app.entries.scroll_to_row(header_row, rows.len(), height);
+        }
+        app.entries.scroll_to_row(selected_row, rows.len(), height);
+    } else {
+        app.entries.scroll_to_row(0, rows.len(), height);
+    }
+
+    let offset = app.entries.offset();
+    let visible_rows = rows
+        .iter()
+        .skip(offset)
+        .take(height)
+        .map(|row| match row {
+            EntryRow::Day(day) => {
+                ListItem::new(Span::styled(day.clone(), app.config.theme.title_style()))
+            }
+            EntryRow::Entry(idx) => entries[*idx].clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut state = ListState::default();
+    state.select(
+        selected_row
+            .map(|row| row - offset)
+            .filter(|row| *row < visible_rows.len()),
+    );
+
+    let entries_titles = List::new(visible_rows).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.config.theme.border_style())
+            .title(Span::styled(title, app.config.theme.title_style())),
+    );
+
+    let entries_titles = match app.selected {
+        Selected::Entries => entries_titles
+            .highlight_style(app.config.theme.highlight_style())
+            .highlight_symbol("> "),
+        // beside the entry being read, which entry it is
+        Selected::Entry(_) => entries_titles.highlight_style(app.config.theme.highlight_style()),
+        _ => entries_titles,
+    };
+
+    f.render_stateful_widget(entries_titles, entries_area, &mut state);
+    app.entries_area = Some(entries_area);
+}
+
+fn draw_entry<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
+where
+    B: Backend,
+{
+    let scroll = app.entry_scroll_position;
+    let entry_meta = match app.reading_entry() {
+        Some(entry_meta) => entry_meta.clone(),
+        // the three-pane layout's reading pane, with no entry selected
+        None => {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.config.theme.border_style());
+            f.render_widget(block, area);
+            return;
+        }
+    };
+    let default_entry_title = "No entry title".to_string();
+    let default_feed_title = "No feed title".to_string();
+
+    let entry_title = entry_meta.title.as_ref().unwrap_or(&default_entry_title);
+
+    let feed_title = app
+        .feeds
+        .items
+        .iter()
+        .find(|feed| feed.id == entry_meta.feed_id)
+        .and_then(|feed| feed.title.as_ref())
+        .unwrap_or(&default_feed_title);
+
+    let mut title = entry_title.to_owned();
+    if let Some(author) = &entry_meta.author {
+        title.push_str(" by ");
+        title.push_str(author);
+    }
+    title.push_str(" - ");
+    title.push_str(feed_title);
+    if entry_meta.word_count > 0 {
+        title.push_str(&format!(
+            " - {} words, {}",
+            entry_meta.word_count,
+            crate::util::format_reading_time(entry_meta.word_count, app.config.words_per_minute)
+        ));
+    }
+
+    // zen mode leaves out the borders and the progress gauge, unless there are errors to show.
+    // the text stays where the borders would have it, so links are where they always are
+    if app.is_zen_reading() && app.error_flash.is_empty() {
+        let title_area = Rect::new(area.x + 1, area.y, area.width.saturating_sub(2), 1);
+        let text_area = Rect::new(
+            area.x + 1,
+            area.y + 1,
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        );
+
+        let title = Paragraph::new(Span::styled(&title, app.config.theme.title_style()));
+        let paragraph = Paragraph::new(app.rendered_entry.text.clone())
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+
+        app.entry_lines_rendered_len = text_area.height;
+        app.entry_text_area = Some(area);
+        f.render_widget(title, title_area);
+        f.render_widget(paragraph, text_area);
+        return;
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.config.theme.border_style())
+        .title(Span::styled(&title, app.config.theme.title_style()));
+
+    let paragraph = Paragraph::new(app.rendered_entry.text.clone())
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+
+    let entry_chunk_height = area.height - 2;
+
+    let progress_gauge_chunk_percent = 3;
+
+    let entry_percent = 100.0 - progress_gauge_chunk_percent as f32;
+
+    let real_entry_chunk_height =
+        (entry_chunk_height as f32 * (entry_percent / 100.0)).floor() as u16;
+
+    app.entry_lines_rendered_len = real_entry_chunk_height;
 
     let percent = if app.entry_lines_len > 0 {
         let furthest_visible_position = app.entry_scroll_position + real_entry_chunk_height;
@@ -441,7 +1980,7 @@ fn draw_entry<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
     let ratio = percent as f64 / 100.0;
     let gauge = LineGauge::default()
         .block(Block::default().borders(Borders::NONE))
-        .gauge_style(Style::default().fg(Color::Rgb(255, 150, 167)))
+        .gauge_style(Style::default().fg(app.config.theme.highlight))
         .ratio(ratio)
         .label(label);
 
@@ -459,18 +1998,20 @@ fn draw_entry<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
             .split(area);
         {
             let error_text = error_text(&app.error_flash);
-            let block = Block::default().borders(Borders::ALL).title(Span::styled(
-                "Error - press 'q' to close",
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::Cyan),
-            ));
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.config.theme.error))
+                .title(Span::styled(
+                    "Error - press 'q' to close",
+                    app.config.theme.error_style(),
+                ));
 
             let error_widget = Paragraph::new(error_text)
                 .block(block)
                 .wrap(Wrap { trim: false })
                 .scroll((0, 0));
 
+            app.entry_text_area = Some(chunks[0]);
             f.render_widget(paragraph, chunks[0]);
             f.render_widget(gauge, chunks[1]);
             f.render_widget(error_widget, chunks[2]);
@@ -487,11 +2028,157 @@ fn draw_entry<B>(f: &mut Frame<B>, area: Rect, app: &mut AppImpl)
             .direction(Direction::Vertical)
             .split(area);
 
+        app.entry_text_area = Some(chunks[0]);
         f.render_widget(paragraph, chunks[0]);
         f.render_widget(gauge, chunks[1]);
     }
 }
 
+/// makes the title and the visible links of the entry being read clickable.
+/// tui cannot draw OSC 8 hyperlinks, so they are written over what it drew
+pub fn draw_hyperlinks<W: std::io::Write>(w: &mut W, app: &AppImpl) -> std::io::Result<()> {
+    let (area, entry_meta) = match (app.entry_text_area, app.reading_entry()) {
+        (Some(area), Some(entry_meta)) => (area, entry_meta),
+        _ => return Ok(()),
+    };
+
+    // inside the block's borders
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let scroll = app.entry_scroll_position as usize;
+
+    if let (Some(title), Some(link)) = (&entry_meta.title, &entry_meta.link) {
+        if Span::raw(title.as_str()).width() <= inner_width {
+            let style = app.config.theme.title_style();
+            draw_hyperlink(w, (area.x + 1, area.y), link, title, style)?;
+        }
+    }
+
+    for link_span in &app.rendered_entry.link_spans {
+        let is_visible = link_span.line >= scroll
+            && link_span.line < scroll + inner_height
+            && link_span.column + Span::raw(link_span.text.as_str()).width() <= inner_width;
+
+        if !is_visible {
+            continue;
+        }
+
+        if let Some(link) = app.rendered_entry.links.get(link_span.link_number - 1) {
+            let position = (
+                area.x + 1 + link_span.column as u16,
+                area.y + 1 + (link_span.line - scroll) as u16,
+            );
+            draw_hyperlink(w, position, link, &link_span.text, link_span.style)?;
+        }
+    }
+
+    w.flush()
+}
+
+/// draws the fetched images of the entry being read where tui left room for them.
+/// they are only drawn again when they move
+pub fn draw_images<W: std::io::Write>(
+    w: &mut W,
+    app: &mut AppImpl,
+    protocol: GraphicsProtocol,
+) -> std::io::Result<()> {
+    let visible_images = visible_images(app);
+
+    if visible_images == app.drawn_images {
+        return Ok(());
+    }
+
+    if protocol == GraphicsProtocol::Kitty {
+        crossterm::queue!(w, crossterm::style::Print(crate::images::DELETE_ALL_IMAGES))?;
+    }
+
+    for (x, y, placement) in &visible_images {
+        let sequence = app
+            .image_cache
+            .get(&placement.url)
+            .and_then(|image| image.sequence(protocol, placement.columns, placement.rows));
+
+        if let Some(sequence) = sequence {
+            crossterm::queue!(
+                w,
+                crossterm::cursor::MoveTo(*x, *y),
+                crossterm::style::Print(sequence)
+            )?;
+        }
+    }
+
+    app.drawn_images = visible_images;
+
+    w.flush()
+}
+
+/// the images that fit whole in the entry's visible lines, and where they go on the screen
+pub fn visible_images(app: &AppImpl) -> Vec<(u16, u16, crate::html::ImagePlacement)> {
+    let area = match (app.entry_text_area, app.reading_entry()) {
+        (Some(area), Some(_)) if matches!(app.mode, Mode::Normal) => area,
+        _ => return vec![],
+    };
+
+    // inside the block's borders
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let scroll = app.entry_scroll_position as usize;
+
+    app.rendered_entry
+        .image_placements
+        .iter()
+        .filter(|placement| {
+            placement.line >= scroll
+                && placement.line + placement.rows as usize <= scroll + inner_height
+                && placement.column + placement.columns as usize <= inner_width
+        })
+        .map(|placement| {
+            (
+                area.x + 1 + placement.column as u16,
+                area.y + 1 + (placement.line - scroll) as u16,
+                placement.clone(),
+            )
+        })
+        .collect()
+}
+
+fn draw_hyperlink<W: std::io::Write>(
+    w: &mut W,
+    (x, y): (u16, u16),
+    link: &str,
+    text: &str,
+    style: Style,
+) -> std::io::Result<()> {
+    use crossterm::style::{
+        Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+    };
+    use tui::style::Modifier;
+
+    crossterm::queue!(w, crossterm::cursor::MoveTo(x, y))?;
+
+    if let Some(fg) = style.fg {
+        crossterm::queue!(w, SetForegroundColor(fg.into()))?;
+    }
+    if let Some(bg) = style.bg {
+        crossterm::queue!(w, SetBackgroundColor(bg.into()))?;
+    }
+    for (modifier, attribute) in [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            crossterm::queue!(w, SetAttribute(attribute))?;
+        }
+    }
+
+    crossterm::queue!(
+        w,
+        Print(crate::util::osc8_sequence(link, text)),
+        SetAttribute(Attribute::Reset)
+    )
+}
+
 fn error_text(errors: &[anyhow::Error]) -> String {
     errors
         .iter()
@@ -1,9 +1,12 @@
+use crate::images::GraphicsProtocol;
 use tui::widgets::ListState;
 
 #[derive(Debug)]
 pub struct StatefulList<T> {
     pub state: ListState,
     pub items: Vec<T>,
+    /// the index of the item at the top of the list, as it was last drawn
+    offset: usize,
 }
 
 impl<T> StatefulList<T> {
@@ -11,9 +14,46 @@ pub fn with_items(items: Vec<T>) -> StatefulList<T> {
         StatefulList {
             state: ListState::default(),
             items,
+            offset: 0,
         }
     }
 
+    /// scrolls the list the way tui's `List` does when it is drawn `height` rows tall,
+    /// as tui does not tell where it scrolled to
+    pub fn scroll_to_selected(&mut self, height: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.scroll_to_row(selected, self.items.len(), height);
+    }
+
+    /// like `scroll_to_selected`, for a list drawn with `rows_len` rows, some of which are not items,
+    /// like headers. the offset is then the row at the top, and `row` is what is scrolled to
+    pub fn scroll_to_row(&mut self, row: usize, rows_len: usize, height: usize) {
+        if rows_len == 0 {
+            self.offset = 0;
+            return;
+        }
+
+        let row = row.min(rows_len - 1);
+        self.offset = self.offset.min(rows_len - 1);
+
+        if row < self.offset {
+            self.offset = row;
+        } else if height > 0 && row >= self.offset + height {
+            self.offset = row + 1 - height;
+        }
+    }
+
+    /// the row at the top of the list, as it was last drawn
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the index of the item drawn `row` rows from the top of the list
+    pub fn item_at_row(&self, row: usize) -> Option<usize> {
+        let idx = self.offset + row;
+        (idx < self.items.len()).then_some(idx)
+    }
+
     pub fn next(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
@@ -57,8 +97,343 @@ fn from(other: Vec<T>) -> Self {
     }
 }
 
+/// opens `link` with `open_command` if given, otherwise with `$BROWSER`,
+/// otherwise with the platform's default opener
+pub(crate) fn open_link(link: &str, open_command: Option<&str>) -> anyhow::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let open_command = open_command
+        .map(|command| command.to_owned())
+        .or_else(|| std::env::var("BROWSER").ok())
+        .unwrap_or_else(|| default_open_command().to_owned());
+
+    let args = command_args(&open_command, link);
+
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("The open command is empty"))?;
+
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Unable to run {}: {}", program, e))?;
+
+    Ok(())
+}
+
+/// plays `link` with `play_command` in the foreground, and waits for it to exit
+pub(crate) fn play(link: &str, play_command: &str) -> anyhow::Result<()> {
+    let args = command_args(play_command, link);
+
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("The play command is empty"))?;
+
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Unable to run {}: {}", program, e))?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} failed with {}", program, status));
+    }
+
+    Ok(())
+}
+
+/// runs `command` in the foreground with `input` as its stdin, and waits for it to exit
+pub(crate) fn pipe(input: &str, command: &str) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let args = command.split_whitespace().collect::<Vec<_>>();
+
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("The pipe command is empty"))?;
+
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Unable to run {}: {}", program, e))?;
+
+    // a command can quit before reading all of its input, like `less` does
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input.as_bytes());
+    }
+
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} failed with {}", command, status));
+    }
+
+    Ok(())
+}
+
+/// splits a command on whitespace and puts `arg` in place of `{}`,
+/// or at the end if there is no `{}`
+pub(crate) fn command_args(command: &str, arg: &str) -> Vec<String> {
+    let mut args = command
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect::<Vec<_>>();
+
+    if args.iter().any(|a| a.contains("{}")) {
+        for a in args.iter_mut() {
+            *a = a.replace("{}", arg);
+        }
+    } else {
+        args.push(arg.to_owned());
+    }
+
+    args
+}
+
+/// `template` with `{url}` replaced with `link`, `{host}` with its host,
+/// and `{path}` with its path, query, and fragment, like `https://nitter.net{path}`.
+/// a link that is not a URL is left as it is
+pub(crate) fn expand_link_template(template: &str, link: &str) -> String {
+    let url = match url::Url::parse(link) {
+        Ok(url) => url,
+        Err(_) => return link.to_owned(),
+    };
+
+    let path = &url[url::Position::BeforePath..];
+
+    template
+        .replace("{url}", link)
+        .replace("{host}", url.host_str().unwrap_or_default())
+        .replace("{path}", path)
+}
+
+fn default_open_command() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
+
+/// puts `s` in the clipboard.
+/// over SSH, or when there is no system clipboard to talk to (like on a headless box),
+/// this asks the terminal to do it with an OSC 52 escape sequence instead.
+pub(crate) fn set_clipboard_contents(s: &str, is_wsl: bool) -> anyhow::Result<()> {
+    use copypasta::{ClipboardContext, ClipboardProvider};
+
+    if is_wsl {
+        #[cfg(target_os = "linux")]
+        {
+            return set_wsl_clipboard_contents(s);
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            unreachable!("This should never happen. This code should only be reachable if the target OS is WSL.")
+        }
+    }
+
+    if is_ssh_session() {
+        return set_osc52_clipboard_contents(s);
+    }
+
+    match ClipboardContext::new() {
+        Ok(mut ctx) => ctx
+            .set_contents(s.to_owned())
+            .map_err(|e| anyhow::anyhow!(e)),
+        Err(_) => set_osc52_clipboard_contents(s),
+    }
+}
+
+/// what is in the clipboard, as text.
+/// over SSH there is no clipboard to read, as terminals rarely answer OSC 52 queries
+pub(crate) fn get_clipboard_contents(is_wsl: bool) -> anyhow::Result<String> {
+    use copypasta::{ClipboardContext, ClipboardProvider};
+
+    if is_wsl {
+        #[cfg(target_os = "linux")]
+        {
+            return get_wsl_clipboard_contents();
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            unreachable!("This should never happen. This code should only be reachable if the target OS is WSL.")
+        }
+    }
+
+    if is_ssh_session() {
+        return Err(anyhow::anyhow!(
+            "Russ cannot read the clipboard over SSH, paste the URL instead"
+        ));
+    }
+
+    ClipboardContext::new()
+        .and_then(|mut ctx| ctx.get_contents())
+        .map_err(|e| anyhow::anyhow!("Unable to read the clipboard: {}", e))
+}
+
+/// query parameters that only say where a link was clicked, by name or by prefix
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi",
+];
+const TRACKING_PARAM_PREFIXES: &[&str] = &["utm_"];
+
+/// the URL in a form two URLs of the same feed or page share: https rather than http, without
+/// a trailing slash, a fragment, or tracking parameters, and with the host in lowercase.
+/// for comparing URLs only, as the feed or page may not be at the normalized one
+pub(crate) fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+
+    let mut parsed = match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => return url.to_owned(),
+    };
+
+    // the port goes with http, like http://example.com:80, which parsing already dropped
+    let _ = parsed.set_scheme("https");
+    parsed.set_fragment(None);
+
+    let query_pairs = parsed
+        .query_pairs()
+        .filter(|(name, _)| {
+            !TRACKING_PARAMS.contains(&name.as_ref())
+                && !TRACKING_PARAM_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if query_pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query_pairs);
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_owned();
+    parsed.set_path(&path);
+
+    parsed.to_string()
+}
+
+/// the first http or https URL in `text`, like a copied link with some text around it.
+/// `feed:` URLs, which some browsers hand feeds out as, are turned into the URL they wrap
+pub(crate) fn find_feed_url(text: &str) -> Option<String> {
+    text.split_whitespace().find_map(|word| {
+        let word = match word.strip_prefix("feed:") {
+            Some(wrapped) if wrapped.starts_with("//") => format!("http:{}", wrapped),
+            Some(wrapped) => wrapped.to_owned(),
+            None => word.to_owned(),
+        };
+
+        url::Url::parse(&word)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(|_| word)
+    })
+}
+
+fn is_ssh_session() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+fn set_osc52_clipboard_contents(s: &str) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52_sequence(s, std::env::var_os("TMUX").is_some()).as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// tmux swallows OSC 52 unless it is wrapped in a DCS passthrough
+fn osc52_sequence(s: &str, is_tmux: bool) -> String {
+    let osc52 = format!("\x1b]52;c;{}\x07", base64::encode(s));
+
+    if is_tmux {
+        format!("\x1bPtmux;{}\x1b\\", osc52.replace('\x1b', "\x1b\x1b"))
+    } else {
+        osc52
+    }
+}
+
+/// a link that terminals supporting OSC 8 make clickable
+pub(crate) fn osc8_sequence(link: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", link, text)
+}
+
+/// whether the terminal is known to make OSC 8 hyperlinks clickable
+pub(crate) fn terminal_supports_hyperlinks() -> bool {
+    supports_hyperlinks(|name| std::env::var(name).ok())
+}
+
+fn supports_hyperlinks(var: impl Fn(&str) -> Option<String>) -> bool {
+    // multiplexers pass them on inconsistently, if at all
+    if var("TMUX").is_some() || var("STY").is_some() {
+        return false;
+    }
+
+    let is_known_terminal = matches!(
+        var("TERM_PROGRAM").as_deref(),
+        Some("iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper")
+    ) || ["KITTY_WINDOW_ID", "WT_SESSION", "KONSOLE_VERSION"]
+        .iter()
+        .any(|name| var(name).is_some());
+
+    // VTE terminals, like GNOME Terminal, since 0.50
+    let is_new_vte = var("VTE_VERSION")
+        .and_then(|version| version.parse::<u32>().ok())
+        .is_some_and(|version| version >= 5000);
+
+    let is_known_term = var("TERM").is_some_and(|term| {
+        ["kitty", "alacritty", "foot", "wezterm"]
+            .iter()
+            .any(|name| term.contains(name))
+    });
+
+    is_known_terminal || is_new_vte || is_known_term
+}
+
+/// how the terminal is known to draw images, if it is.
+/// `None` inside tmux and screen, and in terminals that draw neither kitty graphics nor sixels
+pub(crate) fn terminal_graphics_protocol() -> Option<GraphicsProtocol> {
+    graphics_protocol(|name| std::env::var(name).ok())
+}
+
+fn graphics_protocol(var: impl Fn(&str) -> Option<String>) -> Option<GraphicsProtocol> {
+    // they do not pass the escape sequences on without being set up to
+    if var("TMUX").is_some() || var("STY").is_some() {
+        return None;
+    }
+
+    let term_program = var("TERM_PROGRAM");
+    let term = var("TERM").unwrap_or_default();
+    let term_is = |names: &[&str]| names.iter().any(|name| term.contains(name));
+
+    if matches!(term_program.as_deref(), Some("WezTerm" | "ghostty"))
+        || ["KITTY_WINDOW_ID", "KONSOLE_VERSION"]
+            .iter()
+            .any(|name| var(name).is_some())
+        || term_is(&["kitty", "wezterm", "ghostty"])
+    {
+        Some(GraphicsProtocol::Kitty)
+    } else if matches!(term_program.as_deref(), Some("iTerm.app" | "mintty"))
+        || term_is(&["foot", "mlterm", "contour"])
+    {
+        Some(GraphicsProtocol::Sixel)
+    } else {
+        None
+    }
+}
+
 #[cfg(target_os = "linux")]
-pub(crate) fn set_wsl_clipboard_contents(s: &str) -> anyhow::Result<()> {
+fn set_wsl_clipboard_contents(s: &str) -> anyhow::Result<()> {
     use std::{
         io::Write,
         process::{Command, Stdio},
@@ -77,3 +452,341 @@ pub(crate) fn set_wsl_clipboard_contents(s: &str) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(target_os = "linux")]
+fn get_wsl_clipboard_contents() -> anyhow::Result<String> {
+    let output = std::process::Command::new("powershell.exe")
+        .args(["-NoProfile", "-Command", "Get-Clipboard"])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Unable to read the clipboard, powershell.exe failed with {}",
+            output.status
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// scores `text` against `pattern` when every character of `pattern` appears in `text`,
+/// in order, ignoring case. Higher is better: consecutive characters and
+/// characters at the start of words score more, and gaps cost a little.
+pub(crate) fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text = text.chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut text_idx = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for pattern_char in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let pattern_char = pattern_char.to_lowercase().collect::<String>();
+
+        let match_idx = (text_idx..text.len())
+            .find(|&i| text[i].to_lowercase().collect::<String>() == pattern_char)?;
+
+        let is_word_start = match_idx == 0 || !text[match_idx - 1].is_alphanumeric();
+
+        score += 1;
+        if is_word_start {
+            score += 8;
+        }
+        if let Some(previous) = previous_match {
+            if previous + 1 == match_idx {
+                score += 5;
+            } else {
+                score -= (match_idx - previous - 1).min(5) as i64;
+            }
+        }
+
+        previous_match = Some(match_idx);
+        text_idx = match_idx + 1;
+    }
+
+    Some(score)
+}
+
+/// like `6 min`, rounded up, for `words` read at `words_per_minute`
+pub(crate) fn format_reading_time(words: usize, words_per_minute: usize) -> String {
+    let minutes = words.div_ceil(words_per_minute.max(1));
+    format!("{} min", minutes.max(1))
+}
+
+/// how long before `now` `date` was, like `3h`, `2d`, or `3w`, in as few characters as possible.
+/// dates in the future are `now`, like the last minute
+pub(crate) fn format_age(
+    date: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let age = now.signed_duration_since(date);
+
+    if age.num_minutes() < 1 {
+        "now".to_string()
+    } else if age.num_hours() < 1 {
+        format!("{}m", age.num_minutes())
+    } else if age.num_days() < 1 {
+        format!("{}h", age.num_hours())
+    } else if age.num_weeks() < 1 {
+        format!("{}d", age.num_days())
+    } else if age.num_days() < 365 {
+        format!("{}w", age.num_weeks())
+    } else {
+        format!("{}y", age.num_days() / 365)
+    }
+}
+
+/// `date` in `tz`, formatted with the strftime-style `format`
+pub(crate) fn format_date<Tz: chrono::TimeZone>(
+    date: chrono::DateTime<chrono::Utc>,
+    tz: &Tz,
+    format: &str,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    date.with_timezone(tz).format(format).to_string()
+}
+
+/// like `1.5 MB`
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_are_in_the_largest_unit_that_fits() {
+        let now = chrono::Utc::now();
+        let ago = |duration: chrono::Duration| format_age(now - duration, now);
+
+        assert_eq!(ago(chrono::Duration::seconds(30)), "now");
+        assert_eq!(ago(chrono::Duration::seconds(-600)), "now");
+        assert_eq!(ago(chrono::Duration::minutes(59)), "59m");
+        assert_eq!(ago(chrono::Duration::hours(3)), "3h");
+        assert_eq!(ago(chrono::Duration::days(2)), "2d");
+        assert_eq!(ago(chrono::Duration::days(23)), "3w");
+        assert_eq!(ago(chrono::Duration::days(800)), "2y");
+    }
+
+    #[test]
+    fn dates_are_formatted_in_the_timezone() {
+        let date = chrono::DateTime::parse_from_rfc3339("2024-05-12T23:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let tokyo = chrono::FixedOffset::east(9 * 3600);
+
+        assert_eq!(
+            format_date(date, &tokyo, "%Y-%m-%d %H:%M"),
+            "2024-05-13 08:30"
+        );
+        assert_eq!(format_date(date, &chrono::Utc, "%d.%m."), "12.05.");
+    }
+
+    #[test]
+    fn feed_urls_are_found_among_other_text() {
+        assert_eq!(
+            find_feed_url("  https://example.com/feed.xml\n").as_deref(),
+            Some("https://example.com/feed.xml")
+        );
+        assert_eq!(
+            find_feed_url("Rust blog: http://blog.rust-lang.org/feed.xml (Atom)").as_deref(),
+            Some("http://blog.rust-lang.org/feed.xml")
+        );
+        assert_eq!(
+            find_feed_url("feed://example.com/rss").as_deref(),
+            Some("http://example.com/rss")
+        );
+        assert_eq!(
+            find_feed_url("feed:https://example.com/rss").as_deref(),
+            Some("https://example.com/rss")
+        );
+        assert_eq!(find_feed_url("mailto:someone@example.com notes.txt"), None);
+    }
+
+    #[test]
+    fn urls_are_normalized_for_comparison() {
+        let normalized = normalize_url("https://example.com/blog/feed");
+
+        for url in [
+            "http://example.com/blog/feed",
+            "https://EXAMPLE.com/blog/feed/",
+            "http://example.com:80/blog/feed#top",
+            "https://example.com/blog/feed?utm_source=newsletter&fbclid=abc",
+            " https://example.com/blog/feed ",
+        ] {
+            assert_eq!(normalize_url(url), normalized, "{}", url);
+        }
+
+        assert_eq!(
+            normalize_url("https://example.com/feed?utm_medium=rss&category=rust"),
+            "https://example.com/feed?category=rust"
+        );
+        assert_ne!(
+            normalize_url("https://example.com/feed?category=rust"),
+            normalize_url("https://example.com/feed?category=go")
+        );
+        assert_ne!(
+            normalize_url("https://example.com:8080/feed"),
+            normalize_url("https://example.com/feed")
+        );
+    }
+
+    #[test]
+    fn command_args_substitutes_or_appends() {
+        assert_eq!(
+            command_args("firefox --new-tab {}", "https://example.com"),
+            vec!["firefox", "--new-tab", "https://example.com"]
+        );
+        assert_eq!(
+            command_args("xdg-open", "https://example.com"),
+            vec!["xdg-open", "https://example.com"]
+        );
+    }
+
+    #[test]
+    fn pipe_writes_the_input_to_the_command() {
+        let path = std::env::temp_dir().join(format!("russ-pipe-{}", std::process::id()));
+
+        pipe("title\n\ntext\n", &format!("tee {}", path.display())).unwrap();
+        let piped = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(piped, "title\n\ntext\n");
+        assert!(pipe("text", "false").is_err());
+    }
+
+    #[test]
+    fn link_templates_rewrite_links() {
+        assert_eq!(
+            expand_link_template(
+                "https://nitter.net{path}",
+                "https://twitter.com/rustlang/status/1?s=20#top"
+            ),
+            "https://nitter.net/rustlang/status/1?s=20#top"
+        );
+        assert_eq!(
+            expand_link_template("https://archive.org/wait/{url}", "https://example.com/a"),
+            "https://archive.org/wait/https://example.com/a"
+        );
+        assert_eq!(
+            expand_link_template("https://{host}.example{path}", "not a url"),
+            "not a url"
+        );
+    }
+
+    #[test]
+    fn hyperlink_support_is_detected_from_the_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert!(supports_hyperlinks(env(&[("TERM", "xterm-kitty")])));
+        assert!(supports_hyperlinks(env(&[("VTE_VERSION", "6003")])));
+        assert!(!supports_hyperlinks(env(&[("VTE_VERSION", "4601")])));
+        assert!(!supports_hyperlinks(env(&[
+            ("TERM_PROGRAM", "iTerm.app"),
+            ("TMUX", "/tmp/tmux-1000/default,1,0"),
+        ])));
+        assert!(!supports_hyperlinks(env(&[("TERM", "xterm-256color")])));
+    }
+
+    #[test]
+    fn graphics_protocols_are_detected_from_the_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        let kitty = Some(GraphicsProtocol::Kitty);
+        let sixel = Some(GraphicsProtocol::Sixel);
+
+        assert_eq!(graphics_protocol(env(&[("KITTY_WINDOW_ID", "1")])), kitty);
+        assert_eq!(graphics_protocol(env(&[("TERM", "xterm-kitty")])), kitty);
+        assert_eq!(
+            graphics_protocol(env(&[("TERM_PROGRAM", "WezTerm")])),
+            kitty
+        );
+        assert_eq!(graphics_protocol(env(&[("TERM", "foot")])), sixel);
+        assert_eq!(
+            graphics_protocol(env(&[("TERM_PROGRAM", "iTerm.app")])),
+            sixel
+        );
+        assert_eq!(
+            graphics_protocol(env(&[
+                ("KITTY_WINDOW_ID", "1"),
+                ("TMUX", "/tmp/tmux-1000/default,1,0"),
+            ])),
+            None
+        );
+        assert_eq!(
+            graphics_protocol(env(&[("TERM", "foot"), ("STY", "1.pts-0")])),
+            None
+        );
+        // hyperlinks, but no images
+        assert_eq!(graphics_protocol(env(&[("TERM", "alacritty")])), None);
+        assert_eq!(graphics_protocol(env(&[("VTE_VERSION", "6003")])), None);
+    }
+
+    #[test]
+    fn clicked_rows_are_found_in_scrolled_lists() {
+        let mut list = StatefulList::with_items((0..10).collect::<Vec<_>>());
+        list.state.select(Some(6));
+        list.scroll_to_selected(4);
+        assert_eq!(list.item_at_row(0), Some(3));
+        assert_eq!(list.item_at_row(3), Some(6));
+
+        // scrolling back up only goes as far as the selection
+        list.state.select(Some(4));
+        list.scroll_to_selected(4);
+        assert_eq!(list.item_at_row(0), Some(3));
+        list.state.select(Some(1));
+        list.scroll_to_selected(4);
+        assert_eq!(list.item_at_row(0), Some(1));
+        assert_eq!(list.item_at_row(9), None);
+    }
+
+    #[test]
+    fn osc52_sequence_is_wrapped_for_tmux() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        assert_eq!(fuzzy_score("xyz", "Hacker News"), None);
+        assert!(fuzzy_score("", "Hacker News").is_some());
+
+        let hn = fuzzy_score("hn", "Hacker News").unwrap();
+        let hn_middle = fuzzy_score("hn", "The Verge: Tech").unwrap_or(i64::MIN);
+        assert!(hn > hn_middle);
+
+        let run = fuzzy_score("rust", "This Week in Rust").unwrap();
+        let scattered = fuzzy_score("rust", "Rob's useless thoughts").unwrap();
+        assert!(run > scattered);
+    }
+}